@@ -0,0 +1,50 @@
+use anyhow::Context;
+use gqlient::progress;
+use serde::Serialize;
+use serde_jsonlines::{append_json_lines, write_json_lines};
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+
+/// Writes the results of each phase of a run to a JSON Lines file named after
+/// the phase in the directory given by `--phase-dumps`, as returned by
+/// [`CommonArgs::phase_dumps()`](crate::CommonArgs::phase_dumps).  If no
+/// directory was given, dumping does nothing.
+///
+/// The first dump for a phase during a run replaces any file left over from
+/// an earlier run; later dumps for the same phase (e.g., for each host) are
+/// appended to it.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PhaseDumps {
+    dir: Option<PathBuf>,
+    /// The phases dumped so far
+    dumped: RefCell<BTreeSet<String>>,
+}
+
+impl PhaseDumps {
+    pub(crate) fn new(dir: Option<PathBuf>) -> PhaseDumps {
+        PhaseDumps {
+            dir,
+            dumped: RefCell::default(),
+        }
+    }
+
+    /// Write `records` as the results of the phase `phase`
+    pub fn dump<I>(&self, phase: &str, records: I) -> anyhow::Result<()>
+    where
+        I: IntoIterator,
+        I::Item: Serialize,
+    {
+        let Some(ref dir) = self.dir else {
+            return Ok(());
+        };
+        let path = dir.join(format!("{}.jsonl", phase.replace(' ', "-")));
+        progress!("Dumping results of {phase} phase to {} …", path.display());
+        if self.dumped.borrow_mut().insert(phase.to_owned()) {
+            write_json_lines(&path, records)
+        } else {
+            append_json_lines(&path, records)
+        }
+        .with_context(|| format!("failed to write phase dump to {}", path.display()))
+    }
+}
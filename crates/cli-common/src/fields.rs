@@ -0,0 +1,93 @@
+use anyhow::bail;
+use serde::ser::{Error as _, SerializeMap};
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// The fields to include in each record of an output file, as given by a
+/// `--fields` option in the form `repo,number,title`
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FieldSelection(Vec<String>);
+
+impl FieldSelection {
+    /// Return an error if any of the selected fields is not among `known`
+    pub fn check(&self, known: &[&str]) -> anyhow::Result<()> {
+        for field in &self.0 {
+            if !known.contains(&field.as_str()) {
+                bail!(
+                    "unknown field {field:?} in --fields; valid fields are: {}",
+                    known.join(", ")
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Wrap `value` so that only the selected fields are serialized, in the
+    /// order in which they were selected.  Selected fields that `value`
+    /// omits (such as optional fields that were not fetched) are skipped.
+    pub fn select<'a, T>(&'a self, value: &'a T) -> Selected<'a, T> {
+        Selected {
+            fields: &self.0,
+            value,
+        }
+    }
+}
+
+impl FromStr for FieldSelection {
+    type Err = ParseFieldSelectionError;
+
+    fn from_str(s: &str) -> Result<FieldSelection, ParseFieldSelectionError> {
+        let mut fields = Vec::new();
+        for name in s.split(',').map(str::trim) {
+            if name.is_empty() {
+                return Err(ParseFieldSelectionError);
+            }
+            if !fields.iter().any(|f| f == name) {
+                fields.push(name.to_owned());
+            }
+        }
+        Ok(FieldSelection(fields))
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ParseFieldSelectionError;
+
+impl fmt::Display for ParseFieldSelectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid field selection; expected comma-separated field names")
+    }
+}
+
+impl std::error::Error for ParseFieldSelectionError {}
+
+/// A record whose serialization is restricted to a [`FieldSelection`], as
+/// returned by [`FieldSelection::select()`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Selected<'a, T> {
+    fields: &'a [String],
+    value: &'a T,
+}
+
+impl<T: Serialize> Serialize for Selected<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let serde_json::Value::Object(mut record) =
+            serde_json::to_value(self.value).map_err(S::Error::custom)?
+        else {
+            return Err(S::Error::custom(
+                "fields can only be selected from records that serialize as objects",
+            ));
+        };
+        let entries = self
+            .fields
+            .iter()
+            .filter_map(|f| record.remove_entry(f))
+            .collect::<Vec<_>>();
+        let mut map = serializer.serialize_map(Some(entries.len()))?;
+        for (key, value) in &entries {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+}
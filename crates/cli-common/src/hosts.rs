@@ -0,0 +1,94 @@
+use anyhow::{bail, Context};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// A GitHub API endpoint other than github.com, as configured in a `--hosts`
+/// file
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct HostConfig {
+    /// The URL of the host's GraphQL endpoint, e.g.,
+    /// `https://ghes.example.com/api/graphql`
+    pub graphql_url: String,
+    /// The base URL of the host's REST API, e.g.,
+    /// `https://ghes.example.com/api/v3`
+    pub rest_url: String,
+    /// The name of the environment variable containing the access token for
+    /// the host
+    pub token_env: String,
+}
+
+/// Read a `--hosts` file, a JSON object mapping host names to
+/// [`HostConfig`]s
+pub fn load_hosts(path: &Path) -> anyhow::Result<BTreeMap<String, HostConfig>> {
+    let src = fs::read_to_string(path)
+        .with_context(|| format!("failed to read hosts file {}", path.display()))?;
+    let hosts = serde_json::from_str::<BTreeMap<String, HostConfig>>(&src)
+        .with_context(|| format!("failed to parse hosts file {}", path.display()))?;
+    for name in hosts.keys() {
+        if name.is_empty() || name.contains(':') {
+            bail!(
+                "invalid host name {name:?} in {}: names must be nonempty and may not contain colons",
+                path.display()
+            );
+        }
+    }
+    Ok(hosts)
+}
+
+/// The owners to fetch from a single host
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HostOwners {
+    /// The name of the host as configured in the `--hosts` file, or `None`
+    /// for github.com
+    pub host: Option<String>,
+    /// The owners' logins, without host qualifiers
+    pub owners: Vec<String>,
+}
+
+impl HostOwners {
+    /// Return `name` (an owner login or an `owner/repo` name) qualified with
+    /// the host, as it appears in owner arguments and run reports
+    pub fn qualify(&self, name: &str) -> String {
+        match self.host {
+            Some(ref host) => format!("{host}:{name}"),
+            None => name.to_owned(),
+        }
+    }
+}
+
+/// Split `owners` into groups by host.  Owners of the form `host:owner` are
+/// fetched from the given host in `hosts`, and other owners are fetched from
+/// github.com.  Groups are returned with github.com first, followed by the
+/// other hosts in order of first appearance.
+pub fn group_owners_by_host(
+    owners: &[String],
+    hosts: &BTreeMap<String, HostConfig>,
+) -> anyhow::Result<Vec<HostOwners>> {
+    let mut groups = vec![HostOwners {
+        host: None,
+        owners: Vec::new(),
+    }];
+    for owner in owners {
+        let (host, login) = match owner.split_once(':') {
+            Some((host, login)) => {
+                if !hosts.contains_key(host) {
+                    bail!("owner {owner:?} refers to host {host:?}, which is not configured in a --hosts file");
+                }
+                (Some(host), login)
+            }
+            None => (None, owner.as_str()),
+        };
+        match groups.iter_mut().find(|g| g.host.as_deref() == host) {
+            Some(g) => g.owners.push(login.to_owned()),
+            None => groups.push(HostOwners {
+                host: host.map(String::from),
+                owners: vec![login.to_owned()],
+            }),
+        }
+    }
+    groups.retain(|g| !g.owners.is_empty());
+    Ok(groups)
+}
@@ -0,0 +1,605 @@
+mod dumps;
+mod fields;
+mod hosts;
+mod lock;
+mod profile;
+mod reportdb;
+mod runid;
+mod stale;
+pub use crate::dumps::PhaseDumps;
+pub use crate::fields::{FieldSelection, ParseFieldSelectionError, Selected};
+pub use crate::hosts::{group_owners_by_host, load_hosts, HostConfig, HostOwners};
+pub use crate::lock::{RunLock, LOCKED_EXIT_CODE};
+pub use crate::profile::Profile;
+pub use crate::reportdb::append_report_db;
+pub use crate::runid::new_run_uuid;
+pub use crate::stale::StaleIssues;
+use anyhow::{bail, Context};
+use clap::{parser::ValueSource, ArgMatches, Args, ValueEnum};
+use gqlient::{
+    progress, Anonymizer, CircuitBreaker, Client, EnvToken, LabelMatrix, OwnerKind, RepoOrder,
+    RepoPrivacy, RetryPolicy, DEFAULT_BATCH_SIZE,
+};
+use patharg::{InputArg, OutputArg};
+use serde::{Deserialize, Serialize};
+use serde_jsonlines::append_json_lines;
+use std::collections::{BTreeMap, HashSet};
+use std::io::{ErrorKind, Write};
+use std::num::{NonZeroU32, NonZeroUsize};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+/// The delay before the first retry of a failed request when `--retries` is
+/// given; the delay doubles with each subsequent retry of the same request
+const RETRY_INITIAL_DELAY: Duration = Duration::from_secs(1);
+
+/// Command-line options shared by all of the programs that fetch issues for a
+/// set of owners, for inclusion in each program's arguments with
+/// `#[command(flatten)]`
+#[derive(Args, Clone, Debug, Eq, PartialEq)]
+pub struct CommonArgs {
+    /// Halve the page sizes of the sub-queries for issues in any GraphQL
+    /// request that takes at least the given duration, for their subsequent
+    /// pages
+    ///
+    /// Accepts durations like "30s" or "2m".
+    #[arg(long, value_name = "DURATION", value_parser = humantime::parse_duration)]
+    pub adaptive_page_size: Option<Duration>,
+
+    /// Replace repository & owner names, issue titles, label names, and
+    /// issue authors in all output files and run reports with hashes keyed
+    /// by the key in the given file
+    ///
+    /// If the file does not exist, a random key is generated and saved to
+    /// it.
+    #[arg(long, value_name = "KEYFILE")]
+    pub anonymize: Option<PathBuf>,
+
+    /// Send API requests to the given base URL (e.g., that of a local test
+    /// server) instead of to api.github.com
+    ///
+    /// GraphQL requests are sent to the URL with "/graphql" appended.
+    #[arg(long, value_name = "URL")]
+    pub api_url: Option<String>,
+
+    /// Save the raw response data of sub-queries that cannot be deserialized
+    /// to files in the given directory instead of the system's temporary
+    /// directory
+    ///
+    /// With `--anonymize`, such data is not saved at all.
+    #[arg(long, value_name = "DIR", conflicts_with = "anonymize")]
+    pub bad_data_dir: Option<PathBuf>,
+
+    /// Number of sub-queries to make per GraphQL request
+    #[arg(short = 'B', long)]
+    pub batch_size: Option<NonZeroUsize>,
+
+    /// Save each GraphQL request & response as a fixture in the given
+    /// directory
+    #[arg(long, value_name = "DIR", conflicts_with = "anonymize")]
+    pub capture_fixtures: Option<PathBuf>,
+
+    /// Gzip-compress the body of each GraphQL request that is larger than
+    /// the given number of bytes
+    #[arg(long, value_name = "BYTES")]
+    pub compress_above: Option<usize>,
+
+    /// Stop making requests for a while after the given number of
+    /// consecutive requests fail due to network or server errors
+    #[arg(long, value_name = "FAILURES")]
+    pub circuit_breaker: Option<NonZeroU32>,
+
+    /// How long to wait before trying again after `--circuit-breaker` stops
+    /// requests
+    ///
+    /// Accepts durations like "30s" or "5m".
+    #[arg(long, default_value = "60s", value_name = "DURATION", value_parser = humantime::parse_duration)]
+    pub circuit_cooldown: Duration,
+
+    /// Read parameter presets for `--profile` from the given TOML file
+    #[arg(long, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// Retry each sub-query whose response data cannot be deserialized up to
+    /// the given number of times in a row before giving up on it
+    #[arg(long, default_value_t = 0, value_name = "INT")]
+    pub deserialize_retries: u32,
+
+    /// Take an exclusive lock on the given file (creating it if necessary)
+    /// for the duration of the run, exiting with status 75 without fetching
+    /// anything if another run already holds the lock
+    #[arg(long, value_name = "PATH")]
+    pub lockfile: Option<PathBuf>,
+
+    /// After the run report is composed, run the given shell command with
+    /// the report as JSON on its standard input
+    #[arg(long, value_name = "COMMAND")]
+    pub on_complete: Option<String>,
+
+    /// Read additional newline-separated owners from the given file
+    #[arg(long, value_name = "PATH")]
+    pub owners_from: Option<InputArg>,
+
+    /// Number of items to request per page of results
+    #[arg(short = 'P', long, default_value = "100")]
+    pub page_size: NonZeroUsize,
+
+    /// Pause until the GraphQL rate limit resets whenever fewer than the
+    /// given number of rate limit points remain
+    #[arg(long, value_name = "POINTS")]
+    pub pause_below: Option<u32>,
+
+    /// Write the results of each phase of the run (e.g., the repositories
+    /// fetched by the "fetch repositories" phase) as fetched to a JSON Lines
+    /// file in the given directory, for debugging differences between
+    /// strategies
+    #[arg(long, value_name = "DIR", conflicts_with = "anonymize")]
+    pub phase_dumps: Option<PathBuf>,
+
+    /// Which repositories to fetch based on their visibility: "public",
+    /// "private", or "all"
+    ///
+    /// Fetching private repositories requires a token that can read them.
+    #[arg(long, default_value_t, value_name = "PRIVACY")]
+    pub privacy: RepoPrivacy,
+
+    /// Use the parameter presets in the `[profile.NAME]` table of the
+    /// `--config` file for any of its options not given on the command line
+    #[arg(long, value_name = "NAME", requires = "config")]
+    pub profile: Option<String>,
+
+    /// Do not log progress messages to stderr; warnings and errors are still
+    /// logged
+    #[arg(short, long, conflicts_with = "verbose")]
+    pub quiet: bool,
+
+    /// Record the run report in the given SQLite database, creating it if
+    /// it does not exist
+    #[arg(long, value_name = "PATH")]
+    pub report_db: Option<PathBuf>,
+
+    /// Append a run report to the given file
+    #[arg(short = 'R', long)]
+    pub report_file: Option<PathBuf>,
+
+    /// Warn about response fields that are fetched but never used, as a
+    /// debugging aid for trimming queries
+    #[arg(long)]
+    pub report_unused_fields: bool,
+
+    /// Retry each request that fails due to a network or server error up to
+    /// the given number of times, with exponential backoff
+    #[arg(long, value_name = "INT")]
+    pub retries: Option<u32>,
+
+    /// Make at most the given number of retries over the whole run
+    #[arg(long, value_name = "INT")]
+    pub retry_budget: Option<u32>,
+
+    /// Give up on any GraphQL request that takes longer than the given
+    /// duration
+    ///
+    /// Accepts durations like "90s" or "5m".
+    #[arg(long, value_name = "DURATION", value_parser = humantime::parse_duration)]
+    pub request_timeout: Option<Duration>,
+
+    /// Order in which to fetch repositories: "name", "pushed", "created", or
+    /// "stargazers"
+    #[arg(long, default_value_t, value_name = "ORDER")]
+    pub repo_order: RepoOrder,
+
+    /// Log each GraphQL request that takes at least the given duration,
+    /// along with the owners or repositories queried in it
+    ///
+    /// Accepts durations like "500ms" or "10s".
+    #[arg(long, value_name = "DURATION", value_parser = humantime::parse_duration)]
+    pub slow_request_threshold: Option<Duration>,
+
+    /// Format in which to output a summary of the run at exit
+    ///
+    /// "human" only logs the summary to stderr; "json" additionally prints
+    /// the run report as a single line of JSON to stdout.
+    #[arg(long, value_enum, default_value_t = SummaryFormat::Human)]
+    pub summary: SummaryFormat,
+
+    /// Show a live status line while fetching
+    #[arg(short, long)]
+    pub verbose: bool,
+
+    /// GitHub owners/organizations of repositories to fetch open issues for
+    ///
+    /// An argument of "-" causes newline-separated owners to be read from
+    /// standard input.
+    #[arg(required_unless_present = "owners_from")]
+    pub owners: Vec<String>,
+}
+
+impl CommonArgs {
+    /// Apply the presets of the `--profile` profile, if given, to the
+    /// options that `matches` shows were not given on the command line
+    pub fn apply_profile(&mut self, matches: &ArgMatches) -> anyhow::Result<()> {
+        let (Some(config), Some(name)) = (&self.config, &self.profile) else {
+            return Ok(());
+        };
+        let profile = Profile::load(config, name)?;
+        let unset = |id: &str| matches.value_source(id) != Some(ValueSource::CommandLine);
+        if let Some(batch_size) = profile.batch_size.filter(|_| unset("batch_size")) {
+            self.batch_size = Some(batch_size);
+        }
+        if let Some(page_size) = profile.page_size.filter(|_| unset("page_size")) {
+            self.page_size = page_size;
+        }
+        if let Some(retries) = profile.retries.filter(|_| unset("retries")) {
+            self.retries = Some(retries);
+        }
+        if let Some(budget) = profile.retry_budget.filter(|_| unset("retry_budget")) {
+            self.retry_budget = Some(budget);
+        }
+        if let Some(path) = profile.report_db.filter(|_| unset("report_db")) {
+            self.report_db = Some(path);
+        }
+        if let Some(path) = profile.report_file.filter(|_| unset("report_file")) {
+            self.report_file = Some(path);
+        }
+        if let Some(summary) = profile.summary.filter(|_| unset("summary")) {
+            self.summary = summary;
+        }
+        Ok(())
+    }
+
+    /// Fail if `--summary json` (whether given on the command line or by the
+    /// `--profile`) would print the run report to stdout while any of
+    /// `outputs` is also written to stdout, as the report would then corrupt
+    /// that output
+    pub fn check_summary_output<'a, I>(&self, outputs: I) -> anyhow::Result<()>
+    where
+        I: IntoIterator<Item = &'a OutputArg>,
+    {
+        if self.summary == SummaryFormat::Json && outputs.into_iter().any(OutputArg::is_stdout) {
+            bail!("--summary json cannot be used when other output is written to standard output");
+        }
+        Ok(())
+    }
+
+    /// Return the owners given on the command line, with any `-` arguments
+    /// replaced by the owners read from standard input, followed by the
+    /// owners read from the `--owners-from` file, if any.  Duplicate owners
+    /// are removed.
+    pub fn owners(&self) -> anyhow::Result<Vec<String>> {
+        let mut owners = Vec::new();
+        for owner in &self.owners {
+            if owner == "-" {
+                owners.extend(read_owners(&InputArg::Stdin)?);
+            } else {
+                owners.push(owner.clone());
+            }
+        }
+        if let Some(ref infile) = self.owners_from {
+            owners.extend(read_owners(infile)?);
+        }
+        let mut seen = HashSet::new();
+        owners.retain(|owner| seen.insert(owner.clone()));
+        if owners.is_empty() {
+            bail!("no owners were given");
+        }
+        Ok(owners)
+    }
+
+    /// Return the number of sub-queries to make per GraphQL request
+    pub fn batch_size(&self) -> usize {
+        match self.batch_size {
+            Some(bs) => bs.get(),
+            None => DEFAULT_BATCH_SIZE,
+        }
+    }
+
+    /// Return an anonymizer using the `--anonymize` key file, if given
+    pub fn anonymizer(&self) -> anyhow::Result<Option<Anonymizer>> {
+        self.anonymize
+            .as_deref()
+            .map(Anonymizer::from_key_file)
+            .transpose()
+    }
+
+    /// Create the `--phase-dumps` directory, if given, and return a
+    /// [`PhaseDumps`] for writing to it
+    pub fn phase_dumps(&self) -> anyhow::Result<PhaseDumps> {
+        if let Some(ref dir) = self.phase_dumps {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("failed to create {}", dir.display()))?;
+        }
+        Ok(PhaseDumps::new(self.phase_dumps.clone()))
+    }
+
+    /// Take the lock on the `--lockfile` file, if given.  If another process
+    /// holds the lock, a message is logged and `None` is returned, in which
+    /// case the caller should exit with status [`LOCKED_EXIT_CODE`].
+    pub fn lock(&self) -> anyhow::Result<Option<RunLock>> {
+        let Some(ref path) = self.lockfile else {
+            return Ok(Some(RunLock::none()));
+        };
+        let lock = RunLock::acquire(path)?;
+        if lock.is_none() {
+            eprintln!(
+                "[!] Another run holds the lock on {}; exiting",
+                path.display()
+            );
+        }
+        Ok(lock)
+    }
+
+    /// Create a client authenticated with the local GitHub token and
+    /// configured according to the options.  If `--privacy` selects private
+    /// repositories, the token is checked for the scopes needed to list them.
+    pub fn client(&self) -> anyhow::Result<Client> {
+        self.client_for_host(None)
+    }
+
+    /// Like [`CommonArgs::client()`], but if `host` (a host name and its
+    /// configuration) is given, the client talks to that host's endpoints and
+    /// authenticates with the token in its configured environment variable.
+    /// Fixtures captured from the host with `--capture-fixtures` are saved
+    /// in a subdirectory named after the host so that they are not
+    /// overwritten by those from other hosts.
+    pub fn client_for_host(&self, host: Option<(&str, &HostConfig)>) -> anyhow::Result<Client> {
+        let mut client = match host {
+            Some((_, cfg)) => Client::builder()
+                .graphql_url(cfg.graphql_url.clone())
+                .rest_url(cfg.rest_url.clone())
+                .auth_provider(EnvToken::new(cfg.token_env.clone()))
+                .build()?,
+            None => match self.api_url {
+                Some(ref url) => Client::new_with_local_token_at(url)?,
+                None => Client::new_with_local_token()?,
+            },
+        };
+        if let Some(bsz) = self.batch_size {
+            client.batch_size(bsz);
+        }
+        if let Some(ref dir) = self.capture_fixtures {
+            match host {
+                Some((name, _)) => client.capture_fixtures(dir.join(name)),
+                None => client.capture_fixtures(dir.clone()),
+            }
+        }
+        if let Some(points) = self.pause_below {
+            client.pause_below(points);
+        }
+        if let Some(timeout) = self.request_timeout {
+            client.request_timeout(timeout);
+        }
+        if let Some(threshold) = self.slow_request_threshold {
+            client.slow_request_threshold(threshold);
+        }
+        if let Some(threshold) = self.adaptive_page_size {
+            client.adaptive_page_size(threshold);
+        }
+        if let Some(bytes) = self.compress_above {
+            client.compress_above(bytes);
+        }
+        if let Some(ref dir) = self.bad_data_dir {
+            client.bad_data_dir(dir.clone());
+        } else if self.anonymize.is_some() {
+            // Undeserializable response data cannot be anonymized.
+            client.discard_bad_data();
+        }
+        client.deserialize_retries(self.deserialize_retries);
+        if let Some(retries) = self.retries {
+            client.retry_policy(RetryPolicy::new(retries, RETRY_INITIAL_DELAY));
+        }
+        if let Some(budget) = self.retry_budget {
+            client.retry_budget(budget);
+        }
+        if let Some(failures) = self.circuit_breaker {
+            client.circuit_breaker(CircuitBreaker::new(failures, self.circuit_cooldown));
+        }
+        client.verbose(self.verbose);
+        client.report_unused_fields(self.report_unused_fields);
+        #[cfg(feature = "otel")]
+        if let Some(tracer) = gqlient::Tracer::from_env()? {
+            client.tracer(tracer);
+        }
+        if self.privacy.includes_private() {
+            check_private_scopes(&client, self.privacy)?;
+        }
+        Ok(client)
+    }
+
+    /// Append `report` to the `--report-file` file and record it in the
+    /// `--report-db` database, if either was given
+    pub fn save_report<R: Serialize>(&self, report: &R) -> anyhow::Result<()> {
+        if let Some(ref report_file) = self.report_file {
+            progress!("Appending report to {} …", report_file.display());
+            append_json_lines(report_file, std::iter::once(report))
+                .context("failed to write report")?;
+        }
+        if let Some(ref report_db) = self.report_db {
+            progress!("Recording report in {} …", report_db.display());
+            append_report_db(report_db, report)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum SummaryFormat {
+    Human,
+    Json,
+}
+
+/// A header record written at the start of a JSON Lines issue dump by
+/// `--outfile-header`, so that a dump found on disk later describes the run
+/// that produced it without needing to be matched up with a run report
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(tag = "type", rename = "header")]
+pub struct OutfileHeader<'a, P> {
+    pub program: &'a str,
+    /// The version of the program
+    pub version: &'a str,
+    pub commit: Option<&'a str>,
+    /// The time at which the run started, as in the run report
+    pub timestamp: &'a str,
+    /// The run's UUID, as in the run report
+    pub run_uuid: &'a str,
+    /// The run's parameters, as in the run report
+    pub parameters: &'a P,
+    pub owners: &'a [String],
+}
+
+/// A trailer record appended to a JSON Lines issue dump by
+/// `--outfile-summary`, so that consumers of the dump can check that it is
+/// complete without needing the run report
+///
+/// Barring issues opened or closed during the run, `expected_issues` equals
+/// `issues + bot_issues_skipped + issues_filtered_out` when no owners or
+/// repositories failed.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(tag = "type", rename = "summary")]
+pub struct OutfileSummary<'a> {
+    /// The run's UUID, as in the run report and the header record
+    pub run_uuid: &'a str,
+    /// The number of repositories fetched
+    pub repositories: usize,
+    /// The number of repositories that had open issues
+    pub repos_with_open_issues: usize,
+    /// The total number of open issues reported by the repositories whose
+    /// issues were fetched
+    pub expected_issues: u64,
+    /// The number of issue records in the dump before the trailer
+    pub issues: usize,
+    pub bot_issues_skipped: usize,
+    pub issues_filtered_out: usize,
+    /// The number of owners whose repositories or issues could not all be
+    /// fetched
+    pub failed_owners: usize,
+    /// The total fetch time
+    #[serde(serialize_with = "gqlient::serialize_duration")]
+    pub elapsed: Duration,
+}
+
+/// Look up whether each owner is a user or an organization, logging the
+/// totals and any owners that do not exist
+pub fn resolve_owner_kinds(
+    client: &Client,
+    owners: &[String],
+) -> anyhow::Result<BTreeMap<String, OwnerKind>> {
+    progress!("Resolving owner kinds …");
+    let phase = client.phase("resolve owners");
+    let kinds = client.resolve_owner_kinds(owners)?;
+    drop(phase);
+    let orgs = kinds
+        .values()
+        .filter(|&&k| k == OwnerKind::Organization)
+        .count();
+    progress!(
+        "{orgs} owners are organizations and {} are users",
+        kinds.len() - orgs
+    );
+    for owner in owners {
+        if !kinds.contains_key(owner) {
+            eprintln!("[!] Owner {owner} does not exist");
+        }
+    }
+    Ok(kinds)
+}
+
+/// Fail if the client's token is a classic token without the `repo` scope,
+/// which is needed to list private repositories for `--privacy`.  For tokens
+/// whose scopes cannot be determined, only a warning is logged.
+fn check_private_scopes(client: &Client, privacy: RepoPrivacy) -> anyhow::Result<()> {
+    match client.get_token_scopes()? {
+        Some(scopes) if !scopes.iter().any(|sc| sc == "repo") => {
+            let scopes = if scopes.is_empty() {
+                String::from("none")
+            } else {
+                scopes.join(", ")
+            };
+            bail!(
+                "--privacy {privacy} requires a token with the \"repo\" scope, but the token's scopes are: {scopes}"
+            );
+        }
+        Some(_) => Ok(()),
+        None => {
+            eprintln!(
+                "[!] Could not determine the token's scopes; private repositories will only be fetched if the token can read them"
+            );
+            Ok(())
+        }
+    }
+}
+
+/// Run `command` via the shell, passing it `report` as a line of JSON on
+/// standard input, and fail if the command is not successful.
+///
+/// Programs run this after all of their other output has been written, and a
+/// failure is reported as a warning & nonzero exit status rather than an
+/// error.  A command that exits without reading the report (closing its
+/// standard input) is not treated as a failure on that account.
+pub fn run_on_complete<R: Serialize>(command: &str, report: &R) -> anyhow::Result<()> {
+    let mut cmd = if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.arg("/C");
+        cmd
+    } else {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c");
+        cmd
+    };
+    let mut child = cmd
+        .arg(command)
+        .stdin(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("failed to run on-complete command {command:?}"))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        let written = serde_json::to_writer(&mut stdin, report)
+            .map_err(std::io::Error::from)
+            .and_then(|()| stdin.write_all(b"\n"));
+        match written {
+            Err(e) if e.kind() != ErrorKind::BrokenPipe => {
+                return Err(e).context("failed to write report to on-complete command");
+            }
+            _ => (),
+        }
+    }
+    let status = child
+        .wait()
+        .with_context(|| format!("failed to wait for on-complete command {command:?}"))?;
+    if !status.success() {
+        bail!("on-complete command {command:?} was not successful: {status}");
+    }
+    Ok(())
+}
+
+/// Write `matrix` to `outfile` as CSV if the file name ends in ".csv" and as
+/// JSON otherwise
+pub fn write_label_matrix(matrix: &LabelMatrix, outfile: &OutputArg) -> anyhow::Result<()> {
+    let csv = matches!(
+        outfile,
+        OutputArg::Path(p) if p.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("csv"))
+    );
+    let mut fp = outfile.create().context("failed to open file")?;
+    if csv {
+        matrix.write_csv(&mut fp)
+    } else {
+        matrix.write_json(&mut fp)
+    }
+    .context("failed to write label matrix")?;
+    fp.flush().context("failed to flush filehandle")?;
+    Ok(())
+}
+
+/// Read newline-separated owners from `infile`, ignoring blank lines and lines
+/// starting with `#`
+fn read_owners(infile: &InputArg) -> anyhow::Result<Vec<String>> {
+    let text = infile
+        .read_to_string()
+        .with_context(|| format!("failed to read owners from {infile:#}"))?;
+    Ok(text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect())
+}
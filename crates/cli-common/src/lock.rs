@@ -0,0 +1,45 @@
+use anyhow::Context;
+use fs4::fs_std::FileExt;
+use std::fs::{File, OpenOptions};
+use std::path::Path;
+
+/// The exit status used when another run holds the `--lockfile` lock
+/// (`EX_TEMPFAIL` from `sysexits.h`, as the run can be retried later)
+pub const LOCKED_EXIT_CODE: u8 = 75;
+
+/// An exclusive advisory lock on the file given by `--lockfile`, as returned
+/// by [`CommonArgs::lock()`](crate::CommonArgs::lock).  The lock is held
+/// until the `RunLock` is dropped or the process exits.  If no lockfile was
+/// given, the `RunLock` holds nothing.
+#[derive(Debug)]
+pub struct RunLock {
+    // Only kept open to hold the lock
+    _file: Option<File>,
+}
+
+impl RunLock {
+    /// Open (creating if necessary) the file at `path` and take an exclusive
+    /// lock on it without blocking.  Returns `Ok(None)` if another process
+    /// already holds a lock on the file.
+    pub(crate) fn acquire(path: &Path) -> anyhow::Result<Option<RunLock>> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)
+            .with_context(|| format!("failed to open lockfile {}", path.display()))?;
+        if file
+            .try_lock_exclusive()
+            .with_context(|| format!("failed to lock {}", path.display()))?
+        {
+            Ok(Some(RunLock { _file: Some(file) }))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub(crate) fn none() -> RunLock {
+        RunLock { _file: None }
+    }
+}
@@ -0,0 +1,44 @@
+use crate::SummaryFormat;
+use anyhow::Context;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+
+/// A named set of parameter presets, read from a `[profile.<name>]` table in
+/// a `--config` file and selected with `--profile`.  Each field corresponds
+/// to the command-line option of the same name, and fields that are not set
+/// leave the option's value unchanged.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Profile {
+    pub batch_size: Option<NonZeroUsize>,
+    pub page_size: Option<NonZeroUsize>,
+    pub retries: Option<u32>,
+    pub retry_budget: Option<u32>,
+    pub report_db: Option<PathBuf>,
+    pub report_file: Option<PathBuf>,
+    pub summary: Option<SummaryFormat>,
+}
+
+impl Profile {
+    /// Read the profile named `name` from the TOML config file at `path`
+    pub fn load(path: &Path, name: &str) -> anyhow::Result<Profile> {
+        let src = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read config file {}", path.display()))?;
+        let mut config = toml::from_str::<ConfigFile>(&src)
+            .with_context(|| format!("failed to parse config file {}", path.display()))?;
+        config.profile.remove(name).with_context(|| {
+            format!(
+                "config file {} does not define a profile named {name:?}",
+                path.display()
+            )
+        })
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    profile: BTreeMap<String, Profile>,
+}
@@ -0,0 +1,118 @@
+use anyhow::Context;
+use gqlient::duration_secs;
+use rusqlite::{params, types::Value as SqlValue, Connection};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::path::Path;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS runs (
+        id INTEGER PRIMARY KEY,
+        program TEXT NOT NULL,
+        git_commit TEXT,
+        timestamp TEXT NOT NULL,
+        report TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS parameters (
+        run_id INTEGER NOT NULL REFERENCES runs(id),
+        name TEXT NOT NULL,
+        value,
+        PRIMARY KEY (run_id, name)
+    );
+    CREATE TABLE IF NOT EXISTS metrics (
+        run_id INTEGER NOT NULL REFERENCES runs(id),
+        name TEXT NOT NULL,
+        value REAL NOT NULL,
+        PRIMARY KEY (run_id, name)
+    );
+";
+
+/// Record `report` as a new run in the SQLite database at `path`, creating
+/// the database and its tables if they do not already exist.
+///
+/// The run's program, commit, and timestamp are stored in the `runs` table
+/// along with the complete report as JSON.  Each entry of the report's
+/// `parameters` object becomes a row of the `parameters` table, and each
+/// other top-level field that is a number or a duration (stored in seconds)
+/// becomes a row of the `metrics` table.
+pub fn append_report_db<R: Serialize>(path: &Path, report: &R) -> anyhow::Result<()> {
+    let Value::Object(mut report) =
+        serde_json::to_value(report).context("failed to serialize report")?
+    else {
+        anyhow::bail!("report did not serialize to a JSON object");
+    };
+    let full_report = serde_json::to_string(&report).context("failed to serialize report")?;
+    let program = take_string(&mut report, "program");
+    let commit = take_string(&mut report, "commit");
+    let timestamp = take_string(&mut report, "timestamp");
+    let parameters = match report.remove("parameters") {
+        Some(Value::Object(map)) => map,
+        _ => Map::new(),
+    };
+    let mut conn = Connection::open(path)
+        .with_context(|| format!("failed to open report database {}", path.display()))?;
+    conn.execute_batch(SCHEMA)
+        .context("failed to create report database tables")?;
+    let tx = conn
+        .transaction()
+        .context("failed to start report database transaction")?;
+    tx.execute(
+        "INSERT INTO runs (program, git_commit, timestamp, report) VALUES (?1, ?2, ?3, ?4)",
+        params![program, commit, timestamp, full_report],
+    )
+    .context("failed to insert run into report database")?;
+    let run_id = tx.last_insert_rowid();
+    for (name, value) in parameters {
+        tx.execute(
+            "INSERT INTO parameters (run_id, name, value) VALUES (?1, ?2, ?3)",
+            params![run_id, name, sql_value(value)],
+        )
+        .with_context(|| format!("failed to insert parameter {name:?} into report database"))?;
+    }
+    for (name, value) in report {
+        if let Some(value) = metric_value(&value) {
+            tx.execute(
+                "INSERT INTO metrics (run_id, name, value) VALUES (?1, ?2, ?3)",
+                params![run_id, name, value],
+            )
+            .with_context(|| format!("failed to insert metric {name:?} into report database"))?;
+        }
+    }
+    tx.commit()
+        .context("failed to commit report database transaction")?;
+    Ok(())
+}
+
+fn take_string(map: &mut Map<String, Value>, key: &str) -> Option<String> {
+    match map.remove(key) {
+        Some(Value::String(s)) => Some(s),
+        _ => None,
+    }
+}
+
+/// Convert a report parameter to an SQLite value, storing durations in
+/// seconds and any other non-scalar values as JSON
+fn sql_value(value: Value) -> SqlValue {
+    if let Some(secs) = duration_secs(&value) {
+        return SqlValue::Real(secs);
+    }
+    match value {
+        Value::Null => SqlValue::Null,
+        Value::Bool(b) => SqlValue::Integer(i64::from(b)),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => SqlValue::Integer(i),
+            None => n.as_f64().map_or(SqlValue::Null, SqlValue::Real),
+        },
+        Value::String(s) => SqlValue::Text(s),
+        other => SqlValue::Text(other.to_string()),
+    }
+}
+
+/// If `value` is a number or a serialized duration, return it as a float,
+/// with durations in seconds
+fn metric_value(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        other => duration_secs(other),
+    }
+}
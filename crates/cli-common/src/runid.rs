@@ -0,0 +1,21 @@
+use anyhow::Context;
+use std::fmt::Write;
+
+/// Generate a random (version 4) UUID identifying a single run of a program.
+/// The UUID is recorded in the run report and in each of the run's other
+/// outputs so that all of the artifacts of a run can be matched up later.
+pub fn new_run_uuid() -> anyhow::Result<String> {
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes).context("failed to generate run UUID")?;
+    // Set the version (4) and variant (RFC 4122) bits
+    bytes[6] = (bytes[6] & 0x0F) | 0x40;
+    bytes[8] = (bytes[8] & 0x3F) | 0x80;
+    let mut s = String::with_capacity(36);
+    for (i, b) in bytes.iter().enumerate() {
+        if matches!(i, 4 | 6 | 8 | 10) {
+            s.push('-');
+        }
+        let _ = write!(s, "{b:02x}");
+    }
+    Ok(s)
+}
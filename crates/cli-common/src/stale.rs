@@ -0,0 +1,66 @@
+use gqlient::progress;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Number of repositories with the most stale issues to log
+const STALE_REPOS_TO_LOG: usize = 5;
+
+/// A tally of the open issues that have not been updated within a given
+/// period, as computed for `--stale-after` and recorded in run reports
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct StaleIssues {
+    /// How long an issue must have gone without updates to count as stale
+    #[serde(serialize_with = "gqlient::serialize_duration")]
+    pub stale_after: Duration,
+    /// The total number of stale issues
+    pub total: usize,
+    /// The number of stale issues in each repository that has any
+    pub repos: BTreeMap<String, usize>,
+    #[serde(skip)]
+    cutoff: SystemTime,
+}
+
+impl StaleIssues {
+    /// Start a tally of the issues last updated more than `stale_after`
+    /// before `now`
+    pub fn new(stale_after: Duration, now: SystemTime) -> StaleIssues {
+        StaleIssues {
+            stale_after,
+            total: 0,
+            repos: BTreeMap::new(),
+            cutoff: now.checked_sub(stale_after).unwrap_or(UNIX_EPOCH),
+        }
+    }
+
+    /// Check whether an issue in `repo` that was last updated at
+    /// `updated_at` (an RFC 3339 timestamp) is stale, and, if it is, count
+    /// it.  Issues whose update times are missing or cannot be parsed are
+    /// never stale.
+    pub fn check(&mut self, repo: &str, updated_at: Option<&str>) -> bool {
+        let stale = updated_at
+            .and_then(|ts| humantime::parse_rfc3339(ts).ok())
+            .is_some_and(|t| t < self.cutoff);
+        if stale {
+            self.total += 1;
+            *self.repos.entry(repo.to_owned()).or_default() += 1;
+        }
+        stale
+    }
+
+    /// Log the total number of stale issues and the repositories with the
+    /// most stale issues to stderr
+    pub fn log(&self) {
+        progress!(
+            "{} issues in {} repositories have not been updated in {}",
+            self.total,
+            self.repos.len(),
+            humantime::format_duration(self.stale_after)
+        );
+        let mut repos = self.repos.iter().collect::<Vec<_>>();
+        repos.sort_by_key(|&(_, &qty)| std::cmp::Reverse(qty));
+        for (repo, qty) in repos.into_iter().take(STALE_REPOS_TO_LOG) {
+            progress!("{repo}: {qty} stale issues");
+        }
+    }
+}
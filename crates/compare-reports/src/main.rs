@@ -0,0 +1,252 @@
+use anyhow::{bail, Context};
+use clap::Parser;
+use gqlient::{Filter, SplitMix64};
+use serde_json::Value;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+/// Statistically compare run reports from two sets of parameters
+///
+/// Reports are read from the given JSON Lines files (as written by the
+/// `--report-file` option of the other commands), and those matching the
+/// `--baseline` and `--candidate` filter expressions are compared.
+#[derive(Clone, Debug, Eq, Parser, PartialEq)]
+struct Arguments {
+    /// Filter expression selecting the baseline reports
+    #[arg(short = 'a', long, value_name = "EXPR")]
+    baseline: Filter,
+
+    /// Filter expression selecting the candidate reports
+    #[arg(short = 'b', long, value_name = "EXPR")]
+    candidate: Filter,
+
+    /// Confidence level of the reported intervals, as a percentage
+    #[arg(short, long, default_value_t = 95, value_name = "PERCENT", value_parser = clap::value_parser!(u8).range(1..100))]
+    confidence: u8,
+
+    /// Number of bootstrap resamples to use when computing confidence
+    /// intervals
+    #[arg(short = 'n', long, default_value_t = 10000, value_name = "INT", value_parser = clap::value_parser!(u32).range(1..))]
+    resamples: u32,
+
+    /// Seed for the random number generator used for resampling
+    #[arg(long, default_value_t = 0, value_name = "INT")]
+    seed: u64,
+
+    /// JSON Lines files of run reports
+    #[arg(required = true)]
+    report_files: Vec<PathBuf>,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Arguments::parse();
+    let mut baseline = Vec::new();
+    let mut candidate = Vec::new();
+    for path in &args.report_files {
+        for report in load_reports(path)? {
+            match (
+                args.baseline.matches(&report),
+                args.candidate.matches(&report),
+            ) {
+                (true, true) => bail!(
+                    "a report in {} matches both the baseline and candidate filters",
+                    path.display()
+                ),
+                (true, false) => baseline.push(report),
+                (false, true) => candidate.push(report),
+                (false, false) => (),
+            }
+        }
+    }
+    if baseline.is_empty() {
+        bail!("no reports matched the baseline filter");
+    }
+    if candidate.is_empty() {
+        bail!("no reports matched the candidate filter");
+    }
+    eprintln!(
+        "[·] Comparing {} baseline reports to {} candidate reports",
+        baseline.len(),
+        candidate.len()
+    );
+    let baseline_queries = distinct_query_hashes(&baseline);
+    let candidate_queries = distinct_query_hashes(&candidate);
+    if !baseline_queries.is_empty() && !candidate_queries.is_empty() {
+        if baseline_queries.len() == 1 && baseline_queries == candidate_queries {
+            eprintln!("[·] All reports issued structurally identical queries");
+        } else {
+            eprintln!(
+                "[·] The reports issued structurally different queries ({} distinct sets among the baseline reports, {} among the candidate reports)",
+                baseline_queries.len(),
+                candidate_queries.len()
+            );
+        }
+    }
+
+    let mut rng = SplitMix64::new(args.seed);
+    println!(
+        "{:<20}  {:>14}  {:>14}  {:>12}  {:>26}",
+        "metric",
+        "baseline mean",
+        "candidate mean",
+        "difference",
+        format!("{}% CI", args.confidence)
+    );
+    for metric in [Metric::Elapsed, Metric::RateLimitPoints] {
+        let a = baseline
+            .iter()
+            .filter_map(|r| metric.get(r))
+            .collect::<Vec<_>>();
+        let b = candidate
+            .iter()
+            .filter_map(|r| metric.get(r))
+            .collect::<Vec<_>>();
+        if a.is_empty() || b.is_empty() {
+            println!("{:<20}  (not available)", metric.name());
+            continue;
+        }
+        let (lo, hi) = bootstrap_ci(&a, &b, args.resamples, args.confidence, &mut rng);
+        println!(
+            "{:<20}  {:>14.3}  {:>14.3}  {:>+12.3}  {:>26}",
+            metric.name(),
+            mean(&a),
+            mean(&b),
+            mean(&b) - mean(&a),
+            format!("[{lo:+.3}, {hi:+.3}]")
+        );
+    }
+    Ok(())
+}
+
+fn load_reports(path: &Path) -> anyhow::Result<Vec<Value>> {
+    let fp = File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    let mut reports = Vec::new();
+    for (i, line) in BufReader::new(fp).lines().enumerate() {
+        let line = line.with_context(|| format!("failed to read {}", path.display()))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let report = serde_json::from_str::<Value>(&line).with_context(|| {
+            format!(
+                "failed to parse report on line {} of {}",
+                i + 1,
+                path.display()
+            )
+        })?;
+        reports.push(report);
+    }
+    Ok(reports)
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Metric {
+    Elapsed,
+    RateLimitPoints,
+}
+
+impl Metric {
+    fn name(self) -> &'static str {
+        match self {
+            Metric::Elapsed => "elapsed (seconds)",
+            Metric::RateLimitPoints => "rate limit points",
+        }
+    }
+
+    fn get(self, report: &Value) -> Option<f64> {
+        match self {
+            Metric::Elapsed => gqlient::duration_secs(report.get("elapsed")?),
+            Metric::RateLimitPoints => report
+                .get("rate_limit_points")?
+                .as_u64()
+                .and_then(|n| u32::try_from(n).ok())
+                .map(f64::from),
+        }
+    }
+}
+
+/// Return the distinct `query_hashes` maps of the reports in `reports` that
+/// have one
+fn distinct_query_hashes(reports: &[Value]) -> Vec<&Value> {
+    let mut distinct = Vec::new();
+    for hashes in reports.iter().filter_map(|r| r.get("query_hashes")) {
+        if !distinct.contains(&hashes) {
+            distinct.push(hashes);
+        }
+    }
+    distinct
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / (values.len() as f64)
+}
+
+/// Compute a percentile bootstrap confidence interval for the difference
+/// between the mean of `b` and the mean of `a`
+fn bootstrap_ci(
+    a: &[f64],
+    b: &[f64],
+    resamples: u32,
+    confidence: u8,
+    rng: &mut SplitMix64,
+) -> (f64, f64) {
+    let mut diffs = std::iter::repeat_with(|| mean(&resample(rng, b)) - mean(&resample(rng, a)))
+        .take(usize::try_from(resamples).unwrap_or(usize::MAX))
+        .collect::<Vec<_>>();
+    diffs.sort_by(f64::total_cmp);
+    let n = diffs.len();
+    let tail = n * usize::from(100 - confidence) / 200;
+    let lo = diffs.get(tail).copied().unwrap_or(f64::NAN);
+    let hi = diffs
+        .get(n.saturating_sub(tail + 1))
+        .copied()
+        .unwrap_or(f64::NAN);
+    (lo, hi)
+}
+
+/// Draw a sample of the same size as `values` from `values` with replacement
+fn resample(rng: &mut SplitMix64, values: &[f64]) -> Vec<f64> {
+    std::iter::repeat_with(|| values.get(rng.below(values.len())).copied())
+        .take(values.len())
+        .flatten()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resample_draws_from_values() {
+        let values = [1.0, 2.0, 3.0, 4.0, 5.0];
+        let mut rng = SplitMix64::new(42);
+        for _ in 0..100 {
+            let sample = resample(&mut rng, &values);
+            assert_eq!(sample.len(), values.len());
+            assert!(sample.iter().all(|x| values.contains(x)));
+        }
+    }
+
+    #[test]
+    fn bootstrap_ci_contains_known_difference() {
+        let a = (0..40).map(|i| f64::from(i % 10)).collect::<Vec<_>>();
+        let b = a.iter().map(|x| x + 10.0).collect::<Vec<_>>();
+        let mut rng = SplitMix64::new(0);
+        let (lo, hi) = bootstrap_ci(&a, &b, 2000, 95, &mut rng);
+        assert!(lo <= hi);
+        assert!(lo < 10.0 && 10.0 < hi, "[{lo}, {hi}] does not contain 10");
+        assert!(lo > 0.0, "[{lo}, {hi}] contains 0");
+        // The same seed gives the same interval
+        let mut rng = SplitMix64::new(0);
+        assert_eq!(bootstrap_ci(&a, &b, 2000, 95, &mut rng), (lo, hi));
+    }
+
+    #[test]
+    fn bootstrap_ci_constant_difference() {
+        let a = [3.0; 5];
+        let b = [5.5; 5];
+        let mut rng = SplitMix64::new(7);
+        assert_eq!(bootstrap_ci(&a, &b, 100, 90, &mut rng), (2.5, 2.5));
+    }
+}
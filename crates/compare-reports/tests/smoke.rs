@@ -0,0 +1,63 @@
+use serde_json::json;
+use std::fmt::Write as _;
+use std::process::Command;
+
+#[test]
+fn compare_fixed_reports() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let report_file = tmpdir.path().join("reports.jsonl");
+    let mut reports = String::new();
+    for (batch_size, seconds, points) in [
+        (50, 10.0, 20),
+        (50, 12.0, 22),
+        (50, 11.0, 21),
+        (100, 6.0, 11),
+        (100, 7.0, 12),
+        (100, 5.0, 10),
+    ] {
+        let report = json!({
+            "program": "orgs-then-issues",
+            "parameters": {"batch_size": batch_size},
+            "elapsed": {"human": format!("{seconds}s"), "seconds": seconds},
+            "rate_limit_points": points,
+        });
+        writeln!(reports, "{report}").unwrap();
+    }
+    std::fs::write(&report_file, reports).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_compare-reports"))
+        .arg("--baseline")
+        .arg("parameters.batch_size == 50")
+        .arg("--candidate")
+        .arg("parameters.batch_size == 100")
+        .arg("--resamples")
+        .arg("1000")
+        .arg(&report_file)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Comparing 3 baseline reports to 3 candidate reports"),
+        "{stderr}"
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines = stdout.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 3, "{stdout}");
+    assert!(lines[0].starts_with("metric"), "{stdout}");
+    let elapsed = lines[1].split_whitespace().collect::<Vec<_>>();
+    assert_eq!(
+        elapsed[..5],
+        ["elapsed", "(seconds)", "11.000", "6.000", "-5.000"],
+        "{stdout}"
+    );
+    let points = lines[2].split_whitespace().collect::<Vec<_>>();
+    assert_eq!(
+        points[..6],
+        ["rate", "limit", "points", "21.000", "11.000", "-10.000"],
+        "{stdout}"
+    );
+}
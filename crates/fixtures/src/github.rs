@@ -0,0 +1,280 @@
+use crate::selection::{parse_operation, Selection};
+use crate::{paginate, FakeOrg, FakeRepo};
+use serde_json::{json, Map, Value};
+
+/// A fake GitHub instance whose owners are a set of [`FakeOrg`]s, able to
+/// answer the GraphQL requests made by the programs
+///
+/// Rather than implementing GraphQL's type system, each top-level field of a
+/// query is answered based on its name, the values of the variables passed as
+/// its arguments, and the values of the variables passed as `first` and
+/// `after` to the first paginated connection in it:
+///
+/// - `repositoryOwner` returns an owner and a page of its repositories
+/// - `node` returns a repository and a page of its issues, or a single issue
+/// - `repository` returns a repository, which has a label of every name
+/// - `search` returns a page of the issues in the repository given by a
+///   `repo:` qualifier, restricted to those created in the range given by a
+///   `created:` qualifier
+/// - `rateLimit` returns a cost of one point
+/// - mutations succeed without changing anything
+///
+/// Answers are built with every field that any program asks for in that
+/// place and then reduced to the fields that the query actually selects
+/// there, taking aliases, inline fragments, and `@include(if: $var)`
+/// directives into account.  Selected fields that an answer lacks are
+/// returned as `null`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FakeGitHub {
+    orgs: Vec<FakeOrg>,
+}
+
+impl FakeGitHub {
+    pub fn new<I: IntoIterator<Item = FakeOrg>>(orgs: I) -> FakeGitHub {
+        FakeGitHub {
+            orgs: orgs.into_iter().collect(),
+        }
+    }
+
+    /// Return the response body for a GraphQL request with the given payload
+    /// (an object with `query` and `variables` fields)
+    pub fn respond(&self, payload: &Value) -> Value {
+        let query = payload["query"].as_str().unwrap_or_default();
+        let variables = &payload["variables"];
+        let Some(operation) = parse_operation(query) else {
+            return json!({"errors": [{"message": "Failed to parse query document"}]});
+        };
+        let mutation = query.trim_start().starts_with("mutation");
+        let mut data = Map::new();
+        for field in &operation.selections {
+            let value = if mutation {
+                json!({"clientMutationId": null})
+            } else {
+                self.resolve(field, variables)
+            };
+            data.insert(field.key().to_owned(), field.project(&value, variables));
+        }
+        json!({"data": data})
+    }
+
+    fn resolve(&self, field: &Selection, variables: &Value) -> Value {
+        let arg = |name: &str| field.argument(name, variables).and_then(Value::as_str);
+        let connection = field.connection();
+        let page_size = connection
+            .and_then(|conn| conn.argument("first", variables))
+            .and_then(Value::as_u64)
+            .and_then(|n| usize::try_from(n).ok())
+            .unwrap_or(100);
+        let after = connection
+            .and_then(|conn| conn.argument("after", variables))
+            .and_then(Value::as_str);
+        match field.name.as_str() {
+            "rateLimit" => json!({"cost": 1}),
+            "repositoryOwner" => match arg("login").and_then(|login| self.org(login)) {
+                Some(org) => json!({
+                    "__typename": "Organization",
+                    "login": org.login(),
+                    "repositories": org.repos_page(page_size, after),
+                }),
+                None => Value::Null,
+            },
+            "node" => {
+                let id = arg("id").unwrap_or_default();
+                if let Some(repo) = self.repos().find(|repo| repo.id() == id) {
+                    json!({
+                        "__typename": "Repository",
+                        "nameWithOwner": repo.name_with_owner(),
+                        "issues": repo.issues_page(page_size, after),
+                        // The fake repositories have no pull requests.
+                        "pullRequests": paginate(0, page_size, after, |_| Value::Null),
+                    })
+                } else {
+                    self.repos()
+                        .find_map(|repo| repo.issue_by_id(id))
+                        .unwrap_or(Value::Null)
+                }
+            }
+            "repository" => {
+                let fullname = format!(
+                    "{}/{}",
+                    arg("owner").unwrap_or_default(),
+                    arg("name").unwrap_or_default()
+                );
+                match self
+                    .repos()
+                    .find(|repo| repo.name_with_owner().eq_ignore_ascii_case(&fullname))
+                {
+                    Some(repo) => {
+                        let label = field
+                            .child("label")
+                            .and_then(|label| label.argument("name", variables))
+                            .and_then(Value::as_str)
+                            .unwrap_or_default();
+                        json!({
+                            "id": repo.id(),
+                            "nameWithOwner": repo.name_with_owner(),
+                            "label": {
+                                "id": format!("LA_{}_{label}", repo.id()),
+                            },
+                        })
+                    }
+                    None => Value::Null,
+                }
+            }
+            "search" => self.search(arg("query").unwrap_or_default(), page_size, after),
+            _ => Value::Null,
+        }
+    }
+
+    fn org(&self, login: &str) -> Option<&FakeOrg> {
+        self.orgs
+            .iter()
+            .find(|org| org.login().eq_ignore_ascii_case(login))
+    }
+
+    fn repos(&self) -> impl Iterator<Item = &FakeRepo> {
+        self.orgs.iter().flat_map(FakeOrg::repos)
+    }
+
+    fn search(&self, query: &str, page_size: usize, after: Option<&str>) -> Value {
+        let mut repo = None;
+        let mut created = None;
+        for term in query.split_whitespace() {
+            if let Some(fullname) = term.strip_prefix("repo:") {
+                repo = self
+                    .repos()
+                    .find(|r| r.name_with_owner().eq_ignore_ascii_case(fullname));
+            } else if let Some(range) = term.strip_prefix("created:") {
+                created = range.split_once("..");
+            }
+        }
+        let issues = repo.map_or_else(Vec::new, |repo| {
+            (1..=repo.issues())
+                .map(|number| repo.issue(number))
+                .filter(|issue| {
+                    // Timestamps in the same RFC 3339 format compare the same
+                    // as the times they represent.
+                    created.map_or(true, |(start, end)| {
+                        issue["createdAt"]
+                            .as_str()
+                            .is_some_and(|ts| start <= ts && ts <= end)
+                    })
+                })
+                .collect::<Vec<_>>()
+        });
+        let mut page = paginate(issues.len(), page_size, after, |i| issues[i].clone());
+        page["issueCount"] = issues.len().into();
+        page
+    }
+}
+
+impl FakeRepo {
+    /// Return the issue node for the issue with the given node ID, if it is
+    /// one of the repository's
+    fn issue_by_id(&self, id: &str) -> Option<Value> {
+        id.strip_prefix(&format!("I_{}_{:04}_", self.owner, self.index))
+            .and_then(|n| n.parse::<usize>().ok())
+            .filter(|&n| (1..=self.issues).contains(&n))
+            .map(|n| self.issue(n))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn owner_repos() {
+        let github = FakeGitHub::new([FakeOrg::new("fake-org", 3, 2, 1)]);
+        let query = concat!(
+            "query ($q0_owner: String!, $cursor: String, $page_size: Int!, $ids: Boolean!) {\n",
+            "    q0: repositoryOwner(login: $q0_owner) {\n",
+            "        repositories(first: $page_size, after: $cursor) {\n",
+            "            nodes {\n",
+            "                id\n",
+            "                databaseId @include(if: $ids)\n",
+            "                issues(states: [OPEN]) {\n",
+            "                    totalCount\n",
+            "                }\n",
+            "            }\n",
+            "            pageInfo {\n",
+            "                endCursor\n",
+            "                hasNextPage\n",
+            "            }\n",
+            "        }\n",
+            "    }\n",
+            "\n",
+            "    rateLimit {\n",
+            "        cost\n",
+            "    }\n",
+            "}\n",
+        );
+        let payload = json!({
+            "query": query,
+            "variables": {
+                "q0_owner": "Fake-Org",
+                "cursor": "cursor:1",
+                "page_size": 1,
+                "ids": false,
+            },
+        });
+        assert_eq!(
+            github.respond(&payload),
+            json!({
+                "data": {
+                    "q0": {
+                        "repositories": {
+                            "nodes": [{
+                                "id": "R_fake-org_0001",
+                                "issues": {"totalCount": 2},
+                            }],
+                            "pageInfo": {
+                                "endCursor": "cursor:2",
+                                "hasNextPage": true,
+                            },
+                        },
+                    },
+                    "rateLimit": {"cost": 1},
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn search_created_range() {
+        let github = FakeGitHub::new([FakeOrg::new("fake-org", 1, 5, 0)]);
+        let payload = json!({
+            "query": concat!(
+                "query ($q0_query: String!, $q0_page_size: Int!, $q0_cursor: String) {\n",
+                "    q0: search(\n",
+                "        query: $q0_query,\n",
+                "        type: ISSUE,\n",
+                "        first: $q0_page_size,\n",
+                "        after: $q0_cursor,\n",
+                "    ) {\n",
+                "        issueCount\n",
+                "        nodes {\n",
+                "            number\n",
+                "        }\n",
+                "    }\n",
+                "}\n",
+            ),
+            "variables": {
+                "q0_query": "repo:fake-org/repo-0000 is:issue created:2024-01-03T00:00:00Z..2024-01-05T00:00:00Z",
+                "q0_page_size": 100,
+                "q0_cursor": null,
+            },
+        });
+        assert_eq!(
+            github.respond(&payload),
+            json!({
+                "data": {
+                    "q0": {
+                        "issueCount": 2,
+                        "nodes": [{"number": 2}, {"number": 3}],
+                    },
+                },
+            })
+        );
+    }
+}
@@ -0,0 +1,258 @@
+//! Builders of fake GitHub GraphQL response data for use in tests
+//!
+//! A [`FakeOrg`] is an owner with a given number of repositories, each of
+//! which has a given number of open issues, each of which has a given number
+//! of labels.  Everything about the fake data is derived from the positions
+//! of the repositories, issues, and labels, so the same parameters always
+//! produce the same responses, and tests can use as large an organization as
+//! they need without any JSON being written by hand.
+//!
+//! Connections are paginated with cursors of the form `cursor:<offset>`, where
+//! `<offset>` is the number of nodes that come before the next page.  The
+//! nodes include every field requested by the issue queries of the various
+//! programs, and the programs' types ignore the fields that they do not use.
+//!
+//! For running the programs themselves against fake data, a [`FakeGitHub`]
+//! answers their GraphQL requests from a set of `FakeOrg`s, and a
+//! [`FixtureServer`] serves such answers over HTTP on a local port.
+mod github;
+mod selection;
+mod server;
+pub use crate::github::FakeGitHub;
+pub use crate::server::FixtureServer;
+use serde_json::{json, Value};
+
+/// A fake owner of repositories
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FakeOrg {
+    login: String,
+    repos: Vec<FakeRepo>,
+}
+
+impl FakeOrg {
+    /// Create an owner named `login` with `repos` repositories, each with
+    /// `issues` open issues, each with `labels` labels
+    pub fn new(login: &str, repos: usize, issues: usize, labels: usize) -> FakeOrg {
+        FakeOrg {
+            login: login.to_owned(),
+            repos: (0..repos)
+                .map(|index| FakeRepo {
+                    owner: login.to_owned(),
+                    index,
+                    issues,
+                    labels,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn login(&self) -> &str {
+        &self.login
+    }
+
+    pub fn repos(&self) -> &[FakeRepo] {
+        &self.repos
+    }
+
+    /// Return the total number of open issues across all repositories
+    pub fn total_issues(&self) -> usize {
+        self.repos.iter().map(|repo| repo.issues).sum()
+    }
+
+    /// Return the page of at most `page_size` of the owner's repositories
+    /// that follows `after` (or the first page if `after` is `None`) as a
+    /// connection of [`FakeRepo::node()`]s
+    ///
+    /// # Panics
+    ///
+    /// Panics if `after` is not a cursor produced by this crate.
+    pub fn repos_page(&self, page_size: usize, after: Option<&str>) -> Value {
+        paginate(self.repos.len(), page_size, after, |i| {
+            self.repos[i].node(page_size)
+        })
+    }
+}
+
+/// A fake repository belonging to a [`FakeOrg`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FakeRepo {
+    owner: String,
+    index: usize,
+    issues: usize,
+    labels: usize,
+}
+
+impl FakeRepo {
+    pub fn name(&self) -> String {
+        format!("repo-{:04}", self.index)
+    }
+
+    pub fn name_with_owner(&self) -> String {
+        format!("{}/{}", self.owner, self.name())
+    }
+
+    /// Return the repository's GraphQL node ID
+    pub fn id(&self) -> String {
+        format!("R_{}_{:04}", self.owner, self.index)
+    }
+
+    /// Return the number of open issues in the repository
+    pub fn issues(&self) -> usize {
+        self.issues
+    }
+
+    /// Return the repository's node with every field requested by the
+    /// repository queries of the various programs, including the first page
+    /// of at most `page_size` of its issues
+    pub fn node(&self, page_size: usize) -> Value {
+        let first_issues = self.issues_page(page_size, None);
+        json!({
+            "__typename": "Repository",
+            "id": self.id(),
+            "databaseId": self.index + 1,
+            "name": self.name(),
+            "owner": {
+                "login": self.owner,
+            },
+            "nameWithOwner": self.name_with_owner(),
+            "visibility": "PUBLIC",
+            "isTemplate": false,
+            "viewerPermission": "READ",
+            "issues": first_issues,
+            "firstIssues": first_issues,
+            "latestIssue": {
+                "nodes": [],
+            },
+            "openPullRequests": {
+                "totalCount": 0,
+            },
+            "stargazerCount": 0,
+            "forkCount": 0,
+            "pushedAt": "2024-01-01T00:00:00Z",
+            "updatedAt": "2024-01-01T00:00:00Z",
+            "defaultBranchRef": {
+                "name": "main",
+            },
+            "latestRelease": null,
+            "pinnedIssues": {
+                "nodes": [],
+            },
+            "repositoryTopics": {
+                "nodes": [],
+            },
+            "mentionableUsers": {
+                "totalCount": 0,
+            },
+            "discussionCategories": {
+                "nodes": [],
+            },
+            "issueTemplateDir": null,
+            "issueTemplateGithub": null,
+            "issueTemplateRoot": null,
+            "contributingRoot": null,
+            "contributingGithub": null,
+            "contributingDocs": null,
+        })
+    }
+
+    /// Return the issue node for the issue with the given 1-based number
+    pub fn issue(&self, number: usize) -> Value {
+        let labels = (0..self.labels)
+            .map(|i| {
+                json!({
+                    "id": format!("LA_{}_{:04}_{i:03}", self.owner, self.index),
+                    "name": format!("label-{i:03}"),
+                })
+            })
+            .collect::<Vec<_>>();
+        let timestamp = format!(
+            "2024-01-{:02}T{:02}:{:02}:00Z",
+            number % 28 + 1,
+            number / 60 % 24,
+            number % 60
+        );
+        json!({
+            "id": format!("I_{}_{:04}_{number}", self.owner, self.index),
+            "number": number,
+            "databaseId": self.index * 1_000_000 + number,
+            "title": format!("Issue {number} of {}", self.name_with_owner()),
+            "author": {
+                "login": format!("user-{}", number % 10),
+                "__typename": "User",
+            },
+            "state": "OPEN",
+            "stateReason": null,
+            "repository": {
+                "nameWithOwner": self.name_with_owner(),
+            },
+            "url": format!("https://github.com/{}/issues/{number}", self.name_with_owner()),
+            "createdAt": timestamp,
+            "updatedAt": timestamp,
+            "closedAt": null,
+            "labels": {
+                "nodes": labels,
+            },
+            "closedByPullRequestsReferences": {
+                "totalCount": 0,
+            },
+            "parent": null,
+            "subIssuesSummary": {
+                "total": 0,
+                "completed": 0,
+            },
+        })
+    }
+
+    /// Return the page of at most `page_size` of the repository's issues
+    /// that follows `after` (or the first page if `after` is `None`) as a
+    /// connection with `totalCount`, `nodes`, and `pageInfo` fields
+    ///
+    /// # Panics
+    ///
+    /// Panics if `after` is not a cursor produced by this crate.
+    pub fn issues_page(&self, page_size: usize, after: Option<&str>) -> Value {
+        paginate(self.issues, page_size, after, |i| self.issue(i + 1))
+    }
+
+    /// Return the data for a query for a page of the repository's issues
+    /// via its `node`: an object with the repository's `nameWithOwner` and
+    /// the page as `issues`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `after` is not a cursor produced by this crate.
+    pub fn with_issues_page(&self, page_size: usize, after: Option<&str>) -> Value {
+        json!({
+            "nameWithOwner": self.name_with_owner(),
+            "issues": self.issues_page(page_size, after),
+        })
+    }
+}
+
+/// Return the cursor that comes after the first `offset` nodes of a
+/// connection
+pub fn cursor(offset: usize) -> String {
+    format!("cursor:{offset}")
+}
+
+fn paginate<F>(total: usize, page_size: usize, after: Option<&str>, node: F) -> Value
+where
+    F: FnMut(usize) -> Value,
+{
+    let start = after.map_or(0, |c| {
+        c.strip_prefix("cursor:")
+            .and_then(|s| s.parse::<usize>().ok())
+            .unwrap_or_else(|| panic!("invalid fake cursor: {c:?}"))
+    });
+    let start = start.min(total);
+    let end = start.saturating_add(page_size).min(total);
+    json!({
+        "totalCount": total,
+        "nodes": (start..end).map(node).collect::<Vec<_>>(),
+        "pageInfo": {
+            // As on GitHub, a page with no nodes has a null end cursor.
+            "endCursor": (end > start).then(|| cursor(end)),
+            "hasNextPage": end < total,
+        },
+    })
+}
@@ -0,0 +1,345 @@
+//! Just enough of a GraphQL parser to tell which fields a query selects
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// A field (or inline fragment) selected in a GraphQL query
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub(crate) struct Selection {
+    pub(crate) alias: Option<String>,
+    pub(crate) name: String,
+    /// Map from the names of the field's arguments that are passed variables
+    /// to the names of those variables
+    pub(crate) arguments: HashMap<String, String>,
+    /// The variable of the field's `@include(if: $var)` directive, if any
+    include_if: Option<String>,
+    /// Whether this is an inline fragment (`... on Type { ... }`), whose
+    /// selections are merged into those of the enclosing field
+    fragment: bool,
+    pub(crate) selections: Vec<Selection>,
+}
+
+impl Selection {
+    /// Return the key under which the field appears in the response
+    pub(crate) fn key(&self) -> &str {
+        self.alias.as_deref().unwrap_or(&self.name)
+    }
+
+    /// Return the value of the variable passed to the field's argument
+    /// `name`, if any
+    pub(crate) fn argument<'a>(&self, name: &str, variables: &'a Value) -> Option<&'a Value> {
+        self.arguments.get(name).and_then(|var| variables.get(var))
+    }
+
+    /// Return the first field in this field's subtree (including itself)
+    /// that takes an `after` argument, i.e., the connection that the field
+    /// paginates over
+    pub(crate) fn connection(&self) -> Option<&Selection> {
+        if self.arguments.contains_key("after") {
+            Some(self)
+        } else {
+            self.selections.iter().find_map(Selection::connection)
+        }
+    }
+
+    /// Return the child field with the given name, if any
+    pub(crate) fn child(&self, name: &str) -> Option<&Selection> {
+        self.selections.iter().find_map(|sel| {
+            if sel.fragment {
+                sel.child(name)
+            } else {
+                (sel.name == name).then_some(sel)
+            }
+        })
+    }
+
+    fn included(&self, variables: &Value) -> bool {
+        self.include_if.as_ref().map_or(true, |var| {
+            variables.get(var).and_then(Value::as_bool).unwrap_or(false)
+        })
+    }
+
+    /// Return the part of `value` selected by the field's selections, as
+    /// GraphQL would return it.  Fields that are not in `value` (under either
+    /// their alias or their name) are returned as `null`.
+    pub(crate) fn project(&self, value: &Value, variables: &Value) -> Value {
+        match value {
+            Value::Object(map) => {
+                let mut out = Map::new();
+                self.project_into(map, variables, &mut out);
+                Value::Object(out)
+            }
+            Value::Array(values) => {
+                Value::Array(values.iter().map(|v| self.project(v, variables)).collect())
+            }
+            other => other.clone(),
+        }
+    }
+
+    fn project_into(
+        &self,
+        map: &Map<String, Value>,
+        variables: &Value,
+        out: &mut Map<String, Value>,
+    ) {
+        for sel in &self.selections {
+            if !sel.included(variables) {
+                continue;
+            }
+            if sel.fragment {
+                sel.project_into(map, variables, out);
+                continue;
+            }
+            let source = map
+                .get(sel.key())
+                .or_else(|| map.get(&sel.name))
+                .unwrap_or(&Value::Null);
+            let value = if sel.selections.is_empty() {
+                source.clone()
+            } else {
+                sel.project(source, variables)
+            };
+            out.insert(sel.key().to_owned(), value);
+        }
+    }
+}
+
+/// Parse a GraphQL document consisting of a single query or mutation and
+/// return a pseudo-field whose selections are the operation's top-level
+/// fields.  Returns `None` if the document cannot be parsed.
+pub(crate) fn parse_operation(document: &str) -> Option<Selection> {
+    let mut tokens = Tokens::new(document);
+    // Skip the operation type and variable definitions.
+    while tokens.peek()? != Token::Punct('{') {
+        tokens.next();
+    }
+    Some(Selection {
+        selections: parse_selection_set(&mut tokens)?,
+        ..Selection::default()
+    })
+}
+
+fn parse_selection_set(tokens: &mut Tokens<'_>) -> Option<Vec<Selection>> {
+    tokens.expect(Token::Punct('{'))?;
+    let mut selections = Vec::new();
+    while tokens.peek()? != Token::Punct('}') {
+        selections.push(parse_selection(tokens)?);
+    }
+    tokens.next();
+    Some(selections)
+}
+
+fn parse_selection(tokens: &mut Tokens<'_>) -> Option<Selection> {
+    let mut sel = Selection::default();
+    if tokens.peek()? == Token::Spread {
+        tokens.next();
+        tokens.expect(Token::Name(String::from("on")))?;
+        tokens.name()?;
+        sel.fragment = true;
+        sel.selections = parse_selection_set(tokens)?;
+        return Some(sel);
+    }
+    sel.name = tokens.name()?;
+    if tokens.peek() == Some(Token::Punct(':')) {
+        tokens.next();
+        sel.alias = Some(std::mem::replace(&mut sel.name, tokens.name()?));
+    }
+    if tokens.peek() == Some(Token::Punct('(')) {
+        sel.arguments = parse_arguments(tokens)?;
+    }
+    while tokens.peek() == Some(Token::Punct('@')) {
+        tokens.next();
+        let directive = tokens.name()?;
+        let arguments = parse_arguments(tokens)?;
+        if directive == "include" {
+            sel.include_if = arguments.get("if").cloned();
+        }
+    }
+    if tokens.peek() == Some(Token::Punct('{')) {
+        sel.selections = parse_selection_set(tokens)?;
+    }
+    Some(sel)
+}
+
+/// Parse a parenthesized list of arguments and return a map from the names
+/// of those that are passed variables to the names of the variables.
+/// Arguments nested inside input objects are ignored.
+fn parse_arguments(tokens: &mut Tokens<'_>) -> Option<HashMap<String, String>> {
+    tokens.expect(Token::Punct('('))?;
+    let mut arguments = HashMap::new();
+    let mut depth = 0usize;
+    let mut last_name = None;
+    loop {
+        match tokens.next()? {
+            Token::Punct('(' | '{' | '[') => depth += 1,
+            Token::Punct(')') if depth == 0 => return Some(arguments),
+            Token::Punct(')' | '}' | ']') => depth = depth.checked_sub(1)?,
+            Token::Punct('$') => {
+                let var = tokens.name()?;
+                if depth == 0 {
+                    if let Some(name) = last_name.take() {
+                        arguments.insert(name, var);
+                    }
+                }
+            }
+            Token::Name(name) if tokens.peek() == Some(Token::Punct(':')) => {
+                tokens.next();
+                last_name = Some(name);
+            }
+            _ => (),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Token {
+    Name(String),
+    Punct(char),
+    Spread,
+    /// A string or number literal
+    Literal,
+}
+
+/// An iterator over the tokens of a GraphQL document, skipping whitespace
+/// and commas (which are insignificant in GraphQL)
+#[derive(Clone, Debug)]
+struct Tokens<'a> {
+    chars: Peekable<Chars<'a>>,
+    peeked: Option<Option<Token>>,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(s: &'a str) -> Tokens<'a> {
+        Tokens {
+            chars: s.chars().peekable(),
+            peeked: None,
+        }
+    }
+
+    fn peek(&mut self) -> Option<Token> {
+        if self.peeked.is_none() {
+            self.peeked = Some(self.lex());
+        }
+        self.peeked.clone().flatten()
+    }
+
+    fn expect(&mut self, token: Token) -> Option<()> {
+        (self.next()? == token).then_some(())
+    }
+
+    fn name(&mut self) -> Option<String> {
+        match self.next()? {
+            Token::Name(name) => Some(name),
+            _ => None,
+        }
+    }
+
+    fn lex(&mut self) -> Option<Token> {
+        while self
+            .chars
+            .next_if(|&c| c.is_whitespace() || c == ',')
+            .is_some()
+        {}
+        let c = self.chars.next()?;
+        if c.is_ascii_alphabetic() || c == '_' {
+            let mut name = String::from(c);
+            while let Some(c) = self
+                .chars
+                .next_if(|&c| c.is_ascii_alphanumeric() || c == '_')
+            {
+                name.push(c);
+            }
+            Some(Token::Name(name))
+        } else if c.is_ascii_digit() || c == '-' {
+            while self
+                .chars
+                .next_if(|&c| c.is_ascii_alphanumeric() || c == '.')
+                .is_some()
+            {}
+            Some(Token::Literal)
+        } else if c == '"' {
+            while let Some(c) = self.chars.next() {
+                match c {
+                    '\\' => {
+                        self.chars.next();
+                    }
+                    '"' => break,
+                    _ => (),
+                }
+            }
+            Some(Token::Literal)
+        } else if c == '.' {
+            self.chars.next_if_eq(&'.');
+            self.chars.next_if_eq(&'.');
+            Some(Token::Spread)
+        } else {
+            Some(Token::Punct(c))
+        }
+    }
+}
+
+impl Iterator for Tokens<'_> {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Token> {
+        match self.peeked.take() {
+            Some(token) => token,
+            None => self.lex(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn project_fragments_and_aliases() {
+        let operation = parse_operation(concat!(
+            "query ($id: ID!, $labels: Boolean!) {\n",
+            "    q0: node(id: $id) {\n",
+            "        ... on Issue {\n",
+            "            number\n",
+            "            author { login }\n",
+            "            labels(first: 10) @include(if: $labels) { nodes { name } }\n",
+            "        }\n",
+            "        issueTitle: title\n",
+            "    }\n",
+            "}\n",
+        ))
+        .unwrap();
+        let field = &operation.selections[0];
+        assert_eq!(field.key(), "q0");
+        assert_eq!(field.name, "node");
+        assert_eq!(
+            field.argument("id", &json!({"id": "I_1"})),
+            Some(&json!("I_1"))
+        );
+        let issue = json!({
+            "__typename": "Issue",
+            "number": 1,
+            "title": "An issue",
+            "author": {"login": "user-1", "__typename": "User"},
+            "labels": {"nodes": [{"id": "LA_1", "name": "bug"}]},
+        });
+        assert_eq!(
+            field.project(&issue, &json!({"labels": false})),
+            json!({
+                "number": 1,
+                "author": {"login": "user-1"},
+                "issueTitle": "An issue",
+            })
+        );
+        assert_eq!(
+            field.project(&issue, &json!({"labels": true})),
+            json!({
+                "number": 1,
+                "author": {"login": "user-1"},
+                "labels": {"nodes": [{"name": "bug"}]},
+                "issueTitle": "An issue",
+            })
+        );
+    }
+}
@@ -0,0 +1,139 @@
+use serde_json::{json, Value};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// The rate limit reset time reported by a [`FixtureServer`], 2100-01-01
+const RATE_LIMIT_RESET: u64 = 4_102_444_800;
+
+/// An HTTP server on a local port standing in for the GitHub API, so that
+/// the programs can be run against fake data by pointing them at
+/// [`FixtureServer::api_url()`]
+///
+/// GraphQL requests (`POST /graphql`) are answered with the response body
+/// returned by a callback for each request's payload, such as
+/// [`FakeGitHub::respond()`](crate::FakeGitHub::respond); REST requests for
+/// `/rate_limit` are answered with an unused rate limit, and all other
+/// requests with a 404.  Every response carries the same `x-ratelimit-*`
+/// headers.
+///
+/// The server handles one connection at a time on a background thread that
+/// runs for the rest of the process's life.
+#[derive(Debug)]
+pub struct FixtureServer {
+    api_url: String,
+    requests: Arc<Mutex<Vec<Value>>>,
+}
+
+impl FixtureServer {
+    /// Start a server that answers GraphQL requests with `respond`
+    ///
+    /// # Panics
+    ///
+    /// Panics if a local port cannot be bound.
+    pub fn start<F>(respond: F) -> FixtureServer
+    where
+        F: Fn(&Value) -> Value + Send + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind fixture server");
+        let addr = listener
+            .local_addr()
+            .expect("failed to get fixture server address");
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let received = Arc::clone(&requests);
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                // A failed connection only fails the request made on it.
+                let _ = serve(stream, &respond, &received);
+            }
+        });
+        FixtureServer {
+            api_url: format!("http://{addr}"),
+            requests,
+        }
+    }
+
+    /// Return the base URL of the server's API, for passing to `--api-url`
+    pub fn api_url(&self) -> &str {
+        &self.api_url
+    }
+
+    /// Return the payloads of the GraphQL requests received so far
+    pub fn requests(&self) -> Vec<Value> {
+        self.requests
+            .lock()
+            .map(|requests| requests.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// Read a single request from `stream` and answer it, closing the
+/// connection afterwards
+fn serve<F>(mut stream: TcpStream, respond: &F, requests: &Mutex<Vec<Value>>) -> io::Result<()>
+where
+    F: Fn(&Value) -> Value,
+{
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut content_length = 0;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            break;
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+    let mut parts = request_line.split_whitespace();
+    let (status, response) = match (parts.next(), parts.next()) {
+        (Some("POST"), Some(path)) if path.ends_with("/graphql") => {
+            let payload = serde_json::from_slice::<Value>(&body).unwrap_or_default();
+            let response = respond(&payload);
+            if let Ok(mut requests) = requests.lock() {
+                requests.push(payload);
+            }
+            ("200 OK", response)
+        }
+        (Some("GET"), Some(path)) if path.ends_with("/rate_limit") => (
+            "200 OK",
+            json!({
+                "resources": {
+                    "graphql": {
+                        "limit": 5000,
+                        "remaining": 5000,
+                        "used": 0,
+                        "reset": RATE_LIMIT_RESET,
+                    },
+                },
+            }),
+        ),
+        _ => ("404 Not Found", json!({"message": "Not Found"})),
+    };
+    let body = response.to_string();
+    let length = body.len();
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\n\
+         Content-Type: application/json\r\n\
+         Content-Length: {length}\r\n\
+         Connection: close\r\n\
+         x-ratelimit-limit: 5000\r\n\
+         x-ratelimit-remaining: 5000\r\n\
+         x-ratelimit-used: 0\r\n\
+         x-ratelimit-reset: {RATE_LIMIT_RESET}\r\n\
+         \r\n\
+         {body}"
+    )?;
+    stream.flush()
+}
@@ -0,0 +1,160 @@
+//! Drive a [`BatchPaginator`] by hand, one request at a time, instead of
+//! letting [`Client::batch_paginate()`] run it to completion.
+//!
+//! The paginator fetches the names of the repositories belonging to each
+//! given user or organization.  The repositories of each owner are printed
+//! as soon as they have all been fetched by flushing the paginator's
+//! finished results with [`BatchPaginator::flush()`] after every request.
+//! After `--max-requests` requests, the paginator is broken up with
+//! [`BatchPaginator::into_parts()`] and the owners whose repositories have not
+//! all been fetched yet are listed along with the cursors from which they
+//! could be resumed by passing the parts to [`BatchPaginator::from_parts()`].
+//!
+//! Usage: `cargo run -p gqlient --example manual_steps -- [--max-requests <INT>] <owner> ...`
+use anyhow::{bail, Context};
+use gqlient::{BatchPaginator, Client, Cursor, Page, Paginator, Query, Singleton, Step, Variable};
+use std::fmt::{self, Write};
+use std::time::SystemTime;
+
+/// Fetches the names of the repositories belonging to a user or organization
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct OwnerRepos {
+    owner: String,
+}
+
+impl Paginator for OwnerRepos {
+    type Item = String;
+    type Query = OwnerReposQuery;
+
+    fn for_cursor(&self, cursor: Option<&Cursor>) -> OwnerReposQuery {
+        OwnerReposQuery {
+            owner: self.owner.clone(),
+            cursor: cursor.cloned(),
+            prefix: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct OwnerReposQuery {
+    owner: String,
+    cursor: Option<Cursor>,
+    prefix: Option<String>,
+}
+
+impl OwnerReposQuery {
+    fn varname(&self, field: &str) -> String {
+        match self.prefix {
+            Some(ref prefix) => format!("{prefix}_{field}"),
+            None => field.to_owned(),
+        }
+    }
+}
+
+impl Query for OwnerReposQuery {
+    type Output = Page<String>;
+
+    fn with_variable_prefix(mut self, prefix: String) -> Self {
+        self.prefix = Some(prefix);
+        self
+    }
+
+    fn write_graphql<W: Write>(&self, mut s: W) -> fmt::Result {
+        writeln!(s, "repositoryOwner(login: ${}) {{", self.varname("owner"))?;
+        writeln!(
+            s,
+            "    repositories(first: 100, after: ${}, orderBy: {{field: NAME, direction: ASC}}) {{",
+            self.varname("cursor")
+        )?;
+        writeln!(s, "        nodes {{ nameWithOwner }}")?;
+        writeln!(s, "        pageInfo {{ endCursor hasNextPage }}")?;
+        writeln!(s, "    }}")?;
+        writeln!(s, "}}")
+    }
+
+    fn variables(&self) -> [(String, Variable); 2] {
+        [
+            (
+                self.varname("owner"),
+                Variable {
+                    gql_type: String::from("String!"),
+                    value: self.owner.clone().into(),
+                },
+            ),
+            (
+                self.varname("cursor"),
+                Variable {
+                    gql_type: String::from("String"),
+                    value: self.cursor.clone().into(),
+                },
+            ),
+        ]
+    }
+
+    fn parse_response(&self, value: serde_json::Value) -> Result<Page<String>, serde_json::Error> {
+        let Singleton(page) = gqlient::from_value::<Singleton<Page<Singleton<String>>>>(value)?;
+        Ok(page.map_items(|Singleton(name)| name))
+    }
+}
+
+fn main() -> anyhow::Result<()> {
+    let mut max_requests = usize::MAX;
+    let mut owners = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--max-requests" {
+            max_requests = args
+                .next()
+                .context("--max-requests requires an argument")?
+                .parse()
+                .context("invalid --max-requests value")?;
+        } else {
+            owners.push(arg);
+        }
+    }
+    if owners.is_empty() {
+        bail!("usage: manual_steps [--max-requests <INT>] <owner> ...");
+    }
+    let client = Client::new_with_local_token()?;
+    let mut paginator = BatchPaginator::new(
+        &client,
+        owners
+            .into_iter()
+            .map(|owner| (owner.clone(), OwnerRepos { owner })),
+    );
+    let mut requests = 0;
+    while requests < max_requests {
+        match paginator.step()? {
+            Step::Fetched => {
+                requests += 1;
+                eprintln!("[·] Made request #{requests}");
+                for pr in paginator.flush() {
+                    println!("{}: {} repositories", pr.key, pr.items.len());
+                    for name in pr.items {
+                        println!("  {name}");
+                    }
+                }
+            }
+            Step::Paused { resume_at } => {
+                let wait = resume_at
+                    .duration_since(SystemTime::now())
+                    .unwrap_or_default();
+                eprintln!("[·] Rate limit is low; sleeping for {wait:?}");
+                std::thread::sleep(wait);
+            }
+            Step::Done => break,
+        }
+    }
+    let parts = paginator.into_parts();
+    for pending in parts.pending {
+        match pending.cursor {
+            Some(cursor) => println!(
+                "{}: {} repositories so far; resume after cursor {cursor}",
+                pending.key,
+                pending.items.len()
+            ),
+            None => println!("{}: not started", pending.key),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,179 @@
+//! Run paginators against a mock [`Transport`] that serves made-up responses
+//! instead of talking to GitHub.
+//!
+//! The paginator fetches the names of the repositories belonging to each
+//! given user or organization, two per page.  The client is built with
+//! [`ClientBuilder::transport()`], and its requests are answered by a
+//! transport that reads the variables of each GraphQL request and makes up a
+//! page of repositories (`{owner}/repo0`, `{owner}/repo1`, etc.) for each of
+//! the request's sub-queries.  Each owner is given as many repositories as
+//! there are letters in its name.  Nothing else about running the paginators
+//! needs to change, and no network access or access token is needed.
+//!
+//! Usage: `cargo run -p gqlient --example mock_transport -- <owner> ...`
+use anyhow::{bail, Context};
+use gqlient::{
+    Client, Cursor, HttpRequest, HttpResponse, JsonMap, Page, Paginator, Query, Singleton,
+    Transport, Variable,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::fmt::{self, Write};
+
+const PAGE_SIZE: usize = 2;
+
+/// Fetches the names of the repositories belonging to a user or organization
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct OwnerRepos {
+    owner: String,
+}
+
+impl Paginator for OwnerRepos {
+    type Item = String;
+    type Query = OwnerReposQuery;
+
+    fn for_cursor(&self, cursor: Option<&Cursor>) -> OwnerReposQuery {
+        OwnerReposQuery {
+            owner: self.owner.clone(),
+            cursor: cursor.cloned(),
+            prefix: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct OwnerReposQuery {
+    owner: String,
+    cursor: Option<Cursor>,
+    prefix: Option<String>,
+}
+
+impl OwnerReposQuery {
+    fn varname(&self, field: &str) -> String {
+        match self.prefix {
+            Some(ref prefix) => format!("{prefix}_{field}"),
+            None => field.to_owned(),
+        }
+    }
+}
+
+impl Query for OwnerReposQuery {
+    type Output = Page<String>;
+
+    fn with_variable_prefix(mut self, prefix: String) -> Self {
+        self.prefix = Some(prefix);
+        self
+    }
+
+    fn write_graphql<W: Write>(&self, mut s: W) -> fmt::Result {
+        writeln!(s, "repositoryOwner(login: ${}) {{", self.varname("owner"))?;
+        writeln!(
+            s,
+            "    repositories(first: {PAGE_SIZE}, after: ${}) {{",
+            self.varname("cursor")
+        )?;
+        writeln!(s, "        nodes {{ nameWithOwner }}")?;
+        writeln!(s, "        pageInfo {{ endCursor hasNextPage }}")?;
+        writeln!(s, "    }}")?;
+        writeln!(s, "}}")
+    }
+
+    fn variables(&self) -> [(String, Variable); 2] {
+        [
+            (
+                self.varname("owner"),
+                Variable {
+                    gql_type: String::from("String!"),
+                    value: self.owner.clone().into(),
+                },
+            ),
+            (
+                self.varname("cursor"),
+                Variable {
+                    gql_type: String::from("String"),
+                    value: self.cursor.clone().into(),
+                },
+            ),
+        ]
+    }
+
+    fn parse_response(&self, value: serde_json::Value) -> Result<Page<String>, serde_json::Error> {
+        let Singleton(page) = gqlient::from_value::<Singleton<Page<Singleton<String>>>>(value)?;
+        Ok(page.map_items(|Singleton(name)| name))
+    }
+}
+
+/// A transport that answers each sub-query `qN` of a GraphQL request with a
+/// page of made-up repositories for the owner in its `$qN_owner` variable,
+/// starting after the index in its `$qN_cursor` variable
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+struct MockTransport;
+
+#[derive(Deserialize)]
+struct Payload {
+    variables: JsonMap,
+}
+
+impl Transport for MockTransport {
+    fn send(&self, request: &HttpRequest) -> anyhow::Result<HttpResponse> {
+        let payload = serde_json::from_slice::<Payload>(&request.body)
+            .context("mock transport received a non-GraphQL request")?;
+        let mut data = JsonMap::new();
+        for (name, value) in &payload.variables {
+            let Some(alias) = name.strip_suffix("_owner") else {
+                continue;
+            };
+            let owner = value.as_str().context("owner is not a string")?;
+            let start = match payload.variables.get(&format!("{alias}_cursor")) {
+                Some(serde_json::Value::String(c)) => c.parse::<usize>()?,
+                _ => 0,
+            };
+            let total = owner.len();
+            let end = total.min(start + PAGE_SIZE);
+            let nodes = (start..end)
+                .map(|i| json!({"nameWithOwner": format!("{owner}/repo{i}")}))
+                .collect::<Vec<_>>();
+            data.insert(
+                alias.to_owned(),
+                json!({
+                    "repositories": {
+                        "nodes": nodes,
+                        "pageInfo": {
+                            "endCursor": end.to_string(),
+                            "hasNextPage": end < total,
+                        },
+                    },
+                }),
+            );
+        }
+        let body = serde_json::to_vec(&json!({"data": data}))?;
+        Ok(HttpResponse::new(200, body))
+    }
+}
+
+fn main() -> anyhow::Result<()> {
+    let owners = std::env::args().skip(1).collect::<Vec<_>>();
+    if owners.is_empty() {
+        bail!("usage: mock_transport <owner> ...");
+    }
+    let client = Client::builder()
+        .token("not-a-real-token")
+        .transport(MockTransport)
+        .build()?;
+    let paginators = owners
+        .into_iter()
+        .map(|owner| (owner.clone(), OwnerRepos { owner }));
+    for pr in client.batch_paginate(paginators)? {
+        println!(
+            "{}: {} repositories fetched in {} requests",
+            pr.key,
+            pr.items.len(),
+            pr.requests
+        );
+        for name in pr.items {
+            println!("  {name}");
+        }
+    }
+    println!("{}", client.transfer_stats());
+    Ok(())
+}
@@ -0,0 +1,60 @@
+//! Run a single GraphQL query with a hand-written query document and print
+//! the result.
+//!
+//! The GitHub token is taken from the `GH_TOKEN` or `GITHUB_TOKEN`
+//! environment variable or from the `gh` command's configuration.
+//!
+//! Usage: `cargo run -p gqlient --example single_query -- <owner>/<name>`
+use anyhow::Context;
+use gqlient::{Client, JsonMap};
+use serde::Deserialize;
+
+const QUERY: &str = "
+query($owner: String!, $name: String!) {
+    repository(owner: $owner, name: $name) {
+        nameWithOwner
+        description
+        stargazerCount
+        issues(states: [OPEN]) {
+            totalCount
+        }
+    }
+}
+";
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct Repository {
+    name_with_owner: String,
+    description: Option<String>,
+    stargazer_count: u64,
+    #[serde(deserialize_with = "gqlient::singleton_field")]
+    issues: u64,
+}
+
+fn main() -> anyhow::Result<()> {
+    let fullname = std::env::args()
+        .nth(1)
+        .context("usage: single_query <owner>/<name>")?;
+    let (owner, name) = fullname
+        .split_once('/')
+        .context("repository must be given as <owner>/<name>")?;
+    let client = Client::new_with_local_token()?;
+    let mut variables = JsonMap::new();
+    variables.insert(String::from("owner"), owner.into());
+    variables.insert(String::from("name"), name.into());
+    let mut data = client.query(String::from(QUERY), variables)?;
+    let repo = data
+        .remove("repository")
+        .filter(|value| !value.is_null())
+        .with_context(|| format!("repository {fullname} not found"))?;
+    let repo =
+        serde_json::from_value::<Repository>(repo).context("failed to deserialize repository")?;
+    println!("{}", repo.name_with_owner);
+    if let Some(description) = repo.description {
+        println!("  {description}");
+    }
+    println!("  Stars: {}", repo.stargazer_count);
+    println!("  Open issues: {}", repo.issues);
+    Ok(())
+}
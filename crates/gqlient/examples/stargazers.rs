@@ -0,0 +1,156 @@
+//! Fetch the stargazers of several repositories at once by implementing
+//! [`Paginator`] & [`Query`] and passing the paginators to
+//! [`Client::batch_paginate()`], which combines the page queries for multiple
+//! repositories into each GraphQL request.
+//!
+//! Usage: `cargo run -p gqlient --example stargazers -- <owner>/<name> ...`
+use anyhow::{bail, Context};
+use gqlient::{Client, Cursor, Page, Paginator, Query, Singleton, Variable};
+use serde::Deserialize;
+use std::fmt::{self, Write};
+
+/// Fetches the logins of the users who have starred a repository
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Stargazers {
+    owner: String,
+    name: String,
+}
+
+impl Paginator for Stargazers {
+    type Item = Stargazer;
+    type Query = StargazersQuery;
+
+    fn for_cursor(&self, cursor: Option<&Cursor>) -> StargazersQuery {
+        StargazersQuery {
+            owner: self.owner.clone(),
+            name: self.name.clone(),
+            cursor: cursor.cloned(),
+            prefix: None,
+        }
+    }
+
+    fn nodes_per_page(&self) -> Option<u64> {
+        Some(100)
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct StargazersQuery {
+    owner: String,
+    name: String,
+    cursor: Option<Cursor>,
+    prefix: Option<String>,
+}
+
+impl StargazersQuery {
+    /// Return the name of the query's variable for the given field.  When
+    /// several queries are combined into one request, each one is given a
+    /// distinct prefix so that their variables do not collide.
+    fn varname(&self, field: &str) -> String {
+        match self.prefix {
+            Some(ref prefix) => format!("{prefix}_{field}"),
+            None => field.to_owned(),
+        }
+    }
+}
+
+impl Query for StargazersQuery {
+    type Output = Page<Stargazer>;
+
+    fn with_variable_prefix(mut self, prefix: String) -> Self {
+        self.prefix = Some(prefix);
+        self
+    }
+
+    fn write_graphql<W: Write>(&self, mut s: W) -> fmt::Result {
+        writeln!(
+            s,
+            "repository(owner: ${}, name: ${}) {{",
+            self.varname("owner"),
+            self.varname("name")
+        )?;
+        writeln!(
+            s,
+            "    stargazers(first: 100, after: ${}) {{",
+            self.varname("cursor")
+        )?;
+        writeln!(s, "        nodes {{ login }}")?;
+        writeln!(s, "        pageInfo {{ endCursor hasNextPage }}")?;
+        writeln!(s, "    }}")?;
+        writeln!(s, "}}")
+    }
+
+    fn variables(&self) -> [(String, Variable); 3] {
+        [
+            (
+                self.varname("owner"),
+                Variable {
+                    gql_type: String::from("String!"),
+                    value: self.owner.clone().into(),
+                },
+            ),
+            (
+                self.varname("name"),
+                Variable {
+                    gql_type: String::from("String!"),
+                    value: self.name.clone().into(),
+                },
+            ),
+            (
+                self.varname("cursor"),
+                Variable {
+                    gql_type: String::from("String"),
+                    value: self.cursor.clone().into(),
+                },
+            ),
+        ]
+    }
+
+    fn parse_response(
+        &self,
+        value: serde_json::Value,
+    ) -> Result<Page<Stargazer>, serde_json::Error> {
+        // `value` is the `repository` object, which contains only the
+        // `stargazers` connection
+        let Singleton(page) = gqlient::from_value::<Singleton<Page<Stargazer>>>(value)?;
+        Ok(page)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+struct Stargazer {
+    login: String,
+}
+
+fn main() -> anyhow::Result<()> {
+    let repos = std::env::args().skip(1).collect::<Vec<_>>();
+    if repos.is_empty() {
+        bail!("usage: stargazers <owner>/<name> ...");
+    }
+    let mut paginators = Vec::with_capacity(repos.len());
+    for fullname in repos {
+        let (owner, name) = fullname
+            .split_once('/')
+            .with_context(|| format!("{fullname:?} is not of the form <owner>/<name>"))?;
+        let paginator = Stargazers {
+            owner: owner.to_owned(),
+            name: name.to_owned(),
+        };
+        paginators.push((fullname, paginator));
+    }
+    let mut client = Client::new_with_local_token()?;
+    client.verbose(true);
+    for pr in client.batch_paginate(paginators)? {
+        println!(
+            "{}: {} stargazers fetched in {} requests",
+            pr.key,
+            pr.items.len(),
+            pr.requests
+        );
+        for user in pr.items {
+            println!("  {}", user.login);
+        }
+    }
+    println!("{}", client.transfer_stats());
+    Ok(())
+}
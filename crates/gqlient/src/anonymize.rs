@@ -0,0 +1,292 @@
+use crate::outcomes::{OwnerOutcome, OwnerOutcomes};
+use crate::owners::OwnerKind;
+use anyhow::{bail, Context};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::collections::BTreeMap;
+use std::fmt::{self, Write as _};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write as _};
+use std::path::Path;
+
+/// The length of a generated key in bytes
+const KEY_LEN: usize = 32;
+
+/// Replaces owner & repository names, issue titles, label names, and other
+/// identifying strings with keyed hashes (HMAC-SHA256), so that program
+/// output can be shared without revealing what was queried
+///
+/// The same key always maps the same string to the same hash, so anonymized
+/// outputs from different runs with the same key can still be compared, but
+/// without the key, the hashes cannot be checked against guesses of the
+/// original strings.
+#[derive(Clone)]
+pub struct Anonymizer {
+    mac: Hmac<Sha256>,
+}
+
+impl fmt::Debug for Anonymizer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Anonymizer").finish_non_exhaustive()
+    }
+}
+
+impl Anonymizer {
+    #[allow(clippy::missing_panics_doc)]
+    pub fn new(key: &[u8]) -> Anonymizer {
+        Anonymizer {
+            mac: Hmac::new_from_slice(key).expect("HMAC should accept keys of any length"),
+        }
+    }
+
+    /// Create an anonymizer using the hex-encoded key stored in `path`.  If
+    /// the file does not exist, a random key is generated and written to it,
+    /// readable only by the current user.
+    pub fn from_key_file(path: &Path) -> anyhow::Result<Anonymizer> {
+        let key = match fs::read_to_string(path) {
+            Ok(s) => decode_hex(s.trim())
+                .with_context(|| format!("invalid anonymization key in {}", path.display()))?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                let mut key = vec![0; KEY_LEN];
+                getrandom::getrandom(&mut key).context("failed to generate anonymization key")?;
+                write_key_file(path, &key).with_context(|| {
+                    format!("failed to write anonymization key to {}", path.display())
+                })?;
+                crate::progress!("Generated new anonymization key in {}", path.display());
+                key
+            }
+            Err(e) => {
+                return Err(anyhow::Error::from(e).context(format!(
+                    "failed to read anonymization key from {}",
+                    path.display()
+                )))
+            }
+        };
+        Ok(Anonymizer::new(&key))
+    }
+
+    /// Hash `value` in the given domain, so that equal strings used for
+    /// different purposes (e.g., a label and a title) hash differently
+    fn hash(&self, domain: &str, value: &str) -> String {
+        let mut mac = self.mac.clone();
+        mac.update(domain.as_bytes());
+        mac.update(&[0]);
+        mac.update(value.as_bytes());
+        let digest = mac.finalize().into_bytes();
+        let mut s = String::with_capacity(16);
+        for b in digest.iter().take(8) {
+            let _ = write!(s, "{b:02x}");
+        }
+        s
+    }
+
+    /// Anonymize an owner login.  Logins are case-insensitive, so they are
+    /// lowercased before hashing.  For owners qualified with a host (as
+    /// `host:owner`), the host and login are hashed separately.
+    pub fn owner(&self, owner: &str) -> String {
+        match owner.split_once(':') {
+            Some((host, login)) => format!("{}:{}", self.host(host), self.owner(login)),
+            None => self.hash("owner", &owner.to_lowercase()),
+        }
+    }
+
+    /// Anonymize a repository name of the form `owner/name`, hashing the
+    /// owner and name separately so that repositories with the same owner
+    /// still share an owner after anonymization
+    pub fn repo(&self, fullname: &str) -> String {
+        match fullname.split_once('/') {
+            Some((owner, name)) => format!("{}/{}", self.owner(owner), self.repo_name(name)),
+            None => self.repo_name(fullname),
+        }
+    }
+
+    /// Anonymize the name of a configured API host
+    pub fn host(&self, host: &str) -> String {
+        self.hash("host", &host.to_lowercase())
+    }
+
+    /// Anonymize the name of a repository without its owner
+    pub fn repo_name(&self, name: &str) -> String {
+        self.hash("repo", &name.to_lowercase())
+    }
+
+    /// Anonymize an issue title
+    pub fn title(&self, title: &str) -> String {
+        self.hash("title", title)
+    }
+
+    /// Anonymize a label name
+    pub fn label(&self, label: &str) -> String {
+        self.hash("label", label)
+    }
+
+    /// Anonymize a repository topic.  Topics are matched case-insensitively,
+    /// so they are lowercased before hashing.
+    pub fn topic(&self, topic: &str) -> String {
+        self.hash("topic", &topic.to_lowercase())
+    }
+
+    /// Anonymize the login of an issue author
+    pub fn author(&self, login: &str) -> String {
+        self.hash("author", &login.to_lowercase())
+    }
+
+    /// Return the URL of issue `number` in the (already anonymized)
+    /// repository `repo` under a reserved hostname, so that the URL reveals
+    /// neither the repository nor the GitHub instance
+    pub fn issue_url(repo: &str, number: u64) -> String {
+        format!("https://anonymized.invalid/{repo}/issues/{number}")
+    }
+
+    /// Anonymize the keys of a map of owner kinds
+    pub fn owner_kinds(&self, kinds: &BTreeMap<String, OwnerKind>) -> BTreeMap<String, OwnerKind> {
+        kinds
+            .iter()
+            .map(|(owner, &kind)| (self.owner(owner), kind))
+            .collect()
+    }
+
+    /// Anonymize the owners in a set of owner outcomes.  As error messages
+    /// may mention owners, repositories, and the GitHub instance, they are
+    /// replaced with a placeholder.
+    pub fn owner_outcomes(&self, outcomes: &OwnerOutcomes) -> OwnerOutcomes {
+        OwnerOutcomes(
+            outcomes
+                .0
+                .iter()
+                .map(|(owner, outcome)| {
+                    let outcome = match outcome {
+                        OwnerOutcome::Failed { .. } => OwnerOutcome::Failed {
+                            error: String::from("<redacted>"),
+                        },
+                        other => other.clone(),
+                    };
+                    (self.owner(owner), outcome)
+                })
+                .collect(),
+        )
+    }
+}
+
+fn decode_hex(s: &str) -> anyhow::Result<Vec<u8>> {
+    if s.is_empty() || s.len() % 2 != 0 {
+        bail!("expected a nonempty string of hexadecimal digit pairs");
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| {
+            s.get(i..i + 2)
+                .and_then(|h| u8::from_str_radix(h, 16).ok())
+                .context("expected a nonempty string of hexadecimal digit pairs")
+        })
+        .collect()
+}
+
+fn write_key_file(path: &Path, key: &[u8]) -> io::Result<()> {
+    let mut opts = OpenOptions::new();
+    opts.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        opts.mode(0o600);
+    }
+    let mut fp = opts.open(path)?;
+    let mut hex = String::with_capacity(key.len() * 2);
+    for b in key {
+        let _ = write!(hex, "{b:02x}");
+    }
+    writeln!(fp, "{hex}")?;
+    fp.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hashes_are_keyed() {
+        let a = Anonymizer::new(b"key one");
+        let b = Anonymizer::new(b"key two");
+        assert_eq!(a.title("Fix the bug"), a.title("Fix the bug"));
+        assert_ne!(a.title("Fix the bug"), b.title("Fix the bug"));
+        let hash = a.title("Fix the bug");
+        assert_eq!(hash.len(), 16);
+        assert!(hash.chars().all(|c| c.is_ascii_hexdigit()));
+    }
+
+    #[test]
+    fn domains_are_separate() {
+        let anon = Anonymizer::new(b"key");
+        assert_ne!(anon.label("bug"), anon.title("bug"));
+        assert_ne!(anon.owner("bug"), anon.author("bug"));
+        assert_ne!(anon.owner("bug"), anon.repo_name("bug"));
+    }
+
+    #[test]
+    fn case_folding() {
+        let anon = Anonymizer::new(b"key");
+        assert_eq!(anon.owner("JWodder"), anon.owner("jwodder"));
+        assert_eq!(anon.author("JWodder"), anon.author("jwodder"));
+        assert_eq!(anon.topic("Rust"), anon.topic("rust"));
+        assert_eq!(anon.repo("JWodder/Foo"), anon.repo("jwodder/foo"));
+        assert_ne!(anon.title("Bug"), anon.title("bug"));
+        assert_ne!(anon.label("Bug"), anon.label("bug"));
+    }
+
+    #[test]
+    fn repo_keeps_owner() {
+        let anon = Anonymizer::new(b"key");
+        assert_eq!(
+            anon.repo("jwodder/foo"),
+            format!("{}/{}", anon.owner("jwodder"), anon.repo_name("foo"))
+        );
+        assert_eq!(anon.repo("foo"), anon.repo_name("foo"));
+    }
+
+    #[test]
+    fn host_qualified_owner() {
+        let anon = Anonymizer::new(b"key");
+        assert_eq!(
+            anon.owner("GHE.example.com:JWodder"),
+            format!("{}:{}", anon.host("ghe.example.com"), anon.owner("jwodder"))
+        );
+    }
+
+    #[test]
+    fn owner_outcomes_are_redacted() {
+        let anon = Anonymizer::new(b"key");
+        let mut outcomes = OwnerOutcomes::new(["jwodder", "wheelodex"]);
+        outcomes.fail("wheelodex", "failed to fetch wheelodex/wheelodex");
+        let anonymized = anon.owner_outcomes(&outcomes);
+        assert_eq!(
+            anonymized.0.get(&anon.owner("jwodder")),
+            outcomes.0.get("jwodder")
+        );
+        assert_eq!(
+            anonymized.0.get(&anon.owner("wheelodex")),
+            Some(&OwnerOutcome::Failed {
+                error: String::from("<redacted>")
+            })
+        );
+    }
+
+    #[test]
+    fn decode_hex_keys() {
+        assert_eq!(decode_hex("00ff10Ab").unwrap(), [0x00, 0xFF, 0x10, 0xAB]);
+        for s in ["", "abc", "zz", "0x12"] {
+            assert!(decode_hex(s).is_err(), "{s:?}");
+        }
+    }
+
+    #[test]
+    fn key_file_is_generated_then_reused() {
+        let path = std::env::temp_dir().join(format!("gqlient-anon-key-{}", std::process::id()));
+        let generated = Anonymizer::from_key_file(&path).map(|anon| anon.title("x"));
+        let reloaded = Anonymizer::from_key_file(&path).map(|anon| anon.title("x"));
+        let contents = fs::read_to_string(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(generated.unwrap(), reloaded.unwrap());
+        let key = decode_hex(contents.unwrap().trim()).unwrap();
+        assert_eq!(key.len(), KEY_LEN);
+    }
+}
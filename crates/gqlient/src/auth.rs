@@ -0,0 +1,123 @@
+use anyhow::Context;
+use serde::Deserialize;
+use std::fmt;
+
+static APP_INSTALLATION_TOKEN_URL: &str = "https://api.github.com/app/installations";
+
+/// A source of bearer tokens for authenticating requests to the GitHub API
+///
+/// A [`Client`](crate::Client) calls [`AuthProvider::bearer()`] before making
+/// its first request and again whenever a request fails with a 401
+/// Unauthorized response, so providers of short-lived tokens should fetch a
+/// fresh token on each call.
+pub trait AuthProvider: fmt::Debug {
+    fn bearer(&mut self) -> anyhow::Result<String>;
+}
+
+/// A fixed token
+#[derive(Clone, Eq, PartialEq)]
+pub struct StaticToken(String);
+
+impl StaticToken {
+    pub fn new<S: Into<String>>(token: S) -> StaticToken {
+        StaticToken(token.into())
+    }
+}
+
+// Don't leak the token in debug output
+impl fmt::Debug for StaticToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("StaticToken(..)")
+    }
+}
+
+impl AuthProvider for StaticToken {
+    fn bearer(&mut self) -> anyhow::Result<String> {
+        Ok(self.0.clone())
+    }
+}
+
+/// A token looked up via the `gh-token` crate, i.e., from the `GH_TOKEN` or
+/// `GITHUB_TOKEN` environment variable or from the `gh` command's
+/// configuration
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct GhToken;
+
+impl AuthProvider for GhToken {
+    fn bearer(&mut self) -> anyhow::Result<String> {
+        gh_token::get().context("unable to fetch GitHub access token")
+    }
+}
+
+/// A token read from the given environment variable
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EnvToken(String);
+
+impl EnvToken {
+    pub fn new<S: Into<String>>(var: S) -> EnvToken {
+        EnvToken(var.into())
+    }
+}
+
+impl AuthProvider for EnvToken {
+    fn bearer(&mut self) -> anyhow::Result<String> {
+        std::env::var(&self.0)
+            .with_context(|| format!("unable to read GitHub access token from ${}", self.0))
+    }
+}
+
+/// An installation access token for a GitHub App, which expires after an
+/// hour and is thus re-requested whenever a new token is needed
+///
+/// Requesting an installation token requires a JSON Web Token signed with the
+/// app's private key; this is produced by the `jwt` callback, which is
+/// invoked for every token request.
+pub struct AppInstallationToken<F> {
+    installation_id: u64,
+    jwt: F,
+}
+
+impl<F> AppInstallationToken<F>
+where
+    F: FnMut() -> anyhow::Result<String>,
+{
+    pub fn new(installation_id: u64, jwt: F) -> AppInstallationToken<F> {
+        AppInstallationToken {
+            installation_id,
+            jwt,
+        }
+    }
+}
+
+impl<F> fmt::Debug for AppInstallationToken<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AppInstallationToken")
+            .field("installation_id", &self.installation_id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<F> AuthProvider for AppInstallationToken<F>
+where
+    F: FnMut() -> anyhow::Result<String>,
+{
+    fn bearer(&mut self) -> anyhow::Result<String> {
+        #[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+        struct InstallationToken {
+            token: String,
+        }
+
+        let jwt = (self.jwt)().context("failed to create GitHub App JWT")?;
+        ureq::post(&format!(
+            "{APP_INSTALLATION_TOKEN_URL}/{}/access_tokens",
+            self.installation_id
+        ))
+        .set("Authorization", &format!("Bearer {jwt}"))
+        .set("Accept", "application/vnd.github+json")
+        .call()
+        .context("failed to request GitHub App installation token")?
+        .into_json::<InstallationToken>()
+        .context("failed to deserialize GitHub App installation token response")
+        .map(|r| r.token)
+    }
+}
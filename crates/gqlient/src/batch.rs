@@ -0,0 +1,1166 @@
+use crate::errors::{DeserializeError, MissingAliasError};
+use crate::machine::{
+    declare_variables, render_subquery, BatchMachine, Event, PaginationState, RATE_LIMIT_FIELD,
+};
+use crate::progress::StatusLine;
+use crate::queries::{Paginator, Query};
+use crate::types::{Cursor, JsonMap};
+use crate::Client;
+use serde::Serialize;
+use std::fmt;
+use std::time::{Duration, Instant, SystemTime};
+
+/// A set of paginators being run together by a [`Client`], with up to the
+/// client's batch size of their page queries combined into each GraphQL
+/// request
+///
+/// `BatchPaginator` drives a [`BatchMachine`] configured with the client's
+/// settings, sending the machine's requests with the client (and thus with
+/// the client's [`Transport`](crate::Transport)) and reporting the machine's
+/// events on the client's status line and counters.  The composition of
+/// requests and handling of responses described below is done by the machine
+/// without any I/O, so it can also be driven by other means.
+///
+/// Every request is filled from a single work queue: as soon as a
+/// paginator's final page is fetched, its slot in the next request is given
+/// to another paginator.  Paginators with further pages are requeued ahead of
+/// those that have not started yet so that long pagination chains progress on
+/// every request rather than being left to run alone in nearly-empty requests
+/// at the end.
+///
+/// If the client was configured with [`Client::node_limit()`], each request
+/// is further limited to as many paginators from the front of the queue as
+/// fit within the limit according to [`Paginator::nodes_per_page()`].
+///
+/// Variables with the same name in multiple sub-queries of a request (such as
+/// the page size) are declared only once, and it is an error for them to have
+/// different values.  Each request also queries `rateLimit { cost }` so that
+/// its cost can be attributed to the client's current phase; see
+/// [`Client::points_by_phase()`].
+///
+/// If a response lacks the data for some of a request's sub-queries, the
+/// pages for the other sub-queries are applied as normal, and the missing
+/// sub-queries are retried in the next request.  A sub-query that goes missing
+/// too many times in a row causes the request to fail with a
+/// [`MissingAliasError`].
+///
+/// Likewise, if a response includes errors that each concern a single
+/// sub-query (such as one sub-query timing out), the data for the other
+/// sub-queries is applied as normal, and the sub-queries with errors are
+/// retried as though their data were missing; see
+/// [`Client::partial_responses()`].  A response with any error that does not
+/// concern a single sub-query (such as the rate limit being exceeded) causes
+/// the request to fail with a [`GqlError`](crate::GqlError).
+///
+/// If the data for a sub-query cannot be deserialized, the raw data is saved
+/// to a file and the request fails with a [`DeserializeError`] giving the
+/// file's path, unless the client was configured with
+/// [`Client::deserialize_retries()`], in which case the sub-query is first
+/// retried in the next requests like a missing one.
+///
+/// If GitHub rejects the cursors of some of a request's sub-queries as
+/// invalid or expired (as can happen to cursors saved from an earlier run),
+/// those paginators are restarted from their first pages with
+/// [`Paginator::reset_cursor()`], discarding the items they have fetched so
+/// far, and the request is retried; see [`Client::cursors_reset()`].  Each
+/// paginator is only restarted once.
+///
+/// Each call to [`BatchPaginator::step()`] makes a single request and either
+/// applies all of its results or, on error, none of them, so the paginator
+/// can be checkpointed with [`BatchPaginator::into_parts()`] at any point —
+/// including after a failed request — and later resumed with
+/// [`BatchPaginator::from_parts()`].
+///
+/// If the client was configured with [`Client::pause_below()`] and the rate
+/// limit is about to run out, [`BatchPaginator::step()`] returns
+/// [`Step::Paused`] instead of making a request, leaving it to the caller to
+/// wait until the given time (as [`BatchPaginator::run()`] does) or to
+/// checkpoint the paginator and resume later.
+///
+/// If the client was configured with [`Client::adaptive_page_size()`], then
+/// after a request that took at least the given threshold, each paginator in
+/// it with further pages has its page size lowered with
+/// [`Paginator::reduce_page_size()`], as does each paginator whose
+/// [`Paginator::nodes_per_page()`] comes near the client's node limit; see
+/// [`Client::page_size_reductions()`].
+pub struct BatchPaginator<'a, K, P: Paginator> {
+    client: &'a Client,
+    machine: BatchMachine<K, P>,
+    status: Option<StatusLine>,
+    // When the response to the most recent request was received, unless the
+    // paginator has since paused
+    received: Option<Instant>,
+}
+
+impl<K, P: Paginator> fmt::Debug for BatchPaginator<'_, K, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BatchPaginator")
+            .field("machine", &self.machine)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a, K, P: Paginator> BatchPaginator<'a, K, P> {
+    pub fn new<I>(client: &'a Client, queries: I) -> Self
+    where
+        I: IntoIterator<Item = (K, P)>,
+    {
+        BatchPaginator::from_machine(client, BatchMachine::new(client.machine_config(), queries))
+    }
+
+    /// Reconstruct a `BatchPaginator` from parts previously returned by
+    /// [`BatchPaginator::into_parts()`] (possibly with pending paginators
+    /// added or removed)
+    pub fn from_parts(client: &'a Client, parts: BatchParts<K, P>) -> Self {
+        BatchPaginator::from_machine(
+            client,
+            BatchMachine::from_parts(client.machine_config(), parts),
+        )
+    }
+
+    fn from_machine(client: &'a Client, machine: BatchMachine<K, P>) -> Self {
+        let status = client.verbose.then(|| StatusLine::new(machine.len()));
+        BatchPaginator {
+            client,
+            machine,
+            status,
+            received: None,
+        }
+    }
+
+    /// Break the `BatchPaginator` up into the paginators that still have
+    /// pages left to fetch (in the order in which they would be queried) and
+    /// the results of those that are done
+    pub fn into_parts(self) -> BatchParts<K, P> {
+        self.machine.into_parts()
+    }
+
+    /// Remove and return the results of the paginators that have fetched
+    /// their final page since the `BatchPaginator` was created or last
+    /// flushed, so that they can be written out (e.g., to a checkpoint)
+    /// without waiting for the remaining paginators to finish.  Flushed
+    /// results are not included in those later returned by
+    /// [`BatchPaginator::run()`], [`BatchPaginator::run_isolated()`], or
+    /// [`BatchPaginator::into_parts()`].
+    ///
+    /// The items fetched so far by paginators that have not finished yet can
+    /// be obtained via [`BatchPaginator::into_parts()`].
+    pub fn flush(&mut self) -> Vec<PaginationResults<K, P::Item>> {
+        self.machine.flush()
+    }
+
+    /// Returns true if all paginators have fetched their final page
+    pub fn is_done(&self) -> bool {
+        self.machine.is_done()
+    }
+
+    /// Make a single GraphQL request for the next batch of pages, unless all
+    /// paginators are done or the rate limit is below the client's pause
+    /// threshold.
+    ///
+    /// If the request fails or any part of its response cannot be parsed,
+    /// the paginators in the batch are left as they were before the call.
+    pub fn step(&mut self) -> anyhow::Result<Step> {
+        self.step_batch(self.client.batch_size.get())
+    }
+
+    /// Like [`BatchPaginator::step()`], but with at most `batch_size`
+    /// paginators in the request
+    fn step_batch(&mut self, batch_size: usize) -> anyhow::Result<Step> {
+        if self.machine.next_batch_len(batch_size) == 0 {
+            return Ok(Step::Done);
+        }
+        if let Some(resume_at) = self.client.pause_below.and_then(|threshold| {
+            self.client
+                .last_rate_limit_headers()
+                .filter(|rl| rl.remaining < threshold)
+                .map(|rl| rl.reset_time())
+                .filter(|&t| t > SystemTime::now())
+        }) {
+            return Ok(Step::Paused { resume_at });
+        }
+        let Some(request) = self.machine.start_request(batch_size)? else {
+            return Ok(Step::Done);
+        };
+        // Only describe the sub-queries up front if the request might need to
+        // be logged as slow.
+        let description = self
+            .client
+            .slow_request_threshold
+            .map(|_| (request.aliases.len(), request.describe()));
+        self.client.idle_since.set(self.received.take());
+        let sent = Instant::now();
+        let response = self.client.query_partial(request.query, request.variables);
+        let took = sent.elapsed();
+        self.client.idle_since.set(None);
+        // A request that never reached the transport has no response to idle
+        // after.
+        self.received = self.client.last_response.get().filter(|&t| t >= sent);
+        if let (Some(threshold), Some((n, description))) =
+            (self.client.slow_request_threshold, description)
+        {
+            if took >= threshold {
+                self.message(&format!(
+                    "[!] Slow request took {took:?} for {n} sub-queries: {description}"
+                ));
+            }
+        }
+        let slow = self
+            .client
+            .adaptive_page_size
+            .is_some_and(|threshold| took >= threshold);
+        let r = response.and_then(|response| {
+            self.machine
+                .handle_response(response, sent, slow, |query, value| {
+                    self.client.parse_response(query, value)
+                })
+        });
+        for event in self.machine.take_events() {
+            self.apply_event(event);
+        }
+        if let Err(mut e) = r {
+            if let Some(de) = e.downcast_mut::<DeserializeError>() {
+                if let Some((alias, raw)) = self.machine.take_bad_data() {
+                    de.saved_to = self.client.save_bad_data(&alias, &raw);
+                }
+            }
+            return Err(e);
+        }
+        Ok(Step::Fetched)
+    }
+
+    /// Report an event of the machine on the status line and in the client's
+    /// counters
+    fn apply_event(&mut self, event: Event) {
+        let counter = match event {
+            Event::Warning(msg) => {
+                self.message(&msg);
+                return;
+            }
+            Event::RequestCompleted { cost } => {
+                if let Some(cost) = cost {
+                    self.client.add_query_cost(cost);
+                }
+                if let Some(st) = self.status.as_mut() {
+                    st.request_made(cost.unwrap_or_default());
+                }
+                return;
+            }
+            Event::ItemsFetched(qty) => {
+                if let Some(st) = self.status.as_mut() {
+                    st.items_fetched(qty);
+                }
+                return;
+            }
+            Event::PaginationFinished => {
+                if let Some(st) = self.status.as_mut() {
+                    st.query_finished();
+                }
+                return;
+            }
+            Event::PartialResponse => &self.client.partial_responses,
+            Event::CursorReset => &self.client.cursors_reset,
+            Event::PageSizeReduced => &self.client.page_size_reductions,
+        };
+        counter.set(counter.get() + 1);
+    }
+
+    fn message(&mut self, msg: &str) {
+        match self.status.as_mut() {
+            Some(st) => st.message(msg),
+            None => eprintln!("{msg}"),
+        }
+    }
+
+    /// Make requests until all paginators are done, and return their results.
+    /// Whenever the rate limit drops below the client's pause threshold, this
+    /// sleeps until the rate limit resets.
+    pub fn run(mut self) -> anyhow::Result<Vec<PaginationResults<K, P::Item>>> {
+        let batch_size = self.client.batch_size.get();
+        while self.step_waiting(batch_size)? != Step::Done {}
+        if let Some(st) = self.status.as_mut() {
+            st.finish();
+        }
+        Ok(self.machine.flush())
+    }
+
+    /// Like [`BatchPaginator::run()`], but a failed request does not end the
+    /// run.  Instead, the paginators in the failed request are retried one
+    /// per request, and any that still fail are set aside and returned
+    /// alongside the results of the rest.  A paginator whose data is
+    /// repeatedly missing from responses (see [`MissingAliasError`]) or cannot
+    /// be deserialized (see [`DeserializeError`]) is set aside without
+    /// retrying the rest of its request.
+    pub fn run_isolated(mut self) -> IsolatedResults<K, P::Item> {
+        let batch_size = self.client.batch_size.get();
+        let mut failures = Vec::new();
+        loop {
+            match self.step_waiting(batch_size) {
+                Ok(Step::Done) => break,
+                Ok(_) => (),
+                Err(e) => {
+                    let queue = &mut self.machine.in_progress;
+                    // A sub-query that keeps going missing from responses or
+                    // whose data cannot be deserialized fails on its own
+                    // without the rest of its batch.
+                    let alias = e
+                        .downcast_ref::<MissingAliasError>()
+                        .map(|m| &m.alias)
+                        .or_else(|| e.downcast_ref::<DeserializeError>().map(|d| &d.alias));
+                    if let Some(i) = alias.and_then(|a| queue.iter().position(|st| &st.alias == a))
+                    {
+                        if let Some(state) = queue.remove(i) {
+                            failures.push(PaginationFailure {
+                                key: state.key,
+                                error: e,
+                            });
+                        }
+                        continue;
+                    }
+                    let n = self.machine.next_batch_len(batch_size);
+                    let queue = &mut self.machine.in_progress;
+                    if n == 1 {
+                        if let Some(state) = queue.pop_front() {
+                            failures.push(PaginationFailure {
+                                key: state.key,
+                                error: e,
+                            });
+                        }
+                        continue;
+                    }
+                    // Query each paginator in the failed batch on its own,
+                    // with the rest of the queue set aside.
+                    let suspects = queue.drain(..n).collect::<Vec<_>>();
+                    let rest = std::mem::take(queue);
+                    let mut cleared = Vec::new();
+                    for state in suspects {
+                        self.machine.in_progress.push_back(state);
+                        match self.step_waiting(1) {
+                            Ok(_) => cleared.extend(self.machine.in_progress.pop_front()),
+                            Err(error) => {
+                                if let Some(state) = self.machine.in_progress.pop_front() {
+                                    failures.push(PaginationFailure {
+                                        key: state.key,
+                                        error,
+                                    });
+                                }
+                            }
+                        }
+                    }
+                    self.machine.in_progress = cleared.into();
+                    self.machine.in_progress.extend(rest);
+                }
+            }
+        }
+        if let Some(st) = self.status.as_mut() {
+            st.finish();
+        }
+        IsolatedResults {
+            results: self.machine.flush(),
+            failures,
+        }
+    }
+
+    /// Make a single request for at most `batch_size` paginators, first
+    /// sleeping until the rate limit resets if it is below the client's pause
+    /// threshold.  Never returns [`Step::Paused`].
+    fn step_waiting(&mut self, batch_size: usize) -> anyhow::Result<Step> {
+        loop {
+            match self.step_batch(batch_size)? {
+                Step::Paused { resume_at } => {
+                    let wait = resume_at
+                        .duration_since(SystemTime::now())
+                        .unwrap_or_default();
+                    let msg = format!(
+                        "[·] Rate limit nearly exhausted; pausing for {}s until reset …",
+                        wait.as_secs() + 1
+                    );
+                    match self.status.as_mut() {
+                        Some(st) => st.message(&msg),
+                        None if !crate::is_quiet() => eprintln!("{msg}"),
+                        None => (),
+                    }
+                    // Wait an extra second in case of clock skew, and don't
+                    // count the wait as idle time
+                    self.received = None;
+                    std::thread::sleep(wait + Duration::from_secs(1));
+                }
+                step => return Ok(step),
+            }
+        }
+    }
+}
+
+/// The outcome of a call to [`BatchPaginator::step()`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Step {
+    /// A request was made and its results applied
+    Fetched,
+    /// No request was made because the number of rate limit points remaining
+    /// is below the client's pause threshold; requests can resume at the
+    /// given time, when the rate limit resets
+    Paused { resume_at: SystemTime },
+    /// All paginators are done
+    Done,
+}
+
+/// The results of [`BatchPaginator::run_isolated()`]
+#[derive(Debug)]
+pub struct IsolatedResults<K, T> {
+    /// The results of the paginators that ran to completion
+    pub results: Vec<PaginationResults<K, T>>,
+    /// The paginators that failed, along with their errors
+    pub failures: Vec<PaginationFailure<K>>,
+}
+
+/// A paginator that could not be run to completion
+#[derive(Debug)]
+pub struct PaginationFailure<K> {
+    pub key: K,
+    pub error: anyhow::Error,
+}
+
+/// The state of a [`BatchPaginator`] as returned by
+/// [`BatchPaginator::into_parts()`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BatchParts<K, P: Paginator> {
+    /// Paginators that still have pages left to fetch, in the order in which
+    /// they would be queried
+    pub pending: Vec<PendingPaginator<K, P>>,
+    /// The results of paginators that have fetched their final page
+    pub finished: Vec<PaginationResults<K, P::Item>>,
+}
+
+/// A paginator that still has pages left to fetch, along with the items it
+/// has fetched so far and the cursor to fetch the next page from
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PendingPaginator<K, P: Paginator> {
+    pub key: K,
+    pub paginator: P,
+    /// The cursor after which the next page starts, or `None` if no pages
+    /// have been fetched yet
+    pub cursor: Option<Cursor>,
+    pub items: Vec<P::Item>,
+    /// The number of GraphQL requests made for this paginator so far
+    pub requests: usize,
+}
+
+impl<K, P: Paginator> PendingPaginator<K, P> {
+    pub fn new(key: K, paginator: P) -> Self {
+        PendingPaginator {
+            key,
+            paginator,
+            cursor: None,
+            items: Vec::new(),
+            requests: 0,
+        }
+    }
+}
+
+impl<K, P: Paginator> From<PaginationState<K, P>> for PendingPaginator<K, P> {
+    fn from(value: PaginationState<K, P>) -> Self {
+        PendingPaginator {
+            key: value.key,
+            paginator: value.paginator,
+            cursor: value.cursor,
+            items: value.items,
+            requests: value.requests,
+        }
+    }
+}
+
+/// Compose the document and variables of a single GraphQL request combining
+/// the given queries under the aliases `q0`, `q1`, etc., in the same way that
+/// [`BatchPaginator`] composes its requests
+///
+/// This is useful for inspecting the documents produced by a [`Query`]
+/// implementation, e.g., in snapshot tests.
+pub fn compose_request<Q, I>(queries: I) -> anyhow::Result<(String, JsonMap)>
+where
+    Q: Query,
+    I: IntoIterator<Item = Q>,
+{
+    let queries = queries
+        .into_iter()
+        .enumerate()
+        .map(|(i, query)| {
+            let alias = format!("q{i}");
+            let query = query.with_variable_prefix(alias.clone());
+            (alias, query)
+        })
+        .collect::<Vec<_>>();
+    compose_document(
+        "query",
+        queries.iter().map(|(alias, query)| (alias.as_str(), query)),
+    )
+}
+
+/// Compose the document and variables of a GraphQL operation of the given
+/// type (`query` or `mutation`) whose fields are the given aliased
+/// sub-queries, each of which must already have its variable prefix set
+pub(crate) fn compose_document<'q, Q, I>(
+    operation: &str,
+    subqueries: I,
+) -> anyhow::Result<(String, JsonMap)>
+where
+    Q: Query + 'q,
+    I: IntoIterator<Item = (&'q str, &'q Q)>,
+{
+    let mut variables = JsonMap::new();
+    let mut varstr = String::new();
+    let mut body = String::new();
+    for (alias, query) in subqueries {
+        declare_variables(query, &mut variables, &mut varstr)?;
+        body.push_str(&render_subquery(alias, query)?);
+    }
+    if operation == "query" {
+        body.push_str(RATE_LIMIT_FIELD);
+    }
+    Ok((format!("{operation} ({varstr}) {{\n{body}}}\n"), variables))
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct PaginationResults<K, T> {
+    pub key: K,
+    pub items: Vec<T>,
+    pub end_cursor: Option<Cursor>,
+    /// The number of GraphQL requests that included a query for this
+    /// paginator
+    pub requests: usize,
+    /// The time from the start of the first request for this paginator to
+    /// the end of the last one, not counting any time before the paginator
+    /// was restored with [`BatchPaginator::from_parts()`]
+    #[serde(serialize_with = "crate::serialize_duration")]
+    pub elapsed: Duration,
+}
+
+impl<K, Q: Paginator> From<PaginationState<K, Q>> for PaginationResults<K, Q::Item> {
+    fn from(value: PaginationState<K, Q>) -> Self {
+        PaginationResults {
+            key: value.key,
+            items: value.items,
+            end_cursor: value.cursor,
+            requests: value.requests,
+            elapsed: value.started.map(|t| t.elapsed()).unwrap_or_default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::GqlErrorType;
+    use crate::fixtures::load_fixtures;
+    use crate::machine::{MachineConfig, MISSING_ALIAS_RETRIES};
+    use crate::transport::{HttpRequest, HttpResponse, Transport};
+    use crate::types::{Page, Variable};
+    use crate::Response;
+    use serde::Deserialize;
+    use serde_json::{json, Value};
+    use std::cell::RefCell;
+    use std::fmt::Write;
+    use std::num::NonZeroUsize;
+    use std::path::PathBuf;
+    use std::rc::Rc;
+
+    /// Paginates over the numbers from zero up to (but not including)
+    /// `total`, as served by [`serve_numbers()`]
+    #[derive(Clone, Debug, Eq, PartialEq)]
+    struct Numbers {
+        total: u64,
+        cursor: Option<Cursor>,
+        page_size: NonZeroUsize,
+        /// The number of nodes that each number counts for toward the node
+        /// limit, if known
+        nodes_per_item: Option<u64>,
+    }
+
+    impl Numbers {
+        fn new(total: u64, page_size: usize) -> Numbers {
+            Numbers {
+                total,
+                cursor: None,
+                page_size: NonZeroUsize::new(page_size).unwrap(),
+                nodes_per_item: None,
+            }
+        }
+    }
+
+    impl Paginator for Numbers {
+        type Item = u64;
+        type Query = NumbersQuery;
+
+        fn for_cursor(&self, cursor: Option<&Cursor>) -> NumbersQuery {
+            NumbersQuery {
+                total: self.total,
+                cursor: cursor.or(self.cursor.as_ref()).cloned(),
+                page_size: self.page_size,
+                prefix: String::new(),
+            }
+        }
+
+        fn nodes_per_page(&self) -> Option<u64> {
+            let page_size = u64::try_from(self.page_size.get()).unwrap();
+            self.nodes_per_item.map(|n| n * page_size)
+        }
+
+        fn reset_cursor(&mut self) {
+            self.cursor = None;
+        }
+
+        fn reduce_page_size(&mut self) -> Option<NonZeroUsize> {
+            self.page_size = NonZeroUsize::new(self.page_size.get() / 2)?;
+            Some(self.page_size)
+        }
+    }
+
+    #[derive(Clone, Debug, Eq, PartialEq)]
+    struct NumbersQuery {
+        total: u64,
+        cursor: Option<Cursor>,
+        page_size: NonZeroUsize,
+        prefix: String,
+    }
+
+    impl Query for NumbersQuery {
+        type Output = Page<u64>;
+
+        fn with_variable_prefix(mut self, prefix: String) -> Self {
+            self.prefix = prefix;
+            self
+        }
+
+        fn write_graphql<W: Write>(&self, mut s: W) -> fmt::Result {
+            let p = &self.prefix;
+            writeln!(
+                s,
+                "numbers(total: ${p}_total, first: ${p}_page_size, after: ${p}_cursor) {{"
+            )?;
+            writeln!(s, "    nodes")?;
+            writeln!(s, "    pageInfo {{ endCursor hasNextPage }}")?;
+            writeln!(s, "}}")
+        }
+
+        fn variables(&self) -> [(String, Variable); 3] {
+            let p = &self.prefix;
+            [
+                (
+                    format!("{p}_total"),
+                    Variable {
+                        gql_type: String::from("Int!"),
+                        value: self.total.into(),
+                    },
+                ),
+                (
+                    format!("{p}_page_size"),
+                    Variable {
+                        gql_type: String::from("Int!"),
+                        value: self.page_size.get().into(),
+                    },
+                ),
+                (
+                    format!("{p}_cursor"),
+                    Variable {
+                        gql_type: String::from("String"),
+                        value: self.cursor.clone().into(),
+                    },
+                ),
+            ]
+        }
+
+        fn parse_response(&self, value: Value) -> Result<Page<u64>, serde_json::Error> {
+            crate::from_value(value)
+        }
+    }
+
+    #[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+    struct Payload {
+        query: String,
+        variables: JsonMap,
+    }
+
+    /// Answer each `numbers` sub-query in a request with the page of numbers
+    /// selected by its variables
+    fn serve_numbers(variables: &JsonMap) -> JsonMap {
+        let mut data = JsonMap::new();
+        for (name, total) in variables {
+            let Some(alias) = name.strip_suffix("_total") else {
+                continue;
+            };
+            let total = total.as_u64().unwrap();
+            let page_size = variables[&format!("{alias}_page_size")].as_u64().unwrap();
+            let start = variables[&format!("{alias}_cursor")]
+                .as_str()
+                .map_or(0, |c| c.parse::<u64>().unwrap());
+            let end = total.min(start + page_size);
+            data.insert(
+                alias.to_owned(),
+                json!({
+                    "nodes": (start..end).collect::<Vec<_>>(),
+                    "pageInfo": {
+                        "endCursor": end.to_string(),
+                        "hasNextPage": end < total,
+                    },
+                }),
+            );
+        }
+        data.insert(String::from("rateLimit"), json!({"cost": 1}));
+        data
+    }
+
+    /// A transport that records each GraphQL request it receives and answers
+    /// it with the response returned by a function of the request's number
+    /// (starting from zero) and payload
+    struct FakeServer<F> {
+        respond: F,
+        requests: Rc<RefCell<Vec<Payload>>>,
+    }
+
+    impl<F> fmt::Debug for FakeServer<F> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("FakeServer").finish_non_exhaustive()
+        }
+    }
+
+    impl<F: Fn(usize, &Payload) -> Value> Transport for FakeServer<F> {
+        fn send(&self, request: &HttpRequest) -> anyhow::Result<HttpResponse> {
+            let payload = serde_json::from_slice::<Payload>(&request.body)?;
+            let index = self.requests.borrow().len();
+            let response = (self.respond)(index, &payload);
+            self.requests.borrow_mut().push(payload);
+            Ok(HttpResponse::new(200, serde_json::to_vec(&response)?))
+        }
+    }
+
+    /// Return a client whose requests are answered by `respond`, along with a
+    /// handle on the requests that it makes
+    fn fake_client<F>(respond: F) -> (Client, Rc<RefCell<Vec<Payload>>>)
+    where
+        F: Fn(usize, &Payload) -> Value + 'static,
+    {
+        let requests = Rc::new(RefCell::new(Vec::new()));
+        let client = Client::builder()
+            .token("not-a-real-token")
+            .transport(FakeServer {
+                respond,
+                requests: Rc::clone(&requests),
+            })
+            .build()
+            .expect("a custom transport alone should be accepted");
+        (client, requests)
+    }
+
+    fn items(results: &[PaginationResults<&'static str, u64>], key: &str) -> Vec<u64> {
+        results
+            .iter()
+            .find(|pr| pr.key == key)
+            .map(|pr| pr.items.clone())
+            .unwrap()
+    }
+
+    /// Directory holding the fixtures replayed by `replay_recorded_session()`
+    const NUMBERS_FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/numbers");
+
+    /// Run the paginators whose requests are recorded in [`NUMBERS_FIXTURES`]
+    fn run_numbers_session(client: &mut Client) -> Vec<PaginationResults<&'static str, u64>> {
+        client.batch_size(NonZeroUsize::new(2).unwrap());
+        client
+            .batch_paginate([
+                ("a", Numbers::new(7, 2)),
+                ("b", Numbers::new(1, 2)),
+                ("c", Numbers::new(3, 2)),
+            ])
+            .unwrap()
+    }
+
+    #[test]
+    fn replay_recorded_session() {
+        // The fixtures were captured by `regenerate_numbers_fixtures()` from
+        // a run of the paginators in `run_numbers_session()` against
+        // `serve_numbers()`.  Replaying them checks that the same requests
+        // are made in the same order and that the recorded responses are
+        // applied to the right paginators.
+        let fixtures = load_fixtures(NUMBERS_FIXTURES).unwrap();
+        assert_eq!(fixtures.len(), 4);
+        let (mut client, requests) = fake_client(move |i, payload| {
+            let fixture = &fixtures[i];
+            assert_eq!(payload.query, fixture.query, "query of request {i}");
+            assert_eq!(
+                payload.variables, fixture.variables,
+                "variables of request {i}"
+            );
+            fixture.response.clone()
+        });
+        let results = run_numbers_session(&mut client);
+        assert_eq!(requests.borrow().len(), 4);
+        assert_eq!(items(&results, "a"), [0, 1, 2, 3, 4, 5, 6]);
+        assert_eq!(items(&results, "b"), [0]);
+        assert_eq!(items(&results, "c"), [0, 1, 2]);
+        assert_eq!(client.points_by_phase().values().sum::<u64>(), 4);
+    }
+
+    /// Rewrite the fixtures in [`NUMBERS_FIXTURES`] after a change to the
+    /// requests that `BatchPaginator` makes.  Run with:
+    ///
+    /// ```text
+    /// cargo test -p gqlient -- --ignored regenerate_numbers_fixtures
+    /// ```
+    #[test]
+    #[ignore = "rewrites the fixtures under tests/fixtures/"]
+    fn regenerate_numbers_fixtures() {
+        match std::fs::remove_dir_all(NUMBERS_FIXTURES) {
+            Ok(()) => (),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => (),
+            Err(e) => panic!("failed to remove {NUMBERS_FIXTURES}: {e}"),
+        }
+        let (mut client, _) =
+            fake_client(|_, payload| json!({"data": serve_numbers(&payload.variables)}));
+        client.capture_fixtures(PathBuf::from(NUMBERS_FIXTURES));
+        run_numbers_session(&mut client);
+    }
+
+    #[test]
+    fn capture_fixtures() {
+        let dir = std::env::temp_dir().join(format!("gqlient-fixtures-{}", std::process::id()));
+        let (mut client, requests) =
+            fake_client(|_, payload| json!({"data": serve_numbers(&payload.variables)}));
+        client.capture_fixtures(dir.clone());
+        let results = client
+            .batch_paginate([("a", Numbers::new(3, 2)), ("b", Numbers::new(2, 2))])
+            .unwrap();
+        let fixtures = load_fixtures(&dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+        let fixtures = fixtures.unwrap();
+        assert_eq!(items(&results, "a"), [0, 1, 2]);
+        assert_eq!(items(&results, "b"), [0, 1]);
+        let requests = requests.borrow();
+        assert_eq!(fixtures.len(), requests.len());
+        for (fixture, payload) in fixtures.iter().zip(requests.iter()) {
+            assert_eq!(fixture.query, payload.query);
+            assert_eq!(fixture.variables, payload.variables);
+            assert_eq!(
+                fixture.response,
+                json!({"data": serve_numbers(&payload.variables)})
+            );
+        }
+    }
+
+    fn cursor(s: &str) -> Cursor {
+        serde_json::from_value(s.into()).unwrap()
+    }
+
+    /// Return the value of variable `name` in each of `requests`
+    fn variable_values(requests: &[Payload], name: &str) -> Vec<Value> {
+        requests
+            .iter()
+            .map(|p| p.variables.get(name).cloned().unwrap_or(Value::Null))
+            .collect()
+    }
+
+    #[test]
+    fn missing_alias_is_retried() {
+        let (client, requests) = fake_client(|i, payload| {
+            let mut data = serve_numbers(&payload.variables);
+            if i == 0 {
+                data.remove("q1");
+            }
+            json!({"data": data})
+        });
+        let results = client
+            .batch_paginate([("a", Numbers::new(2, 2)), ("b", Numbers::new(2, 2))])
+            .unwrap();
+        assert_eq!(items(&results, "a"), [0, 1]);
+        assert_eq!(items(&results, "b"), [0, 1]);
+        let requests = requests.borrow();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(
+            variable_values(&requests, "q1_cursor"),
+            [Value::Null, Value::Null]
+        );
+        assert!(!requests[1].variables.contains_key("q0_total"));
+    }
+
+    #[test]
+    fn missing_alias_gives_up() {
+        let (client, requests) = fake_client(|_, payload| {
+            let mut data = serve_numbers(&payload.variables);
+            data.remove("q1");
+            json!({"data": data})
+        });
+        let IsolatedResults { results, failures } =
+            client.batch_paginate_isolated([("a", Numbers::new(4, 2)), ("b", Numbers::new(2, 2))]);
+        assert_eq!(items(&results, "a"), [0, 1, 2, 3]);
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].key, "b");
+        let err = failures[0]
+            .error
+            .downcast_ref::<MissingAliasError>()
+            .unwrap();
+        assert_eq!(err.alias, "q1");
+        let expected = usize::try_from(MISSING_ALIAS_RETRIES).unwrap() + 1;
+        assert_eq!(requests.borrow().len(), expected);
+    }
+
+    #[test]
+    fn subquery_errors_are_retried() {
+        let (client, requests) = fake_client(|i, payload| {
+            let mut data = serve_numbers(&payload.variables);
+            if i == 0 {
+                data.remove("q1");
+                json!({
+                    "data": data,
+                    "errors": [{"type": "TIMEOUT", "message": "timed out", "path": ["q1"]}],
+                })
+            } else {
+                json!({"data": data})
+            }
+        });
+        let results = client
+            .batch_paginate([("a", Numbers::new(2, 2)), ("b", Numbers::new(2, 2))])
+            .unwrap();
+        assert_eq!(items(&results, "a"), [0, 1]);
+        assert_eq!(items(&results, "b"), [0, 1]);
+        assert_eq!(requests.borrow().len(), 2);
+        assert_eq!(client.partial_responses(), 1);
+    }
+
+    #[test]
+    fn undeserializable_data_is_retried() {
+        let (mut client, requests) = fake_client(|i, payload| {
+            let mut data = serve_numbers(&payload.variables);
+            if i == 0 {
+                data.insert(String::from("q0"), json!({"nodes": "garbage"}));
+            }
+            json!({"data": data})
+        });
+        client.deserialize_retries(1);
+        let results = client.batch_paginate([("a", Numbers::new(3, 2))]).unwrap();
+        assert_eq!(items(&results, "a"), [0, 1, 2]);
+        let requests = requests.borrow();
+        assert_eq!(
+            variable_values(&requests, "q0_cursor"),
+            [Value::Null, Value::Null, json!("2")]
+        );
+    }
+
+    #[test]
+    fn rejected_cursor_is_reset() {
+        let (client, requests) = fake_client(|i, payload| {
+            if i == 0 {
+                json!({
+                    "errors": [{
+                        "type": "INVALID_CURSOR_ARGUMENTS",
+                        "message": "`2` does not appear to be a valid cursor.",
+                        "path": ["q1"],
+                    }],
+                })
+            } else {
+                json!({"data": serve_numbers(&payload.variables)})
+            }
+        });
+        let mut saved = Numbers::new(3, 2);
+        saved.cursor = Some(cursor("2"));
+        let results = client
+            .batch_paginate([("a", Numbers::new(1, 2)), ("b", saved)])
+            .unwrap();
+        assert_eq!(items(&results, "a"), [0]);
+        // The items after the saved cursor are fetched again from the start.
+        assert_eq!(items(&results, "b"), [0, 1, 2]);
+        assert_eq!(client.cursors_reset(), 1);
+        let requests = requests.borrow();
+        assert_eq!(
+            variable_values(&requests, "q1_cursor"),
+            [json!("2"), Value::Null, json!("2")]
+        );
+    }
+
+    #[test]
+    fn adapt_page_size_near_node_limit() {
+        let (mut client, requests) =
+            fake_client(|_, payload| json!({"data": serve_numbers(&payload.variables)}));
+        client.node_limit(1000);
+        client.adaptive_page_size(Duration::from_secs(3600));
+        let mut numbers = Numbers::new(20, 10);
+        numbers.nodes_per_item = Some(100);
+        let results = client.batch_paginate([("a", numbers)]).unwrap();
+        assert_eq!(items(&results, "a"), (0..20).collect::<Vec<_>>());
+        // A page of 10 items counts for 1000 nodes, which is within 90% of
+        // the limit; a page of 5 items is not.
+        assert_eq!(
+            variable_values(&requests.borrow(), "q0_page_size"),
+            [json!(10), json!(5), json!(5)]
+        );
+        assert_eq!(client.page_size_reductions(), 1);
+    }
+
+    #[test]
+    fn adapt_page_size_after_slow_request() {
+        let (mut client, requests) =
+            fake_client(|_, payload| json!({"data": serve_numbers(&payload.variables)}));
+        // Every request takes at least zero seconds, so every request is
+        // "slow."
+        client.adaptive_page_size(Duration::ZERO);
+        let results = client
+            .batch_paginate([("a", Numbers::new(7, 4)), ("b", Numbers::new(1, 4))])
+            .unwrap();
+        assert_eq!(items(&results, "a"), (0..7).collect::<Vec<_>>());
+        assert_eq!(items(&results, "b"), [0]);
+        // Only paginators with further pages have their page sizes lowered.
+        assert_eq!(
+            variable_values(&requests.borrow(), "q0_page_size"),
+            [json!(4), json!(2), json!(1)]
+        );
+        assert_eq!(client.page_size_reductions(), 2);
+    }
+
+    /// Return paginators that each count for `nodes` nodes per page, or a
+    /// single node if `nodes` is `None`
+    fn weighted(qty: usize, nodes: Option<u64>) -> Vec<(usize, Numbers)> {
+        (0..qty)
+            .map(|i| {
+                let mut numbers = Numbers::new(1, 1);
+                numbers.nodes_per_item = nodes;
+                (i, numbers)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn machine_without_client() {
+        let mut machine = BatchMachine::new(
+            MachineConfig::default(),
+            [("a", Numbers::new(5, 2)), ("b", Numbers::new(1, 2))],
+        );
+        let mut requests = 0;
+        while let Some(request) = machine.start_request(50).unwrap() {
+            requests += 1;
+            assert_eq!(request.aliases.len(), if requests == 1 { 2 } else { 1 });
+            let response = Response {
+                data: serve_numbers(&request.variables),
+                errors: Vec::new(),
+            };
+            machine
+                .handle_response(response, Instant::now(), false, Query::parse_response)
+                .unwrap();
+            let events = machine.take_events();
+            assert_eq!(events[0], Event::RequestCompleted { cost: Some(1) });
+        }
+        assert_eq!(requests, 3);
+        assert!(machine.is_done());
+        let results = machine.flush();
+        assert_eq!(items(&results, "a"), [0, 1, 2, 3, 4]);
+        assert_eq!(items(&results, "b"), [0]);
+    }
+
+    #[test]
+    fn machine_rejects_unrequested_response() {
+        let mut machine = BatchMachine::new(MachineConfig::default(), [("a", Numbers::new(5, 2))]);
+        let response = Response {
+            data: JsonMap::new(),
+            errors: Vec::new(),
+        };
+        assert!(machine
+            .handle_response(response, Instant::now(), false, Query::parse_response)
+            .is_err());
+        assert!(!machine.is_done());
+    }
+
+    #[test]
+    fn next_batch_len_without_node_limit() {
+        let (client, _) = fake_client(|_, _| unreachable!());
+        let bp = BatchMachine::new(client.machine_config(), weighted(5, Some(1_000_000)));
+        assert_eq!(bp.next_batch_len(3), 3);
+        assert_eq!(bp.next_batch_len(10), 5);
+    }
+
+    #[test]
+    fn next_batch_len_at_node_limit() {
+        let (mut client, _) = fake_client(|_, _| unreachable!());
+        client.node_limit(500_000);
+        // Requests may use exactly as many nodes as the limit.
+        let bp = BatchMachine::new(client.machine_config(), weighted(10, Some(100_000)));
+        assert_eq!(bp.next_batch_len(50), 5);
+        assert_eq!(bp.next_batch_len(3), 3);
+        // Pages of 100 issues with up to 100 labels each
+        let bp = BatchMachine::new(client.machine_config(), weighted(60, Some(10_100)));
+        assert_eq!(bp.next_batch_len(100), 49);
+        // A paginator over the limit on its own is still queried.
+        let bp = BatchMachine::new(client.machine_config(), weighted(2, Some(600_000)));
+        assert_eq!(bp.next_batch_len(50), 1);
+        // Paginators that don't know their node counts count for one node.
+        client.node_limit(3);
+        let bp = BatchMachine::new(client.machine_config(), weighted(5, None));
+        assert_eq!(bp.next_batch_len(50), 3);
+    }
+
+    #[test]
+    fn null_data_with_errors() {
+        let (client, _) = fake_client(|_, _| {
+            json!({
+                "data": null,
+                "errors": [{
+                    "type": "NOT_FOUND",
+                    "message": "Could not resolve to a Repository with the name 'jwodder/nope'.",
+                    "path": ["repository"],
+                }],
+            })
+        });
+        let response = client
+            .query_partial(
+                String::from("query { repository(owner: \"jwodder\", name: \"nope\") { id } }"),
+                JsonMap::new(),
+            )
+            .unwrap();
+        assert!(response.data.is_empty());
+        let err = response.into_data().unwrap_err();
+        assert_eq!(err.errors.len(), 1);
+        assert_eq!(err.errors[0].err_type, Some(GqlErrorType::NotFound));
+    }
+
+    #[test]
+    fn node_limit_error_lowers_page_sizes() {
+        let (client, requests) = fake_client(|i, payload| {
+            if i == 0 {
+                json!({
+                    "errors": [{
+                        "type": "MAX_NODE_LIMIT_EXCEEDED",
+                        "message": "This query requests up to 600,000 possible nodes which exceeds the maximum limit of 500,000.",
+                    }],
+                })
+            } else {
+                json!({"data": serve_numbers(&payload.variables)})
+            }
+        });
+        let results = client
+            .batch_paginate([("a", Numbers::new(4, 4)), ("b", Numbers::new(1, 1))])
+            .unwrap();
+        assert_eq!(items(&results, "a"), [0, 1, 2, 3]);
+        assert_eq!(items(&results, "b"), [0]);
+        let requests = requests.borrow();
+        assert_eq!(
+            variable_values(&requests, "q0_page_size"),
+            [json!(4), json!(2), json!(2)]
+        );
+        // A page size of one cannot be lowered any further.
+        assert_eq!(
+            variable_values(&requests, "q1_page_size"),
+            [json!(1), json!(1), Value::Null]
+        );
+        assert_eq!(client.page_size_reductions(), 1);
+    }
+
+    #[test]
+    fn node_limit_error_at_minimum_page_size() {
+        let (client, requests) = fake_client(|_, _| {
+            json!({
+                "errors": [{
+                    "type": "MAX_NODE_LIMIT_EXCEEDED",
+                    "message": "This query requests too many nodes.",
+                }],
+            })
+        });
+        let r = client.batch_paginate([("a", Numbers::new(4, 1))]);
+        assert!(r.is_err());
+        assert_eq!(requests.borrow().len(), 1);
+    }
+}
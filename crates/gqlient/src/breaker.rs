@@ -0,0 +1,199 @@
+use std::cell::Cell;
+use std::fmt;
+use std::num::NonZeroU32;
+use std::time::{Duration, Instant};
+
+/// Configuration for a circuit breaker that stops a [`Client`](crate::Client)
+/// from making requests while GitHub appears to be unavailable
+///
+/// Once `failure_threshold` consecutive requests have failed due to network
+/// or server errors (after any retries), the circuit "opens," and every
+/// request fails immediately with a [`CircuitOpenError`] until `cooldown` has
+/// elapsed.  The next request after that is sent as a probe: if it succeeds,
+/// the circuit closes and requests are made as normal again; if it fails, the
+/// circuit reopens for another `cooldown`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CircuitBreaker {
+    pub failure_threshold: NonZeroU32,
+    pub cooldown: Duration,
+}
+
+impl CircuitBreaker {
+    pub fn new(failure_threshold: NonZeroU32, cooldown: Duration) -> CircuitBreaker {
+        CircuitBreaker {
+            failure_threshold,
+            cooldown,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum State {
+    Closed { failures: u32 },
+    Open { until: Instant },
+    HalfOpen,
+}
+
+/// A [`CircuitBreaker`] together with its current state
+#[derive(Clone, Debug)]
+pub(crate) struct Breaker {
+    config: CircuitBreaker,
+    state: Cell<State>,
+}
+
+impl Breaker {
+    pub(crate) fn new(config: CircuitBreaker) -> Breaker {
+        Breaker {
+            config,
+            state: Cell::new(State::Closed { failures: 0 }),
+        }
+    }
+
+    /// Return an error if requests should not currently be made.  If the
+    /// circuit is open but its cooldown has elapsed, it becomes half-open, and
+    /// the next request is allowed through as a probe.
+    pub(crate) fn check(&self) -> Result<(), CircuitOpenError> {
+        if let State::Open { until } = self.state.get() {
+            let now = Instant::now();
+            if now < until {
+                return Err(CircuitOpenError {
+                    failures: self.config.failure_threshold.get(),
+                    retry_in: until - now,
+                });
+            }
+            self.state.set(State::HalfOpen);
+        }
+        Ok(())
+    }
+
+    /// Record whether a request failed due to a network or server error
+    pub(crate) fn record(&self, failed: bool) {
+        let state = match (self.state.get(), failed) {
+            (_, false) => State::Closed { failures: 0 },
+            (State::Closed { failures }, true)
+                if failures + 1 < self.config.failure_threshold.get() =>
+            {
+                State::Closed {
+                    failures: failures + 1,
+                }
+            }
+            (State::Open { until }, true) => State::Open { until },
+            (State::Closed { .. }, true) => {
+                eprintln!(
+                    "[!] {} consecutive requests failed; not making any requests for {:?}",
+                    self.config.failure_threshold, self.config.cooldown
+                );
+                self.open_until()
+            }
+            (State::HalfOpen, true) => {
+                eprintln!(
+                    "[!] Probe request failed; not making any requests for another {:?}",
+                    self.config.cooldown
+                );
+                self.open_until()
+            }
+        };
+        self.state.set(state);
+    }
+
+    fn open_until(&self) -> State {
+        State::Open {
+            until: Instant::now() + self.config.cooldown,
+        }
+    }
+}
+
+/// The error returned for requests that a [`Client`](crate::Client) refuses
+/// to make because its [`CircuitBreaker`] is open
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CircuitOpenError {
+    /// The number of consecutive failures that opened the circuit
+    pub failures: u32,
+    /// How long until a request will next be attempted
+    pub retry_in: Duration,
+}
+
+impl fmt::Display for CircuitOpenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "not making request: GitHub appears to be unavailable after {} consecutive failed requests; requests will resume in {:?}",
+            self.failures, self.retry_in
+        )
+    }
+}
+
+impl std::error::Error for CircuitOpenError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn breaker(failure_threshold: u32, cooldown: Duration) -> Breaker {
+        Breaker::new(CircuitBreaker::new(
+            NonZeroU32::new(failure_threshold).unwrap(),
+            cooldown,
+        ))
+    }
+
+    const LONG: Duration = Duration::from_secs(3600);
+
+    #[test]
+    fn opens_after_consecutive_failures() {
+        let b = breaker(3, LONG);
+        b.record(true);
+        b.record(true);
+        assert_eq!(b.state.get(), State::Closed { failures: 2 });
+        assert!(b.check().is_ok());
+        b.record(true);
+        assert!(matches!(b.state.get(), State::Open { .. }));
+        let err = b.check().unwrap_err();
+        assert_eq!(err.failures, 3);
+        assert!(err.retry_in <= LONG);
+    }
+
+    #[test]
+    fn success_resets_failure_count() {
+        let b = breaker(3, LONG);
+        b.record(true);
+        b.record(true);
+        b.record(false);
+        assert_eq!(b.state.get(), State::Closed { failures: 0 });
+        b.record(true);
+        assert_eq!(b.state.get(), State::Closed { failures: 1 });
+    }
+
+    #[test]
+    fn failures_while_open_keep_cooldown() {
+        let b = breaker(1, LONG);
+        b.record(true);
+        let open = b.state.get();
+        assert!(matches!(open, State::Open { .. }));
+        b.record(true);
+        assert_eq!(b.state.get(), open);
+    }
+
+    #[test]
+    fn probe_success_closes() {
+        let b = breaker(1, Duration::ZERO);
+        b.record(true);
+        assert!(matches!(b.state.get(), State::Open { .. }));
+        assert!(b.check().is_ok());
+        assert_eq!(b.state.get(), State::HalfOpen);
+        b.record(false);
+        assert_eq!(b.state.get(), State::Closed { failures: 0 });
+    }
+
+    #[test]
+    fn probe_failure_reopens() {
+        let b = breaker(2, Duration::ZERO);
+        b.record(true);
+        b.record(true);
+        assert!(b.check().is_ok());
+        assert_eq!(b.state.get(), State::HalfOpen);
+        // A single failed probe reopens the circuit, regardless of the
+        // failure threshold.
+        b.record(true);
+        assert!(matches!(b.state.get(), State::Open { .. }));
+    }
+}
@@ -0,0 +1,269 @@
+use crate::auth::{AuthProvider, GhToken, StaticToken};
+use crate::connect::ConnectionStats;
+use crate::transport::{Transport, UreqTransport};
+use crate::{Client, DEFAULT_BATCH_SIZE};
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::num::NonZeroUsize;
+use std::rc::Rc;
+use std::time::Duration;
+use ureq::{AgentBuilder, Middleware};
+
+static DEFAULT_GRAPHQL_URL: &str = "https://api.github.com/graphql";
+static DEFAULT_REST_URL: &str = "https://api.github.com";
+
+/// A builder for configuring a [`Client`]
+///
+/// Unless otherwise configured, the client authenticates with a token looked
+/// up via [`GhToken`], talks to `api.github.com`, only makes HTTPS requests,
+/// and does not retry failed requests.
+///
+/// Requests are sent with a `ureq` agent unless another [`Transport`] is
+/// supplied with [`ClientBuilder::transport()`].  The agent is also given a
+/// host name resolver and TLS connector that time each new connection's DNS
+/// lookup & TLS handshake for [`Client::transfer_stats()`]; replacing either
+/// via [`ClientBuilder::configure_agent()`] leaves the corresponding timings at
+/// zero.
+pub struct ClientBuilder {
+    agent: AgentBuilder,
+    // Whether middleware or other configuration was supplied for the agent,
+    // which a custom transport would ignore
+    agent_configured: bool,
+    transport: Option<Rc<dyn Transport>>,
+    connections: ConnectionStats,
+    auth: Option<Rc<RefCell<dyn AuthProvider>>>,
+    graphql_url: String,
+    rest_url: String,
+    https_only: bool,
+    retry: RetryPolicy,
+}
+
+impl ClientBuilder {
+    pub fn new() -> ClientBuilder {
+        let connections = ConnectionStats::default();
+        ClientBuilder {
+            agent: connections.agent_builder(),
+            agent_configured: false,
+            transport: None,
+            connections,
+            auth: None,
+            graphql_url: String::from(DEFAULT_GRAPHQL_URL),
+            rest_url: String::from(DEFAULT_REST_URL),
+            https_only: true,
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    /// Authenticate with a fixed access token
+    pub fn token(self, token: &str) -> ClientBuilder {
+        self.auth_provider(StaticToken::new(token))
+    }
+
+    /// Obtain access tokens from `auth`.
+    ///
+    /// `auth` is asked for a token before the first request and again
+    /// whenever a request is rejected with a 401 Unauthorized response, in
+    /// which case the request is retried once with the new token.
+    pub fn auth_provider<A: AuthProvider + 'static>(mut self, auth: A) -> ClientBuilder {
+        self.auth = Some(Rc::new(RefCell::new(auth)));
+        self
+    }
+
+    /// Set the URL of the GraphQL endpoint, e.g., for use with GitHub
+    /// Enterprise Server [default: `https://api.github.com/graphql`]
+    pub fn graphql_url<S: Into<String>>(mut self, url: S) -> ClientBuilder {
+        self.graphql_url = url.into();
+        self
+    }
+
+    /// Set the base URL of the REST API, used for rate limit and repository
+    /// lookups [default: `https://api.github.com`]
+    pub fn rest_url<S: Into<String>>(mut self, url: S) -> ClientBuilder {
+        self.rest_url = url.into().trim_end_matches('/').to_owned();
+        self
+    }
+
+    /// Set the timeout for each individual HTTP request
+    pub fn timeout(mut self, timeout: Duration) -> ClientBuilder {
+        self.agent = self.agent.timeout(timeout);
+        self
+    }
+
+    /// Set the timeout for establishing connections
+    pub fn connect_timeout(mut self, timeout: Duration) -> ClientBuilder {
+        self.agent = self.agent.timeout_connect(timeout);
+        self
+    }
+
+    /// Override the `User-Agent` header sent with each request
+    pub fn user_agent(mut self, user_agent: &str) -> ClientBuilder {
+        self.agent = self.agent.user_agent(user_agent);
+        self
+    }
+
+    /// Set how requests that fail due to network or server errors are
+    /// retried
+    pub fn retry_policy(mut self, retry: RetryPolicy) -> ClientBuilder {
+        self.retry = retry;
+        self
+    }
+
+    /// Register middleware with the underlying `ureq` agent.
+    ///
+    /// The authentication header and the other GitHub headers are set on each
+    /// request before it is passed to the agent, and so any middleware added
+    /// here will see requests with those headers set.
+    ///
+    /// Middleware cannot be combined with [`ClientBuilder::transport()`].
+    pub fn middleware<M: Middleware>(mut self, m: M) -> ClientBuilder {
+        self.agent = self.agent.middleware(m);
+        self.agent_configured = true;
+        self
+    }
+
+    /// Further configure the underlying `ureq` agent with `configure`.  The
+    /// same considerations apply as for [`ClientBuilder::middleware()`].
+    pub fn configure_agent<F>(mut self, configure: F) -> ClientBuilder
+    where
+        F: FnOnce(AgentBuilder) -> AgentBuilder,
+    {
+        self.agent = configure(self.agent);
+        self.agent_configured = true;
+        self
+    }
+
+    /// Send requests with `transport` instead of with a `ureq` agent.  The
+    /// options that configure the agent (the timeouts, user agent, and
+    /// HTTPS-only setting) then have no effect, and no connection statistics
+    /// are recorded.  As middleware and [`ClientBuilder::configure_agent()`]
+    /// would likewise have no effect, combining either of them with a custom
+    /// transport makes [`ClientBuilder::build()`] fail.
+    pub fn transport<T: Transport + 'static>(mut self, transport: T) -> ClientBuilder {
+        self.transport = Some(Rc::new(transport));
+        self
+    }
+
+    /// Whether to refuse to make non-HTTPS requests [default: true]
+    pub fn https_only(mut self, https_only: bool) -> ClientBuilder {
+        self.https_only = https_only;
+        self
+    }
+
+    /// Build the client.
+    ///
+    /// # Errors
+    ///
+    /// Fails if both a custom transport and middleware or other agent
+    /// configuration were supplied.
+    pub fn build(self) -> anyhow::Result<Client> {
+        if self.transport.is_some() && self.agent_configured {
+            anyhow::bail!(
+                "ClientBuilder::middleware() and ClientBuilder::configure_agent() cannot be combined with ClientBuilder::transport()"
+            );
+        }
+        Ok(self.build_unchecked())
+    }
+
+    /// Build the client without checking for a custom transport combined
+    /// with agent configuration, for constructors that never supply a
+    /// transport
+    #[allow(clippy::missing_panics_doc)]
+    pub(crate) fn build_unchecked(self) -> Client {
+        let batch_size =
+            NonZeroUsize::new(DEFAULT_BATCH_SIZE).expect("default batch size should be nonzero");
+        Client {
+            transport: self.transport.unwrap_or_else(|| {
+                Rc::new(UreqTransport::new(
+                    self.agent.https_only(self.https_only).build(),
+                ))
+            }),
+            batch_size,
+            node_limit: None,
+            fixture_dir: None,
+            fixture_count: Cell::new(0),
+            verbose: false,
+            pause_below: None,
+            request_timeout: None,
+            slow_request_threshold: None,
+            adaptive_page_size: None,
+            deserialize_retries: 0,
+            bad_data_dir: None,
+            discard_bad_data: false,
+            compress_above: None,
+            transfer: Cell::default(),
+            idle_since: Cell::new(None),
+            last_response: Cell::new(None),
+            connections: self.connections,
+            cursors_reset: Cell::new(0),
+            partial_responses: Cell::new(0),
+            page_size_reductions: Cell::new(0),
+            query_hashes: RefCell::default(),
+            last_rate_limit: Cell::new(None),
+            auth: self.auth.unwrap_or_else(|| Rc::new(RefCell::new(GhToken))),
+            token: RefCell::new(None),
+            graphql_url: self.graphql_url,
+            rest_url: self.rest_url,
+            retry: self.retry,
+            retry_budget: Cell::new(None),
+            breaker: None,
+            phase_points: Rc::default(),
+            unused_fields: None,
+            #[cfg(feature = "otel")]
+            tracer: None,
+        }
+    }
+}
+
+impl Default for ClientBuilder {
+    fn default() -> ClientBuilder {
+        ClientBuilder::new()
+    }
+}
+
+impl fmt::Debug for ClientBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClientBuilder")
+            .field("transport", &self.transport)
+            .field("auth", &self.auth)
+            .field("graphql_url", &self.graphql_url)
+            .field("rest_url", &self.rest_url)
+            .field("https_only", &self.https_only)
+            .field("retry", &self.retry)
+            .finish_non_exhaustive()
+    }
+}
+
+/// How a [`Client`] retries requests that fail due to network errors or
+/// server errors (HTTP 500, 502, 503, or 504)
+///
+/// Retries are made after a delay that starts at `initial_delay` and doubles
+/// on each subsequent retry of the same request.  The default policy makes no
+/// retries.
+///
+/// Requests rejected by GitHub's rate limits (whether with an HTTP 403 or 429
+/// or with a `RATE_LIMITED` GraphQL error) are not subject to the policy:
+/// they are always retried (up to three times in a row) after waiting as long
+/// as GitHub asks.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub initial_delay: Duration,
+}
+
+impl RetryPolicy {
+    pub fn new(max_retries: u32, initial_delay: Duration) -> RetryPolicy {
+        RetryPolicy {
+            max_retries,
+            initial_delay,
+        }
+    }
+
+    /// Return the delay before the given retry (counting from zero), or
+    /// `None` if no more retries should be made
+    pub(crate) fn delay(&self, retry: u32) -> Option<Duration> {
+        (retry < self.max_retries).then(|| {
+            self.initial_delay
+                .saturating_mul(2u32.saturating_pow(retry))
+        })
+    }
+}
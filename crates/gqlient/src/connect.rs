@@ -0,0 +1,134 @@
+use crate::transfer::TransferStats;
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Instant;
+use ureq::{AgentBuilder, ReadWrite, Resolver, TlsConnector};
+
+/// Connection counts & timings recorded by the resolver and TLS connector
+/// that a [`ClientBuilder`](crate::ClientBuilder) installs on its agent.
+/// Only the connection-related fields of the inner [`TransferStats`] are
+/// used.
+///
+/// `ureq` does not report when it opens a connection, but it resolves the
+/// host name for each new connection (and never for one reused from its
+/// pool), and each HTTPS connection's TLS handshake is completed within the
+/// TLS connector's `connect()` method, so timing those two calls is the most
+/// it allows.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ConnectionStats(Arc<Mutex<TransferStats>>);
+
+impl ConnectionStats {
+    pub(crate) fn get(&self) -> TransferStats {
+        *self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn record<F: FnOnce(&mut TransferStats)>(&self, f: F) {
+        f(&mut self.0.lock().unwrap_or_else(PoisonError::into_inner));
+    }
+
+    /// Return a `ureq` agent builder with this instance's resolver and TLS
+    /// connector installed
+    pub(crate) fn agent_builder(&self) -> AgentBuilder {
+        AgentBuilder::new()
+            .resolver(self.resolver())
+            .tls_connector(Arc::new(self.tls_connector()))
+    }
+
+    pub(crate) fn resolver(&self) -> TimedResolver {
+        TimedResolver(self.clone())
+    }
+
+    pub(crate) fn tls_connector(&self) -> TimedTlsConnector {
+        TimedTlsConnector {
+            inner: tls_backend(),
+            stats: self.clone(),
+        }
+    }
+}
+
+/// A [`Resolver`] that resolves host names like `ureq`'s default resolver
+/// and records the time taken
+pub(crate) struct TimedResolver(ConnectionStats);
+
+impl Resolver for TimedResolver {
+    fn resolve(&self, netloc: &str) -> io::Result<Vec<SocketAddr>> {
+        let start = Instant::now();
+        let r = netloc.to_socket_addrs().map(Iterator::collect);
+        let elapsed = start.elapsed();
+        self.0.record(|stats| {
+            stats.connections += 1;
+            stats.dns_time += elapsed;
+        });
+        r
+    }
+}
+
+/// A [`TlsConnector`] that wraps the backend selected via cargo features and
+/// records the time taken by each handshake
+pub(crate) struct TimedTlsConnector {
+    /// The backend, or an error message if it could not be initialized, in
+    /// which case the message is returned for every connection attempt
+    inner: Result<Arc<dyn TlsConnector>, String>,
+    stats: ConnectionStats,
+}
+
+impl TlsConnector for TimedTlsConnector {
+    fn connect(
+        &self,
+        dns_name: &str,
+        io: Box<dyn ReadWrite>,
+    ) -> Result<Box<dyn ReadWrite>, ureq::Error> {
+        let inner = self
+            .inner
+            .as_ref()
+            .map_err(|msg| io::Error::other(msg.clone()))?;
+        let start = Instant::now();
+        let stream = inner.connect(dns_name, io)?;
+        let elapsed = start.elapsed();
+        self.stats.record(|stats| {
+            stats.tls_handshakes += 1;
+            stats.tls_handshake_time += elapsed;
+        });
+        Ok(stream)
+    }
+}
+
+/// The name of the TLS backend selected via cargo features.  If both the
+/// `native-tls` and `rustls` features are enabled, `native-tls` is used.
+pub const TLS_BACKEND: &str = if cfg!(feature = "native-tls") {
+    "native-tls"
+} else if cfg!(feature = "rustls") {
+    "rustls"
+} else {
+    "none"
+};
+
+#[cfg(feature = "native-tls")]
+fn tls_backend() -> Result<Arc<dyn TlsConnector>, String> {
+    match ureq::native_tls::TlsConnector::new() {
+        Ok(connector) => Ok(Arc::new(connector)),
+        Err(e) => Err(format!("failed to initialize native TLS backend: {e}")),
+    }
+}
+
+#[cfg(all(feature = "rustls", not(feature = "native-tls")))]
+fn tls_backend() -> Result<Arc<dyn TlsConnector>, String> {
+    use ureq::rustls::{crypto::ring, ClientConfig, RootCertStore};
+    // Configured the same way as `ureq`'s own default rustls connector
+    let config = ClientConfig::builder_with_provider(ring::default_provider().into())
+        .with_safe_default_protocol_versions()
+        .map_err(|e| format!("failed to initialize rustls TLS backend: {e}"))?
+        .with_root_certificates(RootCertStore {
+            roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+        })
+        .with_no_client_auth();
+    Ok(Arc::new(Arc::new(config)))
+}
+
+#[cfg(not(any(feature = "rustls", feature = "native-tls")))]
+fn tls_backend() -> Result<Arc<dyn TlsConnector>, String> {
+    Err(String::from(
+        "gqlient was built without a TLS backend; enable its \"rustls\" or \"native-tls\" feature",
+    ))
+}
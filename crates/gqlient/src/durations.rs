@@ -0,0 +1,55 @@
+use serde::{Serialize, Serializer};
+use std::time::Duration;
+
+/// Serialize a [`Duration`] as an object with a `seconds` field giving its
+/// length in fractional seconds and a `human` field giving it in
+/// human-readable form (e.g., `"1m 23s 456ms"`, rounded down to the
+/// millisecond), for use with `#[serde(serialize_with)]` on the durations in
+/// run reports
+pub fn serialize_duration<S: Serializer>(d: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    SerializedDuration::new(*d).serialize(serializer)
+}
+
+/// Like [`serialize_duration()`], but for optional durations, which are
+/// serialized as `null` when absent
+pub fn serialize_opt_duration<S: Serializer>(
+    d: &Option<Duration>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    d.map(SerializedDuration::new).serialize(serializer)
+}
+
+/// If `value` is a duration as serialized by [`serialize_duration()`] or as
+/// serialized by serde's default implementation for [`Duration`] (an object
+/// with only `secs` and `nanos` fields, as found in older run reports),
+/// return its length in seconds
+#[allow(clippy::cast_precision_loss)]
+pub fn duration_secs(value: &serde_json::Value) -> Option<f64> {
+    let map = value.as_object()?;
+    if map.len() != 2 {
+        return None;
+    }
+    if map.contains_key("human") {
+        map.get("seconds")?.as_f64()
+    } else {
+        let secs = map.get("secs")?.as_u64()?;
+        let nanos = map.get("nanos")?.as_u64()?;
+        Some(secs as f64 + nanos as f64 / 1e9)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+struct SerializedDuration {
+    seconds: f64,
+    human: String,
+}
+
+impl SerializedDuration {
+    fn new(d: Duration) -> SerializedDuration {
+        let rounded = Duration::new(d.as_secs(), d.subsec_millis() * 1_000_000);
+        SerializedDuration {
+            seconds: d.as_secs_f64(),
+            human: humantime::format_duration(rounded).to_string(),
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use crate::types::Cursor;
+use serde::Deserialize;
+use std::fmt;
+use std::path::PathBuf;
+
+/// An error response from the GraphQL API, consisting of one or more
+/// individual errors
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GqlError {
+    pub errors: Vec<GqlInnerError>,
+}
+
+impl GqlError {
+    /// Returns true if any of the errors is due to exceeding the rate limit
+    pub fn is_rate_limited(&self) -> bool {
+        self.errors
+            .iter()
+            .any(|e| e.err_type == Some(GqlErrorType::RateLimited))
+    }
+
+    /// Returns true if any of the errors is due to the query requesting too
+    /// many nodes
+    pub fn is_node_limit(&self) -> bool {
+        self.errors
+            .iter()
+            .any(|e| e.err_type == Some(GqlErrorType::MaxNodeLimitExceeded))
+    }
+
+    /// If every error is due to a rejected pagination cursor, return the
+    /// first elements of the errors' paths, i.e., the aliases of the
+    /// sub-queries whose cursors were rejected; otherwise, return `None`
+    pub fn invalid_cursor_aliases(&self) -> Option<Vec<&str>> {
+        self.errors
+            .iter()
+            .map(|e| {
+                if e.err_type == Some(GqlErrorType::InvalidCursor) {
+                    e.path.as_ref()?.first().map(String::as_str)
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for GqlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Query errored:")?;
+        let mut first = true;
+        for e in &self.errors {
+            if !std::mem::take(&mut first) {
+                writeln!(f, "---")?;
+            }
+            if let Some(ref t) = e.err_type {
+                writeln!(f, "    Type: {t}")?;
+            }
+            writeln!(f, "    Message: {}", e.message)?;
+            if let Some(ref p) = e.path {
+                writeln!(f, "    Path: {p:?}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for GqlError {}
+
+/// The error returned when a GraphQL response to a batched request lacks the
+/// data for one of the request's sub-queries (or includes errors for it in
+/// place of data), even after the sub-query has been retried
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MissingAliasError {
+    /// The alias under which the sub-query was made
+    pub alias: String,
+    /// The values of the sub-query's own variables other than its cursor,
+    /// identifying what the sub-query was for (e.g., `owner: "jwodder"`)
+    pub key: String,
+    /// The cursor after which the missing page starts, or `None` if it was
+    /// the first page
+    pub cursor: Option<Cursor>,
+    /// The errors that the last response included for the sub-query, if any
+    pub errors: Vec<GqlInnerError>,
+}
+
+impl fmt::Display for MissingAliasError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "GraphQL response did not include data for sub-query {}",
+            self.alias
+        )?;
+        if !self.key.is_empty() {
+            write!(f, " ({})", self.key)?;
+        }
+        match self.cursor {
+            Some(ref cursor) => write!(f, " for the page after cursor {cursor}")?,
+            None => write!(f, " for the first page")?,
+        }
+        for e in &self.errors {
+            match e.err_type {
+                Some(ref t) => write!(f, "; {t}: {}", e.message)?,
+                None => write!(f, "; {}", e.message)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for MissingAliasError {}
+
+/// The error returned when the data for one of a batched request's
+/// sub-queries could not be deserialized, even after any retries configured
+/// with [`Client::deserialize_retries()`](crate::Client::deserialize_retries)
+#[derive(Debug)]
+pub struct DeserializeError {
+    /// The alias under which the sub-query was made
+    pub alias: String,
+    /// The values of the sub-query's own variables other than its cursor,
+    /// identifying what the sub-query was for (e.g., `owner: "jwodder"`)
+    pub key: String,
+    /// The cursor after which the page starts, or `None` if it was the first
+    /// page
+    pub cursor: Option<Cursor>,
+    /// The file to which the sub-query's raw response data was saved, or
+    /// `None` if it could not be saved or was discarded
+    pub saved_to: Option<PathBuf>,
+    pub source: serde_json::Error,
+}
+
+impl fmt::Display for DeserializeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "failed to deserialize GraphQL response data for sub-query {}",
+            self.alias
+        )?;
+        if !self.key.is_empty() {
+            write!(f, " ({})", self.key)?;
+        }
+        match self.cursor {
+            Some(ref cursor) => write!(f, " for the page after cursor {cursor}")?,
+            None => write!(f, " for the first page")?,
+        }
+        if let Some(ref path) = self.saved_to {
+            write!(f, "; raw data saved to {}", path.display())?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for DeserializeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct GqlInnerError {
+    #[serde(default, rename = "type")]
+    pub err_type: Option<GqlErrorType>,
+    pub message: String,
+    #[serde(default)]
+    pub path: Option<Vec<String>>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq)]
+#[serde(from = "String")]
+pub enum GqlErrorType {
+    RateLimited,
+    MaxNodeLimitExceeded,
+    NotFound,
+    Forbidden,
+    Timeout,
+    /// A pagination cursor was invalid or has expired
+    InvalidCursor,
+    Other(String),
+}
+
+impl From<String> for GqlErrorType {
+    fn from(value: String) -> GqlErrorType {
+        match value.as_str() {
+            "RATE_LIMITED" => GqlErrorType::RateLimited,
+            "MAX_NODE_LIMIT_EXCEEDED" => GqlErrorType::MaxNodeLimitExceeded,
+            "NOT_FOUND" => GqlErrorType::NotFound,
+            "FORBIDDEN" => GqlErrorType::Forbidden,
+            "TIMEOUT" => GqlErrorType::Timeout,
+            "INVALID_CURSOR_ARGUMENTS" => GqlErrorType::InvalidCursor,
+            _ => GqlErrorType::Other(value),
+        }
+    }
+}
+
+impl fmt::Display for GqlErrorType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GqlErrorType::RateLimited => f.write_str("RATE_LIMITED"),
+            GqlErrorType::MaxNodeLimitExceeded => f.write_str("MAX_NODE_LIMIT_EXCEEDED"),
+            GqlErrorType::NotFound => f.write_str("NOT_FOUND"),
+            GqlErrorType::Forbidden => f.write_str("FORBIDDEN"),
+            GqlErrorType::Timeout => f.write_str("TIMEOUT"),
+            GqlErrorType::InvalidCursor => f.write_str("INVALID_CURSOR_ARGUMENTS"),
+            GqlErrorType::Other(s) => f.write_str(s),
+        }
+    }
+}
@@ -0,0 +1,558 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+/// A boolean expression over the fields of a serializable record, as given to
+/// the `--filter` option
+///
+/// The grammar is:
+///
+/// ```text
+/// expr       := and_expr ("or" and_expr)*
+/// and_expr   := not_expr ("and" not_expr)*
+/// not_expr   := "not" not_expr | "(" expr ")" | comparison
+/// comparison := field [op value]
+/// field      := name ("." name)*
+/// op         := "==" | "!=" | "<" | "<=" | ">" | ">=" | "contains"
+/// value      := "\"" string "\"" | number | "true" | "false" | "null" | word
+/// ```
+///
+/// Fields are looked up in the record's JSON serialization, with dotted names
+/// descending into nested objects; fields that do not exist are `null`.  A
+/// field without an operator tests whether the field is "truthy" (i.e., not
+/// `null`, `false`, zero, or empty).  Unquoted values that are not numbers or
+/// keywords (e.g., `2024-01-01`) are treated as strings.  `<`, `<=`, `>`, and
+/// `>=` compare numbers numerically and strings lexicographically; `contains`
+/// tests whether an array field has an element equal to the value or whether
+/// a string field contains the value as a substring.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Filter(Expr);
+
+impl Filter {
+    /// Test whether `item` matches the filter.  Items that cannot be
+    /// serialized to JSON never match.
+    pub fn matches<T: Serialize>(&self, item: &T) -> bool {
+        serde_json::to_value(item).is_ok_and(|value| self.0.eval(&value))
+    }
+}
+
+impl FromStr for Filter {
+    type Err = ParseFilterError;
+
+    fn from_str(s: &str) -> Result<Filter, ParseFilterError> {
+        let mut parser = Parser {
+            tokens: tokenize(s)?,
+            pos: 0,
+        };
+        let expr = parser.parse_or()?;
+        match parser.peek() {
+            None => Ok(Filter(expr)),
+            Some(t) => Err(ParseFilterError::new(format!(
+                "unexpected {t} after end of expression"
+            ))),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Truthy(Vec<String>),
+    Compare {
+        field: Vec<String>,
+        op: CmpOp,
+        value: Value,
+    },
+}
+
+impl Expr {
+    fn eval(&self, record: &Value) -> bool {
+        match self {
+            Expr::And(a, b) => a.eval(record) && b.eval(record),
+            Expr::Or(a, b) => a.eval(record) || b.eval(record),
+            Expr::Not(e) => !e.eval(record),
+            Expr::Truthy(field) => is_truthy(lookup(record, field)),
+            Expr::Compare { field, op, value } => op.apply(lookup(record, field), value),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum CmpOp {
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+    Contains,
+}
+
+impl CmpOp {
+    fn apply(self, lhs: &Value, rhs: &Value) -> bool {
+        match self {
+            CmpOp::Eq => values_equal(lhs, rhs),
+            CmpOp::NotEq => !values_equal(lhs, rhs),
+            CmpOp::Lt => compare(lhs, rhs) == Some(Ordering::Less),
+            CmpOp::LtEq => matches!(compare(lhs, rhs), Some(Ordering::Less | Ordering::Equal)),
+            CmpOp::Gt => compare(lhs, rhs) == Some(Ordering::Greater),
+            CmpOp::GtEq => matches!(compare(lhs, rhs), Some(Ordering::Greater | Ordering::Equal)),
+            CmpOp::Contains => match (lhs, rhs) {
+                (Value::Array(items), _) => items.iter().any(|v| values_equal(v, rhs)),
+                (Value::String(s), Value::String(sub)) => s.contains(sub.as_str()),
+                _ => false,
+            },
+        }
+    }
+}
+
+impl fmt::Display for CmpOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            CmpOp::Eq => "==",
+            CmpOp::NotEq => "!=",
+            CmpOp::Lt => "<",
+            CmpOp::LtEq => "<=",
+            CmpOp::Gt => ">",
+            CmpOp::GtEq => ">=",
+            CmpOp::Contains => "contains",
+        };
+        f.write_str(s)
+    }
+}
+
+fn lookup<'a>(record: &'a Value, field: &[String]) -> &'a Value {
+    let mut value = record;
+    for name in field {
+        match value.get(name) {
+            Some(v) => value = v,
+            None => return &Value::Null,
+        }
+    }
+    value
+}
+
+fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64().is_some_and(|x| x != 0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(a) => !a.is_empty(),
+        Value::Object(o) => !o.is_empty(),
+    }
+}
+
+fn values_equal(lhs: &Value, rhs: &Value) -> bool {
+    match (lhs, rhs) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64() == b.as_f64(),
+        _ => lhs == rhs,
+    }
+}
+
+fn compare(lhs: &Value, rhs: &Value) -> Option<Ordering> {
+    match (lhs, rhs) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64()?.partial_cmp(&b.as_f64()?),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        _ => None,
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    LParen,
+    RParen,
+    Op(CmpOp),
+    Str(String),
+    Word(String),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::LParen => f.write_str("\"(\""),
+            Token::RParen => f.write_str("\")\""),
+            Token::Op(op) => write!(f, "operator \"{op}\""),
+            Token::Str(s) => write!(f, "string {s:?}"),
+            Token::Word(w) => write!(f, "{w:?}"),
+        }
+    }
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token>, ParseFilterError> {
+    let mut tokens = Vec::new();
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => (),
+            '(' => tokens.push(Token::LParen),
+            ')' => tokens.push(Token::RParen),
+            '"' => {
+                let mut string = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c) => string.push(c),
+                            None => {
+                                return Err(ParseFilterError::new("unterminated string literal"))
+                            }
+                        },
+                        Some(c) => string.push(c),
+                        None => return Err(ParseFilterError::new("unterminated string literal")),
+                    }
+                }
+                tokens.push(Token::Str(string));
+            }
+            '=' | '!' | '<' | '>' => {
+                let eq = chars.next_if_eq(&'=').is_some();
+                let op = match (c, eq) {
+                    ('=', true) => CmpOp::Eq,
+                    ('!', true) => CmpOp::NotEq,
+                    ('<', false) => CmpOp::Lt,
+                    ('<', true) => CmpOp::LtEq,
+                    ('>', false) => CmpOp::Gt,
+                    ('>', true) => CmpOp::GtEq,
+                    _ => {
+                        return Err(ParseFilterError::new(format!(
+                            "invalid operator {c:?}; did you mean \"{c}=\"?"
+                        )))
+                    }
+                };
+                tokens.push(Token::Op(op));
+            }
+            c => {
+                let mut word = String::from(c);
+                while let Some(c) = chars.next_if(|&c| {
+                    !(c.is_whitespace() || matches!(c, '(' | ')' | '"' | '=' | '!' | '<' | '>'))
+                }) {
+                    word.push(c);
+                }
+                if word == "contains" {
+                    tokens.push(Token::Op(CmpOp::Contains));
+                } else {
+                    tokens.push(Token::Word(word));
+                }
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let t = self.tokens.get(self.pos).cloned();
+        if t.is_some() {
+            self.pos += 1;
+        }
+        t
+    }
+
+    fn eat_keyword(&mut self, kw: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Word(w)) if w == kw) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, ParseFilterError> {
+        let mut expr = self.parse_and()?;
+        while self.eat_keyword("or") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.parse_and()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, ParseFilterError> {
+        let mut expr = self.parse_not()?;
+        while self.eat_keyword("and") {
+            expr = Expr::And(Box::new(expr), Box::new(self.parse_not()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_not(&mut self) -> Result<Expr, ParseFilterError> {
+        if self.eat_keyword("not") {
+            return Ok(Expr::Not(Box::new(self.parse_not()?)));
+        }
+        match self.next() {
+            Some(Token::LParen) => {
+                let expr = self.parse_or()?;
+                match self.next() {
+                    Some(Token::RParen) => Ok(expr),
+                    Some(t) => Err(ParseFilterError::new(format!("expected \")\", got {t}"))),
+                    None => Err(ParseFilterError::new("expected \")\", got end of input")),
+                }
+            }
+            Some(Token::Word(w)) if !matches!(w.as_str(), "and" | "or") => {
+                let field = w.split('.').map(String::from).collect::<Vec<_>>();
+                if field.iter().any(String::is_empty) {
+                    return Err(ParseFilterError::new(format!("invalid field name {w:?}")));
+                }
+                let Some(Token::Op(op)) = self.peek().cloned() else {
+                    return Ok(Expr::Truthy(field));
+                };
+                self.pos += 1;
+                let value = match self.next() {
+                    Some(Token::Str(s)) => Value::String(s),
+                    Some(Token::Word(w)) => parse_word(w),
+                    Some(t) => {
+                        return Err(ParseFilterError::new(format!(
+                            "expected value after \"{op}\", got {t}"
+                        )))
+                    }
+                    None => {
+                        return Err(ParseFilterError::new(format!(
+                            "expected value after \"{op}\", got end of input"
+                        )))
+                    }
+                };
+                Ok(Expr::Compare { field, op, value })
+            }
+            Some(t) => Err(ParseFilterError::new(format!(
+                "expected field name, got {t}"
+            ))),
+            None => Err(ParseFilterError::new(
+                "expected field name, got end of input",
+            )),
+        }
+    }
+}
+
+fn parse_word(word: String) -> Value {
+    match word.as_str() {
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        "null" => Value::Null,
+        _ => {
+            if let Ok(n) = word.parse::<i64>() {
+                Value::from(n)
+            } else if let Some(n) = word
+                .parse::<f64>()
+                .ok()
+                .and_then(serde_json::Number::from_f64)
+            {
+                Value::Number(n)
+            } else {
+                Value::String(word)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParseFilterError(String);
+
+impl ParseFilterError {
+    fn new<S: Into<String>>(msg: S) -> ParseFilterError {
+        ParseFilterError(msg.into())
+    }
+}
+
+impl fmt::Display for ParseFilterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid filter expression: {}", self.0)
+    }
+}
+
+impl std::error::Error for ParseFilterError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn filter(s: &str) -> Filter {
+        s.parse().unwrap()
+    }
+
+    fn parse_err(s: &str) -> String {
+        s.parse::<Filter>().unwrap_err().0
+    }
+
+    fn word(s: &str) -> Vec<String> {
+        vec![String::from(s)]
+    }
+
+    #[test]
+    fn not_binds_tighter_than_and() {
+        assert_eq!(
+            filter("not a and b"),
+            Filter(Expr::And(
+                Box::new(Expr::Not(Box::new(Expr::Truthy(word("a"))))),
+                Box::new(Expr::Truthy(word("b"))),
+            ))
+        );
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        assert_eq!(
+            filter("a or b and c"),
+            Filter(Expr::Or(
+                Box::new(Expr::Truthy(word("a"))),
+                Box::new(Expr::And(
+                    Box::new(Expr::Truthy(word("b"))),
+                    Box::new(Expr::Truthy(word("c"))),
+                )),
+            ))
+        );
+        let f = filter("a or b and c");
+        assert!(f.matches(&json!({"a": true, "b": false, "c": false})));
+        assert!(!f.matches(&json!({"a": false, "b": true, "c": false})));
+        let f = filter("(a or b) and c");
+        assert!(!f.matches(&json!({"a": true, "b": false, "c": false})));
+        assert!(f.matches(&json!({"a": false, "b": true, "c": true})));
+    }
+
+    #[test]
+    fn labels_and_unquoted_date() {
+        let f = filter(r#"labels contains "bug" and updated_at < 2024-01-01"#);
+        assert_eq!(
+            f,
+            Filter(Expr::And(
+                Box::new(Expr::Compare {
+                    field: word("labels"),
+                    op: CmpOp::Contains,
+                    value: json!("bug"),
+                }),
+                Box::new(Expr::Compare {
+                    field: word("updated_at"),
+                    op: CmpOp::Lt,
+                    value: json!("2024-01-01"),
+                }),
+            ))
+        );
+        assert!(f.matches(&json!({
+            "labels": ["bug", "help wanted"],
+            "updated_at": "2023-06-30T12:00:00Z",
+        })));
+        assert!(!f.matches(&json!({
+            "labels": ["bug"],
+            "updated_at": "2024-03-01T00:00:00Z",
+        })));
+        assert!(!f.matches(&json!({
+            "labels": ["bugfix"],
+            "updated_at": "2023-06-30T12:00:00Z",
+        })));
+    }
+
+    #[test]
+    fn dotted_fields() {
+        let f = filter("author.is_bot == false and not author.login == \"octocat\"");
+        assert!(f.matches(&json!({"author": {"login": "jwodder", "is_bot": false}})));
+        assert!(!f.matches(&json!({"author": {"login": "octocat", "is_bot": false}})));
+        assert!(!f.matches(&json!({"author": {"login": "renovate", "is_bot": true}})));
+    }
+
+    #[test]
+    fn missing_fields_are_null() {
+        let f = filter("parent == null");
+        assert!(f.matches(&json!({"number": 1})));
+        assert!(f.matches(&json!({"parent": null})));
+        assert!(!f.matches(&json!({"parent": {"number": 2}})));
+        assert!(filter("parent.number == null").matches(&json!({"number": 1})));
+        assert!(!filter("parent").matches(&json!({"number": 1})));
+        assert!(!filter("number > 0 and missing").matches(&json!({"number": 1})));
+        assert!(!filter("missing < 5").matches(&json!({})));
+        assert!(filter("missing != 5").matches(&json!({})));
+    }
+
+    #[test]
+    fn numeric_vs_string_comparison() {
+        let record = json!({"number": 42, "title": "42", "score": 1.5});
+        assert!(filter("number == 42").matches(&record));
+        assert!(filter("number == 42.0").matches(&record));
+        assert!(filter("number < 100").matches(&record));
+        assert!(filter("score >= 1.5").matches(&record));
+        // Numbers are compared numerically, not lexicographically
+        assert!(filter("number > 9").matches(&record));
+        // Strings are compared lexicographically
+        assert!(!filter("title > \"9\"").matches(&record));
+        // Numbers & strings never compare equal or ordered with each other
+        assert!(!filter("number == \"42\"").matches(&record));
+        assert!(!filter("title == 42").matches(&record));
+        assert!(!filter("title < 100").matches(&record));
+        assert!(!filter("title >= 0").matches(&record));
+        assert!(filter("title != 42").matches(&record));
+    }
+
+    #[test]
+    fn truthiness() {
+        let f = filter("value");
+        assert!(!f.matches(&json!({"value": 0})));
+        assert!(!f.matches(&json!({"value": ""})));
+        assert!(!f.matches(&json!({"value": []})));
+        assert!(!f.matches(&json!({"value": false})));
+        assert!(f.matches(&json!({"value": 1})));
+        assert!(f.matches(&json!({"value": "x"})));
+        assert!(f.matches(&json!({"value": ["x"]})));
+    }
+
+    #[test]
+    fn bang_without_eq() {
+        assert_eq!(
+            parse_err("not a ! b"),
+            "invalid operator '!'; did you mean \"!=\"?"
+        );
+        assert_eq!(
+            parse_err("a = b"),
+            "invalid operator '='; did you mean \"==\"?"
+        );
+    }
+
+    #[test]
+    fn unterminated_string() {
+        assert_eq!(parse_err(r#"title == "foo"#), "unterminated string literal");
+        assert_eq!(
+            parse_err(r#"title == "foo\"#),
+            "unterminated string literal"
+        );
+    }
+
+    #[test]
+    fn escaped_quote_in_string() {
+        let f = filter(r#"title == "say \"hi\"""#);
+        assert!(f.matches(&json!({"title": "say \"hi\""})));
+    }
+
+    #[test]
+    fn trailing_tokens() {
+        assert_eq!(
+            parse_err("a == 1 b"),
+            "unexpected \"b\" after end of expression"
+        );
+        assert_eq!(
+            parse_err("(a) )"),
+            "unexpected \")\" after end of expression"
+        );
+    }
+
+    #[test]
+    fn incomplete_expressions() {
+        assert_eq!(parse_err(""), "expected field name, got end of input");
+        assert_eq!(parse_err("a and"), "expected field name, got end of input");
+        assert_eq!(parse_err("(a"), "expected \")\", got end of input");
+        assert_eq!(
+            parse_err("a <="),
+            "expected value after \"<=\", got end of input"
+        );
+        assert_eq!(parse_err("a..b"), "invalid field name \"a..b\"");
+    }
+}
@@ -0,0 +1,170 @@
+use crate::types::JsonMap;
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// A GraphQL request and the raw response that the server gave for it
+///
+/// Only the request body is recorded; headers (and thus the access token) are
+/// never included.  Fixtures are sanitized before being saved; see
+/// [`Fixture::sanitize()`].
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Fixture {
+    pub query: String,
+    pub variables: JsonMap,
+    pub response: serde_json::Value,
+}
+
+impl Fixture {
+    /// Blank out the parts of the response that describe the account that
+    /// made the request rather than the data requested: every field whose
+    /// name starts with `viewer` (e.g., `viewerPermission`) is set to `null`,
+    /// as are the `remaining`, `resetAt`, and `used` fields of `rateLimit`
+    pub fn sanitize(&mut self) {
+        sanitize_value(&mut self.response);
+    }
+
+    /// Save a sanitized copy of the fixture in `dir` under the name
+    /// `{index:04}.json`
+    pub(crate) fn save(&self, dir: &Path, index: usize) -> anyhow::Result<()> {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create fixture directory {}", dir.display()))?;
+        let path = dir.join(format!("{index:04}.json"));
+        let mut fixture = self.clone();
+        fixture.sanitize();
+        let mut src =
+            serde_json::to_string_pretty(&fixture).context("failed to serialize fixture")?;
+        src.push('\n');
+        fs::write(&path, src)
+            .with_context(|| format!("failed to write fixture to {}", path.display()))
+    }
+}
+
+/// Load all fixtures saved in `dir` by a client with fixture capturing enabled,
+/// in the order in which the requests were made
+///
+/// Files in `dir` that are not named like saved fixtures are ignored.
+pub fn load_fixtures<P: AsRef<Path>>(dir: P) -> anyhow::Result<Vec<Fixture>> {
+    let dir = dir.as_ref();
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir)
+        .with_context(|| format!("failed to read fixture directory {}", dir.display()))?
+    {
+        let path = entry
+            .with_context(|| format!("failed to read fixture directory {}", dir.display()))?
+            .path();
+        if path.extension().is_some_and(|ext| ext == "json") {
+            // Fixtures are named after their indices, which can outgrow the
+            // zero-padding, so they are sorted numerically rather than by
+            // name.
+            if let Some(index) = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.parse::<usize>().ok())
+            {
+                paths.push((index, path));
+            }
+        }
+    }
+    paths.sort();
+    paths
+        .into_iter()
+        .map(|(_, path)| {
+            let src = fs::read_to_string(&path)
+                .with_context(|| format!("failed to read fixture {}", path.display()))?;
+            serde_json::from_str(&src)
+                .with_context(|| format!("failed to deserialize fixture {}", path.display()))
+        })
+        .collect()
+}
+
+/// Recursively apply [`Fixture::sanitize()`] to a JSON value
+fn sanitize_value(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, v) in map.iter_mut() {
+                if key.starts_with("viewer") {
+                    *v = serde_json::Value::Null;
+                } else if key == "rateLimit" {
+                    if let Some(rl) = v.as_object_mut() {
+                        for field in ["remaining", "resetAt", "used"] {
+                            if let Some(x) = rl.get_mut(field) {
+                                *x = serde_json::Value::Null;
+                            }
+                        }
+                    }
+                } else {
+                    sanitize_value(v);
+                }
+            }
+        }
+        serde_json::Value::Array(values) => values.iter_mut().for_each(sanitize_value),
+        _ => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn sanitize() {
+        let mut fixture = Fixture {
+            query: String::from("query { ... }"),
+            variables: JsonMap::new(),
+            response: json!({
+                "data": {
+                    "viewer": {"login": "jwodder"},
+                    "q0": {
+                        "nodes": [
+                            {"name": "foo", "viewerPermission": "ADMIN"},
+                            {"name": "bar", "viewerHasStarred": true},
+                        ],
+                    },
+                    "rateLimit": {"cost": 1, "remaining": 4321, "used": 679},
+                },
+            }),
+        };
+        fixture.sanitize();
+        assert_eq!(
+            fixture.response,
+            json!({
+                "data": {
+                    "viewer": null,
+                    "q0": {
+                        "nodes": [
+                            {"name": "foo", "viewerPermission": null},
+                            {"name": "bar", "viewerHasStarred": null},
+                        ],
+                    },
+                    "rateLimit": {"cost": 1, "remaining": null, "used": null},
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn load_in_numeric_order() {
+        let dir =
+            std::env::temp_dir().join(format!("gqlient-fixture-order-{}", std::process::id()));
+        for index in [10000, 2, 9999] {
+            let fixture = Fixture {
+                query: format!("query {index}"),
+                variables: JsonMap::new(),
+                response: json!({"data": null}),
+            };
+            fixture.save(&dir, index).unwrap();
+        }
+        fs::write(dir.join("notes.json"), "not a fixture").unwrap();
+        let fixtures = load_fixtures(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+        let queries = fixtures
+            .unwrap()
+            .into_iter()
+            .map(|f| f.query)
+            .collect::<Vec<_>>();
+        assert_eq!(queries, ["query 2", "query 9999", "query 10000"]);
+    }
+}
@@ -0,0 +1,24 @@
+use crate::JsonMap;
+use sha2::{Digest, Sha256};
+use std::fmt::Write as _;
+
+/// Return a hash of the structure of a GraphQL request: its query document
+/// and the names (but not the values) of its variables.  The hash is the
+/// first 16 hex digits of a SHA-256 digest, so it is stable across runs,
+/// platforms, and versions of Rust.
+pub(crate) fn query_hash(query: &str, variables: &JsonMap) -> String {
+    let mut names = variables.keys().map(String::as_str).collect::<Vec<_>>();
+    names.sort_unstable();
+    let mut hasher = Sha256::new();
+    hasher.update(query.as_bytes());
+    for name in names {
+        hasher.update([0]);
+        hasher.update(name.as_bytes());
+    }
+    let digest = hasher.finalize();
+    let mut s = String::with_capacity(16);
+    for b in digest.iter().take(8) {
+        let _ = write!(s, "{b:02x}");
+    }
+    s
+}
@@ -0,0 +1,146 @@
+use crate::queries::{Paginator, Query};
+use crate::types::{Cursor, Page, Variable};
+use std::cell::Cell;
+use std::fmt::{self, Debug, Write};
+use std::fs;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+/// A wrapper around a [`Paginator`] that logs every GraphQL query it
+/// produces, every response it receives, and every page it outputs, for use in
+/// debugging
+///
+/// Each event is logged to stderr.  If a directory is supplied via
+/// [`Inspect::with_dir()`], each event is additionally written to a numbered
+/// file in that directory.  Note that, when run via
+/// [`Client::batch_paginate()`](crate::Client::batch_paginate), the GraphQL
+/// text of a paginator's queries is only rendered (and thus logged) for its
+/// first and second pages, as it is reused for later pages.
+#[derive(Clone, Debug)]
+pub struct Inspect<P> {
+    inner: P,
+    dir: Option<PathBuf>,
+    counter: Rc<Cell<usize>>,
+}
+
+impl<P> Inspect<P> {
+    pub fn new(inner: P) -> Inspect<P> {
+        Inspect {
+            inner,
+            dir: None,
+            counter: Rc::new(Cell::new(0)),
+        }
+    }
+
+    pub fn with_dir(mut self, dir: PathBuf) -> Inspect<P> {
+        self.dir = Some(dir);
+        self
+    }
+
+    pub fn into_inner(self) -> P {
+        self.inner
+    }
+}
+
+impl<P> Paginator for Inspect<P>
+where
+    P: Paginator,
+    P::Item: Debug,
+{
+    type Item = P::Item;
+    type Query = InspectQuery<P::Query>;
+
+    fn for_cursor(&self, cursor: Option<&Cursor>) -> InspectQuery<P::Query> {
+        let index = self.counter.get();
+        self.counter.set(index + 1);
+        InspectQuery {
+            inner: self.inner.for_cursor(cursor),
+            logger: Logger {
+                index,
+                dir: self.dir.clone(),
+            },
+        }
+    }
+
+    fn nodes_per_page(&self) -> Option<u64> {
+        self.inner.nodes_per_page()
+    }
+
+    fn reset_cursor(&mut self) {
+        self.inner.reset_cursor();
+    }
+
+    fn reduce_page_size(&mut self) -> Option<NonZeroUsize> {
+        self.inner.reduce_page_size()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct InspectQuery<Q> {
+    inner: Q,
+    logger: Logger,
+}
+
+impl<Q, T> Query for InspectQuery<Q>
+where
+    Q: Query<Output = Page<T>>,
+    T: Debug,
+{
+    type Output = Page<T>;
+
+    fn with_variable_prefix(self, prefix: String) -> Self {
+        InspectQuery {
+            inner: self.inner.with_variable_prefix(prefix),
+            logger: self.logger,
+        }
+    }
+
+    fn write_graphql<W: Write>(&self, mut s: W) -> fmt::Result {
+        let mut graphql = String::new();
+        self.inner.write_graphql(&mut graphql)?;
+        self.logger.log("query", "graphql", &graphql);
+        s.write_str(&graphql)
+    }
+
+    fn variables(&self) -> Vec<(String, Variable)> {
+        let vars = self.inner.variables().into_iter().collect::<Vec<_>>();
+        let mut text = String::new();
+        for (name, Variable { gql_type, value }) in &vars {
+            let _ = writeln!(&mut text, "${name}: {gql_type} = {value}");
+        }
+        self.logger.log("variables", "txt", &text);
+        vars
+    }
+
+    fn parse_response(&self, value: serde_json::Value) -> Result<Page<T>, serde_json::Error> {
+        match serde_json::to_string_pretty(&value) {
+            Ok(text) => self.logger.log("response", "json", &text),
+            Err(e) => eprintln!("[inspect] Failed to serialize response: {e}"),
+        }
+        let r = self.inner.parse_response(value);
+        match r {
+            Ok(ref page) => self.logger.log("output", "txt", &format!("{page:#?}")),
+            Err(ref e) => self.logger.log("output", "txt", &format!("Error: {e}")),
+        }
+        r
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Logger {
+    index: usize,
+    dir: Option<PathBuf>,
+}
+
+impl Logger {
+    fn log(&self, event: &str, ext: &str, text: &str) {
+        eprintln!("[inspect] #{} {event}:\n{text}", self.index);
+        if let Some(ref dir) = self.dir {
+            let path = dir.join(format!("{:04}-{event}.{ext}", self.index));
+            if let Err(e) = fs::create_dir_all(dir).and_then(|()| fs::write(&path, text)) {
+                eprintln!("[inspect] Failed to write {}: {e}", path.display());
+            }
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use crate::batch::PaginationResults;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{BuildHasher, Hash};
+
+/// A collection of values, looked up by key, that the results of batched
+/// paginators can be joined back onto with [`join_results()`]
+pub trait KeyedCollection<K> {
+    type Value;
+
+    /// Return a mutable reference to the value for `key`, if there is one
+    fn value_mut(&mut self, key: &K) -> Option<&mut Self::Value>;
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher> KeyedCollection<K> for HashMap<K, V, S> {
+    type Value = V;
+
+    fn value_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.get_mut(key)
+    }
+}
+
+impl<K: Ord, V> KeyedCollection<K> for BTreeMap<K, V> {
+    type Value = V;
+
+    fn value_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.get_mut(key)
+    }
+}
+
+/// Join the results of batched paginators back onto the values in
+/// `collection` with the same keys, calling `f` on each value and the results
+/// for its key.
+///
+/// Returns the results whose keys are not in `collection`, in the order they
+/// were given.
+pub fn join_results<C, K, T, I, F>(
+    collection: &mut C,
+    results: I,
+    mut f: F,
+) -> Vec<PaginationResults<K, T>>
+where
+    C: KeyedCollection<K> + ?Sized,
+    I: IntoIterator<Item = PaginationResults<K, T>>,
+    F: FnMut(&mut C::Value, PaginationResults<K, T>),
+{
+    let mut unmatched = Vec::new();
+    for pr in results {
+        match collection.value_mut(&pr.key) {
+            Some(value) => f(value, pr),
+            None => unmatched.push(pr),
+        }
+    }
+    unmatched
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn results(key: &str, items: &[u32]) -> PaginationResults<String, u32> {
+        PaginationResults {
+            key: key.to_owned(),
+            items: items.to_vec(),
+            end_cursor: None,
+            requests: 1,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    #[test]
+    fn join_onto_hashmap() {
+        let mut map = HashMap::from([(String::from("a"), 0), (String::from("b"), 10)]);
+        let unmatched = join_results(
+            &mut map,
+            vec![
+                results("a", &[1, 2]),
+                results("c", &[5]),
+                results("b", &[3]),
+            ],
+            |total, pr| *total += pr.items.into_iter().sum::<u32>(),
+        );
+        assert_eq!(map["a"], 3);
+        assert_eq!(map["b"], 13);
+        assert_eq!(unmatched, vec![results("c", &[5])]);
+    }
+
+    #[test]
+    fn join_onto_btreemap() {
+        let mut map = BTreeMap::from([(String::from("a"), Vec::new())]);
+        let unmatched = join_results(
+            &mut map,
+            vec![results("a", &[1]), results("a", &[2, 3])],
+            |items, pr| items.extend(pr.items),
+        );
+        assert_eq!(map["a"], [1, 2, 3]);
+        assert!(unmatched.is_empty());
+    }
+}
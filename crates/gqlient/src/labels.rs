@@ -0,0 +1,64 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io;
+
+/// A tally of how often each pair of labels appears together on the same
+/// issue
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct LabelMatrix(BTreeMap<(String, String), usize>);
+
+impl LabelMatrix {
+    pub fn new() -> LabelMatrix {
+        LabelMatrix::default()
+    }
+
+    /// Record the labels of a single issue
+    pub fn add<S: AsRef<str>>(&mut self, labels: &[S]) {
+        let labels = labels.iter().map(AsRef::as_ref).collect::<BTreeSet<_>>();
+        for (i, &a) in labels.iter().enumerate() {
+            for &b in labels.iter().skip(i + 1) {
+                *self.0.entry((a.to_owned(), b.to_owned())).or_default() += 1;
+            }
+        }
+    }
+
+    /// Write the matrix as CSV with the columns `label1`, `label2`, and
+    /// `count`, one row per pair of labels that appeared together at least
+    /// once
+    pub fn write_csv<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "label1,label2,count")?;
+        for ((a, b), count) in &self.0 {
+            writeln!(writer, "{},{},{count}", csv_field(a), csv_field(b))?;
+        }
+        Ok(())
+    }
+
+    /// Write the matrix as a JSON array of `{"labels": [label1, label2],
+    /// "count": count}` objects
+    pub fn write_json<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        #[derive(Serialize)]
+        struct Entry<'a> {
+            labels: [&'a str; 2],
+            count: usize,
+        }
+
+        let entries = self
+            .0
+            .iter()
+            .map(|((a, b), &count)| Entry {
+                labels: [a, b],
+                count,
+            })
+            .collect::<Vec<_>>();
+        serde_json::to_writer_pretty(&mut writer, &entries)?;
+        writer.write_all(b"\n")
+    }
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_owned()
+    }
+}
@@ -1,93 +1,783 @@
+mod anonymize;
+mod auth;
+mod batch;
+mod breaker;
+mod builder;
+mod connect;
+mod durations;
+mod errors;
+mod filter;
+mod fixtures;
+mod hashes;
+mod inspect;
+mod join;
+mod labels;
+mod machine;
+mod memory;
+mod mutations;
+#[cfg(feature = "otel")]
+mod otel;
+mod outcomes;
+mod owners;
+mod progress;
 mod queries;
+mod quiet;
+mod sample;
+mod transfer;
+mod transport;
 mod types;
+mod unused;
+mod urls;
+pub use crate::anonymize::Anonymizer;
+pub use crate::auth::{AppInstallationToken, AuthProvider, EnvToken, GhToken, StaticToken};
+pub use crate::batch::{
+    compose_request, BatchPaginator, BatchParts, IsolatedResults, PaginationFailure,
+    PaginationResults, PendingPaginator, Step,
+};
+use crate::breaker::Breaker;
+pub use crate::breaker::{CircuitBreaker, CircuitOpenError};
+pub use crate::builder::{ClientBuilder, RetryPolicy};
+use crate::connect::ConnectionStats;
+pub use crate::connect::TLS_BACKEND;
+pub use crate::durations::{duration_secs, serialize_duration, serialize_opt_duration};
+pub use crate::errors::{
+    DeserializeError, GqlError, GqlErrorType, GqlInnerError, MissingAliasError,
+};
+pub use crate::filter::{Filter, ParseFilterError};
+pub use crate::fixtures::{load_fixtures, Fixture};
+pub use crate::inspect::{Inspect, InspectQuery};
+pub use crate::join::{join_results, KeyedCollection};
+pub use crate::labels::LabelMatrix;
+pub use crate::machine::{BatchMachine, BatchRequest, Event, MachineConfig};
+pub use crate::memory::peak_rss;
+pub use crate::mutations::{BatchMutator, MutationOutcome, MutationResult};
+#[cfg(feature = "otel")]
+pub use crate::otel::Tracer;
+pub use crate::outcomes::{OwnerOutcome, OwnerOutcomes};
+pub use crate::owners::OwnerKind;
 pub use crate::queries::{Paginator, Query};
+pub use crate::quiet::{is_quiet, set_quiet};
+pub use crate::sample::{ParseSampleError, Sample, SplitMix64};
+pub use crate::transfer::TransferStats;
+use crate::transport::RequestError;
+pub use crate::transport::{HttpRequest, HttpResponse, Method, Transport, UreqTransport};
 pub use crate::types::*;
+pub use crate::unused::from_value;
+pub use crate::urls::{canonicalize_issue_url, IssueUrlError};
 use anyhow::Context;
-use indenter::indented;
 use serde::{Deserialize, Serialize};
-use std::collections::VecDeque;
-use std::collections::{hash_map::Entry, HashMap};
-use std::fmt::Write;
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, BTreeSet};
 use std::num::NonZeroUsize;
-use ureq::{Agent, AgentBuilder};
-
-static GRAPHQL_API_URL: &str = "https://api.github.com/graphql";
-static RATE_LIMIT_URL: &str = "https://api.github.com/rate_limit";
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use ureq::AgentBuilder;
 
 pub const DEFAULT_BATCH_SIZE: usize = 50;
 
+/// The number of times in a row that a request rejected by GitHub's rate
+/// limits is retried after waiting as long as GitHub asks, regardless of the
+/// client's retry policy
+const RATE_LIMIT_WAITS: u32 = 3;
+
+/// How long to wait before retrying a GraphQL request whose response reports
+/// a `RATE_LIMITED` error without saying when the rate limit resets
+const DEFAULT_RATE_LIMIT_WAIT: Duration = Duration::from_secs(60);
+
 #[derive(Clone, Debug)]
 pub struct Client {
-    inner: Agent,
+    transport: Rc<dyn Transport>,
     batch_size: NonZeroUsize,
+    node_limit: Option<u64>,
+    fixture_dir: Option<PathBuf>,
+    fixture_count: Cell<usize>,
+    verbose: bool,
+    pause_below: Option<u32>,
+    request_timeout: Option<Duration>,
+    slow_request_threshold: Option<Duration>,
+    adaptive_page_size: Option<Duration>,
+    deserialize_retries: u32,
+    bad_data_dir: Option<PathBuf>,
+    discard_bad_data: bool,
+    compress_above: Option<usize>,
+    transfer: Cell<TransferStats>,
+    /// When a `BatchPaginator` received the response to its previous
+    /// request, set while it sends its next one so that the time in between
+    /// can be recorded as idle
+    idle_since: Cell<Option<Instant>>,
+    /// When the transport last returned a response
+    last_response: Cell<Option<Instant>>,
+    connections: ConnectionStats,
+    cursors_reset: Cell<usize>,
+    partial_responses: Cell<usize>,
+    page_size_reductions: Cell<usize>,
+    /// The number of GraphQL requests made with each query hash
+    query_hashes: RefCell<BTreeMap<String, u64>>,
+    last_rate_limit: Cell<Option<RateLimitHeaders>>,
+    auth: Rc<RefCell<dyn AuthProvider>>,
+    token: RefCell<Option<String>>,
+    graphql_url: String,
+    rest_url: String,
+    retry: RetryPolicy,
+    retry_budget: Cell<Option<u32>>,
+    breaker: Option<Breaker>,
+    phase_points: Rc<PhasePoints>,
+    /// The response fields reported as unused so far, if
+    /// `report_unused_fields()` is enabled
+    unused_fields: Option<RefCell<BTreeSet<String>>>,
+    #[cfg(feature = "otel")]
+    tracer: Option<Rc<Tracer>>,
 }
 
 impl Client {
-    #[allow(clippy::missing_panics_doc)]
+    /// Create a client that authenticates with `token` and is otherwise
+    /// configured with the defaults of [`ClientBuilder`]
     pub fn new(token: &str) -> Client {
-        let auth = format!("Bearer {token}");
-        let inner = AgentBuilder::new()
-            .https_only(true)
-            .middleware(move |req: ureq::Request, next: ureq::MiddlewareNext<'_>| {
-                next.handle(
-                    req.set("Authorization", &auth)
-                        .set("X-Github-Next-Global-ID", "1"),
-                )
-            })
-            .build();
-        let batch_size =
-            NonZeroUsize::new(DEFAULT_BATCH_SIZE).expect("default batch size should be nonzero");
-        Client { inner, batch_size }
+        ClientBuilder::new().token(token).build_unchecked()
+    }
+
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::new()
+    }
+
+    /// Create a client whose underlying `ureq` agent is further configured by
+    /// `configure`; see [`ClientBuilder::configure_agent()`]
+    pub fn new_with_agent_config<F>(token: &str, configure: F) -> Client
+    where
+        F: FnOnce(AgentBuilder) -> AgentBuilder,
+    {
+        ClientBuilder::new()
+            .token(token)
+            .configure_agent(configure)
+            .build_unchecked()
+    }
+
+    /// Create a client that obtains its access tokens from `auth` and whose
+    /// underlying `ureq` agent is further configured by `configure`; see
+    /// [`ClientBuilder::auth_provider()`] and
+    /// [`ClientBuilder::configure_agent()`]
+    pub fn new_with_auth_provider<A, F>(auth: A, configure: F) -> Client
+    where
+        A: AuthProvider + 'static,
+        F: FnOnce(AgentBuilder) -> AgentBuilder,
+    {
+        ClientBuilder::new()
+            .auth_provider(auth)
+            .configure_agent(configure)
+            .build_unchecked()
     }
 
     pub fn new_with_local_token() -> anyhow::Result<Client> {
-        let token = gh_token::get().context("unable to fetch GitHub access token")?;
-        Ok(Client::new(&token))
+        let client = ClientBuilder::new()
+            .auth_provider(GhToken)
+            .build_unchecked();
+        // Fail early if no token is available
+        client.bearer(false)?;
+        Ok(client)
+    }
+
+    /// Like [`Client::new_with_local_token()`], but talk to the GitHub API
+    /// at the base URL `api_url` (the equivalent of `https://api.github.com`,
+    /// such as a local test server) instead, with GraphQL requests sent to
+    /// `{api_url}/graphql`.  As the URL is given explicitly, it may use plain
+    /// HTTP.
+    pub fn new_with_local_token_at(api_url: &str) -> anyhow::Result<Client> {
+        let api_url = api_url.trim_end_matches('/');
+        let client = ClientBuilder::new()
+            .auth_provider(GhToken)
+            .graphql_url(format!("{api_url}/graphql"))
+            .rest_url(api_url)
+            .https_only(false)
+            .build_unchecked();
+        // Fail early if no token is available
+        client.bearer(false)?;
+        Ok(client)
     }
 
     pub fn batch_size(&mut self, batch_size: NonZeroUsize) {
         self.batch_size = batch_size;
     }
 
+    /// When paginating, limit each request to sub-queries whose total
+    /// [`Paginator::nodes_per_page()`] is at most `nodes` (while still
+    /// combining no more than the batch size of them), so that requests stay
+    /// under GitHub's limit on the number of nodes a request may ask for
+    pub fn node_limit(&mut self, nodes: u64) {
+        self.node_limit = Some(nodes);
+    }
+
+    /// Display a live status line on stderr while paginating
+    pub fn verbose(&mut self, verbose: bool) {
+        self.verbose = verbose;
+    }
+
+    /// When paginating, if the most recent response reported fewer than
+    /// `remaining` GraphQL rate limit points left, pause until the rate limit
+    /// resets before making the next request
+    pub fn pause_below(&mut self, remaining: u32) {
+        self.pause_below = Some(remaining);
+    }
+
+    /// Set the timeout for each GraphQL request, overriding any timeout set
+    /// with [`ClientBuilder::timeout()`] for those requests
+    pub fn request_timeout(&mut self, timeout: Duration) {
+        self.request_timeout = Some(timeout);
+    }
+
+    /// Set how requests that fail due to network or server errors are
+    /// retried, overriding any policy set with
+    /// [`ClientBuilder::retry_policy()`]
+    pub fn retry_policy(&mut self, retry: RetryPolicy) {
+        self.retry = retry;
+    }
+
+    /// Make at most `retries` retries in total over the lifetime of the
+    /// client, after which failed requests are no longer retried regardless
+    /// of the retry policy
+    pub fn retry_budget(&mut self, retries: u32) {
+        self.retry_budget.set(Some(retries));
+    }
+
+    /// Stop making requests for a while whenever too many consecutive
+    /// requests fail; see [`CircuitBreaker`]
+    pub fn circuit_breaker(&mut self, breaker: CircuitBreaker) {
+        self.breaker = Some(Breaker::new(breaker));
+    }
+
+    /// When paginating, log any request that takes at least `threshold` to
+    /// complete, along with the sub-queries in it
+    pub fn slow_request_threshold(&mut self, threshold: Duration) {
+        self.slow_request_threshold = Some(threshold);
+    }
+
+    /// When paginating, lower the page size of each paginator in any request
+    /// that takes at least `threshold` to complete, as well as of each
+    /// paginator whose pages on their own come near the client's node limit,
+    /// for their subsequent pages; see [`Paginator::reduce_page_size()`]
+    pub fn adaptive_page_size(&mut self, threshold: Duration) {
+        self.adaptive_page_size = Some(threshold);
+    }
+
+    /// When paginating, retry each sub-query whose response data cannot be
+    /// deserialized up to `retries` times in a row before failing with a
+    /// [`DeserializeError`] [default: 0]
+    pub fn deserialize_retries(&mut self, retries: u32) {
+        self.deserialize_retries = retries;
+    }
+
+    /// Return the settings for a [`BatchMachine`] that paginates the way the
+    /// client's [`BatchPaginator`]s do
+    pub fn machine_config(&self) -> MachineConfig {
+        MachineConfig {
+            node_limit: self.node_limit,
+            deserialize_retries: self.deserialize_retries,
+            adaptive_page_size: self.adaptive_page_size.is_some(),
+        }
+    }
+
+    /// Save the raw response data of sub-queries that cannot be deserialized
+    /// to files in `dir` rather than in the system's temporary directory
+    pub fn bad_data_dir(&mut self, dir: PathBuf) {
+        self.bad_data_dir = Some(dir);
+    }
+
+    /// Do not save the raw response data of sub-queries that cannot be
+    /// deserialized anywhere, e.g., because the data must not be written to
+    /// disk as-is
+    pub fn discard_bad_data(&mut self) {
+        self.discard_bad_data = true;
+    }
+
+    /// Save the raw response data `value` for the sub-query `alias`, which
+    /// could not be deserialized, to a new file in the bad data directory and
+    /// return the file's path.  If the data cannot be saved, a warning is
+    /// logged and `None` is returned; if bad data is discarded, `None` is
+    /// returned without saving anything.
+    fn save_bad_data(&self, alias: &str, value: &serde_json::Value) -> Option<PathBuf> {
+        if self.discard_bad_data {
+            return None;
+        }
+        let dir = self.bad_data_dir.clone().unwrap_or_else(std::env::temp_dir);
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let path = dir.join(format!(
+            "gqlient-bad-data-{}-{stamp}-{alias}.json",
+            std::process::id()
+        ));
+        let r = std::fs::create_dir_all(&dir)
+            .and_then(|()| Ok(serde_json::to_vec_pretty(value)?))
+            .and_then(|body| std::fs::write(&path, body));
+        match r {
+            Ok(()) => Some(path),
+            Err(e) => {
+                eprintln!(
+                    "[!] Failed to save undeserializable response data to {}: {e}",
+                    path.display()
+                );
+                None
+            }
+        }
+    }
+
+    /// Gzip-compress the body of each GraphQL request whose serialized
+    /// payload is larger than `bytes` bytes, sending it with a
+    /// `Content-Encoding: gzip` header.  Large batches of sub-queries make for
+    /// payloads of tens of kilobytes, which compress well.
+    pub fn compress_above(&mut self, bytes: usize) {
+        self.compress_above = Some(bytes);
+    }
+
+    /// Return the totals of the sizes of the GraphQL request bodies sent by
+    /// the client so far, before & after compression, and of the time spent
+    /// waiting on the network and idling between batched requests, along
+    /// with the numbers & timings of the connections its agent has opened.  The connection
+    /// statistics are shared with clones of the client, as is the agent.
+    pub fn transfer_stats(&self) -> TransferStats {
+        let mut stats = self.transfer.get();
+        stats += self.connections.get();
+        stats
+    }
+
+    /// Return the base URL of the web interface of the GitHub instance that
+    /// the client talks to (e.g., `https://github.com`), as derived from its
+    /// GraphQL endpoint.  This is the base of the issue URLs that
+    /// [`canonicalize_issue_url()`] expects.
+    pub fn web_url(&self) -> String {
+        urls::web_url(&self.graphql_url)
+    }
+
+    /// Return the number of paginators that the client's batched queries
+    /// have restarted from the beginning so far because GitHub rejected
+    /// their cursors as invalid or expired; see [`BatchPaginator`]
+    pub fn cursors_reset(&self) -> usize {
+        self.cursors_reset.get()
+    }
+
+    /// Return the number of responses to the client's batched queries so far
+    /// that included errors for some sub-queries alongside data for the
+    /// others; see [`BatchPaginator`]
+    pub fn partial_responses(&self) -> usize {
+        self.partial_responses.get()
+    }
+
+    /// Return the number of times so far that the client's batched queries
+    /// have lowered a paginator's page size; see
+    /// [`Client::adaptive_page_size()`]
+    pub fn page_size_reductions(&self) -> usize {
+        self.page_size_reductions.get()
+    }
+
+    /// Return a map from the hash of each distinct GraphQL request structure
+    /// (query document plus variable names) that the client has sent so far
+    /// to the number of requests sent with it, not counting retries.  Two
+    /// runs with equal maps issued structurally identical queries.
+    pub fn query_hashes(&self) -> BTreeMap<String, u64> {
+        self.query_hashes.borrow().clone()
+    }
+
+    /// Log a warning for each response field that the client's queries
+    /// receive but do not use, i.e., that are ignored when deserializing the
+    /// responses with [`from_value()`].  Each field is only reported once per
+    /// query type.  This is meant for finding selections that could be
+    /// dropped to reduce node counts and payload sizes.
+    pub fn report_unused_fields(&mut self, report: bool) {
+        self.unused_fields = report.then(RefCell::default);
+    }
+
+    /// Parse `value` as the response to `query`, reporting any unused fields
+    /// if [`Client::report_unused_fields()`] is enabled
+    fn parse_response<Q: Query>(
+        &self,
+        query: &Q,
+        value: serde_json::Value,
+    ) -> Result<Q::Output, serde_json::Error> {
+        let Some(ref reported) = self.unused_fields else {
+            return query.parse_response(value);
+        };
+        let (r, ignored) = unused::collect_ignored(|| query.parse_response(value));
+        let qname = std::any::type_name::<Q>();
+        let qname = qname.split('<').next().unwrap_or(qname);
+        let qname = qname.rsplit("::").next().unwrap_or(qname);
+        for path in ignored {
+            if reported.borrow_mut().insert(format!("{qname}: {path}")) {
+                eprintln!("[!] Response field {path} of {qname} was received but not used");
+            }
+        }
+        r
+    }
+
+    /// Record a span in `tracer` for each GraphQL request made by the client
+    /// and for each phase started with [`Client::phase()`]
+    #[cfg(feature = "otel")]
+    pub fn tracer(&mut self, tracer: Tracer) {
+        self.tracer = Some(Rc::new(tracer));
+    }
+
+    /// Start a named phase of the program's run, which lasts until the
+    /// returned value is dropped.  The rate limit points spent on the
+    /// client's batched queries during the phase are totalled under its name
+    /// (see [`Client::points_by_phase()`]).  When the client has a
+    /// [`Tracer`], the phase is also recorded as a span, and the GraphQL
+    /// requests made during it are recorded as its children.
+    pub fn phase(&self, name: &str) -> Phase {
+        self.phase_points.open.borrow_mut().push(name.to_owned());
+        Phase {
+            points: Rc::clone(&self.phase_points),
+            #[cfg(feature = "otel")]
+            _span: self.tracer.as_ref().map(|t| t.start_phase(name)),
+        }
+    }
+
+    /// Return the total rate limit points spent so far on the batched
+    /// queries made by the client, keyed by the name of the innermost phase
+    /// open when each query was made.  Queries made outside of any phase are
+    /// counted under "other".
+    ///
+    /// The cost of each query is read from a `rateLimit { cost }` field that
+    /// [`BatchPaginator`] and [`compose_request()`] add to every query
+    /// document.
+    pub fn points_by_phase(&self) -> BTreeMap<String, u64> {
+        self.phase_points.points.borrow().clone()
+    }
+
+    /// Remove the `rateLimit` field from the data of a query response, add
+    /// its cost to the current phase, and return the cost (or 0 if the field
+    /// is absent)
+    pub(crate) fn record_query_cost(&self, data: &mut JsonMap) -> u64 {
+        let Some(cost) = data
+            .remove("rateLimit")
+            .and_then(|rl| rl.get("cost")?.as_u64())
+        else {
+            return 0;
+        };
+        self.add_query_cost(cost);
+        cost
+    }
+
+    /// Add `cost` rate limit points to the total for the current phase
+    pub(crate) fn add_query_cost(&self, cost: u64) {
+        let phase = self
+            .phase_points
+            .open
+            .borrow()
+            .last()
+            .cloned()
+            .unwrap_or_else(|| String::from("other"));
+        *self
+            .phase_points
+            .points
+            .borrow_mut()
+            .entry(phase)
+            .or_default() += cost;
+    }
+
+    /// If the client has a [`Tracer`], export the spans recorded so far;
+    /// otherwise, do nothing
+    pub fn export_traces(&self) -> anyhow::Result<()> {
+        #[cfg(feature = "otel")]
+        if let Some(ref tracer) = self.tracer {
+            if self.verbose {
+                crate::progress!("Exporting traces …");
+            }
+            tracer.export()?;
+        }
+        Ok(())
+    }
+
+    /// Save each GraphQL request made by the client and its raw response as a
+    /// [`Fixture`] in `dir`, sanitized with [`Fixture::sanitize()`]
+    pub fn capture_fixtures(&mut self, dir: PathBuf) {
+        self.fixture_dir = Some(dir);
+    }
+
+    /// Return the current access token, asking the auth provider for a new
+    /// one if there is none yet or if `refresh` is true
+    fn bearer(&self, refresh: bool) -> anyhow::Result<String> {
+        let mut token = self.token.borrow_mut();
+        match *token {
+            Some(ref t) if !refresh => Ok(t.clone()),
+            _ => {
+                let t = self.auth.borrow_mut().bearer()?;
+                *token = Some(t.clone());
+                Ok(t)
+            }
+        }
+    }
+
+    /// Perform a request built by `send` with the current access token,
+    /// retrying once with a fresh token if the request is rejected as
+    /// unauthorized and retrying network & server errors according to the
+    /// client's retry policy and retry budget.
+    ///
+    /// Requests rejected by one of GitHub's rate limits are also retried
+    /// according to the retry policy and budget, but only after waiting as
+    /// long as GitHub asks (via the `retry-after` header, or until the rate
+    /// limit resets if there are no points remaining).  As a client makes
+    /// only one request at a time, this is the only pacing needed to follow
+    /// GitHub's guidelines for avoiding secondary rate limits.
+    ///
+    /// If the client has a circuit breaker that is open, the request is not
+    /// made, and a [`CircuitOpenError`] is returned instead.
+    fn send_authorized(
+        &self,
+        mut request: HttpRequest,
+    ) -> anyhow::Result<Result<HttpResponse, RequestError>> {
+        if let Some(ref breaker) = self.breaker {
+            breaker.check()?;
+        }
+        request.set_header("X-Github-Next-Global-ID", "1");
+        let mut retry = 0;
+        let mut rate_limit_waits = 0;
+        loop {
+            request.set_header("Authorization", format!("Bearer {}", self.bearer(false)?));
+            let mut r = self.send(&request);
+            if r.as_ref().err().and_then(RequestError::status) == Some(401) {
+                request.set_header("Authorization", format!("Bearer {}", self.bearer(true)?));
+                r = self.send(&request);
+            }
+            let rate_limited = match r {
+                Err(RequestError::Status { ref response, .. })
+                    if matches!(response.status, 403 | 429) =>
+                {
+                    rate_limit_wait(response)
+                }
+                _ => None,
+            };
+            if let Some(wait) = rate_limited {
+                if rate_limit_waits < RATE_LIMIT_WAITS {
+                    wait_for_rate_limit(wait);
+                    rate_limit_waits += 1;
+                    continue;
+                }
+            }
+            let retryable = match r {
+                Err(RequestError::Transport(_)) => true,
+                Err(ref e) => matches!(e.status(), Some(500 | 502 | 503 | 504)),
+                Ok(_) => false,
+            };
+            match self.retry.delay(retry) {
+                Some(delay) if retryable && self.take_retry() => {
+                    std::thread::sleep(delay);
+                    retry += 1;
+                }
+                _ => {
+                    if let Some(ref breaker) = self.breaker {
+                        breaker.record(retryable);
+                    }
+                    return Ok(r);
+                }
+            }
+        }
+    }
+
+    /// Send `request` via the client's transport, treating responses with
+    /// unsuccessful statuses as errors, and record the time spent waiting on
+    /// the transport and any time idled since a `BatchPaginator` received its
+    /// previous response
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse, RequestError> {
+        let start = Instant::now();
+        let r = self.transport.send(request);
+        let received = Instant::now();
+        let mut stats = self.transfer.get();
+        stats.network_time += received.saturating_duration_since(start);
+        if let Some(since) = self.idle_since.take() {
+            stats.idle_periods += 1;
+            stats.idle_time += start.saturating_duration_since(since);
+        }
+        self.transfer.set(stats);
+        self.last_response.set(Some(received));
+        match r {
+            Ok(response) if response.status >= 400 => Err(RequestError::Status {
+                url: request.url.clone(),
+                response,
+            }),
+            Ok(response) => Ok(response),
+            Err(e) => Err(RequestError::Transport(e)),
+        }
+    }
+
+    /// Use up one retry from the retry budget, returning false if there are
+    /// none left
+    fn take_retry(&self) -> bool {
+        match self.retry_budget.get() {
+            Some(0) => false,
+            Some(n) => {
+                if n == 1 {
+                    eprintln!(
+                        "[!] Retry budget exhausted; failed requests will no longer be retried"
+                    );
+                }
+                self.retry_budget.set(Some(n - 1));
+                true
+            }
+            None => true,
+        }
+    }
+
     pub fn get_rate_limit(&self) -> anyhow::Result<RateLimit> {
-        self.inner
-            .get(RATE_LIMIT_URL)
-            .call()
+        self.send_authorized(HttpRequest::get(format!("{}/rate_limit", self.rest_url)))?
             .context("failed to perform rate limit request")?
-            .into_json::<RateLimitResponse>()
+            .json::<RateLimitResponse>()
             .context("failed to deserialize rate limit response")
             .map(|r| r.resources.graphql)
     }
 
+    /// Fetch details of the repository with the given full name
+    /// (`"{owner}/{name}"`) via the REST API.
+    ///
+    /// Returns `None` if the repository does not exist or is not accessible
+    /// with the client's access token.  Requests for repositories that have
+    /// been transferred or renamed are redirected, so the returned
+    /// `full_name` may differ from the one requested.
+    pub fn get_repository(&self, fullname: &str) -> anyhow::Result<Option<RepositoryInfo>> {
+        let url = format!("{}/repos/{fullname}", self.rest_url);
+        match self.send_authorized(HttpRequest::get(url))? {
+            Ok(resp) => resp
+                .json::<RepositoryInfo>()
+                .context("failed to deserialize repository response")
+                .map(Some),
+            Err(e) if e.status() == Some(404) => Ok(None),
+            Err(e) => {
+                Err(e).with_context(|| format!("failed to fetch repository details for {fullname}"))
+            }
+        }
+    }
+
+    /// Fetch the OAuth scopes granted to the client's access token, as
+    /// reported in the `X-OAuth-Scopes` header of a REST response.
+    ///
+    /// Returns `None` if the header is absent, as it is for fine-grained
+    /// personal access tokens and GitHub App installation tokens, whose
+    /// permissions cannot be determined this way.
+    pub fn get_token_scopes(&self) -> anyhow::Result<Option<Vec<String>>> {
+        let resp = self
+            .send_authorized(HttpRequest::get(format!("{}/rate_limit", self.rest_url)))?
+            .context("failed to perform token scopes request")?;
+        Ok(resp.header("x-oauth-scopes").map(|scopes| {
+            scopes
+                .split(',')
+                .map(str::trim)
+                .filter(|sc| !sc.is_empty())
+                .map(String::from)
+                .collect()
+        }))
+    }
+
+    /// Returns the rate limit information from the headers of the most
+    /// recent GraphQL response, if any
+    pub fn last_rate_limit_headers(&self) -> Option<RateLimitHeaders> {
+        self.last_rate_limit.get()
+    }
+
     pub fn query(&self, query: String, variables: JsonMap) -> anyhow::Result<JsonMap> {
-        let r = self
-            .inner
-            .post(GRAPHQL_API_URL)
-            .send_json(Payload { query, variables })
-            .context("failed to perform GraphQL request")?
-            .into_json::<Response>()
-            .context("failed to deserialize GraphQL response")?;
-        if !r.errors.is_empty() {
-            let mut msg = String::from("Query errored:\n");
-            let mut first = true;
-            for e in r.errors {
-                if !std::mem::take(&mut first) {
-                    writeln!(&mut msg, "---")?;
-                }
-                if let Some(t) = e.err_type {
-                    writeln!(&mut msg, "    Type: {t}")?;
+        Ok(self.query_partial(query, variables)?.into_data()?)
+    }
+
+    /// Perform a GraphQL request and return both the data and the errors in
+    /// the response, as some fields may succeed while others fail
+    pub fn query_partial(&self, query: String, variables: JsonMap) -> anyhow::Result<Response> {
+        #[cfg(feature = "otel")]
+        if let Some(ref tracer) = self.tracer {
+            let start = SystemTime::now();
+            let operation = if query.trim_start().starts_with("mutation") {
+                "mutation"
+            } else {
+                "query"
+            };
+            let r = self.send_graphql(query, variables);
+            let mut attributes = vec![("graphql.operation.type", operation.into())];
+            if let Ok(ref resp) = r {
+                attributes.push(("graphql.error_count", resp.errors.len().into()));
+                if let Some(rl) = self.last_rate_limit.get() {
+                    attributes.push(("github.ratelimit.remaining", rl.remaining.into()));
                 }
-                writeln!(&mut msg, "    Message: {}", e.message)?;
-                if let Some(p) = e.path {
-                    writeln!(&mut msg, "    Path: {p:?}")?;
+            }
+            tracer.record_request(
+                start,
+                attributes,
+                r.as_ref().err().map(|e| format!("{e:#}")),
+            );
+            return r;
+        }
+        self.send_graphql(query, variables)
+    }
+
+    fn send_graphql(&self, query: String, variables: JsonMap) -> anyhow::Result<Response> {
+        *self
+            .query_hashes
+            .borrow_mut()
+            .entry(hashes::query_hash(&query, &variables))
+            .or_insert(0) += 1;
+        let payload = Payload { query, variables };
+        let body = serde_json::to_vec(&payload).context("failed to serialize GraphQL request")?;
+        let compressed = match self.compress_above {
+            Some(threshold) if body.len() > threshold => {
+                Some(transfer::gzip(&body).context("failed to compress GraphQL request")?)
+            }
+            _ => None,
+        };
+        let payload_len = body.len();
+        let is_compressed = compressed.is_some();
+        let mut request = HttpRequest::post(&self.graphql_url, compressed.unwrap_or(body));
+        request.set_header("Content-Type", "application/json");
+        if is_compressed {
+            request.set_header("Content-Encoding", "gzip");
+        }
+        request.timeout = self.request_timeout;
+        let mut rate_limit_waits = 0;
+        loop {
+            let mut stats = self.transfer.get();
+            stats.record(payload_len, request.body.len(), is_compressed);
+            self.transfer.set(stats);
+            let resp = self
+                .send_authorized(request.clone())?
+                .context("failed to perform GraphQL request")?;
+            if let Some(rl) = RateLimitHeaders::from_response(&resp) {
+                self.last_rate_limit.set(Some(rl));
+            }
+            let mut response = if let Some(ref dir) = self.fixture_dir {
+                let raw = resp
+                    .json::<serde_json::Value>()
+                    .context("failed to deserialize GraphQL response")?;
+                let index = self.fixture_count.get();
+                self.fixture_count.set(index + 1);
+                let fixture = Fixture {
+                    query: payload.query.clone(),
+                    variables: payload.variables.clone(),
+                    response: raw,
+                };
+                fixture.save(dir, index)?;
+                serde_json::from_value::<Response>(fixture.response)
+                    .context("failed to deserialize GraphQL response")?
+            } else {
+                resp.json::<Response>()
+                    .context("failed to deserialize GraphQL response")?
+            };
+            // GitHub can also report rate limiting in a successful response's
+            // errors, in which case the request is retried like one rejected
+            // with an HTTP 403 or 429.
+            if !response.errors.is_empty() && rate_limit_waits < RATE_LIMIT_WAITS {
+                let error = GqlError {
+                    errors: response.errors,
+                };
+                if error.is_rate_limited() {
+                    wait_for_rate_limit(rate_limit_wait(&resp).unwrap_or(DEFAULT_RATE_LIMIT_WAIT));
+                    rate_limit_waits += 1;
+                    continue;
                 }
+                response.errors = error.errors;
             }
-            Err(anyhow::Error::msg(msg))
-        } else {
-            Ok(r.data)
+            return Ok(response);
         }
     }
 
+    /// Run the given paginators to completion, combining up to `batch_size`
+    /// of their page queries into each GraphQL request.
+    ///
+    /// See [`BatchPaginator`] for details on how requests are composed.
     pub fn batch_paginate<K, Q, I>(
         &self,
         queries: I,
@@ -96,54 +786,66 @@ impl Client {
         Q: Paginator,
         I: IntoIterator<Item = (K, Q)>,
     {
-        let mut in_progress = queries
-            .into_iter()
-            .map(|(key, paginator)| PaginationState::new(key, paginator))
-            .collect::<VecDeque<_>>();
-        let mut results = Vec::new();
-        while !in_progress.is_empty() {
-            let mut active = HashMap::new();
-            let mut variables = JsonMap::new();
-            let mut varstr = String::new();
-            let mut qstr = String::new();
-            let mut qwrite = indented(&mut qstr).with_str("    ");
-            for (i, state) in in_progress
-                .drain(0..(in_progress.len().min(self.batch_size.get())))
-                .enumerate()
-            {
-                let alias = format!("q{i}");
-                let query = state
-                    .paginator
-                    .for_cursor(state.cursor.as_ref())
-                    .with_variable_prefix(alias.clone());
-                for (name, Variable { gql_type, value }) in query.variables() {
-                    if i > 0 {
-                        write!(&mut varstr, ", ")?;
-                    }
-                    write!(&mut varstr, "${name}: {gql_type}")?;
-                    variables.insert(name, value);
-                }
-                write!(&mut qwrite, "{alias}: ")?;
-                query.write_graphql(&mut qwrite)?;
-                active.insert(alias, ActiveQuery { state, query });
-            }
-            let full_query = format!("query ({varstr}) {{\n{qstr}}}\n");
-            let data = self.query(full_query, variables)?;
-            for (alias, value) in data {
-                let Entry::Occupied(aqo) = active.entry(alias) else {
-                    // TODO: Warn or error
-                    continue;
-                };
-                let state = aqo.remove().process_response(value)?;
-                if state.has_next_page {
-                    in_progress.push_back(state);
-                } else {
-                    results.push(PaginationResults::from(state));
-                }
-            }
-        }
-        Ok(results)
+        BatchPaginator::new(self, queries).run()
     }
+
+    /// Like [`Client::batch_paginate()`], but paginators whose requests fail
+    /// are reported separately instead of ending the run; see
+    /// [`BatchPaginator::run_isolated()`]
+    pub fn batch_paginate_isolated<K, Q, I>(&self, queries: I) -> IsolatedResults<K, Q::Item>
+    where
+        Q: Paginator,
+        I: IntoIterator<Item = (K, Q)>,
+    {
+        BatchPaginator::new(self, queries).run_isolated()
+    }
+
+    /// Look up whether each of the given owners is a user or an
+    /// organization, combining up to `batch_size` of the lookups into each
+    /// GraphQL request.  Owners that do not exist are omitted from the
+    /// returned map.
+    pub fn resolve_owner_kinds(
+        &self,
+        owners: &[String],
+    ) -> anyhow::Result<BTreeMap<String, OwnerKind>> {
+        owners::resolve_owner_kinds(self, owners)
+    }
+
+    /// Perform the given mutations, combining up to `batch_size` of them into
+    /// each GraphQL request, and return the outcome of each one.
+    ///
+    /// See [`BatchMutator`] for details, including how to perform a dry run.
+    pub fn batch_mutate<K, M, I>(&self, mutations: I) -> Vec<MutationResult<K, M::Output>>
+    where
+        M: Query,
+        I: IntoIterator<Item = (K, M)>,
+    {
+        BatchMutator::new(self, mutations).run()
+    }
+}
+
+/// A phase of a program's run started with [`Client::phase()`], which ends
+/// when this value is dropped
+#[derive(Debug)]
+#[must_use = "the phase ends as soon as this value is dropped"]
+pub struct Phase {
+    points: Rc<PhasePoints>,
+    #[cfg(feature = "otel")]
+    _span: Option<otel::PhaseSpan>,
+}
+
+impl Drop for Phase {
+    fn drop(&mut self) {
+        self.points.open.borrow_mut().pop();
+    }
+}
+
+/// The names of a client's open phases, innermost last, and the rate limit
+/// points spent on queries in each phase
+#[derive(Debug, Default)]
+struct PhasePoints {
+    open: RefCell<Vec<String>>,
+    points: RefCell<BTreeMap<String, u64>>,
 }
 
 // This can't be replaced with Singleton because the JSON contains more than
@@ -172,90 +874,240 @@ impl RateLimit {
     }
 }
 
+impl From<RateLimitHeaders> for RateLimit {
+    fn from(value: RateLimitHeaders) -> RateLimit {
+        RateLimit {
+            used: value.used,
+            reset: value.reset,
+        }
+    }
+}
+
+/// Details about a repository as returned by the REST API
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct RepositoryInfo {
+    pub full_name: String,
+    pub private: bool,
+    pub archived: bool,
+}
+
+/// Rate limit information reported in the `x-ratelimit-*` headers of a
+/// response
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct RateLimitHeaders {
+    pub limit: u32,
+    pub remaining: u32,
+    pub used: u32,
+    pub reset: u64,
+}
+
+impl RateLimitHeaders {
+    /// Return the time at which the rate limit resets
+    pub fn reset_time(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(self.reset)
+    }
+
+    fn from_response(resp: &HttpResponse) -> Option<RateLimitHeaders> {
+        Some(RateLimitHeaders {
+            limit: resp.header("x-ratelimit-limit")?.parse().ok()?,
+            remaining: resp.header("x-ratelimit-remaining")?.parse().ok()?,
+            used: resp.header("x-ratelimit-used")?.parse().ok()?,
+            reset: resp.header("x-ratelimit-reset")?.parse().ok()?,
+        })
+    }
+}
+
+/// If `resp` is a response to a request rejected by one of GitHub's rate
+/// limits, return how long GitHub asks clients to wait before retrying: the
+/// number of seconds in the `retry-after` header if present, or else the time
+/// until the rate limit resets if no points remain
+fn rate_limit_wait(resp: &HttpResponse) -> Option<Duration> {
+    if let Some(secs) = resp
+        .header("retry-after")
+        .and_then(|s| s.trim().parse::<u64>().ok())
+    {
+        return Some(Duration::from_secs(secs));
+    }
+    let rl = RateLimitHeaders::from_response(resp)?;
+    (rl.remaining == 0).then(|| {
+        // Wait an extra second in case of clock skew
+        rl.reset_time()
+            .duration_since(SystemTime::now())
+            .unwrap_or_default()
+            + Duration::from_secs(1)
+    })
+}
+
+fn wait_for_rate_limit(wait: Duration) {
+    eprintln!(
+        "[!] Request rejected by GitHub's rate limits; waiting {}s before retrying",
+        wait.as_secs()
+    );
+    std::thread::sleep(wait);
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Serialize)]
 struct Payload {
     query: String,
     variables: JsonMap,
 }
 
+/// A response from the GraphQL API, which can contain data for some fields
+/// alongside errors for others
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
-struct Response {
-    #[serde(default)]
-    data: JsonMap,
+pub struct Response {
+    /// The response's data; empty if the response's `data` field is absent or
+    /// null, as it is when an error prevents any data from being returned
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub data: JsonMap,
     #[serde(default)]
-    errors: Vec<GraphQLError>,
+    pub errors: Vec<GqlInnerError>,
 }
 
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
-struct GraphQLError {
-    #[serde(default, rename = "type")]
-    err_type: Option<String>,
-    message: String,
-    #[serde(default)]
-    path: Option<Vec<String>>,
+impl Response {
+    /// Return the response's data if it has no errors; otherwise, discard
+    /// the data and return the errors
+    pub fn into_data(self) -> Result<JsonMap, GqlError> {
+        if self.errors.is_empty() {
+            Ok(self.data)
+        } else {
+            Err(GqlError {
+                errors: self.errors,
+            })
+        }
+    }
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
-struct PaginationState<K, P: Paginator> {
-    key: K,
-    paginator: P,
-    items: Vec<P::Item>,
-    cursor: Option<Cursor>,
-    has_next_page: bool,
+// Utility function for deserializing a null value as the type's default
+fn null_as_default<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: Default + Deserialize<'de>,
+    D: serde::Deserializer<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Option::unwrap_or_default)
 }
 
-impl<K, P: Paginator> PaginationState<K, P> {
-    fn new(key: K, paginator: P) -> Self {
-        PaginationState {
-            key,
-            paginator,
-            items: Vec::new(),
-            cursor: None,
-            has_next_page: true,
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::collections::VecDeque;
+
+    /// A transport that answers requests with canned responses in order
+    #[derive(Debug, Default)]
+    struct Scripted {
+        responses: RefCell<VecDeque<HttpResponse>>,
+        sent: Cell<usize>,
+    }
+
+    impl Transport for Rc<Scripted> {
+        fn send(&self, _request: &HttpRequest) -> anyhow::Result<HttpResponse> {
+            self.sent.set(self.sent.get() + 1);
+            self.responses
+                .borrow_mut()
+                .pop_front()
+                .context("no more responses")
         }
     }
-}
 
-// Implementing these traits requires matching bounds on P::Query, which the
-// derive macros don't handle, so the traits can only be implemented manually
-// here — but I don't need them and that's busywork, so…
-//#[derive(Clone, Debug, Eq, PartialEq)]
-struct ActiveQuery<K, P: Paginator> {
-    state: PaginationState<K, P>,
-    query: P::Query,
-}
+    fn scripted_client<I: IntoIterator<Item = HttpResponse>>(
+        responses: I,
+    ) -> (Client, Rc<Scripted>) {
+        let scripted = Rc::new(Scripted {
+            responses: RefCell::new(responses.into_iter().collect()),
+            sent: Cell::new(0),
+        });
+        let client = Client::builder()
+            .token("not-a-real-token")
+            .transport(Rc::clone(&scripted))
+            .build()
+            .expect("a custom transport alone should be accepted");
+        (client, scripted)
+    }
 
-impl<K, P: Paginator> ActiveQuery<K, P> {
-    fn process_response(
-        mut self,
-        value: serde_json::Value,
-    ) -> Result<PaginationState<K, P>, serde_json::Error> {
-        let page = self.query.parse_response(value)?;
-        self.state.items.extend(page.items);
-        if page.end_cursor.is_some() {
-            // endCursor is null when the page has no items, which happens when
-            // the current cursor is already at the end, so don't update the
-            // cursor to null.
-            self.state.cursor = page.end_cursor;
+    fn response(status: u16, body: &serde_json::Value, retry_after: Option<u64>) -> HttpResponse {
+        let mut resp = HttpResponse::new(status, serde_json::to_vec(body).unwrap());
+        if let Some(secs) = retry_after {
+            resp.headers
+                .push((String::from("retry-after"), secs.to_string()));
         }
-        self.state.has_next_page = page.has_next_page;
-        Ok(self.state)
+        resp
     }
-}
 
-#[derive(Clone, Debug, Eq, PartialEq)]
-pub struct PaginationResults<K, T> {
-    pub key: K,
-    pub items: Vec<T>,
-    pub end_cursor: Option<Cursor>,
-}
+    fn ok() -> HttpResponse {
+        response(
+            200,
+            &json!({"data": {"viewer": {"login": "jwodder"}}}),
+            None,
+        )
+    }
 
-impl<K, Q: Paginator> From<PaginationState<K, Q>> for PaginationResults<K, Q::Item> {
-    fn from(value: PaginationState<K, Q>) -> Self {
-        PaginationResults {
-            key: value.key,
-            items: value.items,
-            end_cursor: value.cursor,
-        }
+    fn query(client: &Client) -> anyhow::Result<JsonMap> {
+        client.query(String::from("query { viewer { login } }"), JsonMap::new())
+    }
+
+    #[test]
+    fn http_rate_limit_waits_without_retry_policy() {
+        let rejected = response(429, &json!({"message": "slow down"}), Some(0));
+        let (client, scripted) = scripted_client([rejected, ok()]);
+        let data = query(&client).unwrap();
+        assert_eq!(data["viewer"]["login"], "jwodder");
+        assert_eq!(scripted.sent.get(), 2);
+    }
+
+    #[test]
+    fn graphql_rate_limit_waits() {
+        let rejected = response(
+            200,
+            &json!({
+                "data": null,
+                "errors": [{
+                    "type": "RATE_LIMITED",
+                    "message": "API rate limit exceeded for user ID 1.",
+                }],
+            }),
+            Some(0),
+        );
+        let (client, scripted) = scripted_client([rejected, ok()]);
+        let data = query(&client).unwrap();
+        assert_eq!(data["viewer"]["login"], "jwodder");
+        assert_eq!(scripted.sent.get(), 2);
+    }
+
+    #[test]
+    fn rate_limit_waits_give_up() {
+        let rejected = response(403, &json!({"message": "secondary rate limit"}), Some(0));
+        let (client, scripted) = scripted_client(
+            std::iter::repeat(rejected).take(usize::try_from(RATE_LIMIT_WAITS).unwrap() + 1),
+        );
+        assert!(query(&client).is_err());
+        assert_eq!(
+            scripted.sent.get(),
+            usize::try_from(RATE_LIMIT_WAITS).unwrap() + 1
+        );
+    }
+
+    #[test]
+    fn forbidden_without_rate_limit_is_not_retried() {
+        let forbidden = response(403, &json!({"message": "Resource not accessible"}), None);
+        let (client, scripted) = scripted_client([forbidden, ok()]);
+        assert!(query(&client).is_err());
+        assert_eq!(scripted.sent.get(), 1);
+    }
+
+    #[test]
+    fn transport_with_agent_config_is_rejected() {
+        let built = Client::builder()
+            .token("not-a-real-token")
+            .configure_agent(|agent| agent.timeout(Duration::from_secs(5)))
+            .transport(Rc::new(Scripted::default()))
+            .build();
+        assert!(built.is_err());
+        let built = Client::builder()
+            .token("not-a-real-token")
+            .transport(Rc::new(Scripted::default()))
+            .timeout(Duration::from_secs(5))
+            .build();
+        assert!(built.is_ok());
     }
 }
@@ -0,0 +1,724 @@
+use crate::batch::{BatchParts, PaginationResults, PendingPaginator};
+use crate::errors::{DeserializeError, GqlError, GqlInnerError, MissingAliasError};
+use crate::queries::{Paginator, Query};
+use crate::types::{Cursor, JsonMap, Page, Variable};
+use crate::Response;
+use indenter::indented;
+use std::collections::{HashMap, VecDeque};
+use std::fmt::{self, Write};
+use std::time::Instant;
+
+/// The number of times in a row that a sub-query whose data is missing from
+/// a response is retried before giving up with a [`MissingAliasError`]
+pub(crate) const MISSING_ALIAS_RETRIES: u32 = 2;
+
+/// A field added to every composed query so that the rate limit points spent
+/// on each request can be attributed to the client's current phase
+pub(crate) const RATE_LIMIT_FIELD: &str = "    rateLimit {\n        cost\n    }\n";
+
+/// When adapting page sizes, a paginator whose pages on their own count for
+/// at least this percentage of the node limit has its page size lowered
+const NEAR_NODE_LIMIT_PERCENT: u64 = 90;
+
+/// The sans-IO core of a [`BatchPaginator`](crate::BatchPaginator): a queue
+/// of paginators that composes the GraphQL requests for their pages and
+/// applies the responses to those requests, without performing any I/O of
+/// its own
+///
+/// A driver runs the machine by repeatedly calling
+/// [`BatchMachine::start_request()`], sending the returned request however it
+/// likes, and passing the response to [`BatchMachine::handle_response()`],
+/// until the machine [is done](BatchMachine::is_done).  After each call to
+/// `handle_response()`, the driver should take the machine's
+/// [events](BatchMachine::take_events) in order to display its warnings and
+/// keep count of its statistics.  See [`BatchPaginator`](crate::BatchPaginator)
+/// for how requests are composed and how responses are handled.
+pub struct BatchMachine<K, P: Paginator> {
+    config: MachineConfig,
+    pub(crate) in_progress: VecDeque<PaginationState<K, P>>,
+    pub(crate) results: Vec<PaginationResults<K, P::Item>>,
+    // The aliases in the most recent request and its query document
+    last_request: Option<(Vec<String>, String)>,
+    // The number of paginators in the request awaiting a response and their
+    // queries
+    in_flight: Option<Vec<P::Query>>,
+    events: Vec<Event>,
+    bad_data: Option<(String, serde_json::Value)>,
+}
+
+impl<K, P: Paginator> fmt::Debug for BatchMachine<K, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BatchMachine")
+            .field("config", &self.config)
+            .field("pending", &self.in_progress.len())
+            .field("finished", &self.results.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<K, P: Paginator> BatchMachine<K, P> {
+    pub fn new<I>(config: MachineConfig, queries: I) -> Self
+    where
+        I: IntoIterator<Item = (K, P)>,
+    {
+        BatchMachine::from_parts(
+            config,
+            BatchParts {
+                pending: queries
+                    .into_iter()
+                    .map(|(key, paginator)| PendingPaginator::new(key, paginator))
+                    .collect(),
+                finished: Vec::new(),
+            },
+        )
+    }
+
+    /// Reconstruct a `BatchMachine` from parts previously returned by
+    /// [`BatchMachine::into_parts()`] (possibly with pending paginators added
+    /// or removed)
+    pub fn from_parts(config: MachineConfig, parts: BatchParts<K, P>) -> Self {
+        let in_progress = parts
+            .pending
+            .into_iter()
+            .enumerate()
+            // Each paginator keeps the same alias for its whole lifetime so
+            // that its rendered sub-query text can be reused from page to
+            // page.
+            .map(|(i, pending)| PaginationState::from_pending(pending, format!("q{i}")))
+            .collect();
+        BatchMachine {
+            config,
+            in_progress,
+            results: parts.finished,
+            last_request: None,
+            in_flight: None,
+            events: Vec::new(),
+            bad_data: None,
+        }
+    }
+
+    /// Break the `BatchMachine` up into the paginators that still have pages
+    /// left to fetch (in the order in which they would be queried) and the
+    /// results of those that are done
+    pub fn into_parts(self) -> BatchParts<K, P> {
+        BatchParts {
+            pending: self
+                .in_progress
+                .into_iter()
+                .map(PendingPaginator::from)
+                .collect(),
+            finished: self.results,
+        }
+    }
+
+    /// Remove and return the results of the paginators that have fetched
+    /// their final page since the `BatchMachine` was created or last flushed
+    pub fn flush(&mut self) -> Vec<PaginationResults<K, P::Item>> {
+        std::mem::take(&mut self.results)
+    }
+
+    /// Returns true if all paginators have fetched their final page
+    pub fn is_done(&self) -> bool {
+        self.in_progress.is_empty()
+    }
+
+    /// Return the total number of paginators, finished or not
+    pub fn len(&self) -> usize {
+        self.in_progress.len() + self.results.len()
+    }
+
+    /// Returns true if the machine has no paginators, finished or not
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Compose the GraphQL request for the next batch of at most
+    /// `batch_size` pages, or return `None` if all paginators are done.
+    ///
+    /// The response to the request must be passed to
+    /// [`BatchMachine::handle_response()`] before the next call to this
+    /// method; if the request could not be made, this method can simply be
+    /// called again to compose it anew.
+    pub fn start_request(&mut self, batch_size: usize) -> anyhow::Result<Option<BatchRequest>> {
+        self.in_flight = None;
+        let batch_size = self.next_batch_len(batch_size);
+        if batch_size == 0 {
+            return Ok(None);
+        }
+        let reusable = self
+            .in_progress
+            .iter()
+            .take(batch_size)
+            .all(|st| st.cursor.is_some() && st.rendered.is_some())
+            && self.last_request.as_ref().is_some_and(|(aliases, _)| {
+                aliases
+                    .iter()
+                    .eq(self.in_progress.iter().take(batch_size).map(|st| &st.alias))
+            });
+        let mut queries = Vec::with_capacity(batch_size);
+        let mut variables = JsonMap::new();
+        let mut varstr = String::new();
+        let mut qstr = String::new();
+        for state in self.in_progress.iter_mut().take(batch_size) {
+            let query = state
+                .paginator
+                .for_cursor(state.cursor.as_ref())
+                .with_variable_prefix(state.alias.clone());
+            declare_variables(&query, &mut variables, &mut varstr)?;
+            if !reusable {
+                match state.rendered {
+                    Some(ref snippet) if state.cursor.is_some() => qstr.push_str(snippet),
+                    _ => {
+                        let snippet = render_subquery(&state.alias, &query)?;
+                        qstr.push_str(&snippet);
+                        if state.cursor.is_some() {
+                            state.rendered = Some(snippet);
+                        }
+                    }
+                }
+            }
+            queries.push(query);
+        }
+        let aliases = self
+            .in_progress
+            .iter()
+            .take(batch_size)
+            .map(|st| st.alias.clone())
+            .collect::<Vec<_>>();
+        let query = match self.last_request {
+            Some((_, ref doc)) if reusable => doc.clone(),
+            _ => {
+                let doc = format!("query ({varstr}) {{\n{qstr}{RATE_LIMIT_FIELD}}}\n");
+                self.last_request = Some((aliases.clone(), doc.clone()));
+                doc
+            }
+        };
+        self.in_flight = Some(queries);
+        Ok(Some(BatchRequest {
+            query,
+            variables,
+            aliases,
+        }))
+    }
+
+    /// Apply the response to the request last returned by
+    /// [`BatchMachine::start_request()`], which was sent at `sent`.  `slow`
+    /// is whether the request took long enough that, if page sizes are
+    /// adapted, the paginators in it should have their page sizes lowered.
+    /// Each sub-query's data is parsed with `parse`, which is normally just
+    /// [`Query::parse_response()`].
+    ///
+    /// If any part of the response cannot be applied, an error is returned
+    /// and the paginators in the batch are left as they were before the
+    /// request.  If the error is a [`DeserializeError`], its
+    /// [`saved_to`](DeserializeError::saved_to) field is `None`, and the raw
+    /// data that could not be deserialized can be obtained from
+    /// [`BatchMachine::take_bad_data()`].
+    pub fn handle_response<F>(
+        &mut self,
+        response: Response,
+        sent: Instant,
+        slow: bool,
+        mut parse: F,
+    ) -> anyhow::Result<()>
+    where
+        F: FnMut(&P::Query, serde_json::Value) -> Result<Page<P::Item>, serde_json::Error>,
+    {
+        let Some(queries) = self.in_flight.take() else {
+            anyhow::bail!("BatchMachine received a response without a request in flight");
+        };
+        let batch_size = queries.len();
+        let Response { mut data, errors } = response;
+        let mut errored = HashMap::new();
+        if !errors.is_empty() {
+            let error = GqlError { errors };
+            if self.reset_rejected_cursors(&error, batch_size) {
+                return Ok(());
+            }
+            if error.is_node_limit() && self.reduce_page_sizes(batch_size) {
+                return Ok(());
+            }
+            errored = self.errors_by_alias(error, batch_size)?;
+            self.events.push(Event::PartialResponse);
+            // The sub-queries with errors are treated as missing.
+            for alias in errored.keys() {
+                data.remove(alias);
+            }
+        }
+        let cost = data
+            .remove("rateLimit")
+            .and_then(|rl| rl.get("cost")?.as_u64());
+        self.events.push(Event::RequestCompleted { cost });
+        // Parse every page before updating any paginator so that a bad
+        // response leaves the whole batch untouched.  A page that is to be
+        // retried is an `Err`.
+        let mut pages = Vec::with_capacity(batch_size);
+        for (state, query) in self.in_progress.iter().zip(&queries) {
+            let Some(value) = data.remove(&state.alias) else {
+                let errors = errored.remove(&state.alias).unwrap_or_default();
+                if state.missing < MISSING_ALIAS_RETRIES {
+                    pages.push(Err(Retry::Missing(errors)));
+                    continue;
+                }
+                return Err(MissingAliasError {
+                    alias: state.alias.clone(),
+                    key: describe_key(&state.alias, query),
+                    cursor: state.cursor.clone(),
+                    errors,
+                }
+                .into());
+            };
+            // Parsing consumes the data, so a copy is kept in case it needs
+            // to be saved for debugging.
+            let raw = value.clone();
+            match parse(query, value) {
+                Ok(page) => pages.push(Ok(page)),
+                Err(e) if state.undeserializable < self.config.deserialize_retries => {
+                    pages.push(Err(Retry::Undeserializable(e)));
+                }
+                Err(source) => {
+                    self.bad_data = Some((state.alias.clone(), raw));
+                    return Err(DeserializeError {
+                        alias: state.alias.clone(),
+                        key: describe_key(&state.alias, query),
+                        cursor: state.cursor.clone(),
+                        saved_to: None,
+                        source,
+                    }
+                    .into());
+                }
+            }
+        }
+        let mut continuing = Vec::new();
+        for (mut state, page) in self.in_progress.drain(0..batch_size).zip(pages) {
+            state.requests += 1;
+            state.started.get_or_insert(sent);
+            let page = match page {
+                Ok(page) => page,
+                Err(retry) => {
+                    let msg = match retry {
+                        Retry::Missing(errors) => {
+                            state.missing += 1;
+                            if errors.is_empty() {
+                                format!(
+                                    "[!] GraphQL response did not include data for sub-query {}; retrying it",
+                                    state.alias
+                                )
+                            } else {
+                                format!(
+                                    "[!] GraphQL response included errors for sub-query {}: {}; retrying it",
+                                    state.alias,
+                                    describe_errors(&errors)
+                                )
+                            }
+                        }
+                        Retry::Undeserializable(e) => {
+                            state.missing = 0;
+                            state.undeserializable += 1;
+                            format!(
+                                "[!] Could not deserialize GraphQL response data for sub-query {}: {e}; retrying it",
+                                state.alias
+                            )
+                        }
+                    };
+                    self.events.push(Event::Warning(msg));
+                    continuing.push(state);
+                    continue;
+                }
+            };
+            state.missing = 0;
+            state.undeserializable = 0;
+            self.events.push(Event::ItemsFetched(page.items.len()));
+            state.apply(page);
+            if state.has_next_page {
+                continuing.push(state);
+            } else {
+                self.events.push(Event::PaginationFinished);
+                self.results.push(PaginationResults::from(state));
+            }
+        }
+        for state in &mut continuing {
+            self.adapt_page_size(state, slow);
+        }
+        for state in continuing.into_iter().rev() {
+            self.in_progress.push_front(state);
+        }
+        Ok(())
+    }
+
+    /// Remove and return the events that have occurred since the last call
+    pub fn take_events(&mut self) -> Vec<Event> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// After [`BatchMachine::handle_response()`] failed with a
+    /// [`DeserializeError`], return the alias of the sub-query whose data
+    /// could not be deserialized along with the raw data
+    pub fn take_bad_data(&mut self) -> Option<(String, serde_json::Value)> {
+        self.bad_data.take()
+    }
+
+    /// If `error` is solely due to GitHub rejecting the cursors of
+    /// paginators among the first `batch_size` in the queue, none of which
+    /// have been restarted before, restart them from their first pages and
+    /// return true
+    fn reset_rejected_cursors(&mut self, error: &GqlError, batch_size: usize) -> bool {
+        let Some(aliases) = error
+            .invalid_cursor_aliases()
+            .filter(|aliases| !aliases.is_empty())
+        else {
+            return false;
+        };
+        let resettable = aliases.iter().all(|&alias| {
+            self.in_progress
+                .iter()
+                .take(batch_size)
+                .any(|st| st.alias == alias && !st.cursor_reset)
+        });
+        if !resettable {
+            return false;
+        }
+        for state in self.in_progress.iter_mut().take(batch_size) {
+            if aliases.contains(&state.alias.as_str()) {
+                state.restart();
+                self.events.push(Event::CursorReset);
+                self.events.push(Event::Warning(format!(
+                    "[!] GitHub rejected the cursor for sub-query {}; restarting its pagination from the beginning",
+                    state.alias
+                )));
+            }
+        }
+        // The restarted sub-queries have to be rendered anew.
+        self.last_request = None;
+        true
+    }
+
+    /// Lower the page sizes of the first `batch_size` paginators in the queue
+    /// after GitHub rejected a request for them for asking for too many
+    /// nodes, so that the request can be retried.  Returns false if none of
+    /// their page sizes could be lowered.
+    fn reduce_page_sizes(&mut self, batch_size: usize) -> bool {
+        let mut reduced = false;
+        for state in self.in_progress.iter_mut().take(batch_size) {
+            let Some(page_size) = state.paginator.reduce_page_size() else {
+                continue;
+            };
+            reduced = true;
+            // The sub-query has to be rendered anew with its new page size.
+            state.rendered = None;
+            self.events.push(Event::PageSizeReduced);
+            self.events.push(Event::Warning(format!(
+                "[!] Request with sub-query {} exceeded GitHub's node limit; lowering its page size to {page_size}",
+                state.alias
+            )));
+        }
+        if reduced {
+            self.last_request = None;
+        }
+        reduced
+    }
+
+    /// If page sizes are adapted and `state`'s paginator was in a slow
+    /// request or its pages come near the node limit on their own, lower its
+    /// page size for its subsequent pages
+    fn adapt_page_size(&mut self, state: &mut PaginationState<K, P>, slow: bool) {
+        if !self.config.adaptive_page_size {
+            return;
+        }
+        let reason = if slow {
+            "was in a slow request"
+        } else if self
+            .config
+            .node_limit
+            .zip(state.paginator.nodes_per_page())
+            .is_some_and(|(limit, nodes)| {
+                nodes.saturating_mul(100) >= limit.saturating_mul(NEAR_NODE_LIMIT_PERCENT)
+            })
+        {
+            "comes near the node limit"
+        } else {
+            return;
+        };
+        let Some(page_size) = state.paginator.reduce_page_size() else {
+            return;
+        };
+        // The sub-query has to be rendered anew with its new page size.
+        state.rendered = None;
+        self.last_request = None;
+        self.events.push(Event::PageSizeReduced);
+        self.events.push(Event::Warning(format!(
+            "[!] Sub-query {} {reason}; lowering its page size to {page_size}",
+            state.alias
+        )));
+    }
+
+    /// If every error in `error` concerns a sub-query of one of the first
+    /// `batch_size` paginators in the queue (i.e., the first element of its
+    /// path is the sub-query's alias), return the errors grouped by alias;
+    /// otherwise, return `error` unchanged
+    fn errors_by_alias(
+        &self,
+        error: GqlError,
+        batch_size: usize,
+    ) -> Result<HashMap<String, Vec<GqlInnerError>>, GqlError> {
+        let concerns_one = |e: &GqlInnerError| {
+            e.path
+                .as_ref()
+                .and_then(|path| path.first())
+                .is_some_and(|a| {
+                    self.in_progress
+                        .iter()
+                        .take(batch_size)
+                        .any(|st| &st.alias == a)
+                })
+        };
+        if !error.errors.iter().all(concerns_one) {
+            return Err(error);
+        }
+        let mut by_alias = HashMap::<String, Vec<GqlInnerError>>::new();
+        for e in error.errors {
+            if let Some(alias) = e.path.as_ref().and_then(|path| path.first()) {
+                by_alias.entry(alias.clone()).or_default().push(e);
+            }
+        }
+        Ok(by_alias)
+    }
+
+    /// Return the number of paginators at the front of the queue to include
+    /// in the next request: at most `batch_size` of them and, if there is a
+    /// node limit, only as many as fit within it (but always at least one)
+    pub fn next_batch_len(&self, batch_size: usize) -> usize {
+        let batch_size = self.in_progress.len().min(batch_size);
+        let Some(limit) = self.config.node_limit else {
+            return batch_size;
+        };
+        let mut total = 0u64;
+        let mut n = 0;
+        for state in self.in_progress.iter().take(batch_size) {
+            total = total.saturating_add(state.paginator.nodes_per_page().unwrap_or(1));
+            if total > limit && n > 0 {
+                break;
+            }
+            n += 1;
+        }
+        n
+    }
+}
+
+/// The settings of a [`BatchMachine`], normally taken from those of a
+/// [`Client`](crate::Client)
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct MachineConfig {
+    /// The maximum number of nodes to request at once; see
+    /// [`Client::node_limit()`](crate::Client::node_limit)
+    pub node_limit: Option<u64>,
+    /// The number of times in a row to retry a sub-query whose data cannot be
+    /// deserialized; see
+    /// [`Client::deserialize_retries()`](crate::Client::deserialize_retries)
+    pub deserialize_retries: u32,
+    /// Whether to lower the page sizes of paginators in slow requests or
+    /// whose pages come near the node limit; see
+    /// [`Client::adaptive_page_size()`](crate::Client::adaptive_page_size)
+    pub adaptive_page_size: bool,
+}
+
+/// A GraphQL request composed by [`BatchMachine::start_request()`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BatchRequest {
+    pub query: String,
+    pub variables: JsonMap,
+    /// The aliases of the request's sub-queries, in order
+    pub aliases: Vec<String>,
+}
+
+impl BatchRequest {
+    /// Describe each of the request's sub-queries by its alias and the
+    /// values of its own variables other than cursors, e.g.,
+    /// `q3 (owner: "jwodder")`
+    pub fn describe(&self) -> String {
+        let mut s = String::new();
+        for alias in &self.aliases {
+            if !s.is_empty() {
+                s.push_str(", ");
+            }
+            s.push_str(alias);
+            let own = describe_own_variables(alias, &self.variables);
+            if !own.is_empty() {
+                let _ = write!(s, " ({own})");
+            }
+        }
+        s
+    }
+}
+
+/// Something that happened while a [`BatchMachine`] handled a response, for
+/// its driver to display or keep count of
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Event {
+    /// A message to show the user
+    Warning(String),
+    /// The response was applied, and its query cost the given number of rate
+    /// limit points (if known)
+    RequestCompleted { cost: Option<u64> },
+    /// A page with the given number of items was applied to a paginator
+    ItemsFetched(usize),
+    /// A paginator fetched its final page
+    PaginationFinished,
+    /// The response had errors concerning only some of its sub-queries, which
+    /// are retried; see [`Client::partial_responses()`](crate::Client::partial_responses)
+    PartialResponse,
+    /// A paginator whose cursor was rejected was restarted; see
+    /// [`Client::cursors_reset()`](crate::Client::cursors_reset)
+    CursorReset,
+    /// A paginator's page size was lowered; see
+    /// [`Client::page_size_reductions()`](crate::Client::page_size_reductions)
+    PageSizeReduced,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct PaginationState<K, P: Paginator> {
+    pub(crate) key: K,
+    pub(crate) paginator: P,
+    pub(crate) items: Vec<P::Item>,
+    pub(crate) cursor: Option<Cursor>,
+    has_next_page: bool,
+    pub(crate) alias: String,
+    // The rendered text of the paginator's sub-query for pages after the
+    // first
+    rendered: Option<String>,
+    pub(crate) requests: usize,
+    pub(crate) started: Option<Instant>,
+    // The number of consecutive responses that have lacked this paginator's
+    // data
+    missing: u32,
+    // The number of consecutive responses in which this paginator's data
+    // could not be deserialized
+    undeserializable: u32,
+    // Whether the paginator has been restarted due to a rejected cursor
+    cursor_reset: bool,
+}
+
+impl<K, P: Paginator> PaginationState<K, P> {
+    fn from_pending(pending: PendingPaginator<K, P>, alias: String) -> Self {
+        PaginationState {
+            key: pending.key,
+            paginator: pending.paginator,
+            items: pending.items,
+            cursor: pending.cursor,
+            has_next_page: true,
+            alias,
+            rendered: None,
+            requests: pending.requests,
+            started: None,
+            missing: 0,
+            undeserializable: 0,
+            cursor_reset: false,
+        }
+    }
+
+    /// Start paginating over from the first page after the paginator's
+    /// cursor was rejected
+    fn restart(&mut self) {
+        self.paginator.reset_cursor();
+        self.items.clear();
+        self.cursor = None;
+        self.has_next_page = true;
+        self.rendered = None;
+        self.cursor_reset = true;
+    }
+
+    fn apply(&mut self, page: Page<P::Item>) {
+        self.items.extend(page.items);
+        if page.end_cursor.is_some() {
+            // endCursor is null when the page has no items, which happens when
+            // the current cursor is already at the end, so don't update the
+            // cursor to null.
+            self.cursor = page.end_cursor;
+        }
+        self.has_next_page = page.has_next_page;
+    }
+}
+
+/// Why a sub-query's page in a response was not applied, causing the
+/// sub-query to be retried in the next request
+#[derive(Debug)]
+enum Retry {
+    /// The response did not include data for the sub-query, possibly because
+    /// of the given errors
+    Missing(Vec<GqlInnerError>),
+    /// The sub-query's data could not be deserialized
+    Undeserializable(serde_json::Error),
+}
+
+/// Describe the messages of the given errors on a single line
+fn describe_errors(errors: &[GqlInnerError]) -> String {
+    errors
+        .iter()
+        .map(|e| match e.err_type {
+            Some(ref t) => format!("{t}: {}", e.message),
+            None => e.message.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Describe the values of `query`'s own variables other than its cursor,
+/// where `query` is the sub-query with the given alias
+fn describe_key<Q: Query>(alias: &str, query: &Q) -> String {
+    let own_vars = query
+        .variables()
+        .into_iter()
+        .map(|(name, var)| (name, var.value))
+        .collect::<JsonMap>();
+    describe_own_variables(alias, &own_vars)
+}
+
+/// Describe the values of the variables in `variables` that belong to the
+/// sub-query with the given alias, other than its cursor, e.g.,
+/// `owner: "jwodder"`
+fn describe_own_variables(alias: &str, variables: &JsonMap) -> String {
+    let prefix = format!("{alias}_");
+    variables
+        .iter()
+        .filter_map(|(name, value)| Some((name.strip_prefix(&prefix)?, value)))
+        .filter(|&(name, _)| name != "cursor")
+        .map(|(name, value)| format!("{name}: {value}"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Add the variables of `query` to the variables of a request being composed,
+/// along with their declarations
+///
+/// Variables shared between sub-queries (e.g., page sizes) are only declared
+/// once per request, and it is an error for them to have different values.
+pub(crate) fn declare_variables<Q: Query>(
+    query: &Q,
+    variables: &mut JsonMap,
+    varstr: &mut String,
+) -> anyhow::Result<()> {
+    for (name, Variable { gql_type, value }) in query.variables() {
+        if let Some(prev) = variables.get(&name) {
+            if prev != &value {
+                anyhow::bail!(
+                    "sub-queries in the same batch gave conflicting values for GraphQL variable ${name}"
+                );
+            }
+            continue;
+        }
+        if !varstr.is_empty() {
+            varstr.push_str(", ");
+        }
+        write!(varstr, "${name}: {gql_type}")?;
+        variables.insert(name, value);
+    }
+    Ok(())
+}
+
+pub(crate) fn render_subquery<Q: Query>(alias: &str, query: &Q) -> Result<String, fmt::Error> {
+    let mut snippet = String::new();
+    let mut w = indented(&mut snippet).with_str("    ");
+    write!(w, "{alias}: ")?;
+    query.write_graphql(&mut w)?;
+    Ok(snippet)
+}
@@ -0,0 +1,20 @@
+use std::fs;
+
+/// Return the peak resident set size of the current process in bytes, or
+/// `None` if it cannot be determined.
+///
+/// This reads the `VmHWM` ("high water mark") field of `/proc/self/status`
+/// and so is only supported on Linux.
+pub fn peak_rss() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    parse_vm_hwm(&status)
+}
+
+fn parse_vm_hwm(status: &str) -> Option<u64> {
+    let value = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmHWM:"))?
+        .trim();
+    let kib = value.strip_suffix("kB").unwrap_or(value).trim();
+    kib.parse::<u64>().ok()?.checked_mul(1024)
+}
@@ -0,0 +1,181 @@
+use crate::batch::compose_document;
+use crate::errors::{GqlError, GqlInnerError};
+use crate::queries::Query;
+use crate::Client;
+use std::collections::HashMap;
+use std::fmt;
+
+/// A set of GraphQL mutations run by a [`Client`], with up to the client's
+/// batch size of them combined into each request as aliased fields of a
+/// single `mutation` operation
+///
+/// Each mutation is described by a [`Query`] whose GraphQL text is a mutation
+/// field (e.g., `addLabelsToLabelable(input: {...}) { clientMutationId }`)
+/// rather than a query field.  As mutations in the same request usually
+/// differ in their inputs, their variables should be named using the prefix
+/// passed to [`Query::with_variable_prefix()`].
+///
+/// The server does not roll back the mutations in a request when one of them
+/// fails, so the outcome of each mutation is reported separately: errors
+/// whose path starts with a mutation's alias are attributed to that mutation
+/// alone, while a request that fails outright fails every mutation in it.
+/// Later requests are still made after a failure.
+pub struct BatchMutator<'a, K, M> {
+    client: &'a Client,
+    mutations: Vec<(K, M)>,
+    dry_run: bool,
+}
+
+impl<K, M> fmt::Debug for BatchMutator<'_, K, M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BatchMutator")
+            .field("mutations", &self.mutations.len())
+            .field("dry_run", &self.dry_run)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a, K, M: Query> BatchMutator<'a, K, M> {
+    pub fn new<I>(client: &'a Client, mutations: I) -> Self
+    where
+        I: IntoIterator<Item = (K, M)>,
+    {
+        BatchMutator {
+            client,
+            mutations: mutations.into_iter().collect(),
+            dry_run: false,
+        }
+    }
+
+    /// If `dry_run` is true, compose each request but do not send it, and
+    /// report every mutation as [`MutationOutcome::DryRun`].  If the client
+    /// is verbose, the composed requests are logged to stderr.
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Make requests until all mutations have been attempted, and return
+    /// their outcomes in the order the mutations were given
+    pub fn run(self) -> Vec<MutationResult<K, M::Output>> {
+        let batch_size = self.client.batch_size.get();
+        let mut results = Vec::with_capacity(self.mutations.len());
+        let mut mutations = self.mutations.into_iter();
+        loop {
+            let batch = mutations.by_ref().take(batch_size).collect::<Vec<_>>();
+            if batch.is_empty() {
+                break;
+            }
+            results.extend(run_batch(self.client, self.dry_run, batch));
+        }
+        results
+    }
+}
+
+fn run_batch<K, M: Query>(
+    client: &Client,
+    dry_run: bool,
+    batch: Vec<(K, M)>,
+) -> Vec<MutationResult<K, M::Output>> {
+    let batch = batch
+        .into_iter()
+        .enumerate()
+        .map(|(i, (key, mutation))| {
+            let alias = format!("m{i}");
+            let mutation = mutation.with_variable_prefix(alias.clone());
+            (key, alias, mutation)
+        })
+        .collect::<Vec<_>>();
+    let response = compose_document(
+        "mutation",
+        batch.iter().map(|(_, alias, m)| (alias.as_str(), m)),
+    )
+    .and_then(|(doc, variables)| {
+        if dry_run {
+            if client.verbose {
+                crate::progress!("Dry run; not sending request:\n{doc}");
+            }
+            Ok(None)
+        } else {
+            client.query_partial(doc, variables).map(Some)
+        }
+    });
+    let mut response = match response {
+        Ok(Some(r)) => r,
+        Ok(None) => {
+            return batch
+                .into_iter()
+                .map(|(key, _, _)| MutationResult {
+                    key,
+                    outcome: MutationOutcome::DryRun,
+                })
+                .collect()
+        }
+        Err(e) => {
+            let msg = format!("{e:#}");
+            return batch
+                .into_iter()
+                .map(|(key, _, _)| MutationResult {
+                    key,
+                    outcome: MutationOutcome::Failed(anyhow::anyhow!("{msg}")),
+                })
+                .collect();
+        }
+    };
+    let mut attributed = HashMap::<String, Vec<GqlInnerError>>::new();
+    let mut unattributed = Vec::new();
+    for e in response.errors {
+        match e.path.as_ref().and_then(|p| p.first()) {
+            Some(alias) if batch.iter().any(|(_, a, _)| a == alias) => {
+                attributed.entry(alias.clone()).or_default().push(e);
+            }
+            _ => unattributed.push(e),
+        }
+    }
+    batch
+        .into_iter()
+        .map(|(key, alias, mutation)| {
+            let outcome = if let Some(errors) = attributed.remove(&alias) {
+                MutationOutcome::Failed(GqlError { errors }.into())
+            } else {
+                match response.data.remove(&alias) {
+                    Some(value) if !value.is_null() => {
+                        match client.parse_response(&mutation, value) {
+                            Ok(output) => MutationOutcome::Applied(output),
+                            Err(e) => MutationOutcome::Failed(e.into()),
+                        }
+                    }
+                    _ if !unattributed.is_empty() => MutationOutcome::Failed(
+                        GqlError {
+                            errors: unattributed.clone(),
+                        }
+                        .into(),
+                    ),
+                    _ => MutationOutcome::Failed(anyhow::anyhow!(
+                        "GraphQL response did not include data for mutation {alias}"
+                    )),
+                }
+            };
+            MutationResult { key, outcome }
+        })
+        .collect()
+}
+
+/// The outcome of a single mutation run by a [`BatchMutator`]
+#[derive(Debug)]
+pub struct MutationResult<K, T> {
+    pub key: K,
+    pub outcome: MutationOutcome<T>,
+}
+
+#[derive(Debug)]
+pub enum MutationOutcome<T> {
+    /// The mutation was performed, and the server responded with the given
+    /// output
+    Applied(T),
+    /// The mutation failed or its result could not be determined
+    Failed(anyhow::Error),
+    /// The mutation was not sent because the [`BatchMutator`] was in dry-run
+    /// mode
+    DryRun,
+}
@@ -0,0 +1,386 @@
+use crate::connect::ConnectionStats;
+use crate::sample::SplitMix64;
+use anyhow::{bail, Context};
+use serde_json::{json, Value};
+use std::cell::RefCell;
+use std::path::Path;
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A collector of spans for a single run of a program, exported as one trace
+/// to an OpenTelemetry collector via OTLP over HTTP (with JSON encoding) when
+/// [`Tracer::export()`] is called
+///
+/// The trace consists of a root span covering the whole run, a span for each
+/// phase started with [`Client::phase()`](crate::Client::phase), and a span
+/// for each GraphQL request, which is a child of the innermost phase open at
+/// the time.
+#[derive(Debug)]
+pub struct Tracer {
+    agent: ureq::Agent,
+    endpoint: String,
+    headers: Vec<(String, String)>,
+    service_name: String,
+    trace_id: String,
+    root_id: String,
+    start: SystemTime,
+    ids: RefCell<SplitMix64>,
+    open_phases: RefCell<Vec<String>>,
+    spans: RefCell<Vec<SpanRecord>>,
+}
+
+impl Tracer {
+    /// Create a tracer configured by the standard OpenTelemetry environment
+    /// variables, or return `None` if neither `OTEL_EXPORTER_OTLP_ENDPOINT`
+    /// nor `OTEL_EXPORTER_OTLP_TRACES_ENDPOINT` is set.
+    ///
+    /// The following variables are honored:
+    ///
+    /// - `OTEL_EXPORTER_OTLP_TRACES_ENDPOINT` — the URL to export to as-is
+    /// - `OTEL_EXPORTER_OTLP_ENDPOINT` — a base URL to which `/v1/traces` is
+    ///   appended
+    /// - `OTEL_EXPORTER_OTLP_HEADERS` & `OTEL_EXPORTER_OTLP_TRACES_HEADERS` —
+    ///   comma-separated `key=value` pairs to send as HTTP headers
+    /// - `OTEL_EXPORTER_OTLP_PROTOCOL` &
+    ///   `OTEL_EXPORTER_OTLP_TRACES_PROTOCOL` — only `http/json` is supported
+    /// - `OTEL_SERVICE_NAME` — defaults to `unknown_service:` followed by the
+    ///   name of the executable
+    pub fn from_env() -> anyhow::Result<Option<Tracer>> {
+        let endpoint = if let Some(url) = env_var("OTEL_EXPORTER_OTLP_TRACES_ENDPOINT") {
+            url
+        } else if let Some(url) = env_var("OTEL_EXPORTER_OTLP_ENDPOINT") {
+            format!("{}/v1/traces", url.trim_end_matches('/'))
+        } else {
+            return Ok(None);
+        };
+        if let Some(protocol) = env_var("OTEL_EXPORTER_OTLP_TRACES_PROTOCOL")
+            .or_else(|| env_var("OTEL_EXPORTER_OTLP_PROTOCOL"))
+        {
+            if protocol != "http/json" {
+                bail!("unsupported OTLP protocol {protocol:?}; only \"http/json\" is supported");
+            }
+        }
+        let mut headers = Vec::new();
+        for var in [
+            "OTEL_EXPORTER_OTLP_HEADERS",
+            "OTEL_EXPORTER_OTLP_TRACES_HEADERS",
+        ] {
+            if let Some(value) = env_var(var) {
+                headers.extend(parse_headers(&value).with_context(|| format!("invalid {var}"))?);
+            }
+        }
+        let service_name = env_var("OTEL_SERVICE_NAME").unwrap_or_else(|| {
+            let exe = std::env::current_exe().ok();
+            let name = exe
+                .as_deref()
+                .and_then(Path::file_stem)
+                .map_or_else(String::new, |s| s.to_string_lossy().into_owned());
+            format!("unknown_service:{name}")
+        });
+        let start = SystemTime::now();
+        let seed = start
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| u64::from(d.subsec_nanos()) ^ d.as_secs())
+            ^ u64::from(std::process::id()).rotate_left(32);
+        let mut ids = SplitMix64::new(seed);
+        let trace_id = format!("{:016x}{:016x}", ids.next_u64(), ids.next_u64());
+        let root_id = format!("{:016x}", ids.next_u64());
+        // The exports use the same TLS backend as the client's requests, but
+        // their connections are not counted in its transfer statistics.
+        let agent = ConnectionStats::default().agent_builder().build();
+        Ok(Some(Tracer {
+            agent,
+            endpoint,
+            headers,
+            service_name,
+            trace_id,
+            root_id,
+            start,
+            ids: RefCell::new(ids),
+            open_phases: RefCell::new(Vec::new()),
+            spans: RefCell::new(Vec::new()),
+        }))
+    }
+
+    fn new_span_id(&self) -> String {
+        format!("{:016x}", self.ids.borrow_mut().next_u64())
+    }
+
+    /// Return the ID of the innermost open phase, or of the root span if no
+    /// phase is open
+    fn current_parent(&self) -> String {
+        self.open_phases
+            .borrow()
+            .last()
+            .cloned()
+            .unwrap_or_else(|| self.root_id.clone())
+    }
+
+    pub(crate) fn start_phase(self: &Rc<Self>, name: &str) -> PhaseSpan {
+        let parent_id = self.current_parent();
+        let span_id = self.new_span_id();
+        self.open_phases.borrow_mut().push(span_id.clone());
+        PhaseSpan {
+            tracer: Rc::clone(self),
+            span_id,
+            parent_id,
+            name: name.to_owned(),
+            start: SystemTime::now(),
+        }
+    }
+
+    /// Record a span for a GraphQL request that ran from `start` until now
+    pub(crate) fn record_request(
+        &self,
+        start: SystemTime,
+        attributes: Vec<(&'static str, Value)>,
+        error: Option<String>,
+    ) {
+        let span = SpanRecord {
+            span_id: self.new_span_id(),
+            parent_id: Some(self.current_parent()),
+            name: String::from("graphql request"),
+            kind: SPAN_KIND_CLIENT,
+            start,
+            end: SystemTime::now(),
+            attributes,
+            error,
+        };
+        self.spans.borrow_mut().push(span);
+    }
+
+    /// End the root span and send all recorded spans to the collector.
+    /// Spans for phases that are still open are not included.
+    pub fn export(&self) -> anyhow::Result<()> {
+        let root = SpanRecord {
+            span_id: self.root_id.clone(),
+            parent_id: None,
+            name: String::from("run"),
+            kind: SPAN_KIND_INTERNAL,
+            start: self.start,
+            end: SystemTime::now(),
+            attributes: Vec::new(),
+            error: None,
+        };
+        let spans = std::iter::once(&root)
+            .chain(self.spans.borrow().iter())
+            .map(|span| span.to_json(&self.trace_id))
+            .collect::<Vec<_>>();
+        let payload = json!({
+            "resourceSpans": [{
+                "resource": {
+                    "attributes": [attribute("service.name", &Value::from(self.service_name.clone()))],
+                },
+                "scopeSpans": [{
+                    "scope": {
+                        "name": env!("CARGO_PKG_NAME"),
+                        "version": env!("CARGO_PKG_VERSION"),
+                    },
+                    "spans": spans,
+                }],
+            }],
+        });
+        let mut req = self.agent.post(&self.endpoint);
+        for (name, value) in &self.headers {
+            req = req.set(name, value);
+        }
+        req.send_json(payload)
+            .with_context(|| format!("failed to export traces to {}", self.endpoint))?;
+        Ok(())
+    }
+}
+
+/// An open span for a phase of a run, which is recorded when dropped
+#[derive(Debug)]
+pub(crate) struct PhaseSpan {
+    tracer: Rc<Tracer>,
+    span_id: String,
+    parent_id: String,
+    name: String,
+    start: SystemTime,
+}
+
+impl Drop for PhaseSpan {
+    fn drop(&mut self) {
+        self.tracer
+            .open_phases
+            .borrow_mut()
+            .retain(|id| id != &self.span_id);
+        let span = SpanRecord {
+            span_id: std::mem::take(&mut self.span_id),
+            parent_id: Some(std::mem::take(&mut self.parent_id)),
+            name: std::mem::take(&mut self.name),
+            kind: SPAN_KIND_INTERNAL,
+            start: self.start,
+            end: SystemTime::now(),
+            attributes: Vec::new(),
+            error: None,
+        };
+        self.tracer.spans.borrow_mut().push(span);
+    }
+}
+
+const SPAN_KIND_INTERNAL: u8 = 1;
+const SPAN_KIND_CLIENT: u8 = 3;
+const STATUS_CODE_ERROR: u8 = 2;
+
+#[derive(Clone, Debug, PartialEq)]
+struct SpanRecord {
+    span_id: String,
+    parent_id: Option<String>,
+    name: String,
+    kind: u8,
+    start: SystemTime,
+    end: SystemTime,
+    attributes: Vec<(&'static str, Value)>,
+    error: Option<String>,
+}
+
+impl SpanRecord {
+    fn to_json(&self, trace_id: &str) -> Value {
+        let mut span = json!({
+            "traceId": trace_id,
+            "spanId": self.span_id,
+            "name": self.name,
+            "kind": self.kind,
+            "startTimeUnixNano": unix_nanos(self.start),
+            "endTimeUnixNano": unix_nanos(self.end),
+            "attributes": self
+                .attributes
+                .iter()
+                .map(|(key, value)| attribute(key, value))
+                .collect::<Vec<_>>(),
+        });
+        if let Some(ref parent_id) = self.parent_id {
+            span["parentSpanId"] = Value::from(parent_id.clone());
+        }
+        if let Some(ref message) = self.error {
+            span["status"] = json!({"code": STATUS_CODE_ERROR, "message": message});
+        }
+        span
+    }
+}
+
+/// Render an attribute as an OTLP `KeyValue`
+fn attribute(key: &str, value: &Value) -> Value {
+    let value = match value {
+        Value::Bool(b) => json!({"boolValue": b}),
+        // OTLP/JSON encodes 64-bit integers as strings
+        Value::Number(n) if n.is_i64() || n.is_u64() => json!({"intValue": n.to_string()}),
+        Value::Number(n) => json!({"doubleValue": n}),
+        Value::String(s) => json!({"stringValue": s}),
+        other => json!({"stringValue": other.to_string()}),
+    };
+    json!({"key": key, "value": value})
+}
+
+fn unix_nanos(t: SystemTime) -> String {
+    t.duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos())
+        .to_string()
+}
+
+fn env_var(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|s| !s.is_empty())
+}
+
+/// Parse a list of headers in the format used by
+/// `OTEL_EXPORTER_OTLP_HEADERS`: comma-separated `key=value` pairs with
+/// percent-encoded values
+fn parse_headers(s: &str) -> anyhow::Result<Vec<(String, String)>> {
+    let mut headers = Vec::new();
+    for pair in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let Some((key, value)) = pair.split_once('=') else {
+            bail!("header {pair:?} is not of the form key=value");
+        };
+        headers.push((key.trim().to_owned(), percent_decode(value.trim())?));
+    }
+    Ok(headers)
+}
+
+fn percent_decode(s: &str) -> anyhow::Result<String> {
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut iter = s.bytes();
+    while let Some(b) = iter.next() {
+        if b == b'%' {
+            let hex = [iter.next(), iter.next()];
+            let [Some(hi), Some(lo)] = hex else {
+                bail!("truncated percent-escape in {s:?}");
+            };
+            // `u8::from_str_radix()` would also accept a leading "+", so each
+            // digit is decoded on its own.
+            let (Some(hi), Some(lo)) = (char::from(hi).to_digit(16), char::from(lo).to_digit(16))
+            else {
+                bail!("invalid percent-escape in {s:?}");
+            };
+            bytes.push(u8::try_from(hi * 16 + lo).unwrap_or_default());
+        } else {
+            bytes.push(b);
+        }
+    }
+    String::from_utf8(bytes).with_context(|| format!("percent-decoded {s:?} is not UTF-8"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pairs(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|&(k, v)| (k.to_owned(), v.to_owned()))
+            .collect()
+    }
+
+    #[test]
+    fn parse_header_list() {
+        assert_eq!(
+            parse_headers("api-key=secret, x-tenant = a%20b ,,").unwrap(),
+            pairs(&[("api-key", "secret"), ("x-tenant", "a b")])
+        );
+        assert_eq!(
+            parse_headers("authorization=Basic%20dXNlcjpwYXNz=").unwrap(),
+            pairs(&[("authorization", "Basic dXNlcjpwYXNz=")])
+        );
+        assert_eq!(parse_headers("").unwrap(), pairs(&[]));
+    }
+
+    #[test]
+    fn parse_header_without_value() {
+        let e = parse_headers("a=b,novalue").unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "header \"novalue\" is not of the form key=value"
+        );
+    }
+
+    #[test]
+    fn percent_decode_valid() {
+        assert_eq!(percent_decode("plain").unwrap(), "plain");
+        assert_eq!(percent_decode("%41%62%2c").unwrap(), "Ab,");
+        assert_eq!(percent_decode("caf%C3%A9").unwrap(), "café");
+        assert_eq!(percent_decode("100%25").unwrap(), "100%");
+    }
+
+    #[test]
+    fn percent_decode_truncated() {
+        for s in ["100%", "%4", "a%"] {
+            let e = percent_decode(s).unwrap_err();
+            assert_eq!(e.to_string(), format!("truncated percent-escape in {s:?}"));
+        }
+    }
+
+    #[test]
+    fn percent_decode_invalid() {
+        for s in ["%zz", "%4g", "%+1", "%é"] {
+            let e = percent_decode(s).unwrap_err();
+            assert_eq!(e.to_string(), format!("invalid percent-escape in {s:?}"));
+        }
+    }
+
+    #[test]
+    fn percent_decode_not_utf8() {
+        let e = percent_decode("%FF").unwrap_err();
+        assert_eq!(e.to_string(), "percent-decoded \"%FF\" is not UTF-8");
+    }
+}
@@ -0,0 +1,106 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// The outcome of fetching the data for a single owner in a run
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum OwnerOutcome {
+    Succeeded,
+    Failed { error: String },
+    Skipped,
+}
+
+/// A map from each owner given to a run to the outcome of fetching its data,
+/// recorded in run reports so that automation can retry only the owners that
+/// failed
+///
+/// Owners start out as [`OwnerOutcome::Succeeded`].  Once an owner has
+/// failed, later calls to [`OwnerOutcomes::skip()`] do not change its outcome.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+#[serde(transparent)]
+pub struct OwnerOutcomes(pub(crate) BTreeMap<String, OwnerOutcome>);
+
+impl OwnerOutcomes {
+    pub fn new<I, S>(owners: I) -> OwnerOutcomes
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        OwnerOutcomes(
+            owners
+                .into_iter()
+                .map(|owner| (owner.into(), OwnerOutcome::Succeeded))
+                .collect(),
+        )
+    }
+
+    /// Record that fetching data for `owner` failed with `error`.  If the
+    /// owner has already failed, the new error is appended to the old.
+    ///
+    /// `owner` is matched case-insensitively against the owners given to
+    /// [`OwnerOutcomes::new()`].
+    pub fn fail<E: fmt::Display>(&mut self, owner: &str, error: E) {
+        let error = format!("{error:#}");
+        match self.get_mut(owner) {
+            Some(OwnerOutcome::Failed { error: old }) => {
+                old.push_str("; ");
+                old.push_str(&error);
+            }
+            Some(outcome) => *outcome = OwnerOutcome::Failed { error },
+            None => {
+                self.0
+                    .insert(owner.to_owned(), OwnerOutcome::Failed { error });
+            }
+        }
+    }
+
+    /// Record that some of the data for `owner` was not fetched on purpose
+    /// (e.g., due to a deadline)
+    pub fn skip(&mut self, owner: &str) {
+        if let Some(outcome) = self.get_mut(owner) {
+            if *outcome == OwnerOutcome::Succeeded {
+                *outcome = OwnerOutcome::Skipped;
+            }
+        }
+    }
+
+    fn get_mut(&mut self, owner: &str) -> Option<&mut OwnerOutcome> {
+        self.0
+            .iter_mut()
+            .find(|(k, _)| k.eq_ignore_ascii_case(owner))
+            .map(|(_, v)| v)
+    }
+
+    /// Iterate over the owners that failed and their errors
+    pub fn failures(&self) -> impl Iterator<Item = (&str, &str)> + '_ {
+        self.0.iter().filter_map(|(owner, outcome)| match outcome {
+            OwnerOutcome::Failed { error } => Some((owner.as_str(), error.as_str())),
+            _ => None,
+        })
+    }
+
+    /// Return the number of owners that failed
+    pub fn failed(&self) -> usize {
+        self.failures().count()
+    }
+}
+
+impl fmt::Display for OwnerOutcomes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut succeeded = 0;
+        let mut failed = 0;
+        let mut skipped = 0;
+        for outcome in self.0.values() {
+            match outcome {
+                OwnerOutcome::Succeeded => succeeded += 1,
+                OwnerOutcome::Failed { .. } => failed += 1,
+                OwnerOutcome::Skipped => skipped += 1,
+            }
+        }
+        write!(
+            f,
+            "{succeeded} owners succeeded, {failed} owners failed, {skipped} owners skipped"
+        )
+    }
+}
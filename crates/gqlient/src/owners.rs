@@ -0,0 +1,130 @@
+use crate::batch::compose_document;
+use crate::queries::Query;
+use crate::types::Variable;
+use crate::{Client, GqlError};
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::{self, Write};
+
+/// The kind of account that owns a set of repositories
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub enum OwnerKind {
+    User,
+    Organization,
+}
+
+impl fmt::Display for OwnerKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OwnerKind::User => f.write_str("user"),
+            OwnerKind::Organization => f.write_str("organization"),
+        }
+    }
+}
+
+/// Look up the kind of each owner in `owners`; see
+/// [`Client::resolve_owner_kinds()`]
+pub(crate) fn resolve_owner_kinds(
+    client: &Client,
+    owners: &[String],
+) -> anyhow::Result<BTreeMap<String, OwnerKind>> {
+    let mut kinds = BTreeMap::new();
+    for chunk in owners.chunks(client.batch_size.get()) {
+        let queries = chunk
+            .iter()
+            .enumerate()
+            .map(|(i, owner)| {
+                let alias = format!("q{i}");
+                let query = GetOwnerKind::new(owner.clone()).with_variable_prefix(alias.clone());
+                (alias, query)
+            })
+            .collect::<Vec<_>>();
+        let (doc, variables) = compose_document(
+            "query",
+            queries.iter().map(|(alias, q)| (alias.as_str(), q)),
+        )?;
+        let mut response = client
+            .query_partial(doc, variables)
+            .context("failed to resolve owner kinds")?;
+        client.record_query_cost(&mut response.data);
+        if !response.errors.is_empty() {
+            return Err(anyhow::Error::from(GqlError {
+                errors: response.errors,
+            })
+            .context("failed to resolve owner kinds"));
+        }
+        for (alias, query) in queries {
+            let value = response.data.remove(&alias).unwrap_or_default();
+            if let Some(kind) = client
+                .parse_response(&query, value)
+                .context("failed to deserialize owner kind")?
+            {
+                kinds.insert(query.owner, kind);
+            }
+        }
+    }
+    Ok(kinds)
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct GetOwnerKind {
+    owner: String,
+    prefix: Option<String>,
+}
+
+impl GetOwnerKind {
+    fn new(owner: String) -> GetOwnerKind {
+        GetOwnerKind {
+            owner,
+            prefix: None,
+        }
+    }
+
+    fn owner_varname(&self) -> String {
+        match self.prefix {
+            Some(ref prefix) => format!("{prefix}_owner"),
+            None => String::from("owner"),
+        }
+    }
+}
+
+impl Query for GetOwnerKind {
+    type Output = Option<OwnerKind>;
+
+    fn with_variable_prefix(mut self, prefix: String) -> Self {
+        self.prefix = Some(prefix);
+        self
+    }
+
+    fn write_graphql<W: Write>(&self, mut s: W) -> fmt::Result {
+        writeln!(
+            s,
+            "repositoryOwner(login: ${}) {{\n    __typename\n}}",
+            self.owner_varname()
+        )
+    }
+
+    fn variables(&self) -> [(String, Variable); 1] {
+        [(
+            self.owner_varname(),
+            Variable {
+                gql_type: String::from("String!"),
+                value: self.owner.clone().into(),
+            },
+        )]
+    }
+
+    fn parse_response(
+        &self,
+        value: serde_json::Value,
+    ) -> Result<Option<OwnerKind>, serde_json::Error> {
+        crate::from_value::<Option<RawOwner>>(value).map(|r| r.map(|o| o.typename))
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+struct RawOwner {
+    #[serde(rename = "__typename")]
+    typename: OwnerKind,
+}
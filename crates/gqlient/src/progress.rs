@@ -0,0 +1,134 @@
+use std::io::{self, IsTerminal, Write};
+use std::time::{Duration, Instant};
+
+/// Minimum amount of time between refreshes of the status line
+const REFRESH_INTERVAL: Duration = Duration::from_millis(200);
+
+/// A single line on stderr, rewritten in place at a throttled rate, showing
+/// the progress of a batch pagination.
+///
+/// If stderr is not a terminal, the line cannot be rewritten, so the status
+/// is instead printed on a line of its own each time a pagination completes.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct StatusLine {
+    start: Instant,
+    last_refresh: Option<Instant>,
+    /// Whether stderr is a terminal
+    tty: bool,
+    total: usize,
+    done: usize,
+    items: usize,
+    requests: usize,
+    points: u64,
+}
+
+impl StatusLine {
+    pub(crate) fn new(total: usize) -> StatusLine {
+        StatusLine {
+            start: Instant::now(),
+            last_refresh: None,
+            tty: io::stderr().is_terminal(),
+            total,
+            done: 0,
+            items: 0,
+            requests: 0,
+            points: 0,
+        }
+    }
+
+    /// Record a request that cost `points` rate limit points
+    pub(crate) fn request_made(&mut self, points: u64) {
+        self.requests += 1;
+        self.points += points;
+        self.refresh(false);
+    }
+
+    pub(crate) fn items_fetched(&mut self, qty: usize) {
+        self.items += qty;
+        self.refresh(false);
+    }
+
+    pub(crate) fn query_finished(&mut self) {
+        self.done += 1;
+        if self.tty {
+            self.refresh(false);
+        } else if self.done < self.total {
+            // The final status is printed by `finish()`.
+            eprintln!("{}", self.render(Instant::now()));
+        }
+    }
+
+    /// Display a message on its own line above the status line
+    pub(crate) fn message(&mut self, msg: &str) {
+        if self.tty {
+            eprintln!("\r\x1B[K{msg}");
+            self.refresh(true);
+        } else {
+            eprintln!("{msg}");
+        }
+    }
+
+    /// Display the final state of the status line and end it with a newline
+    pub(crate) fn finish(&mut self) {
+        if self.tty {
+            self.refresh(true);
+            eprintln!();
+        } else {
+            eprintln!("{}", self.render(Instant::now()));
+        }
+    }
+
+    /// Redraw the status line if stderr is a terminal and either `force` is
+    /// true or at least [`REFRESH_INTERVAL`] has passed since the last redraw
+    fn refresh(&mut self, force: bool) {
+        if !self.tty {
+            return;
+        }
+        let now = Instant::now();
+        if !force
+            && self
+                .last_refresh
+                .is_some_and(|t| now.saturating_duration_since(t) < REFRESH_INTERVAL)
+        {
+            return;
+        }
+        self.last_refresh = Some(now);
+        let mut stderr = io::stderr().lock();
+        // Errors writing to stderr are not worth failing over.
+        let _ = write!(stderr, "\r\x1B[K{}", self.render(now));
+        let _ = stderr.flush();
+    }
+
+    fn render(&self, now: Instant) -> String {
+        let elapsed = now.saturating_duration_since(self.start);
+        format!(
+            "[·] {}/{} paginations complete, {} items fetched, {} requests, {} points, {:.1}s elapsed",
+            self.done,
+            self.total,
+            self.items,
+            self.requests,
+            self.points,
+            elapsed.as_secs_f64(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render() {
+        let mut status = StatusLine::new(3);
+        status.tty = false;
+        status.request_made(2);
+        status.items_fetched(10);
+        status.request_made(1);
+        status.items_fetched(5);
+        let now = status.start + Duration::from_millis(1300);
+        assert_eq!(
+            status.render(now),
+            "[·] 0/3 paginations complete, 15 items fetched, 2 requests, 3 points, 1.3s elapsed"
+        );
+    }
+}
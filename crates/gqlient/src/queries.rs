@@ -1,4 +1,5 @@
 use crate::types::{Cursor, Page, Variable};
+use std::num::NonZeroUsize;
 
 pub trait Query: Sized {
     type Output;
@@ -13,5 +14,52 @@ pub trait Paginator {
     type Query: Query<Output = Page<Self::Item>>;
     type Item;
 
+    /// Return the query for fetching the page after `cursor`, or the first
+    /// page if `cursor` is `None`.
+    ///
+    /// The cursor should only be passed to the query via a variable: once a
+    /// paginator has been given a cursor, the GraphQL text written by its
+    /// queries must not change from page to page (except after a call to
+    /// [`reduce_page_size()`](Paginator::reduce_page_size)), as
+    /// [`BatchPaginator`](crate::BatchPaginator) renders it only once.
     fn for_cursor(&self, cursor: Option<&Cursor>) -> Self::Query;
+
+    /// Return the number of nodes that GitHub counts toward its limit on
+    /// nodes per request for each page query of this paginator, if known.
+    ///
+    /// When the client has a node limit set with
+    /// [`Client::node_limit()`](crate::Client::node_limit),
+    /// [`BatchPaginator`](crate::BatchPaginator) uses this to keep the total
+    /// for each request under the limit.  Paginators that return `None` are
+    /// counted as a single node.
+    fn nodes_per_page(&self) -> Option<u64> {
+        None
+    }
+
+    /// Discard any cursor that the paginator was created with (such as one
+    /// saved from an earlier run), so that calling
+    /// [`for_cursor(None)`](Paginator::for_cursor) returns a query for the
+    /// very first page.
+    ///
+    /// [`BatchPaginator`](crate::BatchPaginator) calls this when GitHub
+    /// rejects a paginator's cursor as invalid or expired before restarting
+    /// the paginator from the beginning.  The default implementation does
+    /// nothing.
+    fn reset_cursor(&mut self) {}
+
+    /// Lower the page size of the paginator's subsequent queries and return
+    /// the new page size, or return `None` if the page size cannot be
+    /// lowered any further.
+    ///
+    /// When the client adapts page sizes (see
+    /// [`Client::adaptive_page_size()`](crate::Client::adaptive_page_size)),
+    /// [`BatchPaginator`](crate::BatchPaginator) calls this for paginators
+    /// whose requests are slow or whose pages come near the node limit, and
+    /// it then renders the paginator's sub-query anew.  As paginators that
+    /// share a request must give their variables of the same name the same
+    /// values, a lowered page size should be passed in a variable named
+    /// after the query's prefix.  The default implementation does nothing.
+    fn reduce_page_size(&mut self) -> Option<NonZeroUsize> {
+        None
+    }
 }
@@ -0,0 +1,28 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Whether progress messages are currently suppressed
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Set whether progress messages logged via [`progress!`](crate::progress!)
+/// should be suppressed for the rest of the process's life.  Warnings and
+/// errors are always logged.
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+/// Return whether progress messages are currently suppressed
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// Log a progress message to stderr, prefixed with `[·]`, unless progress
+/// messages have been suppressed with [`set_quiet()`].  Takes the same
+/// arguments as [`format!`].
+#[macro_export]
+macro_rules! progress {
+    ($($arg:tt)*) => {
+        if !$crate::is_quiet() {
+            ::std::eprintln!("[·] {}", ::std::format_args!($($arg)*));
+        }
+    };
+}
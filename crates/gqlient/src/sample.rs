@@ -0,0 +1,204 @@
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// A small, seedable pseudorandom number generator, so that results that
+/// depend on random choices are reproducible
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SplitMix64(u64);
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> SplitMix64 {
+        SplitMix64(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Return a number in the range `0..n`, or 0 if `n` is 0
+    pub fn below(&mut self, n: usize) -> usize {
+        let n = u64::try_from(n).unwrap_or(u64::MAX).max(1);
+        usize::try_from(self.next_u64() % n).unwrap_or_default()
+    }
+}
+
+/// How many items to keep when sampling a collection: either a fixed number
+/// or a fraction of the collection
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Sample {
+    Count(usize),
+    /// A fraction in the range (0, 1]
+    Fraction(f64),
+}
+
+// Fractions are always finite, so equality is reflexive.
+impl Eq for Sample {}
+
+impl Sample {
+    /// Return the number of items to keep out of `len`
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_precision_loss,
+        clippy::cast_sign_loss
+    )]
+    pub fn size(&self, len: usize) -> usize {
+        match *self {
+            Sample::Count(n) => n.min(len),
+            Sample::Fraction(f) => ((f * len as f64).round() as usize).min(len),
+        }
+    }
+
+    /// Remove a pseudorandomly chosen subset of `items` so that only
+    /// [`Sample::size()`] of them remain, preserving the order of the
+    /// remaining items.  The same `seed` and items always produce the same
+    /// sample.  Returns the number of items removed.
+    pub fn apply<T>(&self, items: &mut Vec<T>, seed: u64) -> usize {
+        let len = items.len();
+        let keep = self.size(len);
+        // Partial Fisher-Yates shuffle of the indices
+        let mut rng = SplitMix64::new(seed);
+        let mut indices = (0..len).collect::<Vec<_>>();
+        for i in 0..keep {
+            let j = i + rng.below(len - i);
+            indices.swap(i, j);
+        }
+        let mut chosen = vec![false; len];
+        for &i in indices.iter().take(keep) {
+            if let Some(c) = chosen.get_mut(i) {
+                *c = true;
+            }
+        }
+        let mut chosen = chosen.into_iter();
+        items.retain(|_| chosen.next().unwrap_or_default());
+        len - keep
+    }
+}
+
+impl fmt::Display for Sample {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Sample::Count(n) => write!(f, "{n}"),
+            Sample::Fraction(x) => write!(f, "{x}"),
+        }
+    }
+}
+
+impl FromStr for Sample {
+    type Err = ParseSampleError;
+
+    /// Parse an integer as a count and a decimal number in the range (0, 1]
+    /// as a fraction
+    fn from_str(s: &str) -> Result<Sample, ParseSampleError> {
+        if let Ok(n) = s.parse::<usize>() {
+            return Ok(Sample::Count(n));
+        }
+        match s.parse::<f64>() {
+            Ok(x) if x > 0.0 && x <= 1.0 => Ok(Sample::Fraction(x)),
+            _ => Err(ParseSampleError),
+        }
+    }
+}
+
+impl Serialize for Sample {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ParseSampleError;
+
+impl fmt::Display for ParseSampleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid sample size; expected a count or a fraction between 0 and 1")
+    }
+}
+
+impl std::error::Error for ParseSampleError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splitmix64_reference_values() {
+        let mut rng = SplitMix64::new(0);
+        assert_eq!(rng.next_u64(), 0xE220_A839_7B1D_CDAF);
+        assert_eq!(rng.next_u64(), 0x6E78_9E6A_A1B9_65F4);
+        assert_eq!(rng.next_u64(), 0x06C4_5D18_8009_454F);
+    }
+
+    #[test]
+    fn below() {
+        let mut rng = SplitMix64::new(42);
+        assert_eq!(rng.below(0), 0);
+        assert_eq!(rng.below(1), 0);
+        assert!((0..1000).all(|_| rng.below(7) < 7));
+    }
+
+    #[test]
+    fn parse_sample() {
+        assert_eq!("10".parse::<Sample>(), Ok(Sample::Count(10)));
+        assert_eq!("0".parse::<Sample>(), Ok(Sample::Count(0)));
+        assert_eq!("1".parse::<Sample>(), Ok(Sample::Count(1)));
+        assert_eq!("0.25".parse::<Sample>(), Ok(Sample::Fraction(0.25)));
+        assert_eq!("1.0".parse::<Sample>(), Ok(Sample::Fraction(1.0)));
+        for s in ["0.0", "1.5", "-1", "-0.5", "NaN", "inf", "", "ten"] {
+            assert_eq!(s.parse::<Sample>(), Err(ParseSampleError), "{s:?}");
+        }
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for sample in [Sample::Count(5), Sample::Fraction(0.1)] {
+            assert_eq!(sample.to_string().parse::<Sample>(), Ok(sample));
+        }
+    }
+
+    #[test]
+    fn size() {
+        assert_eq!(Sample::Count(5).size(3), 3);
+        assert_eq!(Sample::Count(5).size(10), 5);
+        assert_eq!(Sample::Fraction(0.25).size(10), 3);
+        assert_eq!(Sample::Fraction(0.2).size(10), 2);
+        assert_eq!(Sample::Fraction(1.0).size(7), 7);
+        assert_eq!(Sample::Fraction(0.5).size(0), 0);
+    }
+
+    #[test]
+    fn apply_keeps_order() {
+        let all = (0..100).collect::<Vec<_>>();
+        let mut items = all.clone();
+        assert_eq!(Sample::Count(10).apply(&mut items, 1), 90);
+        assert_eq!(items.len(), 10);
+        let mut sorted = items.clone();
+        sorted.sort_unstable();
+        assert_eq!(items, sorted);
+        assert!(items.iter().all(|i| all.contains(i)));
+    }
+
+    #[test]
+    fn apply_is_reproducible() {
+        let sample = Sample::Fraction(0.3);
+        let mut a = (0..50).collect::<Vec<_>>();
+        let mut b = a.clone();
+        let mut c = a.clone();
+        sample.apply(&mut a, 7);
+        sample.apply(&mut b, 7);
+        sample.apply(&mut c, 8);
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn apply_everything() {
+        let mut items = vec!["a", "b", "c"];
+        assert_eq!(Sample::Count(5).apply(&mut items, 0), 0);
+        assert_eq!(items, ["a", "b", "c"]);
+    }
+}
@@ -0,0 +1,143 @@
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::Serialize;
+use std::fmt;
+use std::io::Write;
+use std::ops::AddAssign;
+use std::time::Duration;
+
+/// Totals of the sizes of the GraphQL request bodies sent by a
+/// [`Client`](crate::Client), of the time it spent waiting on the network and
+/// idling between requests, and of the numbers & timings of the connections
+/// it opened, as returned by
+/// [`Client::transfer_stats()`](crate::Client::transfer_stats)
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct TransferStats {
+    /// The number of GraphQL requests sent, not counting retries
+    pub requests: u64,
+
+    /// The total size in bytes of the serialized request payloads before
+    /// compression
+    pub payload_bytes: u64,
+
+    /// The total size in bytes of the request bodies as sent, after any
+    /// compression
+    pub sent_bytes: u64,
+
+    /// The number of requests whose bodies were gzip-compressed
+    pub compressed_requests: u64,
+
+    /// The number of new connections opened, counted by their host name
+    /// lookups.  Requests sent over connections reused from the agent's pool
+    /// do not open new ones.
+    pub connections: u64,
+
+    /// The total time spent looking up host names for new connections
+    #[serde(serialize_with = "crate::serialize_duration")]
+    pub dns_time: Duration,
+
+    /// The number of TLS handshakes completed
+    pub tls_handshakes: u64,
+
+    /// The total time spent on TLS handshakes
+    #[serde(serialize_with = "crate::serialize_duration")]
+    pub tls_handshake_time: Duration,
+
+    /// The total time spent waiting on the transport for responses to HTTP
+    /// requests, including retried attempts and REST requests
+    #[serde(serialize_with = "crate::serialize_duration")]
+    pub network_time: Duration,
+
+    /// The number of requests made by a
+    /// [`BatchPaginator`](crate::BatchPaginator) right after a response to
+    /// its previous request
+    pub idle_periods: u64,
+
+    /// The total time between a [`BatchPaginator`](crate::BatchPaginator)
+    /// receiving a response and sending its next request, during which no
+    /// request was in flight.  This covers deserializing the response,
+    /// updating the paginators, and composing & serializing the next query,
+    /// but not pauses for the rate limit or waits before retries.
+    #[serde(serialize_with = "crate::serialize_duration")]
+    pub idle_time: Duration,
+}
+
+impl TransferStats {
+    /// Record a request with a serialized payload of `payload` bytes that was
+    /// sent as a body of `sent` bytes
+    pub(crate) fn record(&mut self, payload: usize, sent: usize, compressed: bool) {
+        self.requests += 1;
+        self.payload_bytes += u64::try_from(payload).unwrap_or(u64::MAX);
+        self.sent_bytes += u64::try_from(sent).unwrap_or(u64::MAX);
+        if compressed {
+            self.compressed_requests += 1;
+        }
+    }
+
+    /// Return the fraction of the time spent either waiting on the network
+    /// or idling between requests that was spent idling, i.e., the most that
+    /// overlapping the handling of responses with further requests could
+    /// save.  Returns `None` if no time was recorded.
+    pub fn idle_fraction(&self) -> Option<f64> {
+        let total = self.network_time + self.idle_time;
+        (!total.is_zero()).then(|| self.idle_time.as_secs_f64() / total.as_secs_f64())
+    }
+}
+
+impl AddAssign for TransferStats {
+    fn add_assign(&mut self, rhs: TransferStats) {
+        self.requests += rhs.requests;
+        self.payload_bytes += rhs.payload_bytes;
+        self.sent_bytes += rhs.sent_bytes;
+        self.compressed_requests += rhs.compressed_requests;
+        self.connections += rhs.connections;
+        self.dns_time += rhs.dns_time;
+        self.tls_handshakes += rhs.tls_handshakes;
+        self.tls_handshake_time += rhs.tls_handshake_time;
+        self.network_time += rhs.network_time;
+        self.idle_periods += rhs.idle_periods;
+        self.idle_time += rhs.idle_time;
+    }
+}
+
+impl fmt::Display for TransferStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Sent {} bytes of GraphQL payloads in {} requests",
+            self.payload_bytes, self.requests
+        )?;
+        if self.compressed_requests > 0 {
+            write!(
+                f,
+                " ({} bytes on the wire after compressing {} requests)",
+                self.sent_bytes, self.compressed_requests
+            )?;
+        }
+        if self.connections > 0 {
+            write!(
+                f,
+                " over {} new connections (DNS lookups: {:?}; {} TLS handshakes: {:?})",
+                self.connections, self.dns_time, self.tls_handshakes, self.tls_handshake_time
+            )?;
+        }
+        if let Some(fraction) = self.idle_fraction() {
+            write!(
+                f,
+                "; waited {:?} on the network and idled {:?} between {} responses and the next requests ({:.1}% idle)",
+                self.network_time,
+                self.idle_time,
+                self.idle_periods,
+                fraction * 100.0
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Gzip-compress a request body
+pub(crate) fn gzip(body: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(body)?;
+    encoder.finish()
+}
@@ -0,0 +1,222 @@
+use anyhow::Context;
+use serde::de::DeserializeOwned;
+use std::fmt;
+use std::io::Read;
+use std::time::Duration;
+use ureq::Agent;
+
+/// The means by which a [`Client`](crate::Client) sends its HTTP requests
+///
+/// A client builds each request in full — including its authentication and
+/// other GitHub-specific headers — and leaves the transport only the job of
+/// sending it and returning the response.  Retries, rate limit handling,
+/// circuit breaking, and everything to do with GraphQL happen in the client
+/// and in the paginators it runs, so a transport can be swapped out (e.g., for
+/// one built on a different HTTP library, or for a mock that serves canned
+/// responses in tests) without changing any of that.
+///
+/// By default, clients use a [`UreqTransport`]; use
+/// [`ClientBuilder::transport()`](crate::ClientBuilder::transport) to supply
+/// another.
+pub trait Transport: fmt::Debug {
+    /// Send `request` and return the response.
+    ///
+    /// Responses with unsuccessful (4xx or 5xx) statuses must be returned as
+    /// `Ok`, as the client decides which of them to retry.  An `Err` should
+    /// only be returned if no complete response was received, and the client
+    /// treats all such errors as network errors, retrying them according to
+    /// its retry policy.
+    fn send(&self, request: &HttpRequest) -> anyhow::Result<HttpResponse>;
+}
+
+/// An HTTP method used by a [`Client`](crate::Client)
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Method {
+    Get,
+    Post,
+}
+
+impl Method {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Method::Get => "GET",
+            Method::Post => "POST",
+        }
+    }
+}
+
+impl fmt::Display for Method {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// An HTTP request to be sent by a [`Transport`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HttpRequest {
+    pub method: Method,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    /// The timeout for the request, overriding any default timeout of the
+    /// transport
+    pub timeout: Option<Duration>,
+}
+
+impl HttpRequest {
+    /// Create a `GET` request for `url` with no headers
+    pub fn get<S: Into<String>>(url: S) -> HttpRequest {
+        HttpRequest {
+            method: Method::Get,
+            url: url.into(),
+            headers: Vec::new(),
+            body: Vec::new(),
+            timeout: None,
+        }
+    }
+
+    /// Create a `POST` request for `url` with the given body and no headers
+    pub fn post<S: Into<String>>(url: S, body: Vec<u8>) -> HttpRequest {
+        HttpRequest {
+            method: Method::Post,
+            url: url.into(),
+            headers: Vec::new(),
+            body,
+            timeout: None,
+        }
+    }
+
+    /// Return the value of the header with the given (case-insensitive)
+    /// name, if any
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+
+    /// Set the header with the given name to `value`, replacing any previous
+    /// values
+    pub fn set_header<V: Into<String>>(&mut self, name: &str, value: V) {
+        self.headers.retain(|(n, _)| !n.eq_ignore_ascii_case(name));
+        self.headers.push((name.to_owned(), value.into()));
+    }
+}
+
+/// An HTTP response returned by a [`Transport`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    /// The response body, after undoing any `Content-Encoding`
+    pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+    /// Create a response with the given status and body and no headers
+    pub fn new(status: u16, body: Vec<u8>) -> HttpResponse {
+        HttpResponse {
+            status,
+            headers: Vec::new(),
+            body,
+        }
+    }
+
+    /// Return the value of the header with the given (case-insensitive)
+    /// name, if any
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+
+    /// Deserialize the response body as JSON
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T, serde_json::Error> {
+        serde_json::from_slice(&self.body)
+    }
+}
+
+fn find_header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, v)| v.as_str())
+}
+
+/// A [`Transport`] that sends requests with a `ureq` agent.  This is the
+/// transport used by clients unless another is supplied.
+#[derive(Clone, Debug)]
+pub struct UreqTransport {
+    agent: Agent,
+}
+
+impl UreqTransport {
+    pub fn new(agent: Agent) -> UreqTransport {
+        UreqTransport { agent }
+    }
+}
+
+impl Transport for UreqTransport {
+    fn send(&self, request: &HttpRequest) -> anyhow::Result<HttpResponse> {
+        let mut req = self.agent.request(request.method.as_str(), &request.url);
+        for (name, value) in &request.headers {
+            req = req.set(name, value);
+        }
+        if let Some(timeout) = request.timeout {
+            req = req.timeout(timeout);
+        }
+        let r = match request.method {
+            Method::Get => req.call(),
+            Method::Post => req.send_bytes(&request.body),
+        };
+        let resp = match r {
+            Ok(resp) | Err(ureq::Error::Status(_, resp)) => resp,
+            Err(e) => return Err(e.into()),
+        };
+        let status = resp.status();
+        let headers = resp
+            .headers_names()
+            .into_iter()
+            .filter_map(|name| {
+                let value = resp.header(&name)?.to_owned();
+                Some((name, value))
+            })
+            .collect();
+        let mut body = Vec::new();
+        resp.into_reader()
+            .read_to_end(&mut body)
+            .context("failed to read response body")?;
+        Ok(HttpResponse {
+            status,
+            headers,
+            body,
+        })
+    }
+}
+
+/// Why a request sent through a client's [`Transport`] failed
+#[derive(Debug)]
+pub(crate) enum RequestError {
+    /// No response was received
+    Transport(anyhow::Error),
+    /// The server responded with an unsuccessful status
+    Status { url: String, response: HttpResponse },
+}
+
+impl RequestError {
+    /// Return the response's status, if a response was received
+    pub(crate) fn status(&self) -> Option<u16> {
+        match self {
+            RequestError::Transport(_) => None,
+            RequestError::Status { response, .. } => Some(response.status),
+        }
+    }
+}
+
+impl fmt::Display for RequestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RequestError::Transport(e) => write!(f, "{e:#}"),
+            RequestError::Status { url, response } => {
+                write!(f, "{url}: status code {}", response.status)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RequestError {}
@@ -4,6 +4,7 @@ use serde::{
 };
 use std::fmt;
 use std::marker::PhantomData;
+use std::str::FromStr;
 
 pub type JsonMap = serde_json::Map<String, serde_json::Value>;
 
@@ -21,19 +22,122 @@ impl From<Id> for serde_json::Value {
 #[serde(transparent)]
 pub struct Cursor(String);
 
+impl fmt::Display for Cursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
 impl From<Cursor> for serde_json::Value {
     fn from(value: Cursor) -> serde_json::Value {
         value.0.into()
     }
 }
 
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct Ided<T> {
     pub id: Id,
     #[serde(flatten)]
     pub data: T,
 }
 
+/// The author of an issue or other item
+///
+/// This deserializes both from the GraphQL API's `{login, __typename}` form and
+/// from its own serialized form.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(from = "RawAuthor")]
+pub struct Author {
+    pub login: String,
+    pub is_bot: bool,
+}
+
+impl From<RawAuthor> for Author {
+    fn from(value: RawAuthor) -> Author {
+        Author {
+            login: value.login,
+            is_bot: value.is_bot || value.typename.is_some_and(|t| t == "Bot"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+struct RawAuthor {
+    login: String,
+    #[serde(default, rename = "__typename")]
+    typename: Option<String>,
+    #[serde(default)]
+    is_bot: bool,
+}
+
+/// The parent of an issue that is a sub-issue
+///
+/// This deserializes both from the GraphQL API's `{number, repository:
+/// {nameWithOwner}}` form and from its own serialized form.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(from = "RawParentIssue")]
+pub struct ParentIssue {
+    /// The full name of the parent issue's repository, which may differ from
+    /// that of the sub-issue
+    pub repo: String,
+    pub number: u64,
+}
+
+impl From<RawParentIssue> for ParentIssue {
+    fn from(value: RawParentIssue) -> ParentIssue {
+        match value {
+            RawParentIssue::Api { number, repository } => ParentIssue {
+                repo: repository,
+                number,
+            },
+            RawParentIssue::Own { repo, number } => ParentIssue { repo, number },
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(untagged)]
+enum RawParentIssue {
+    Api {
+        number: u64,
+        #[serde(deserialize_with = "singleton_field")]
+        repository: String,
+    },
+    Own {
+        repo: String,
+        number: u64,
+    },
+}
+
+/// The numbers of an issue's sub-issues in total and that have been closed as
+/// completed, as given by the GraphQL API's `subIssuesSummary` field
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SubIssues {
+    pub total: u64,
+    pub completed: u64,
+}
+
+/// Whether an item fetched from a repository's issues or pull requests is an
+/// issue or a pull request
+///
+/// This deserializes both from the GraphQL API's `__typename` values and from
+/// its own serialized form.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    #[default]
+    #[serde(alias = "Issue")]
+    Issue,
+    #[serde(alias = "PullRequest")]
+    PullRequest,
+}
+
+impl IssueKind {
+    pub fn is_issue(&self) -> bool {
+        *self == IssueKind::Issue
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
 #[serde(from = "Connection<T>")]
 pub struct Page<T> {
@@ -79,6 +183,171 @@ struct PageInfo {
     has_next_page: bool,
 }
 
+/// The order in which to fetch an owner's repositories
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RepoOrder {
+    /// Alphabetically by name
+    #[default]
+    Name,
+    /// Most recently pushed to first
+    Pushed,
+    /// Most recently created first
+    Created,
+    /// Most stargazers first
+    Stargazers,
+}
+
+impl RepoOrder {
+    /// Returns the value of the `orderBy` argument to pass to a
+    /// `repositories` connection in order to fetch repositories in this
+    /// order, as a GraphQL variable
+    pub fn as_variable(self) -> Variable {
+        let (field, direction) = match self {
+            RepoOrder::Name => ("NAME", "ASC"),
+            RepoOrder::Pushed => ("PUSHED_AT", "DESC"),
+            RepoOrder::Created => ("CREATED_AT", "DESC"),
+            RepoOrder::Stargazers => ("STARGAZERS", "DESC"),
+        };
+        Variable {
+            gql_type: String::from("RepositoryOrder!"),
+            value: serde_json::json!({"field": field, "direction": direction}),
+        }
+    }
+}
+
+impl fmt::Display for RepoOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            RepoOrder::Name => "name",
+            RepoOrder::Pushed => "pushed",
+            RepoOrder::Created => "created",
+            RepoOrder::Stargazers => "stargazers",
+        };
+        f.write_str(s)
+    }
+}
+
+impl FromStr for RepoOrder {
+    type Err = ParseRepoOrderError;
+
+    fn from_str(s: &str) -> Result<RepoOrder, ParseRepoOrderError> {
+        match s {
+            "name" => Ok(RepoOrder::Name),
+            "pushed" => Ok(RepoOrder::Pushed),
+            "created" => Ok(RepoOrder::Created),
+            "stargazers" => Ok(RepoOrder::Stargazers),
+            _ => Err(ParseRepoOrderError),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ParseRepoOrderError;
+
+impl fmt::Display for ParseRepoOrderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(
+            r#"invalid repository order; expected "name", "pushed", "created", or "stargazers""#,
+        )
+    }
+}
+
+impl std::error::Error for ParseRepoOrderError {}
+
+/// Which repositories to fetch based on their visibility
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RepoPrivacy {
+    /// Only public repositories
+    #[default]
+    Public,
+    /// Only private (and internal) repositories
+    Private,
+    /// All repositories that the access token can see
+    All,
+}
+
+impl RepoPrivacy {
+    /// Returns the value of the `privacy` argument to pass to a
+    /// `repositories` connection in order to fetch repositories with this
+    /// visibility, as a GraphQL variable
+    pub fn as_variable(self) -> Variable {
+        let value = match self {
+            RepoPrivacy::Public => "PUBLIC".into(),
+            RepoPrivacy::Private => "PRIVATE".into(),
+            RepoPrivacy::All => serde_json::Value::Null,
+        };
+        Variable {
+            gql_type: String::from("RepositoryPrivacy"),
+            value,
+        }
+    }
+
+    /// Returns true if repositories with this visibility may include private
+    /// repositories, which can only be listed with sufficient token scopes
+    pub fn includes_private(self) -> bool {
+        self != RepoPrivacy::Public
+    }
+}
+
+impl fmt::Display for RepoPrivacy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            RepoPrivacy::Public => "public",
+            RepoPrivacy::Private => "private",
+            RepoPrivacy::All => "all",
+        };
+        f.write_str(s)
+    }
+}
+
+impl FromStr for RepoPrivacy {
+    type Err = ParseRepoPrivacyError;
+
+    fn from_str(s: &str) -> Result<RepoPrivacy, ParseRepoPrivacyError> {
+        match s {
+            "public" => Ok(RepoPrivacy::Public),
+            "private" => Ok(RepoPrivacy::Private),
+            "all" => Ok(RepoPrivacy::All),
+            _ => Err(ParseRepoPrivacyError),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ParseRepoPrivacyError;
+
+impl fmt::Display for ParseRepoPrivacyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(r#"invalid repository privacy; expected "public", "private", or "all""#)
+    }
+}
+
+impl std::error::Error for ParseRepoPrivacyError {}
+
+/// The visibility of a repository, as given by its `visibility` field
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum RepoVisibility {
+    Public,
+    Private,
+    /// Visible to all members of the repository's enterprise
+    Internal,
+}
+
+/// The access token's level of access to a repository, as given by the
+/// repository's `viewerPermission` field
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum RepoPermission {
+    Admin,
+    Maintain,
+    Write,
+    Triage,
+    Read,
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Variable {
     pub gql_type: String,
@@ -135,3 +404,13 @@ where
 {
     Singleton::<T>::deserialize(deserializer).map(|r| r.0)
 }
+
+// Utility function for deserializing a nullable single-field map as the
+// field's value
+pub fn optional_singleton_field<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    Option::<Singleton<T>>::deserialize(deserializer).map(|r| r.map(|s| s.0))
+}
@@ -0,0 +1,68 @@
+use serde::de::DeserializeOwned;
+use serde_ignored::Path;
+use std::cell::RefCell;
+
+thread_local! {
+    /// The paths of the response fields ignored by calls to [`from_value()`]
+    /// while unused fields are being collected, or `None` if they are not
+    static IGNORED: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+}
+
+/// Deserialize a GraphQL response value.
+///
+/// [`Query::parse_response()`](crate::Query::parse_response) implementations
+/// should use this in place of `serde_json::from_value()` so that, when
+/// enabled with [`Client::report_unused_fields()`](crate::Client::report_unused_fields),
+/// response fields that the deserializer ignores can be reported, revealing
+/// selections that could be dropped from the query.
+pub fn from_value<T: DeserializeOwned>(value: serde_json::Value) -> Result<T, serde_json::Error> {
+    if IGNORED.with(|ig| ig.borrow().is_none()) {
+        return serde_json::from_value(value);
+    }
+    let mut paths = Vec::new();
+    let r = serde_ignored::deserialize(value, |path| {
+        let mut s = String::new();
+        write_path(&path, &mut s);
+        paths.push(s);
+    });
+    IGNORED.with(|ig| {
+        if let Some(ignored) = ig.borrow_mut().as_mut() {
+            ignored.extend(paths);
+        }
+    });
+    r
+}
+
+/// Call `f`, returning its result along with the paths of the response fields
+/// ignored by any calls to [`from_value()`] that it made
+pub(crate) fn collect_ignored<R, F: FnOnce() -> R>(f: F) -> (R, Vec<String>) {
+    let outer = IGNORED.with(|ig| ig.replace(Some(Vec::new())));
+    let r = f();
+    let ignored = IGNORED.with(|ig| ig.replace(outer)).unwrap_or_default();
+    (r, ignored)
+}
+
+/// Write `path` with object keys separated by periods and with array indices
+/// replaced by `[]`, so that a field ignored in every element of an array is
+/// reported only once
+fn write_path(path: &Path<'_>, s: &mut String) {
+    match path {
+        Path::Root => (),
+        Path::Seq { parent, .. } => {
+            write_path(parent, s);
+            s.push_str("[]");
+        }
+        Path::Map { parent, key } => {
+            write_path(parent, s);
+            if !s.is_empty() {
+                s.push('.');
+            }
+            s.push_str(key);
+        }
+        Path::Some { parent }
+        | Path::NewtypeStruct { parent }
+        | Path::NewtypeVariant { parent } => {
+            write_path(parent, s);
+        }
+    }
+}
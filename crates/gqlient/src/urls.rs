@@ -0,0 +1,232 @@
+use std::fmt;
+
+/// Return the base URL of the web interface of the GitHub instance whose
+/// GraphQL endpoint is `graphql_url`.
+///
+/// For github.com and other instances whose API lives on an `api.`
+/// subdomain (e.g., `https://api.github.com/graphql`), the subdomain is
+/// dropped; for GitHub Enterprise Server instances (e.g.,
+/// `https://ghes.example.com/api/graphql`), the base URL is the endpoint's
+/// scheme and host.
+pub(crate) fn web_url(graphql_url: &str) -> String {
+    let (scheme, rest) = graphql_url
+        .split_once("://")
+        .unwrap_or(("https", graphql_url));
+    let (authority, path) = rest.split_once('/').unwrap_or((rest, ""));
+    let authority = match authority.strip_prefix("api.") {
+        Some(host) if path == "graphql" => host,
+        _ => authority,
+    };
+    format!("{scheme}://{authority}")
+}
+
+/// Check that `url` is the URL of issue (or pull request) number `number` in
+/// the repository `repo` (given in "owner/name" form) on the GitHub instance
+/// whose web interface is at `base_url` (e.g., `https://github.com`; see
+/// [`Client::web_url()`](crate::Client::web_url)) and return the URL in
+/// canonical form.
+///
+/// Scheme and host are compared case-insensitively, as are the owner &
+/// repository names (which GitHub treats case-insensitively), and any trailing
+/// slash, query string, or fragment is discarded.  The canonical URL uses the
+/// capitalization of `repo`.
+pub fn canonicalize_issue_url(
+    url: &str,
+    base_url: &str,
+    repo: &str,
+    number: u64,
+) -> Result<String, IssueUrlError> {
+    let base_url = base_url.strip_suffix('/').unwrap_or(base_url);
+    let err = |kind| IssueUrlError {
+        url: url.to_owned(),
+        base_url: base_url.to_owned(),
+        kind,
+    };
+    let stripped = url.split(['?', '#']).next().unwrap_or_default();
+    let stripped = stripped.strip_suffix('/').unwrap_or(stripped);
+    let path = strip_prefix_ignore_case(stripped, base_url)
+        .ok_or_else(|| err(IssueUrlErrorKind::BadBase))?;
+    let mut parts = path.split('/');
+    if parts.next() != Some("") {
+        return Err(err(IssueUrlErrorKind::BadBase));
+    }
+    let (Some(owner), Some(name), Some(kind), Some(n), None) = (
+        parts.next(),
+        parts.next(),
+        parts.next(),
+        parts.next(),
+        parts.next(),
+    ) else {
+        return Err(err(IssueUrlErrorKind::BadPath));
+    };
+    if kind != "issues" && kind != "pull" {
+        return Err(err(IssueUrlErrorKind::BadPath));
+    }
+    let url_repo = format!("{owner}/{name}");
+    if !url_repo.eq_ignore_ascii_case(repo) {
+        return Err(err(IssueUrlErrorKind::RepoMismatch {
+            expected: repo.to_owned(),
+        }));
+    }
+    if n.parse::<u64>().ok() != Some(number) {
+        return Err(err(IssueUrlErrorKind::NumberMismatch { expected: number }));
+    }
+    Ok(format!("{base_url}/{repo}/{kind}/{number}"))
+}
+
+fn strip_prefix_ignore_case<'a>(s: &'a str, prefix: &str) -> Option<&'a str> {
+    let head = s.get(..prefix.len())?;
+    head.eq_ignore_ascii_case(prefix)
+        .then(|| &s[prefix.len()..])
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct IssueUrlError {
+    url: String,
+    base_url: String,
+    kind: IssueUrlErrorKind,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum IssueUrlErrorKind {
+    BadBase,
+    BadPath,
+    RepoMismatch { expected: String },
+    NumberMismatch { expected: u64 },
+}
+
+impl fmt::Display for IssueUrlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            IssueUrlErrorKind::BadBase => {
+                write!(f, "issue URL {:?} is not under {}", self.url, self.base_url)
+            }
+            IssueUrlErrorKind::BadPath => {
+                write!(
+                    f,
+                    "issue URL {:?} is not of the form {}/{{owner}}/{{repo}}/{{issues|pull}}/{{number}}",
+                    self.url, self.base_url
+                )
+            }
+            IssueUrlErrorKind::RepoMismatch { ref expected } => {
+                write!(
+                    f,
+                    "issue URL {:?} does not point to repository {expected}",
+                    self.url
+                )
+            }
+            IssueUrlErrorKind::NumberMismatch { expected } => {
+                write!(
+                    f,
+                    "issue URL {:?} does not point to issue #{expected}",
+                    self.url
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for IssueUrlError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn web_url_for_github_com() {
+        assert_eq!(
+            web_url("https://api.github.com/graphql"),
+            "https://github.com"
+        );
+    }
+
+    #[test]
+    fn web_url_for_ghe_com() {
+        assert_eq!(
+            web_url("https://api.octocorp.ghe.com/graphql"),
+            "https://octocorp.ghe.com"
+        );
+    }
+
+    #[test]
+    fn web_url_for_ghes() {
+        assert_eq!(
+            web_url("https://ghes.example.com/api/graphql"),
+            "https://ghes.example.com"
+        );
+        assert_eq!(
+            web_url("http://localhost:8080/api/graphql"),
+            "http://localhost:8080"
+        );
+    }
+
+    #[test]
+    fn canonicalize_github_com() {
+        assert_eq!(
+            canonicalize_issue_url(
+                "HTTPS://GitHub.com/JWodder/Query-Issues/issues/42/?x=1#top",
+                "https://github.com",
+                "jwodder/query-issues",
+                42,
+            ),
+            Ok(String::from(
+                "https://github.com/jwodder/query-issues/issues/42"
+            ))
+        );
+    }
+
+    #[test]
+    fn canonicalize_ghes() {
+        assert_eq!(
+            canonicalize_issue_url(
+                "https://ghes.example.com/team/app/pull/7",
+                "https://ghes.example.com/",
+                "team/app",
+                7,
+            ),
+            Ok(String::from("https://ghes.example.com/team/app/pull/7"))
+        );
+    }
+
+    #[test]
+    fn canonicalize_wrong_host() {
+        let e = canonicalize_issue_url(
+            "https://github.com/team/app/issues/7",
+            "https://ghes.example.com",
+            "team/app",
+            7,
+        )
+        .unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "issue URL \"https://github.com/team/app/issues/7\" is not under https://ghes.example.com"
+        );
+    }
+
+    #[test]
+    fn canonicalize_mismatches() {
+        let base = "https://github.com";
+        assert_eq!(
+            canonicalize_issue_url("https://github.com/a/b/issues/1", base, "a/c", 1)
+                .map_err(|e| e.kind),
+            Err(IssueUrlErrorKind::RepoMismatch {
+                expected: String::from("a/c")
+            })
+        );
+        assert_eq!(
+            canonicalize_issue_url("https://github.com/a/b/issues/1", base, "a/b", 2)
+                .map_err(|e| e.kind),
+            Err(IssueUrlErrorKind::NumberMismatch { expected: 2 })
+        );
+        assert_eq!(
+            canonicalize_issue_url("https://github.com/a/b/commit/1", base, "a/b", 1)
+                .map_err(|e| e.kind),
+            Err(IssueUrlErrorKind::BadPath)
+        );
+        assert_eq!(
+            canonicalize_issue_url("https://github.community/a/b/issues/1", base, "a/b", 1)
+                .map_err(|e| e.kind),
+            Err(IssueUrlErrorKind::BadBase)
+        );
+    }
+}
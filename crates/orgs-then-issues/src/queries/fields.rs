@@ -0,0 +1,91 @@
+use gqlient::Variable;
+
+/// The optional fields fetched for each issue.  Each field is fetched (via
+/// `@include`) only if the `Boolean!` variable of the same name is true.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub(crate) struct IssueFields {
+    /// Fetch the issue's `databaseId` (`--database-ids`)
+    pub(crate) database_ids: bool,
+    /// Fetch the issue's labels (`--labels` or `--label-matrix`)
+    pub(crate) labels: bool,
+    /// Fetch whether the issue has a linked pull request (`--linked-prs`)
+    pub(crate) linked_prs: bool,
+    /// Fetch the issue's parent & sub-issue counts (`--sub-issues`)
+    pub(crate) sub_issues: bool,
+    /// Fetch when the issue was last updated (`--stale-after`)
+    pub(crate) updated_at: bool,
+}
+
+impl IssueFields {
+    /// Return the query variables for the fields
+    pub(crate) fn variables(&self) -> impl Iterator<Item = (String, Variable)> {
+        boolean_variables([
+            ("database_ids", self.database_ids),
+            ("labels", self.labels),
+            ("linked_prs", self.linked_prs),
+            ("sub_issues", self.sub_issues),
+            ("updated_at", self.updated_at),
+        ])
+    }
+}
+
+/// The optional fields fetched for each repository, along with those fetched
+/// for the issues in its first page when `inline_first_page` is true.  Each
+/// field is fetched (via `@include`) only if the `Boolean!` variable of the
+/// same name is true.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub(crate) struct RepoFields {
+    /// Fetch whether the repository has issue templates & a contributing
+    /// guide (`--community-files`)
+    pub(crate) community_files: bool,
+    /// Fetch the repository's number of mentionable users (`--contributors`)
+    pub(crate) contributors: bool,
+    /// Fetch the repository's discussion categories (`--discussions`)
+    pub(crate) discussions: bool,
+    /// Fetch the repository's number of open pull requests (`--include-prs`)
+    pub(crate) include_prs: bool,
+    /// Fetch the first page of the repository's open issues
+    /// (`--inline-first-page`)
+    pub(crate) inline_first_page: bool,
+    /// Fetch the repository's pinned issues (`--pinned`)
+    pub(crate) pinned: bool,
+    /// Fetch the repository's topics (`--topic`)
+    pub(crate) topics: bool,
+    /// The fields to fetch for issues; `database_ids` also applies to the
+    /// repository itself
+    pub(crate) issues: IssueFields,
+}
+
+impl RepoFields {
+    /// Return the query variables for the fields
+    pub(crate) fn variables(&self) -> impl Iterator<Item = (String, Variable)> {
+        boolean_variables([
+            ("community_files", self.community_files),
+            ("contributors", self.contributors),
+            ("database_ids", self.issues.database_ids),
+            ("discussions", self.discussions),
+            ("include_prs", self.include_prs),
+            ("inline_first_page", self.inline_first_page),
+            ("labels", self.issues.labels),
+            ("linked_prs", self.issues.linked_prs),
+            ("pinned", self.pinned),
+            ("sub_issues", self.issues.sub_issues),
+            ("topics", self.topics),
+            ("updated_at", self.issues.updated_at),
+        ])
+    }
+}
+
+fn boolean_variables<const N: usize>(
+    flags: [(&'static str, bool); N],
+) -> impl Iterator<Item = (String, Variable)> {
+    flags.into_iter().map(|(name, value)| {
+        (
+            String::from(name),
+            Variable {
+                gql_type: String::from("Boolean!"),
+                value: value.into(),
+            },
+        )
+    })
+}
@@ -0,0 +1,129 @@
+use crate::types::DiscussionCategory;
+use gqlient::{Cursor, Id, Page, Paginator, Query, Singleton, Variable};
+use std::collections::BTreeMap;
+use std::fmt::{self, Write};
+
+/// Fetches the number of discussions in each of a repository's discussion
+/// categories.  As GitHub's GraphQL API does not provide discussion counts on
+/// the categories themselves, each category's discussions are counted under a
+/// separate alias, and the results come back as a single page.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct GetDiscussionCounts {
+    repo_id: Id,
+    categories: Vec<DiscussionCategory>,
+}
+
+impl GetDiscussionCounts {
+    pub(crate) fn new(repo_id: Id, categories: Vec<DiscussionCategory>) -> GetDiscussionCounts {
+        GetDiscussionCounts {
+            repo_id,
+            categories,
+        }
+    }
+}
+
+impl Paginator for GetDiscussionCounts {
+    type Item = (String, u64);
+    type Query = GetDiscussionCountsQuery;
+
+    fn for_cursor(&self, _cursor: Option<&Cursor>) -> GetDiscussionCountsQuery {
+        GetDiscussionCountsQuery {
+            repo_id: self.repo_id.clone(),
+            categories: self.categories.clone(),
+            prefix: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct GetDiscussionCountsQuery {
+    repo_id: Id,
+    categories: Vec<DiscussionCategory>,
+    prefix: Option<String>,
+}
+
+impl GetDiscussionCountsQuery {
+    fn repo_id_varname(&self) -> String {
+        match self.prefix {
+            Some(ref prefix) => format!("{prefix}_repo_id"),
+            None => String::from("repo_id"),
+        }
+    }
+
+    fn category_varname(&self, i: usize) -> String {
+        match self.prefix {
+            Some(ref prefix) => format!("{prefix}_category{i}"),
+            None => format!("category{i}"),
+        }
+    }
+}
+
+impl Query for GetDiscussionCountsQuery {
+    type Output = Page<(String, u64)>;
+
+    fn with_variable_prefix(mut self, prefix: String) -> Self {
+        self.prefix = Some(prefix);
+        self
+    }
+
+    fn write_graphql<W: Write>(&self, mut s: W) -> fmt::Result {
+        writeln!(s, "node(id: ${}) {{", self.repo_id_varname())?;
+        writeln!(s, "    ... on Repository {{")?;
+        for i in 0..self.categories.len() {
+            writeln!(
+                s,
+                "        c{i}: discussions(categoryId: ${}) {{",
+                self.category_varname(i)
+            )?;
+            writeln!(s, "            totalCount")?;
+            writeln!(s, "        }}")?;
+        }
+        writeln!(s, "    }}")?;
+        writeln!(s, "}}")
+    }
+
+    fn variables(&self) -> Vec<(String, Variable)> {
+        let mut vars = vec![(
+            self.repo_id_varname(),
+            Variable {
+                gql_type: String::from("ID!"),
+                value: self.repo_id.clone().into(),
+            },
+        )];
+        for (i, cat) in self.categories.iter().enumerate() {
+            vars.push((
+                self.category_varname(i),
+                Variable {
+                    gql_type: String::from("ID!"),
+                    value: cat.id.clone().into(),
+                },
+            ));
+        }
+        vars
+    }
+
+    fn parse_response(
+        &self,
+        value: serde_json::Value,
+    ) -> Result<Page<(String, u64)>, serde_json::Error> {
+        let mut counts = gqlient::from_value::<BTreeMap<String, Singleton<u64>>>(value)?;
+        let items = self
+            .categories
+            .iter()
+            .enumerate()
+            .map(|(i, cat)| {
+                counts
+                    .remove(&format!("c{i}"))
+                    .map(|Singleton(qty)| (cat.name.clone(), qty))
+                    .ok_or_else(|| {
+                        <serde_json::Error as serde::de::Error>::missing_field("discussions")
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Page {
+            items,
+            end_cursor: None,
+            has_next_page: false,
+        })
+    }
+}
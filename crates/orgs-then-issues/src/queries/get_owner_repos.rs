@@ -1,5 +1,6 @@
+use super::RepoFields;
 use crate::types::Repository;
-use gqlient::{Cursor, Ided, Page, Paginator, Query, Singleton, Variable};
+use gqlient::{Cursor, Ided, Page, Paginator, Query, RepoOrder, RepoPrivacy, Singleton, Variable};
 use indoc::indoc;
 use std::fmt::{self, Write};
 use std::num::NonZeroUsize;
@@ -8,11 +9,26 @@ use std::num::NonZeroUsize;
 pub(crate) struct GetOwnerRepos {
     owner: String,
     page_size: NonZeroUsize,
+    order: RepoOrder,
+    privacy: RepoPrivacy,
+    fields: RepoFields,
 }
 
 impl GetOwnerRepos {
-    pub(crate) fn new(owner: String, page_size: NonZeroUsize) -> GetOwnerRepos {
-        GetOwnerRepos { owner, page_size }
+    pub(crate) fn new(
+        owner: String,
+        page_size: NonZeroUsize,
+        order: RepoOrder,
+        privacy: RepoPrivacy,
+        fields: RepoFields,
+    ) -> GetOwnerRepos {
+        GetOwnerRepos {
+            owner,
+            page_size,
+            order,
+            privacy,
+            fields,
+        }
     }
 }
 
@@ -21,7 +37,14 @@ impl Paginator for GetOwnerRepos {
     type Query = GetOwnerReposQuery;
 
     fn for_cursor(&self, cursor: Option<&Cursor>) -> GetOwnerReposQuery {
-        GetOwnerReposQuery::new(self.owner.clone(), cursor.cloned(), self.page_size)
+        GetOwnerReposQuery::new(
+            self.owner.clone(),
+            cursor.cloned(),
+            self.page_size,
+            self.order,
+            self.privacy,
+            self.fields,
+        )
     }
 }
 
@@ -30,15 +53,28 @@ pub(crate) struct GetOwnerReposQuery {
     owner: String,
     cursor: Option<Cursor>,
     page_size: NonZeroUsize,
+    order: RepoOrder,
+    privacy: RepoPrivacy,
+    fields: RepoFields,
     prefix: Option<String>,
 }
 
 impl GetOwnerReposQuery {
-    fn new(owner: String, cursor: Option<Cursor>, page_size: NonZeroUsize) -> GetOwnerReposQuery {
+    fn new(
+        owner: String,
+        cursor: Option<Cursor>,
+        page_size: NonZeroUsize,
+        order: RepoOrder,
+        privacy: RepoPrivacy,
+        fields: RepoFields,
+    ) -> GetOwnerReposQuery {
         GetOwnerReposQuery {
             owner,
             cursor,
             page_size,
+            order,
+            privacy,
+            fields,
             prefix: None,
         }
     }
@@ -50,10 +86,11 @@ impl GetOwnerReposQuery {
         }
     }
 
+    /// Queries without a cursor all share a single null `$cursor` variable
     fn cursor_varname(&self) -> String {
-        match self.prefix {
-            Some(ref prefix) => format!("{prefix}_cursor"),
-            None => String::from("cursor"),
+        match (&self.prefix, &self.cursor) {
+            (Some(prefix), Some(_)) => format!("{prefix}_cursor"),
+            _ => String::from("cursor"),
         }
     }
 }
@@ -72,20 +109,23 @@ impl Query for GetOwnerReposQuery {
             indoc! {"
             repositoryOwner(login: ${owner_varname}) {{
                 repositories(
-                    orderBy: {{field: NAME, direction: ASC}},
+                    orderBy: $order,
                     ownerAffiliations: [OWNER],
                     isArchived: false,
                     isFork: false,
-                    privacy: PUBLIC,
-                    first: {page_size},
+                    privacy: $privacy,
+                    first: $page_size,
                     after: ${cursor_varname},
                 ) {{
-                    nodes {{
-                        id
-                        nameWithOwner
-                        issues(states: [OPEN]) {{
-                            totalCount
-                        }}
+                    nodes {{"
+            },
+            owner_varname = self.owner_varname(),
+            cursor_varname = self.cursor_varname(),
+        )?;
+        write_repository_fields(&mut s, 12)?;
+        writeln!(
+            s,
+            indoc! {"
                     }}
                     pageInfo {{
                         endCursor
@@ -93,15 +133,12 @@ impl Query for GetOwnerReposQuery {
                     }}
                 }}
             }}
-        "},
-            owner_varname = self.owner_varname(),
-            cursor_varname = self.cursor_varname(),
-            page_size = self.page_size,
+        "}
         )
     }
 
-    fn variables(&self) -> [(String, Variable); 2] {
-        [
+    fn variables(&self) -> Vec<(String, Variable)> {
+        let mut variables = vec![
             (
                 self.owner_varname(),
                 Variable {
@@ -116,10 +153,136 @@ impl Query for GetOwnerReposQuery {
                     value: self.cursor.clone().into(),
                 },
             ),
-        ]
+            (
+                String::from("page_size"),
+                Variable {
+                    gql_type: String::from("Int!"),
+                    value: self.page_size.get().into(),
+                },
+            ),
+            (String::from("order"), self.order.as_variable()),
+            (String::from("privacy"), self.privacy.as_variable()),
+        ];
+        variables.extend(self.fields.variables());
+        variables
     }
 
     fn parse_response(&self, value: serde_json::Value) -> Result<Self::Output, serde_json::Error> {
-        serde_json::from_value::<Singleton<Self::Output>>(value).map(|r| r.0)
+        gqlient::from_value::<Singleton<Self::Output>>(value).map(|r| r.0)
+    }
+}
+
+/// The fields fetched for each repository, shared with
+/// [`GetStarredRepos`](super::GetStarredRepos)
+const REPOSITORY_FIELDS: &str = indoc! {r#"
+    id
+    nameWithOwner
+    databaseId @include(if: $database_ids)
+    visibility
+    isTemplate
+    viewerPermission
+    issues(states: [OPEN]) {
+        totalCount
+    }
+    openPullRequests: pullRequests(states: [OPEN]) @include(if: $include_prs) {
+        totalCount
+    }
+    stargazerCount
+    forkCount
+    firstIssues: issues(
+        first: $page_size,
+        orderBy: {field: CREATED_AT, direction: ASC},
+        states: [OPEN],
+    ) @include(if: $inline_first_page) {
+        nodes {
+            number
+            databaseId @include(if: $database_ids)
+            title
+            author {
+                login
+                __typename
+            }
+            url
+            updatedAt @include(if: $updated_at)
+            closedByPullRequestsReferences(first: 1) @include(if: $linked_prs) {
+                totalCount
+            }
+            parent @include(if: $sub_issues) {
+                number
+                repository {
+                    nameWithOwner
+                }
+            }
+            subIssuesSummary @include(if: $sub_issues) {
+                total
+                completed
+            }
+            labels(first: 100) @include(if: $labels) {
+                nodes {
+                    name
+                }
+            }
+        }
+        pageInfo {
+            endCursor
+            hasNextPage
+        }
+    }
+    defaultBranchRef {
+        name
+    }
+    latestRelease {
+        tagName
+        publishedAt
+    }
+    pinnedIssues(first: 3) @include(if: $pinned) {
+        nodes {
+            issue {
+                number
+            }
+        }
+    }
+    repositoryTopics(first: 100) @include(if: $topics) {
+        nodes {
+            topic {
+                name
+            }
+        }
+    }
+    mentionableUsers @include(if: $contributors) {
+        totalCount
+    }
+    discussionCategories(first: 25) @include(if: $discussions) {
+        nodes {
+            id
+            name
+        }
+    }
+    issueTemplateDir: object(expression: "HEAD:.github/ISSUE_TEMPLATE") @include(if: $community_files) {
+        __typename
+    }
+    issueTemplateGithub: object(expression: "HEAD:.github/ISSUE_TEMPLATE.md") @include(if: $community_files) {
+        __typename
+    }
+    issueTemplateRoot: object(expression: "HEAD:ISSUE_TEMPLATE.md") @include(if: $community_files) {
+        __typename
+    }
+    contributingRoot: object(expression: "HEAD:CONTRIBUTING.md") @include(if: $community_files) {
+        __typename
+    }
+    contributingGithub: object(expression: "HEAD:.github/CONTRIBUTING.md") @include(if: $community_files) {
+        __typename
+    }
+    contributingDocs: object(expression: "HEAD:docs/CONTRIBUTING.md") @include(if: $community_files) {
+        __typename
+    }
+"#};
+
+/// Write [`REPOSITORY_FIELDS`] to `s` with each line indented by `indent`
+/// spaces
+pub(super) fn write_repository_fields<W: Write>(mut s: W, indent: usize) -> fmt::Result {
+    for line in REPOSITORY_FIELDS.lines() {
+        writeln!(s, "{:indent$}{line}", "")?;
     }
+    Ok(())
 }
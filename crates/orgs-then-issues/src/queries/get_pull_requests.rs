@@ -0,0 +1,211 @@
+use crate::types::{Issue, RepoWithIssues};
+use gqlient::{Cursor, Id, Page, Paginator, Query, Variable};
+use indoc::indoc;
+use std::fmt::{self, Write};
+use std::num::NonZeroUsize;
+
+/// Paginates over the open pull requests of a repository for `--include-prs`,
+/// fetching the fields that issues and pull requests have in common
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct GetPullRequests {
+    repo_id: Id,
+    page_size: NonZeroUsize,
+    database_ids: bool,
+    labels: bool,
+    updated_at: bool,
+    /// Whether the page size has been lowered from the one given on the
+    /// command line and so must be passed in a variable of its own
+    own_page_size: bool,
+}
+
+impl GetPullRequests {
+    pub(crate) fn new(
+        repo_id: Id,
+        page_size: NonZeroUsize,
+        database_ids: bool,
+        labels: bool,
+        updated_at: bool,
+    ) -> GetPullRequests {
+        GetPullRequests {
+            repo_id,
+            page_size,
+            database_ids,
+            labels,
+            updated_at,
+            own_page_size: false,
+        }
+    }
+}
+
+impl Paginator for GetPullRequests {
+    type Item = Issue;
+    type Query = GetPullRequestsQuery;
+
+    fn for_cursor(&self, cursor: Option<&Cursor>) -> GetPullRequestsQuery {
+        GetPullRequestsQuery {
+            repo_id: self.repo_id.clone(),
+            cursor: cursor.cloned(),
+            page_size: self.page_size,
+            database_ids: self.database_ids,
+            labels: self.labels,
+            updated_at: self.updated_at,
+            own_page_size: self.own_page_size,
+            prefix: None,
+        }
+    }
+
+    /// Halve the page size, down to a minimum of one pull request per page
+    fn reduce_page_size(&mut self) -> Option<NonZeroUsize> {
+        let page_size = NonZeroUsize::new(self.page_size.get() / 2)?;
+        self.page_size = page_size;
+        self.own_page_size = true;
+        Some(page_size)
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct GetPullRequestsQuery {
+    repo_id: Id,
+    cursor: Option<Cursor>,
+    page_size: NonZeroUsize,
+    database_ids: bool,
+    labels: bool,
+    updated_at: bool,
+    own_page_size: bool,
+    prefix: Option<String>,
+}
+
+impl GetPullRequestsQuery {
+    fn repo_id_varname(&self) -> String {
+        match self.prefix {
+            Some(ref prefix) => format!("{prefix}_repo_id"),
+            None => String::from("repo_id"),
+        }
+    }
+
+    /// Queries without a cursor all share a single null `$cursor` variable
+    fn cursor_varname(&self) -> String {
+        match (&self.prefix, &self.cursor) {
+            (Some(prefix), Some(_)) => format!("{prefix}_cursor"),
+            _ => String::from("cursor"),
+        }
+    }
+
+    /// Queries with the default page size all share a single `$page_size`
+    /// variable
+    fn page_size_varname(&self) -> String {
+        match self.prefix {
+            Some(ref prefix) if self.own_page_size => format!("{prefix}_page_size"),
+            _ => String::from("page_size"),
+        }
+    }
+}
+
+impl Query for GetPullRequestsQuery {
+    type Output = Page<Issue>;
+
+    fn with_variable_prefix(mut self, prefix: String) -> Self {
+        self.prefix = Some(prefix);
+        self
+    }
+
+    fn write_graphql<W: Write>(&self, mut s: W) -> fmt::Result {
+        writeln!(
+            s,
+            indoc! {"
+            node(id: ${repo_id_varname}) {{
+                ... on Repository {{
+                    nameWithOwner
+                    pullRequests(
+                        first: ${page_size_varname},
+                        after: ${cursor_varname},
+                        orderBy: {{field: CREATED_AT, direction: ASC}},
+                        states: [OPEN],
+                    ) {{
+                        nodes {{
+                            __typename
+                            number
+                            databaseId @include(if: $database_ids)
+                            title
+                            author {{
+                                login
+                                __typename
+                            }}
+                            url
+                            updatedAt @include(if: $updated_at)
+                            labels(first: 100) @include(if: $labels) {{
+                                nodes {{
+                                    name
+                                }}
+                            }}
+                        }}
+                        pageInfo {{
+                            endCursor
+                            hasNextPage
+                        }}
+                    }}
+                }}
+            }}
+        "},
+            repo_id_varname = self.repo_id_varname(),
+            cursor_varname = self.cursor_varname(),
+            page_size_varname = self.page_size_varname(),
+        )
+    }
+
+    fn variables(&self) -> [(String, Variable); 6] {
+        [
+            (
+                self.repo_id_varname(),
+                Variable {
+                    gql_type: String::from("ID!"),
+                    value: self.repo_id.clone().into(),
+                },
+            ),
+            (
+                self.cursor_varname(),
+                Variable {
+                    gql_type: String::from("String"),
+                    value: self.cursor.clone().into(),
+                },
+            ),
+            (
+                self.page_size_varname(),
+                Variable {
+                    gql_type: String::from("Int!"),
+                    value: self.page_size.get().into(),
+                },
+            ),
+            (
+                String::from("database_ids"),
+                Variable {
+                    gql_type: String::from("Boolean!"),
+                    value: self.database_ids.into(),
+                },
+            ),
+            (
+                String::from("labels"),
+                Variable {
+                    gql_type: String::from("Boolean!"),
+                    value: self.labels.into(),
+                },
+            ),
+            (
+                String::from("updated_at"),
+                Variable {
+                    gql_type: String::from("Boolean!"),
+                    value: self.updated_at.into(),
+                },
+            ),
+        ]
+    }
+
+    fn parse_response(&self, value: serde_json::Value) -> Result<Page<Issue>, serde_json::Error> {
+        let raw = gqlient::from_value::<RepoWithIssues>(value)?;
+        Ok(Page {
+            items: raw.issues,
+            end_cursor: raw.issue_cursor,
+            has_next_page: raw.has_more_issues,
+        })
+    }
+}
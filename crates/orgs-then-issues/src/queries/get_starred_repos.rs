@@ -0,0 +1,142 @@
+use super::get_owner_repos::write_repository_fields;
+use super::RepoFields;
+use crate::types::Repository;
+use gqlient::{Cursor, Ided, Page, Paginator, Query, Singleton, Variable};
+use indoc::indoc;
+use std::fmt::{self, Write};
+use std::num::NonZeroUsize;
+
+/// Paginates over the repositories starred by a user, most recently starred
+/// first, fetching the same fields as [`GetOwnerRepos`](super::GetOwnerRepos)
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct GetStarredRepos {
+    user: String,
+    page_size: NonZeroUsize,
+    fields: RepoFields,
+}
+
+impl GetStarredRepos {
+    pub(crate) fn new(
+        user: String,
+        page_size: NonZeroUsize,
+        fields: RepoFields,
+    ) -> GetStarredRepos {
+        GetStarredRepos {
+            user,
+            page_size,
+            fields,
+        }
+    }
+}
+
+impl Paginator for GetStarredRepos {
+    type Item = Ided<Repository>;
+    type Query = GetStarredReposQuery;
+
+    fn for_cursor(&self, cursor: Option<&Cursor>) -> GetStarredReposQuery {
+        GetStarredReposQuery {
+            user: self.user.clone(),
+            cursor: cursor.cloned(),
+            page_size: self.page_size,
+            fields: self.fields,
+            prefix: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct GetStarredReposQuery {
+    user: String,
+    cursor: Option<Cursor>,
+    page_size: NonZeroUsize,
+    fields: RepoFields,
+    prefix: Option<String>,
+}
+
+impl GetStarredReposQuery {
+    fn user_varname(&self) -> String {
+        match self.prefix {
+            Some(ref prefix) => format!("{prefix}_user"),
+            None => String::from("user"),
+        }
+    }
+
+    /// Queries without a cursor all share a single null `$cursor` variable
+    fn cursor_varname(&self) -> String {
+        match (&self.prefix, &self.cursor) {
+            (Some(prefix), Some(_)) => format!("{prefix}_cursor"),
+            _ => String::from("cursor"),
+        }
+    }
+}
+
+impl Query for GetStarredReposQuery {
+    type Output = Page<Ided<Repository>>;
+
+    fn with_variable_prefix(mut self, prefix: String) -> Self {
+        self.prefix = Some(prefix);
+        self
+    }
+
+    fn write_graphql<W: Write>(&self, mut s: W) -> fmt::Result {
+        writeln!(
+            s,
+            indoc! {"
+            user(login: ${user_varname}) {{
+                starredRepositories(
+                    orderBy: {{field: STARRED_AT, direction: DESC}},
+                    first: $page_size,
+                    after: ${cursor_varname},
+                ) {{
+                    nodes {{"
+            },
+            user_varname = self.user_varname(),
+            cursor_varname = self.cursor_varname(),
+        )?;
+        write_repository_fields(&mut s, 12)?;
+        writeln!(
+            s,
+            indoc! {"
+                    }}
+                    pageInfo {{
+                        endCursor
+                        hasNextPage
+                    }}
+                }}
+            }}
+        "}
+        )
+    }
+
+    fn variables(&self) -> Vec<(String, Variable)> {
+        let mut variables = vec![
+            (
+                self.user_varname(),
+                Variable {
+                    gql_type: String::from("String!"),
+                    value: self.user.clone().into(),
+                },
+            ),
+            (
+                self.cursor_varname(),
+                Variable {
+                    gql_type: String::from("String"),
+                    value: self.cursor.clone().into(),
+                },
+            ),
+            (
+                String::from("page_size"),
+                Variable {
+                    gql_type: String::from("Int!"),
+                    value: self.page_size.get().into(),
+                },
+            ),
+        ];
+        variables.extend(self.fields.variables());
+        variables
+    }
+
+    fn parse_response(&self, value: serde_json::Value) -> Result<Self::Output, serde_json::Error> {
+        gqlient::from_value::<Singleton<Self::Output>>(value).map(|r| r.0)
+    }
+}
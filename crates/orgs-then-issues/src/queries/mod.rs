@@ -1,4 +1,210 @@
+mod fields;
+mod get_discussion_counts;
 mod get_issues;
 mod get_owner_repos;
+mod get_pull_requests;
+mod get_starred_repos;
+pub(crate) use self::fields::{IssueFields, RepoFields};
+pub(crate) use self::get_discussion_counts::GetDiscussionCounts;
 pub(crate) use self::get_issues::GetIssues;
 pub(crate) use self::get_owner_repos::GetOwnerRepos;
+pub(crate) use self::get_pull_requests::GetPullRequests;
+pub(crate) use self::get_starred_repos::GetStarredRepos;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::DiscussionCategory;
+    use fixtures::FakeOrg;
+    use gqlient::{compose_request, Cursor, Id, Paginator, Query, RepoOrder, RepoPrivacy};
+    use insta::assert_snapshot;
+    use std::num::NonZeroUsize;
+
+    fn render<Q: Query>(queries: Vec<Q>) -> String {
+        let (doc, variables) = compose_request(queries).unwrap();
+        format!(
+            "{doc}\n{}",
+            serde_json::to_string_pretty(&variables).unwrap()
+        )
+    }
+
+    fn cursor(s: &str) -> Cursor {
+        serde_json::from_value(s.into()).unwrap()
+    }
+
+    fn id(s: &str) -> Id {
+        serde_json::from_value(s.into()).unwrap()
+    }
+
+    fn all_issue_fields() -> IssueFields {
+        IssueFields {
+            database_ids: true,
+            labels: true,
+            linked_prs: true,
+            sub_issues: true,
+            updated_at: true,
+        }
+    }
+
+    #[test]
+    fn get_owner_repos_first_page() {
+        let paginator = GetOwnerRepos::new(
+            String::from("jwodder"),
+            NonZeroUsize::new(100).unwrap(),
+            RepoOrder::Name,
+            RepoPrivacy::Public,
+            RepoFields::default(),
+        );
+        assert_snapshot!(render(vec![paginator.for_cursor(None)]));
+    }
+
+    #[test]
+    fn get_owner_repos_batch() {
+        let page_size = NonZeroUsize::new(25).unwrap();
+        let all_fields = RepoFields {
+            community_files: true,
+            contributors: true,
+            discussions: true,
+            include_prs: true,
+            inline_first_page: true,
+            pinned: true,
+            topics: true,
+            issues: all_issue_fields(),
+        };
+        let first = GetOwnerRepos::new(
+            String::from("jwodder"),
+            page_size,
+            RepoOrder::Pushed,
+            RepoPrivacy::All,
+            all_fields,
+        );
+        let second = GetOwnerRepos::new(
+            String::from("wheelodex"),
+            page_size,
+            RepoOrder::Pushed,
+            RepoPrivacy::All,
+            all_fields,
+        );
+        assert_snapshot!(render(vec![
+            first.for_cursor(None),
+            second.for_cursor(Some(&cursor("Y3Vyc29yOnYyOpHOABCDEF"))),
+        ]));
+    }
+
+    #[test]
+    fn get_starred_repos_batch() {
+        let page_size = NonZeroUsize::new(100).unwrap();
+        let first = GetStarredRepos::new(String::from("jwodder"), page_size, RepoFields::default());
+        let second =
+            GetStarredRepos::new(String::from("octocat"), page_size, RepoFields::default());
+        assert_snapshot!(render(vec![
+            first.for_cursor(None),
+            second.for_cursor(Some(&cursor("Y3Vyc29yOnYyOpHOABCDEF"))),
+        ]));
+    }
+
+    #[test]
+    fn get_discussion_counts_batch() {
+        let category = |cid: &str, name: &str| DiscussionCategory {
+            id: id(cid),
+            name: String::from(name),
+        };
+        let first = GetDiscussionCounts::new(
+            id("R_kgDOABCDEF"),
+            vec![
+                category("DIC_kwDOABCDEF4CAAAA", "Q&A"),
+                category("DIC_kwDOABCDEF4CAAAB", "Ideas"),
+            ],
+        );
+        let second = GetDiscussionCounts::new(
+            id("R_kgDOGHIJKL"),
+            vec![category("DIC_kwDOGHIJKL4CAAAA", "General")],
+        );
+        assert_snapshot!(render(vec![
+            first.for_cursor(None),
+            second.for_cursor(None)
+        ]));
+    }
+
+    #[test]
+    fn get_issues_first_page() {
+        let paginator = GetIssues::new(
+            id("R_kgDOABCDEF"),
+            None,
+            NonZeroUsize::new(100).unwrap(),
+            IssueFields::default(),
+        );
+        assert_snapshot!(render(vec![paginator.for_cursor(None)]));
+    }
+
+    #[test]
+    fn get_issues_batch() {
+        let page_size = NonZeroUsize::new(25).unwrap();
+        let fields = all_issue_fields();
+        let first = GetIssues::new(id("R_kgDOABCDEF"), None, page_size, fields);
+        let second = GetIssues::new(id("R_kgDOGHIJKL"), None, page_size, fields);
+        let third = GetIssues::new(
+            id("R_kgDOMNOPQR"),
+            Some(cursor("Y3Vyc29yOnYyOpHOMNOPQR")),
+            page_size,
+            fields,
+        );
+        assert_snapshot!(render(vec![
+            first.for_cursor(None),
+            second.for_cursor(Some(&cursor("Y3Vyc29yOnYyOpHOABCDEF"))),
+            third.for_cursor(None),
+        ]));
+    }
+
+    #[test]
+    fn get_pull_requests_batch() {
+        let page_size = NonZeroUsize::new(25).unwrap();
+        let first = GetPullRequests::new(id("R_kgDOABCDEF"), page_size, true, true, false);
+        let second = GetPullRequests::new(id("R_kgDOGHIJKL"), page_size, true, true, false);
+        assert_snapshot!(render(vec![
+            first.for_cursor(None),
+            second.for_cursor(Some(&cursor("Y3Vyc29yOnYyOpHOGHIJKL"))),
+        ]));
+    }
+
+    #[test]
+    fn get_issues_fake_repo() {
+        let org = FakeOrg::new("fake-org", 1, 250, 3);
+        let repo = &org.repos()[0];
+        let paginator = GetIssues::new(
+            id(&repo.id()),
+            None,
+            NonZeroUsize::new(100).unwrap(),
+            IssueFields {
+                labels: true,
+                ..IssueFields::default()
+            },
+        );
+        let mut after: Option<Cursor> = None;
+        let mut issues = Vec::new();
+        let mut pages = 0;
+        loop {
+            let after_str = after.as_ref().map(ToString::to_string);
+            let data = repo.with_issues_page(100, after_str.as_deref());
+            let page = paginator
+                .for_cursor(after.as_ref())
+                .parse_response(data)
+                .unwrap();
+            issues.extend(page.items);
+            pages += 1;
+            if !page.has_next_page {
+                break;
+            }
+            after = page.end_cursor;
+        }
+        assert_eq!(pages, 3);
+        assert_eq!(issues.len(), org.total_issues());
+        assert!(issues
+            .iter()
+            .all(|issue| issue.repo == repo.name_with_owner()
+                && issue
+                    .labels
+                    .as_ref()
+                    .is_some_and(|labels| labels.len() == 3)));
+    }
+}
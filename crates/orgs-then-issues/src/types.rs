@@ -1,12 +1,218 @@
-use gqlient::{Cursor, Page};
-use serde::{Deserialize, Serialize};
+use gqlient::{
+    Anonymizer, Author, Cursor, Id, IssueKind, IssueUrlError, Page, ParentIssue, RepoPermission,
+    RepoVisibility, SubIssues,
+};
+use serde::{de::IgnoredAny, Deserialize, Deserializer, Serialize};
+use std::collections::{BTreeMap, HashMap};
 
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(from = "RawRepository")]
 pub(crate) struct Repository {
-    #[serde(rename = "nameWithOwner")]
+    /// The name of the `--hosts` host that the repository was fetched from,
+    /// if not github.com
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) host: Option<String>,
     pub(crate) fullname: String,
-    #[serde(rename = "issues", deserialize_with = "gqlient::singleton_field")]
+    /// The repository's numeric ID as used by the REST API; only fetched
+    /// when `--database-ids` is given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) database_id: Option<u64>,
+    pub(crate) visibility: RepoVisibility,
+    pub(crate) is_template: bool,
+    /// The access token's level of access to the repository, if any
+    pub(crate) viewer_permission: Option<RepoPermission>,
     pub(crate) open_issues: u64,
+    /// The repository's number of open pull requests; only fetched when
+    /// `--include-prs` is given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) open_pull_requests: Option<u64>,
+    pub(crate) stargazer_count: u64,
+    pub(crate) fork_count: u64,
+    pub(crate) default_branch: Option<String>,
+    pub(crate) latest_release: Option<Release>,
+    /// Whether the repository has issue templates; only fetched when
+    /// `--community-files` is given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) has_issue_templates: Option<bool>,
+    /// Whether the repository has a `CONTRIBUTING.md` file; only fetched when
+    /// `--community-files` is given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) has_contributing: Option<bool>,
+    /// The names of the repository's topics; only fetched when `--topic` is
+    /// given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) topics: Option<Vec<String>>,
+    /// The numbers of the issues pinned to the repository; only fetched when
+    /// `--pinned` is given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) pinned_issues: Option<Vec<u64>>,
+    /// The number of users who can be mentioned in the repository
+    /// (contributors, collaborators, and organization members); only fetched
+    /// when `--contributors` is given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) contributors: Option<u64>,
+    /// The number of discussions in each of the repository's discussion
+    /// categories, keyed by category name; only fetched when `--discussions`
+    /// is given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) discussion_categories: Option<BTreeMap<String, u64>>,
+    /// The repository's discussion categories, whose discussions are counted
+    /// in a separate query; only fetched when `--discussions` is given
+    #[serde(skip_serializing)]
+    pub(crate) categories: Option<Vec<DiscussionCategory>>,
+    /// The first page of the repository's open issues; only fetched when
+    /// `--inline-first-page` is given
+    #[serde(skip_serializing)]
+    pub(crate) first_issues: Option<RepoWithIssues>,
+}
+
+impl Repository {
+    /// Replace the repository's host, name, and topics with hashes
+    pub(crate) fn anonymize(&mut self, anon: &Anonymizer) {
+        if let Some(ref mut host) = self.host {
+            *host = anon.host(host);
+        }
+        self.fullname = anon.repo(&self.fullname);
+        if let Some(ref mut topics) = self.topics {
+            for topic in topics {
+                *topic = anon.topic(topic);
+            }
+        }
+    }
+
+    /// Returns true if the repository's topics (if fetched) include any of
+    /// the given topics, compared case-insensitively
+    pub(crate) fn has_any_topic(&self, topics: &[String]) -> bool {
+        self.topics.as_ref().is_some_and(|ours| {
+            ours.iter()
+                .any(|t| topics.iter().any(|wanted| t.eq_ignore_ascii_case(wanted)))
+        })
+    }
+}
+
+// The `object(expression:)` lookups for issue templates & contributing guides
+// are queried under the aliases given here; each is null if the file or
+// directory does not exist and absent if `--community-files` was not given.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct RawRepository {
+    name_with_owner: String,
+    #[serde(default)]
+    database_id: Option<u64>,
+    visibility: RepoVisibility,
+    is_template: bool,
+    viewer_permission: Option<RepoPermission>,
+    #[serde(deserialize_with = "gqlient::singleton_field")]
+    issues: u64,
+    #[serde(default, deserialize_with = "gqlient::optional_singleton_field")]
+    open_pull_requests: Option<u64>,
+    stargazer_count: u64,
+    fork_count: u64,
+    #[serde(deserialize_with = "gqlient::optional_singleton_field")]
+    default_branch_ref: Option<String>,
+    latest_release: Option<Release>,
+    #[serde(default, deserialize_with = "object_exists")]
+    issue_template_dir: Option<bool>,
+    #[serde(default, deserialize_with = "object_exists")]
+    issue_template_github: Option<bool>,
+    #[serde(default, deserialize_with = "object_exists")]
+    issue_template_root: Option<bool>,
+    #[serde(default, deserialize_with = "object_exists")]
+    contributing_root: Option<bool>,
+    #[serde(default, deserialize_with = "object_exists")]
+    contributing_github: Option<bool>,
+    #[serde(default, deserialize_with = "object_exists")]
+    contributing_docs: Option<bool>,
+    #[serde(default, deserialize_with = "gqlient::optional_singleton_field")]
+    repository_topics: Option<Vec<RawRepositoryTopic>>,
+    #[serde(default, deserialize_with = "gqlient::optional_singleton_field")]
+    pinned_issues: Option<Vec<RawPinnedIssue>>,
+    #[serde(default, deserialize_with = "gqlient::optional_singleton_field")]
+    mentionable_users: Option<u64>,
+    #[serde(default, deserialize_with = "gqlient::optional_singleton_field")]
+    discussion_categories: Option<Vec<DiscussionCategory>>,
+    #[serde(default)]
+    first_issues: Option<Page<RawIssue>>,
+}
+
+impl From<RawRepository> for Repository {
+    fn from(value: RawRepository) -> Repository {
+        let first_issues = value
+            .first_issues
+            .map(|page| RepoWithIssues::from_page(&value.name_with_owner, page));
+        Repository {
+            host: None,
+            fullname: value.name_with_owner,
+            database_id: value.database_id,
+            visibility: value.visibility,
+            is_template: value.is_template,
+            viewer_permission: value.viewer_permission,
+            open_issues: value.issues,
+            open_pull_requests: value.open_pull_requests,
+            stargazer_count: value.stargazer_count,
+            fork_count: value.fork_count,
+            default_branch: value.default_branch_ref,
+            latest_release: value.latest_release,
+            has_issue_templates: any_exists([
+                value.issue_template_dir,
+                value.issue_template_github,
+                value.issue_template_root,
+            ]),
+            has_contributing: any_exists([
+                value.contributing_root,
+                value.contributing_github,
+                value.contributing_docs,
+            ]),
+            topics: value
+                .repository_topics
+                .map(|topics| topics.into_iter().map(|t| t.topic).collect()),
+            pinned_issues: value
+                .pinned_issues
+                .map(|pinned| pinned.into_iter().map(|p| p.issue).collect()),
+            contributors: value.mentionable_users,
+            discussion_categories: None,
+            categories: value.discussion_categories,
+            first_issues,
+        }
+    }
+}
+
+// Deserialize an `object(expression:)` lookup as whether the object exists;
+// lookups that were not queried are left as `None` via `#[serde(default)]`
+fn object_exists<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<bool>, D::Error> {
+    Option::<IgnoredAny>::deserialize(deserializer).map(|obj| Some(obj.is_some()))
+}
+
+// Returns whether any of the given lookups found an object, or `None` if none
+// of them were queried
+fn any_exists<const N: usize>(lookups: [Option<bool>; N]) -> Option<bool> {
+    lookups.into_iter().flatten().reduce(|a, b| a || b)
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+struct RawRepositoryTopic {
+    #[serde(deserialize_with = "gqlient::singleton_field")]
+    topic: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+struct RawPinnedIssue {
+    #[serde(deserialize_with = "gqlient::singleton_field")]
+    issue: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub(crate) struct DiscussionCategory {
+    pub(crate) id: Id,
+    pub(crate) name: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub(crate) struct Release {
+    #[serde(rename(deserialize = "tagName"))]
+    pub(crate) tag_name: String,
+    #[serde(rename(deserialize = "publishedAt"))]
+    pub(crate) published_at: Option<String>,
 }
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
@@ -21,43 +227,184 @@ pub(crate) struct RepoWithIssues {
 #[serde(rename_all = "camelCase")]
 struct RawRepoDetails {
     name_with_owner: String,
+    #[serde(alias = "pullRequests")]
     issues: Page<RawIssue>,
 }
 
-impl From<RawRepoDetails> for RepoWithIssues {
-    fn from(value: RawRepoDetails) -> RepoWithIssues {
+impl RepoWithIssues {
+    /// Convert a page of the open issues or pull requests in the repository
+    /// `fullname`
+    fn from_page(fullname: &str, page: Page<RawIssue>) -> RepoWithIssues {
         RepoWithIssues {
-            issues: value
-                .issues
+            issues: page
                 .items
                 .into_iter()
                 .map(|ri| Issue {
-                    repo: value.name_with_owner.clone(),
+                    host: None,
+                    repo: fullname.to_owned(),
                     number: ri.number,
+                    kind: ri.kind,
+                    database_id: ri.database_id,
                     title: ri.title,
+                    author: ri.author,
+                    labels: ri
+                        .labels
+                        .map(|labels| labels.into_iter().map(|lbl| lbl.name).collect()),
                     url: ri.url,
+                    updated_at: ri.updated_at,
+                    has_linked_pr: ri.linked_prs.map(|qty| qty > 0),
+                    parent: ri.parent,
+                    sub_issues: ri.sub_issues,
+                    pinned: None,
                 })
                 .collect(),
-            issue_cursor: value.issues.end_cursor,
-            has_more_issues: value.issues.has_next_page,
+            issue_cursor: page.end_cursor,
+            has_more_issues: page.has_next_page,
         }
     }
 }
 
+impl From<RawRepoDetails> for RepoWithIssues {
+    fn from(value: RawRepoDetails) -> RepoWithIssues {
+        RepoWithIssues::from_page(&value.name_with_owner, value.issues)
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub(crate) struct Issue {
+    /// The name of the `--hosts` host that the issue was fetched from, if
+    /// not github.com
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) host: Option<String>,
     pub(crate) repo: String,
     pub(crate) number: u64,
+    /// Whether the item is an issue or a pull request; the latter are only
+    /// fetched when `--include-prs` is given
+    #[serde(default)]
+    pub(crate) kind: IssueKind,
+    /// The issue's numeric ID as used by the REST API; only fetched when
+    /// `--database-ids` is given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) database_id: Option<u64>,
     pub(crate) title: String,
-    //pub(crate) author: String,
+    pub(crate) author: Option<Author>,
+    /// The names of the issue's labels; only fetched when `--labels` or
+    /// `--label-matrix` is given
     // Note: Reportedly, the max number of labels on an issue is 100
-    //pub(crate) labels: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) labels: Option<Vec<String>>,
     pub(crate) url: String,
+    /// When the issue was last updated; only fetched when `--stale-after` is
+    /// given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) updated_at: Option<String>,
+    /// Whether a pull request that will close the issue is linked to it;
+    /// only fetched when `--linked-prs` is given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) has_linked_pr: Option<bool>,
+    /// The issue's parent issue, if it is a sub-issue; only fetched when
+    /// `--sub-issues` is given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) parent: Option<ParentIssue>,
+    /// The numbers of the issue's sub-issues; only fetched when
+    /// `--sub-issues` is given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) sub_issues: Option<SubIssues>,
+    /// Whether the issue is pinned to its repository; only fetched when
+    /// `--pinned` is given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) pinned: Option<bool>,
+}
+
+impl Issue {
+    /// The names of the fields of serialized issues, which may be selected
+    /// with `--fields`
+    pub(crate) const FIELDS: &'static [&'static str] = &[
+        "host",
+        "repo",
+        "number",
+        "kind",
+        "database_id",
+        "title",
+        "author",
+        "labels",
+        "url",
+        "updated_at",
+        "has_linked_pr",
+        "parent",
+        "sub_issues",
+        "pinned",
+    ];
+
+    pub(crate) fn is_bot_authored(&self) -> bool {
+        self.author.as_ref().is_some_and(|a| a.is_bot)
+    }
+
+    /// Set whether the issue is pinned to its repository, given a map from
+    /// repository full names to the numbers of their pinned issues
+    pub(crate) fn mark_pinned(&mut self, pinned_issues: &HashMap<String, Vec<u64>>) {
+        self.pinned = Some(
+            pinned_issues
+                .get(&self.repo)
+                .is_some_and(|numbers| numbers.contains(&self.number)),
+        );
+    }
+
+    /// Check & canonicalize the issue's URL against the web base URL of the
+    /// host it was fetched from
+    pub(crate) fn canonicalize_url(&mut self, base_url: &str) -> Result<(), IssueUrlError> {
+        self.url = gqlient::canonicalize_issue_url(&self.url, base_url, &self.repo, self.number)?;
+        Ok(())
+    }
+
+    /// Replace the issue's host, repository, title, author, labels, URL, and
+    /// parent issue's repository with hashes
+    pub(crate) fn anonymize(&mut self, anon: &Anonymizer) {
+        if let Some(ref mut host) = self.host {
+            *host = anon.host(host);
+        }
+        self.repo = anon.repo(&self.repo);
+        self.title = anon.title(&self.title);
+        if let Some(ref mut author) = self.author {
+            author.login = anon.author(&author.login);
+        }
+        for label in self.labels.iter_mut().flatten() {
+            *label = anon.label(label);
+        }
+        self.url = Anonymizer::issue_url(&self.repo, self.number);
+        if let Some(ref mut parent) = self.parent {
+            parent.repo = anon.repo(&parent.repo);
+        }
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
 struct RawIssue {
     number: u64,
+    #[serde(default, rename = "__typename")]
+    kind: IssueKind,
+    #[serde(default, rename = "databaseId")]
+    database_id: Option<u64>,
     title: String,
+    author: Option<Author>,
+    #[serde(default, deserialize_with = "gqlient::optional_singleton_field")]
+    labels: Option<Vec<RawLabel>>,
     url: String,
+    #[serde(default, rename = "updatedAt")]
+    updated_at: Option<String>,
+    #[serde(
+        default,
+        rename = "closedByPullRequestsReferences",
+        deserialize_with = "gqlient::optional_singleton_field"
+    )]
+    linked_prs: Option<u64>,
+    #[serde(default)]
+    parent: Option<ParentIssue>,
+    #[serde(default, rename = "subIssuesSummary")]
+    sub_issues: Option<SubIssues>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+struct RawLabel {
+    name: String,
 }
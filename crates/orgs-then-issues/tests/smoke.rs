@@ -0,0 +1,58 @@
+use fixtures::{FakeGitHub, FakeOrg, FixtureServer};
+use serde_json::Value;
+use std::path::Path;
+use std::process::Command;
+
+fn read_json_lines(path: &Path) -> Vec<Value> {
+    std::fs::read_to_string(path)
+        .expect("failed to read JSON Lines file")
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("invalid JSON line"))
+        .collect()
+}
+
+#[test]
+fn fetch_fake_org() {
+    let github = FakeGitHub::new([FakeOrg::new("fake-org", 3, 150, 2)]);
+    let server = FixtureServer::start(move |payload| github.respond(payload));
+    let tmpdir = tempfile::tempdir().unwrap();
+    let outfile = tmpdir.path().join("issues.jsonl");
+    let report_file = tmpdir.path().join("report.jsonl");
+    let output = Command::new(env!("CARGO_BIN_EXE_orgs-then-issues"))
+        .env("GH_TOKEN", "not-a-real-token")
+        .arg("--api-url")
+        .arg(server.api_url())
+        .arg("--labels")
+        .arg("--report-file")
+        .arg(&report_file)
+        .arg("--outfile")
+        .arg(&outfile)
+        .arg("fake-org")
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let issues = read_json_lines(&outfile);
+    assert_eq!(issues.len(), 450);
+    assert!(issues.iter().all(|issue| issue["repo"]
+        .as_str()
+        .is_some_and(|repo| repo.starts_with("fake-org/"))
+        && issue["labels"] == serde_json::json!(["label-000", "label-001"])));
+
+    let reports = read_json_lines(&report_file);
+    assert_eq!(reports.len(), 1);
+    let report = &reports[0];
+    assert_eq!(report["program"], "orgs-then-issues");
+    assert_eq!(report["owners"], serde_json::json!(["fake-org"]));
+    assert_eq!(report["repositories"], 3);
+    assert_eq!(report["open_issues"], 450);
+    assert_eq!(report["parameters"]["labels"], true);
+
+    // One request to resolve the owner's kind, one for its repositories, and
+    // two pages of issues for the three repositories in one batch
+    assert_eq!(server.requests().len(), 4);
+}
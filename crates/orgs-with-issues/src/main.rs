@@ -1,152 +1,522 @@
 mod queries;
 mod types;
-use crate::queries::{GetIssues, GetOwnerRepos};
-use anyhow::Context;
-use clap::Parser;
-use gqlient::{Client, Ided, DEFAULT_BATCH_SIZE};
+use crate::queries::{GetIssues, GetOwnerRepos, IssueFields};
+use crate::types::Issue;
+use anyhow::{bail, Context};
+use clap::{CommandFactory, FromArgMatches, Parser};
+use cli_common::{
+    group_owners_by_host, load_hosts, new_run_uuid, resolve_owner_kinds, run_on_complete,
+    write_label_matrix, CommonArgs, FieldSelection, OutfileHeader, OutfileSummary, StaleIssues,
+    SummaryFormat, LOCKED_EXIT_CODE,
+};
+use gqlient::{
+    peak_rss, progress, Filter, Id, Ided, IsolatedResults, LabelMatrix, OwnerKind, OwnerOutcomes,
+    RateLimit, RepoOrder, RepoPrivacy, TransferStats,
+};
+use patharg::OutputArg;
 use serde::Serialize;
-use serde_jsonlines::{append_json_lines, WriteExt};
+use serde_jsonlines::WriteExt;
+use std::collections::{BTreeMap, HashMap};
 use std::io::Write;
 use std::num::NonZeroUsize;
 use std::path::PathBuf;
+use std::process::ExitCode;
 use std::time::{Duration, Instant, SystemTime};
 
 /// Measure time to fetch open GitHub issues via GraphQL
 #[derive(Clone, Debug, Eq, Parser, PartialEq)]
 struct Arguments {
-    /// Number of sub-queries to make per GraphQL request
-    #[arg(short = 'B', long)]
-    batch_size: Option<NonZeroUsize>,
+    /// Also fetch the numeric database ID (as used by the REST API) of each
+    /// issue
+    #[arg(long)]
+    database_ids: bool,
+
+    /// Omit issues authored by bots from the output
+    #[arg(long)]
+    exclude_bots: bool,
+
+    /// Only include the given comma-separated fields in the issue records
+    /// written to the `--outfile` dump, e.g., "repo,number,title"
+    #[arg(long, requires = "outfile", value_name = "FIELDS")]
+    fields: Option<FieldSelection>,
+
+    /// Only output issues matching the given filter expression
+    #[arg(long, value_name = "EXPR")]
+    filter: Option<Filter>,
+
+    /// Read a JSON file of additional GitHub API hosts that owners of the
+    /// form "host:owner" are fetched from
+    #[arg(long, value_name = "PATH")]
+    hosts: Option<PathBuf>,
+
+    /// Output each issue label as an object containing the label's node ID
+    /// and name rather than as just a name
+    ///
+    /// This implies `--labels`.
+    #[arg(long)]
+    label_ids: bool,
+
+    /// Write a matrix of how often each pair of labels appears together on
+    /// an open issue to the given file
+    ///
+    /// The matrix is written as CSV if the file name ends in ".csv" and as
+    /// JSON otherwise.
+    #[arg(long, value_name = "PATH")]
+    label_matrix: Option<OutputArg>,
+
+    /// Also fetch the names of each issue's labels
+    #[arg(long)]
+    labels: bool,
+
+    /// Also fetch whether each issue has a linked pull request that will
+    /// close it
+    #[arg(long)]
+    linked_prs: bool,
 
     /// Dump fetched issue information to the given file
+    ///
+    /// This option can be given multiple times to write the same issues to
+    /// multiple files.
     #[arg(short, long)]
-    outfile: Option<patharg::OutputArg>,
+    outfile: Vec<OutputArg>,
 
-    /// Number of items to request per page of results
-    #[arg(short = 'P', long, default_value = "100")]
-    page_size: NonZeroUsize,
+    /// Write a header record to the start of the `--outfile` dump giving the
+    /// program, version, commit, parameters, and owners of the run
+    #[arg(long, requires = "outfile")]
+    outfile_header: bool,
 
-    /// Append a run report to the given file
-    #[arg(short = 'R', long)]
-    report_file: Option<PathBuf>,
+    /// Append a summary record to the `--outfile` dump giving the numbers of
+    /// repositories & issues fetched and the number of issues expected
+    #[arg(long, requires = "outfile")]
+    outfile_summary: bool,
 
-    /// GitHub owners/organizations of repositories to fetch open issues for
-    #[arg(required = true)]
-    owners: Vec<String>,
+    /// Also fetch the issues pinned to each repository and mark them in the
+    /// output
+    #[arg(long)]
+    pinned: bool,
+
+    /// Count the open issues that have not been updated in the given amount
+    /// of time, per repository, and include the counts in the run report
+    ///
+    /// Accepts durations like "180d" or "6months".
+    #[arg(long, value_name = "DURATION", value_parser = humantime::parse_duration)]
+    stale_after: Option<Duration>,
+
+    /// Dump just the issues counted by `--stale-after` to the given file
+    #[arg(long, value_name = "PATH", requires = "stale_after")]
+    stale_outfile: Option<OutputArg>,
+
+    /// Also fetch each issue's parent issue and the numbers of its sub-issues
+    #[arg(long)]
+    sub_issues: bool,
+
+    #[command(flatten)]
+    common: CommonArgs,
 }
 
-fn main() -> anyhow::Result<()> {
-    let args = Arguments::parse();
-    let mut client = Client::new_with_local_token()?;
-    if let Some(bsz) = args.batch_size {
-        client.batch_size(bsz);
+fn main() -> anyhow::Result<ExitCode> {
+    let matches = Arguments::command().get_matches();
+    let mut args = Arguments::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    args.common.apply_profile(&matches)?;
+    args.common.check_summary_output(
+        args.outfile
+            .iter()
+            .chain(&args.label_matrix)
+            .chain(&args.stale_outfile),
+    )?;
+    gqlient::set_quiet(args.common.quiet);
+    let Some(_lock) = args.common.lock()? else {
+        return Ok(ExitCode::from(LOCKED_EXIT_CODE));
+    };
+    let owners = args.common.owners()?;
+    if let Some(ref fields) = args.fields {
+        fields.check(Issue::FIELDS)?;
     }
-    let start_rate_limit = client.get_rate_limit()?;
+    let hosts = match args.hosts {
+        Some(ref path) => load_hosts(path)?,
+        None => BTreeMap::new(),
+    };
+    let host_groups = group_owners_by_host(&owners, &hosts)?;
+    let anonymizer = args.common.anonymizer()?;
+    let dumps = args.common.phase_dumps()?;
+    let issue_fields = IssueFields {
+        database_ids: args.database_ids,
+        label_ids: args.label_ids,
+        labels: args.labels || args.label_ids || args.label_matrix.is_some(),
+        linked_prs: args.linked_prs,
+        sub_issues: args.sub_issues,
+        updated_at: args.stale_after.is_some(),
+    };
 
     let big_start = Instant::now();
     let timestamp = SystemTime::now();
+    let run_uuid = new_run_uuid()?;
+    let mut owner_kinds = BTreeMap::new();
+    let mut owner_outcomes = OwnerOutcomes::new(&owners);
+    let mut rate_limit_points = Some(0);
+    let mut points_by_phase = BTreeMap::new();
+    let mut transfer = TransferStats::default();
+    let mut partial_responses = 0;
+    let mut page_size_reductions = 0;
+    let mut query_hashes = BTreeMap::new();
     let mut repo_qty = 0;
     let mut repos_with_issues_qty: usize = 0;
+    let mut expected_issues: u64 = 0;
     let mut issues = Vec::new();
 
-    eprintln!("[·] Fetching repositories …");
-    let owner_queries = args
-        .owners
-        .clone()
-        .into_iter()
-        .map(|owner| (owner.clone(), GetOwnerRepos::new(owner, args.page_size)));
-    let repos_start = Instant::now();
-    let repos = client.batch_paginate(owner_queries)?;
-    let elapsed = repos_start.elapsed();
-
-    let mut issue_queries = Vec::new();
-    for Ided { id, data: repo } in repos.into_iter().flat_map(|pr| pr.items) {
-        repo_qty += 1;
-        if !repo.issues.is_empty() {
-            repos_with_issues_qty += 1;
-            issues.extend(repo.issues);
+    for group in &host_groups {
+        if let Some(ref host) = group.host {
+            progress!("Fetching from host {host} …");
         }
-        if repo.has_more_issues {
-            issue_queries.push((
-                id.clone(),
-                GetIssues::new(id, repo.issue_cursor, args.page_size),
-            ));
+        let client = args
+            .common
+            .client_for_host(group.host.as_deref().map(|host| (host, &hosts[host])))?;
+        let start_rate_limit = client.get_rate_limit()?;
+        let kinds = resolve_owner_kinds(&client, &group.owners)?;
+        owner_kinds.extend(
+            kinds
+                .into_iter()
+                .map(|(owner, kind)| (group.qualify(&owner), kind)),
+        );
+        let first_issue = issues.len();
+        let mut group_repo_qty = 0;
+        let mut group_repos_with_issues_qty: usize = 0;
+
+        progress!("Fetching repositories …");
+        let owner_queries = group.owners.iter().map(|owner| {
+            (
+                group.qualify(owner),
+                GetOwnerRepos::new(
+                    owner.clone(),
+                    args.common.page_size,
+                    args.pinned,
+                    issue_fields,
+                    args.common.repo_order,
+                    args.common.privacy,
+                ),
+            )
+        });
+        let phase = client.phase("fetch repositories");
+        let repos_start = Instant::now();
+        let IsolatedResults {
+            results: repos,
+            failures,
+        } = client.batch_paginate_isolated(owner_queries);
+        let elapsed = repos_start.elapsed();
+        drop(phase);
+        dumps.dump("fetch repositories", &repos)?;
+        for f in failures {
+            owner_outcomes.fail(&f.key, f.error);
         }
-    }
-    eprintln!(
-        "[·] Fetched {} repositories ({} with open issues; {} open issues in total) in {:?}",
-        repo_qty,
-        repos_with_issues_qty,
-        issues.len(),
-        elapsed
-    );
 
-    if !issue_queries.is_empty() {
-        eprintln!(
-            "[·] Fetching more issues for {} repositories …",
-            issue_queries.len()
-        );
-        let start = Instant::now();
-        let more_issues = client.batch_paginate(issue_queries)?;
-        let elapsed = start.elapsed();
-        let mut issue_qty = 0;
-        issues.extend(
-            more_issues
-                .into_iter()
-                .flat_map(|pr| pr.items)
-                .inspect(|_| issue_qty += 1),
+        let mut issue_queries = Vec::new();
+        // Maps repository IDs to host-qualified full names so that issue
+        // fetch failures can be attributed to owners
+        let mut repo_names = HashMap::new();
+        // Maps repository full names to the numbers of their pinned issues,
+        // for `--pinned`
+        let mut pinned_issues = HashMap::new();
+        for Ided { id, data: repo } in repos.into_iter().flat_map(|pr| pr.items) {
+            group_repo_qty += 1;
+            repo_names.insert(id.clone(), group.qualify(&repo.fullname));
+            if let Some(ref numbers) = repo.pinned_issues {
+                pinned_issues.insert(repo.fullname.clone(), numbers.clone());
+            }
+            expected_issues += repo.open_issues.unwrap_or_default();
+            if !repo.issues.is_empty() {
+                group_repos_with_issues_qty += 1;
+                issues.extend(repo.issues);
+            }
+            if repo.has_more_issues {
+                issue_queries.push((
+                    id.clone(),
+                    GetIssues::new(id, repo.issue_cursor, args.common.page_size, issue_fields),
+                ));
+            }
+        }
+        progress!(
+            "Fetched {} repositories ({} with open issues; {} open issues in total) in {:?}",
+            group_repo_qty,
+            group_repos_with_issues_qty,
+            issues.len() - first_issue,
+            elapsed
         );
-        eprintln!("[·] Fetched {issue_qty} more issues in {elapsed:?}");
+        repo_qty += group_repo_qty;
+        repos_with_issues_qty += group_repos_with_issues_qty;
+
+        if !issue_queries.is_empty() {
+            progress!(
+                "Fetching more issues for {} repositories …",
+                issue_queries.len()
+            );
+            let phase = client.phase("fetch more issues");
+            let start = Instant::now();
+            let IsolatedResults {
+                results: more_issues,
+                failures,
+            } = client.batch_paginate_isolated(issue_queries);
+            let elapsed = start.elapsed();
+            drop(phase);
+            dumps.dump("fetch more issues", &more_issues)?;
+            for f in failures {
+                record_issue_failure(&mut owner_outcomes, &repo_names, &f.key, f.error);
+            }
+            let mut issue_qty = 0;
+            issues.extend(
+                more_issues
+                    .into_iter()
+                    .flat_map(|pr| pr.items)
+                    .inspect(|_| issue_qty += 1),
+            );
+            progress!("Fetched {issue_qty} more issues in {elapsed:?}");
+        }
+
+        if args.pinned {
+            for issue in &mut issues[first_issue..] {
+                issue.mark_pinned(&pinned_issues);
+            }
+        }
+        let web_url = client.web_url();
+        for issue in &mut issues[first_issue..] {
+            issue.host.clone_from(&group.host);
+            if let Err(e) = issue.canonicalize_url(&web_url) {
+                eprintln!("[!] {e}");
+            }
+        }
+
+        let end_rate_limit = match client.last_rate_limit_headers() {
+            Some(rl) => RateLimit::from(rl),
+            None => client.get_rate_limit()?,
+        };
+        rate_limit_points = rate_limit_points
+            .zip(end_rate_limit.used_since(start_rate_limit))
+            .map(|(total, used)| total + used);
+        for (phase, points) in client.points_by_phase() {
+            *points_by_phase.entry(phase).or_insert(0) += points;
+        }
+        transfer += client.transfer_stats();
+        partial_responses += client.partial_responses();
+        page_size_reductions += client.page_size_reductions();
+        for (hash, qty) in client.query_hashes() {
+            *query_hashes.entry(hash).or_insert(0) += qty;
+        }
+        client.export_traces()?;
     }
 
     let elapsed = big_start.elapsed();
-    eprintln!(
-        "[·] Total of {} issues fetched in {:?}",
-        issues.len(),
-        elapsed
-    );
+    progress!("Total of {} issues fetched in {:?}", issues.len(), elapsed);
+
+    let bot_issues_skipped = if args.exclude_bots {
+        let before = issues.len();
+        issues.retain(|issue| !issue.is_bot_authored());
+        let skipped = before - issues.len();
+        progress!("Skipped {skipped} bot-authored issues");
+        skipped
+    } else {
+        0
+    };
+
+    let issues_filtered_out = if let Some(ref filter) = args.filter {
+        let before = issues.len();
+        issues.retain(|issue| filter.matches(issue));
+        let filtered_out = before - issues.len();
+        progress!("Filtered out {filtered_out} issues not matching filter");
+        filtered_out
+    } else {
+        0
+    };
+
+    if let Some(ref anon) = anonymizer {
+        for issue in &mut issues {
+            issue.anonymize(anon);
+        }
+    }
+
+    let mut stale_issues = Vec::new();
+    let stale = if let Some(age) = args.stale_after {
+        let mut stale = StaleIssues::new(age, timestamp);
+        for issue in &issues {
+            if stale.check(&issue.repo, issue.updated_at.as_deref()) {
+                stale_issues.push(issue);
+            }
+        }
+        stale.log();
+        Some(stale)
+    } else {
+        None
+    };
 
-    let end_rate_limit = client.get_rate_limit()?;
-    let rate_limit_points = end_rate_limit.used_since(start_rate_limit);
     if let Some(used) = rate_limit_points {
-        eprintln!("[·] Used {used} rate limit points");
+        progress!("Used {used} rate limit points");
     } else {
-        eprintln!("[·] Could not determine rate limit points used due to intervening reset");
-    }
-
-    if let Some(report_file) = args.report_file {
-        eprintln!("[·] Appending report to {} …", report_file.display());
-        let report = Report {
-            program: env!("CARGO_BIN_NAME"),
-            commit: option_env!("GIT_COMMIT"),
-            timestamp: humantime::format_rfc3339(timestamp).to_string(),
-            owners: args.owners,
-            parameters: Parameters {
-                batch_size: match args.batch_size {
-                    Some(bs) => bs.get(),
-                    None => DEFAULT_BATCH_SIZE,
-                },
-                page_size: args.page_size,
-            },
-            repositories: repo_qty,
-            open_issues: issues.len(),
-            repos_with_open_issues: repos_with_issues_qty,
-            elapsed,
-            rate_limit_points,
-        };
-        append_json_lines(report_file, std::iter::once(report))
-            .context("failed to write report")?;
+        progress!("Could not determine rate limit points used due to intervening reset");
+    }
+    if !points_by_phase.is_empty() {
+        let by_phase = points_by_phase
+            .iter()
+            .map(|(phase, points)| format!("{phase}: {points}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        progress!("Rate limit points by phase: {by_phase}");
+    }
+    progress!("{transfer}");
+    if partial_responses > 0 {
+        eprintln!(
+            "[!] {partial_responses} responses included errors for some sub-queries, which were retried"
+        );
+    }
+    if page_size_reductions > 0 {
+        progress!("Lowered the page sizes of sub-queries {page_size_reductions} times");
     }
 
-    if let Some(outfile) = args.outfile {
-        eprintln!("[·] Dumping to {outfile:#} …");
+    let retained_issues = issues.len();
+    let retained_labels: usize = issues
+        .iter()
+        .map(|issue| issue.labels.as_ref().map_or(0, Vec::len))
+        .sum();
+    let peak_rss_bytes = peak_rss();
+    if let Some(bytes) = peak_rss_bytes {
+        progress!("Peak memory usage: {} MiB", bytes / (1024 * 1024));
+    }
+    progress!("Retained {retained_issues} issues with {retained_labels} labels");
+    progress!("{owner_outcomes}");
+    for (owner, error) in owner_outcomes.failures() {
+        eprintln!("[!] {owner}: {error}");
+    }
+
+    let mut report = Report {
+        program: env!("CARGO_BIN_NAME"),
+        commit: option_env!("GIT_COMMIT"),
+        timestamp: humantime::format_rfc3339(timestamp).to_string(),
+        run_uuid,
+        owners,
+        owner_kinds,
+        parameters: Parameters {
+            batch_size: args.common.batch_size(),
+            page_size: args.common.page_size,
+            repo_order: args.common.repo_order,
+            privacy: args.common.privacy,
+            tls_backend: gqlient::TLS_BACKEND,
+            labels: issue_fields.labels,
+        },
+        repositories: repo_qty,
+        open_issues: issues.len(),
+        repos_with_open_issues: repos_with_issues_qty,
+        bot_issues_skipped,
+        issues_filtered_out,
+        elapsed,
+        rate_limit_points,
+        points_by_phase,
+        transfer,
+        partial_responses,
+        page_size_reductions,
+        query_hashes,
+        peak_rss_bytes,
+        retained_issues,
+        retained_labels,
+        stale,
+        owner_outcomes,
+    };
+
+    if let Some(ref anon) = anonymizer {
+        report.owners = report
+            .owners
+            .iter()
+            .map(|owner| anon.owner(owner))
+            .collect();
+        report.owner_kinds = anon.owner_kinds(&report.owner_kinds);
+        report.owner_outcomes = anon.owner_outcomes(&report.owner_outcomes);
+    }
+
+    args.common.save_report(&report)?;
+
+    for outfile in args.outfile {
+        progress!("Dumping to {outfile:#} …");
         let mut fp = outfile.create().context("failed to open file")?;
-        fp.write_json_lines(issues)
-            .context("failed to dump issues")?;
+        if args.outfile_header {
+            let header = OutfileHeader {
+                program: report.program,
+                version: env!("CARGO_PKG_VERSION"),
+                commit: report.commit,
+                timestamp: &report.timestamp,
+                run_uuid: &report.run_uuid,
+                parameters: &report.parameters,
+                owners: &report.owners,
+            };
+            fp.write_json_lines(std::iter::once(header))
+                .context("failed to write header record")?;
+        }
+        match args.fields {
+            Some(ref fields) => {
+                fp.write_json_lines(issues.iter().map(|issue| fields.select(issue)))
+            }
+            None => fp.write_json_lines(&issues),
+        }
+        .context("failed to dump issues")?;
+        if args.outfile_summary {
+            let summary = OutfileSummary {
+                run_uuid: &report.run_uuid,
+                repositories: report.repositories,
+                repos_with_open_issues: report.repos_with_open_issues,
+                expected_issues,
+                issues: issues.len(),
+                bot_issues_skipped: report.bot_issues_skipped,
+                issues_filtered_out: report.issues_filtered_out,
+                failed_owners: report.owner_outcomes.failed(),
+                elapsed: report.elapsed,
+            };
+            fp.write_json_lines(std::iter::once(summary))
+                .context("failed to write summary record")?;
+        }
         fp.flush().context("failed to flush filehandle")?;
     }
 
-    Ok(())
+    if let Some(outfile) = args.stale_outfile {
+        progress!("Dumping stale issues to {outfile:#} …");
+        let mut fp = outfile.create().context("failed to open file")?;
+        fp.write_json_lines(&stale_issues)
+            .context("failed to dump stale issues")?;
+        fp.flush().context("failed to flush filehandle")?;
+    }
+
+    if let Some(outfile) = args.label_matrix {
+        progress!("Writing label co-occurrence matrix to {outfile:#} …");
+        let mut matrix = LabelMatrix::new();
+        for issue in &issues {
+            matrix.add(issue.labels.as_deref().unwrap_or_default());
+        }
+        write_label_matrix(&matrix, &outfile)?;
+    }
+
+    if args.common.summary == SummaryFormat::Json {
+        let mut stdout = std::io::stdout().lock();
+        serde_json::to_writer(&mut stdout, &report).context("failed to write summary")?;
+        writeln!(stdout).context("failed to write summary")?;
+        stdout.flush().context("failed to flush stdout")?;
+    }
+
+    let mut hook_failed = false;
+    if let Some(ref command) = args.common.on_complete {
+        progress!("Running on-complete command …");
+        if let Err(e) = run_on_complete(command, &report) {
+            eprintln!("[!] {e:#}");
+            hook_failed = true;
+        }
+    }
+
+    if report.owner_outcomes.failed() > 0 {
+        bail!(
+            "failed to fetch data for {} of {} owners",
+            report.owner_outcomes.failed(),
+            report.owners.len()
+        );
+    }
+
+    Ok(if hook_failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    })
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Serialize)]
@@ -154,17 +524,55 @@ struct Report {
     program: &'static str,
     commit: Option<&'static str>,
     timestamp: String,
+    run_uuid: String,
     owners: Vec<String>,
+    owner_kinds: BTreeMap<String, OwnerKind>,
     parameters: Parameters,
     repositories: usize,
     open_issues: usize,
     repos_with_open_issues: usize,
+    bot_issues_skipped: usize,
+    issues_filtered_out: usize,
+    #[serde(serialize_with = "gqlient::serialize_duration")]
     elapsed: Duration,
     rate_limit_points: Option<u32>,
+    points_by_phase: BTreeMap<String, u64>,
+    transfer: TransferStats,
+    partial_responses: usize,
+    page_size_reductions: usize,
+    query_hashes: BTreeMap<String, u64>,
+    peak_rss_bytes: Option<u64>,
+    retained_issues: usize,
+    retained_labels: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stale: Option<StaleIssues>,
+    owner_outcomes: OwnerOutcomes,
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize)]
 struct Parameters {
     batch_size: usize,
     page_size: NonZeroUsize,
+    repo_order: RepoOrder,
+    privacy: RepoPrivacy,
+    tls_backend: &'static str,
+    labels: bool,
+}
+
+/// Record the failure to fetch the issues for the repository with the given
+/// ID as a failure of the repository's owner
+fn record_issue_failure(
+    outcomes: &mut OwnerOutcomes,
+    repo_names: &HashMap<Id, String>,
+    repo_id: &Id,
+    error: anyhow::Error,
+) {
+    let fullname = repo_names
+        .get(repo_id)
+        .map_or("<unknown repository>", String::as_str);
+    let owner = fullname.split('/').next().unwrap_or(fullname);
+    outcomes.fail(
+        owner,
+        format!("failed to fetch issues for {fullname}: {error:#}"),
+    );
 }
@@ -0,0 +1,45 @@
+use gqlient::Variable;
+
+/// The optional fields fetched for each issue.  Each field is fetched (via
+/// `@include`) only if the `Boolean!` variable of the same name is true.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub(crate) struct IssueFields {
+    /// Fetch the issue's `databaseId` (`--database-ids`)
+    pub(crate) database_ids: bool,
+    /// Fetch the IDs of the issue's labels along with their names
+    /// (`--label-ids`); only has an effect if `labels` is also true
+    pub(crate) label_ids: bool,
+    /// Fetch the issue's labels (`--labels`, `--label-ids`, or
+    /// `--label-matrix`)
+    pub(crate) labels: bool,
+    /// Fetch whether the issue has a linked pull request (`--linked-prs`)
+    pub(crate) linked_prs: bool,
+    /// Fetch the issue's parent & sub-issue counts (`--sub-issues`)
+    pub(crate) sub_issues: bool,
+    /// Fetch when the issue was last updated (`--stale-after`)
+    pub(crate) updated_at: bool,
+}
+
+impl IssueFields {
+    /// Return the query variables for the fields
+    pub(crate) fn variables(&self) -> impl Iterator<Item = (String, Variable)> {
+        [
+            ("database_ids", self.database_ids),
+            ("label_ids", self.label_ids),
+            ("labels", self.labels),
+            ("linked_prs", self.linked_prs),
+            ("sub_issues", self.sub_issues),
+            ("updated_at", self.updated_at),
+        ]
+        .into_iter()
+        .map(|(name, value)| {
+            (
+                String::from(name),
+                Variable {
+                    gql_type: String::from("Boolean!"),
+                    value: value.into(),
+                },
+            )
+        })
+    }
+}
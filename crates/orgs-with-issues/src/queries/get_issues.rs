@@ -1,3 +1,4 @@
+use super::IssueFields;
 use crate::types::{Issue, RepoWithIssues};
 use gqlient::{Cursor, Id, Page, Paginator, Query, Variable};
 use indoc::indoc;
@@ -9,14 +10,25 @@ pub(crate) struct GetIssues {
     repo_id: Id,
     cursor: Option<Cursor>,
     page_size: NonZeroUsize,
+    fields: IssueFields,
+    /// Whether the page size has been lowered from the one given on the
+    /// command line and so must be passed in a variable of its own
+    own_page_size: bool,
 }
 
 impl GetIssues {
-    pub(crate) fn new(repo_id: Id, cursor: Option<Cursor>, page_size: NonZeroUsize) -> GetIssues {
+    pub(crate) fn new(
+        repo_id: Id,
+        cursor: Option<Cursor>,
+        page_size: NonZeroUsize,
+        fields: IssueFields,
+    ) -> GetIssues {
         GetIssues {
             repo_id,
             cursor,
             page_size,
+            fields,
+            own_page_size: false,
         }
     }
 }
@@ -26,14 +38,26 @@ impl Paginator for GetIssues {
     type Query = GetIssuesQuery;
 
     fn for_cursor(&self, cursor: Option<&Cursor>) -> GetIssuesQuery {
-        GetIssuesQuery::new(
-            self.repo_id.clone(),
-            match cursor {
-                Some(c) => Some(c.clone()),
-                None => self.cursor.clone(),
-            },
-            self.page_size,
-        )
+        GetIssuesQuery {
+            own_page_size: self.own_page_size,
+            ..GetIssuesQuery::new(
+                self.repo_id.clone(),
+                match cursor {
+                    Some(c) => Some(c.clone()),
+                    None => self.cursor.clone(),
+                },
+                self.page_size,
+                self.fields,
+            )
+        }
+    }
+
+    /// Halve the page size, down to a minimum of one issue per page
+    fn reduce_page_size(&mut self) -> Option<NonZeroUsize> {
+        let page_size = NonZeroUsize::new(self.page_size.get() / 2)?;
+        self.page_size = page_size;
+        self.own_page_size = true;
+        Some(page_size)
     }
 }
 
@@ -42,15 +66,24 @@ pub(crate) struct GetIssuesQuery {
     repo_id: Id,
     cursor: Option<Cursor>,
     page_size: NonZeroUsize,
+    fields: IssueFields,
+    own_page_size: bool,
     prefix: Option<String>,
 }
 
 impl GetIssuesQuery {
-    fn new(repo_id: Id, cursor: Option<Cursor>, page_size: NonZeroUsize) -> GetIssuesQuery {
+    fn new(
+        repo_id: Id,
+        cursor: Option<Cursor>,
+        page_size: NonZeroUsize,
+        fields: IssueFields,
+    ) -> GetIssuesQuery {
         GetIssuesQuery {
             repo_id,
             cursor,
             page_size,
+            fields,
+            own_page_size: false,
             prefix: None,
         }
     }
@@ -62,10 +95,20 @@ impl GetIssuesQuery {
         }
     }
 
+    /// Queries without a cursor all share a single null `$cursor` variable
     fn cursor_varname(&self) -> String {
+        match (&self.prefix, &self.cursor) {
+            (Some(prefix), Some(_)) => format!("{prefix}_cursor"),
+            _ => String::from("cursor"),
+        }
+    }
+
+    /// Queries with the default page size all share a single `$page_size`
+    /// variable
+    fn page_size_varname(&self) -> String {
         match self.prefix {
-            Some(ref prefix) => format!("{prefix}_cursor"),
-            None => String::from("cursor"),
+            Some(ref prefix) if self.own_page_size => format!("{prefix}_page_size"),
+            _ => String::from("page_size"),
         }
     }
 }
@@ -86,15 +129,40 @@ impl Query for GetIssuesQuery {
                 ... on Repository {{
                     nameWithOwner
                     issues(
-                        first: {page_size},
+                        first: ${page_size_varname},
                         after: ${cursor_varname},
                         orderBy: {{field: CREATED_AT, direction: ASC}},
                         states: [OPEN],
                     ) {{
                         nodes {{
                             number
+                            databaseId @include(if: $database_ids)
                             title
+                            author {{
+                                login
+                                __typename
+                            }}
                             url
+                            updatedAt @include(if: $updated_at)
+                            closedByPullRequestsReferences(first: 1) @include(if: $linked_prs) {{
+                                totalCount
+                            }}
+                            parent @include(if: $sub_issues) {{
+                                number
+                                repository {{
+                                    nameWithOwner
+                                }}
+                            }}
+                            subIssuesSummary @include(if: $sub_issues) {{
+                                total
+                                completed
+                            }}
+                            labels(first: 100) @include(if: $labels) {{
+                                nodes {{
+                                    id @include(if: $label_ids)
+                                    name
+                                }}
+                            }}
                         }}
                         pageInfo {{
                             endCursor
@@ -106,12 +174,12 @@ impl Query for GetIssuesQuery {
         "},
             repo_id_varname = self.repo_id_varname(),
             cursor_varname = self.cursor_varname(),
-            page_size = self.page_size,
+            page_size_varname = self.page_size_varname(),
         )
     }
 
-    fn variables(&self) -> [(String, Variable); 2] {
-        [
+    fn variables(&self) -> Vec<(String, Variable)> {
+        let mut variables = vec![
             (
                 self.repo_id_varname(),
                 Variable {
@@ -126,11 +194,20 @@ impl Query for GetIssuesQuery {
                     value: self.cursor.clone().into(),
                 },
             ),
-        ]
+            (
+                self.page_size_varname(),
+                Variable {
+                    gql_type: String::from("Int!"),
+                    value: self.page_size.get().into(),
+                },
+            ),
+        ];
+        variables.extend(self.fields.variables());
+        variables
     }
 
     fn parse_response(&self, value: serde_json::Value) -> Result<Self::Output, serde_json::Error> {
-        let raw = serde_json::from_value::<RepoWithIssues>(value)?;
+        let raw = gqlient::from_value::<RepoWithIssues>(value)?;
         Ok(Page {
             items: raw.issues,
             end_cursor: raw.issue_cursor,
@@ -1,5 +1,6 @@
+use super::IssueFields;
 use crate::types::RepoWithIssues;
-use gqlient::{Cursor, Ided, Page, Paginator, Query, Singleton, Variable};
+use gqlient::{Cursor, Ided, Page, Paginator, Query, RepoOrder, RepoPrivacy, Singleton, Variable};
 use indoc::indoc;
 use std::fmt::{self, Write};
 use std::num::NonZeroUsize;
@@ -8,11 +9,29 @@ use std::num::NonZeroUsize;
 pub(crate) struct GetOwnerRepos {
     owner: String,
     page_size: NonZeroUsize,
+    pinned: bool,
+    fields: IssueFields,
+    order: RepoOrder,
+    privacy: RepoPrivacy,
 }
 
 impl GetOwnerRepos {
-    pub(crate) fn new(owner: String, page_size: NonZeroUsize) -> GetOwnerRepos {
-        GetOwnerRepos { owner, page_size }
+    pub(crate) fn new(
+        owner: String,
+        page_size: NonZeroUsize,
+        pinned: bool,
+        fields: IssueFields,
+        order: RepoOrder,
+        privacy: RepoPrivacy,
+    ) -> GetOwnerRepos {
+        GetOwnerRepos {
+            owner,
+            page_size,
+            pinned,
+            fields,
+            order,
+            privacy,
+        }
     }
 }
 
@@ -21,7 +40,15 @@ impl Paginator for GetOwnerRepos {
     type Query = GetOwnerReposQuery;
 
     fn for_cursor(&self, cursor: Option<&Cursor>) -> GetOwnerReposQuery {
-        GetOwnerReposQuery::new(self.owner.clone(), cursor.cloned(), self.page_size)
+        GetOwnerReposQuery::new(
+            self.owner.clone(),
+            cursor.cloned(),
+            self.page_size,
+            self.pinned,
+            self.fields,
+            self.order,
+            self.privacy,
+        )
     }
 }
 
@@ -30,15 +57,31 @@ pub(crate) struct GetOwnerReposQuery {
     owner: String,
     cursor: Option<Cursor>,
     page_size: NonZeroUsize,
+    pinned: bool,
+    fields: IssueFields,
+    order: RepoOrder,
+    privacy: RepoPrivacy,
     prefix: Option<String>,
 }
 
 impl GetOwnerReposQuery {
-    fn new(owner: String, cursor: Option<Cursor>, page_size: NonZeroUsize) -> GetOwnerReposQuery {
+    fn new(
+        owner: String,
+        cursor: Option<Cursor>,
+        page_size: NonZeroUsize,
+        pinned: bool,
+        fields: IssueFields,
+        order: RepoOrder,
+        privacy: RepoPrivacy,
+    ) -> GetOwnerReposQuery {
         GetOwnerReposQuery {
             owner,
             cursor,
             page_size,
+            pinned,
+            fields,
+            order,
+            privacy,
             prefix: None,
         }
     }
@@ -50,10 +93,11 @@ impl GetOwnerReposQuery {
         }
     }
 
+    /// Queries without a cursor all share a single null `$cursor` variable
     fn cursor_varname(&self) -> String {
-        match self.prefix {
-            Some(ref prefix) => format!("{prefix}_cursor"),
-            None => String::from("cursor"),
+        match (&self.prefix, &self.cursor) {
+            (Some(prefix), Some(_)) => format!("{prefix}_cursor"),
+            _ => String::from("cursor"),
         }
     }
 }
@@ -72,26 +116,59 @@ impl Query for GetOwnerReposQuery {
             indoc! {"
             repositoryOwner(login: ${owner_varname}) {{
                 repositories(
-                    orderBy: {{field: NAME, direction: ASC}},
+                    orderBy: $order,
                     ownerAffiliations: [OWNER],
                     isArchived: false,
                     isFork: false,
-                    privacy: PUBLIC,
-                    first: {page_size},
+                    privacy: $privacy,
+                    first: $page_size,
                     after: ${cursor_varname},
                 ) {{
                     nodes {{
                         id
                         nameWithOwner
+                        pinnedIssues(first: 3) @include(if: $pinned) {{
+                            nodes {{
+                                issue {{
+                                    number
+                                }}
+                            }}
+                        }}
                         issues(
-                            first: {page_size},
+                            first: $page_size,
                             orderBy: {{field: CREATED_AT, direction: ASC}},
                             states: [OPEN],
                         ) {{
+                            totalCount
                             nodes {{
                                 number
+                                databaseId @include(if: $database_ids)
                                 title
+                                author {{
+                                    login
+                                    __typename
+                                }}
                                 url
+                                updatedAt @include(if: $updated_at)
+                                closedByPullRequestsReferences(first: 1) @include(if: $linked_prs) {{
+                                    totalCount
+                                }}
+                                parent @include(if: $sub_issues) {{
+                                    number
+                                    repository {{
+                                        nameWithOwner
+                                    }}
+                                }}
+                                subIssuesSummary @include(if: $sub_issues) {{
+                                    total
+                                    completed
+                                }}
+                                labels(first: 100) @include(if: $labels) {{
+                                    nodes {{
+                                        id @include(if: $label_ids)
+                                        name
+                                    }}
+                                }}
                             }}
                             pageInfo {{
                                 endCursor
@@ -108,12 +185,11 @@ impl Query for GetOwnerReposQuery {
         "},
             owner_varname = self.owner_varname(),
             cursor_varname = self.cursor_varname(),
-            page_size = self.page_size,
         )
     }
 
-    fn variables(&self) -> [(String, Variable); 2] {
-        [
+    fn variables(&self) -> Vec<(String, Variable)> {
+        let mut variables = vec![
             (
                 self.owner_varname(),
                 Variable {
@@ -128,10 +204,28 @@ impl Query for GetOwnerReposQuery {
                     value: self.cursor.clone().into(),
                 },
             ),
-        ]
+            (
+                String::from("page_size"),
+                Variable {
+                    gql_type: String::from("Int!"),
+                    value: self.page_size.get().into(),
+                },
+            ),
+            (
+                String::from("pinned"),
+                Variable {
+                    gql_type: String::from("Boolean!"),
+                    value: self.pinned.into(),
+                },
+            ),
+            (String::from("order"), self.order.as_variable()),
+            (String::from("privacy"), self.privacy.as_variable()),
+        ];
+        variables.extend(self.fields.variables());
+        variables
     }
 
     fn parse_response(&self, value: serde_json::Value) -> Result<Self::Output, serde_json::Error> {
-        serde_json::from_value::<Singleton<Self::Output>>(value).map(|r| r.0)
+        gqlient::from_value::<Singleton<Self::Output>>(value).map(|r| r.0)
     }
 }
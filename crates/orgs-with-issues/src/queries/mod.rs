@@ -1,4 +1,156 @@
+mod fields;
 mod get_issues;
 mod get_owner_repos;
+pub(crate) use self::fields::IssueFields;
 pub(crate) use self::get_issues::GetIssues;
 pub(crate) use self::get_owner_repos::GetOwnerRepos;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fixtures::FakeOrg;
+    use gqlient::{compose_request, Cursor, Id, Paginator, Query, RepoOrder, RepoPrivacy};
+    use insta::assert_snapshot;
+    use std::num::NonZeroUsize;
+
+    fn render<Q: Query>(queries: Vec<Q>) -> String {
+        let (doc, variables) = compose_request(queries).unwrap();
+        format!(
+            "{doc}\n{}",
+            serde_json::to_string_pretty(&variables).unwrap()
+        )
+    }
+
+    fn cursor(s: &str) -> Cursor {
+        serde_json::from_value(s.into()).unwrap()
+    }
+
+    fn id(s: &str) -> Id {
+        serde_json::from_value(s.into()).unwrap()
+    }
+
+    fn all_issue_fields() -> IssueFields {
+        IssueFields {
+            database_ids: true,
+            label_ids: true,
+            labels: true,
+            linked_prs: true,
+            sub_issues: true,
+            updated_at: true,
+        }
+    }
+
+    #[test]
+    fn get_owner_repos_first_page() {
+        let paginator = GetOwnerRepos::new(
+            String::from("jwodder"),
+            NonZeroUsize::new(100).unwrap(),
+            false,
+            IssueFields::default(),
+            RepoOrder::Name,
+            RepoPrivacy::Public,
+        );
+        assert_snapshot!(render(vec![paginator.for_cursor(None)]));
+    }
+
+    #[test]
+    fn get_owner_repos_batch() {
+        let page_size = NonZeroUsize::new(25).unwrap();
+        let all_fields = all_issue_fields();
+        let first = GetOwnerRepos::new(
+            String::from("jwodder"),
+            page_size,
+            true,
+            all_fields,
+            RepoOrder::Created,
+            RepoPrivacy::All,
+        );
+        let second = GetOwnerRepos::new(
+            String::from("wheelodex"),
+            page_size,
+            true,
+            all_fields,
+            RepoOrder::Created,
+            RepoPrivacy::All,
+        );
+        assert_snapshot!(render(vec![
+            first.for_cursor(None),
+            second.for_cursor(Some(&cursor("Y3Vyc29yOnYyOpHOABCDEF"))),
+        ]));
+    }
+
+    #[test]
+    fn get_issues_continuation() {
+        // This program only uses GetIssues to fetch pages after the first.
+        let paginator = GetIssues::new(
+            id("R_kgDOABCDEF"),
+            Some(cursor("Y3Vyc29yOnYyOpHOABCDEF")),
+            NonZeroUsize::new(100).unwrap(),
+            IssueFields::default(),
+        );
+        assert_snapshot!(render(vec![paginator.for_cursor(None)]));
+    }
+
+    #[test]
+    fn get_issues_batch() {
+        let page_size = NonZeroUsize::new(25).unwrap();
+        let all_fields = all_issue_fields();
+        let first = GetIssues::new(
+            id("R_kgDOABCDEF"),
+            Some(cursor("Y3Vyc29yOnYyOpHOABCDEF")),
+            page_size,
+            all_fields,
+        );
+        let second = GetIssues::new(
+            id("R_kgDOGHIJKL"),
+            Some(cursor("Y3Vyc29yOnYyOpHOGHIJKL")),
+            page_size,
+            all_fields,
+        );
+        assert_snapshot!(render(vec![
+            first.for_cursor(None),
+            second.for_cursor(Some(&cursor("Y3Vyc29yOnYyOpHOMNOPQR"))),
+        ]));
+    }
+
+    #[test]
+    fn get_issues_fake_repo() {
+        let org = FakeOrg::new("fake-org", 1, 250, 3);
+        let repo = &org.repos()[0];
+        let paginator = GetIssues::new(
+            id(&repo.id()),
+            None,
+            NonZeroUsize::new(100).unwrap(),
+            IssueFields {
+                labels: true,
+                ..IssueFields::default()
+            },
+        );
+        let mut after: Option<Cursor> = None;
+        let mut issues = Vec::new();
+        let mut pages = 0;
+        loop {
+            let after_str = after.as_ref().map(ToString::to_string);
+            let data = repo.with_issues_page(100, after_str.as_deref());
+            let page = paginator
+                .for_cursor(after.as_ref())
+                .parse_response(data)
+                .unwrap();
+            issues.extend(page.items);
+            pages += 1;
+            if !page.has_next_page {
+                break;
+            }
+            after = page.end_cursor;
+        }
+        assert_eq!(pages, 3);
+        assert_eq!(issues.len(), org.total_issues());
+        assert!(issues
+            .iter()
+            .all(|issue| issue.repo == repo.name_with_owner()
+                && issue
+                    .labels
+                    .as_ref()
+                    .is_some_and(|labels| labels.len() == 3)));
+    }
+}
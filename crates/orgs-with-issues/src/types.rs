@@ -1,9 +1,18 @@
-use gqlient::{Cursor, Page};
+use gqlient::{Anonymizer, Author, Cursor, Id, IssueUrlError, Page, ParentIssue, SubIssues};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 #[serde(from = "RawRepoDetails")]
 pub(crate) struct RepoWithIssues {
+    pub(crate) fullname: String,
+    /// The total number of open issues in the repository; only fetched
+    /// along with the repository itself, not with later pages of its issues
+    pub(crate) open_issues: Option<u64>,
+    /// The numbers of the issues pinned to the repository; only fetched
+    /// along with the repository itself when `--pinned` is given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) pinned_issues: Option<Vec<u64>>,
     pub(crate) issues: Vec<Issue>,
     pub(crate) issue_cursor: Option<Cursor>,
     pub(crate) has_more_issues: bool,
@@ -13,43 +22,229 @@ pub(crate) struct RepoWithIssues {
 #[serde(rename_all = "camelCase")]
 struct RawRepoDetails {
     name_with_owner: String,
-    issues: Page<RawIssue>,
+    #[serde(default, deserialize_with = "gqlient::optional_singleton_field")]
+    pinned_issues: Option<Vec<RawPinnedIssue>>,
+    issues: RawIssueConnection,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+struct RawPinnedIssue {
+    #[serde(deserialize_with = "gqlient::singleton_field")]
+    issue: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+struct RawIssueConnection {
+    #[serde(default, rename = "totalCount")]
+    total_count: Option<u64>,
+    #[serde(flatten)]
+    page: Page<RawIssue>,
 }
 
 impl From<RawRepoDetails> for RepoWithIssues {
     fn from(value: RawRepoDetails) -> RepoWithIssues {
         RepoWithIssues {
+            fullname: value.name_with_owner.clone(),
+            open_issues: value.issues.total_count,
+            pinned_issues: value
+                .pinned_issues
+                .map(|pinned| pinned.into_iter().map(|p| p.issue).collect()),
             issues: value
                 .issues
+                .page
                 .items
                 .into_iter()
                 .map(|ri| Issue {
+                    host: None,
                     repo: value.name_with_owner.clone(),
                     number: ri.number,
+                    database_id: ri.database_id,
                     title: ri.title,
+                    author: ri.author,
+                    labels: ri
+                        .labels
+                        .map(|labels| labels.into_iter().map(Label::from).collect()),
                     url: ri.url,
+                    updated_at: ri.updated_at,
+                    has_linked_pr: ri.linked_prs.map(|qty| qty > 0),
+                    parent: ri.parent,
+                    sub_issues: ri.sub_issues,
+                    pinned: None,
                 })
                 .collect(),
-            issue_cursor: value.issues.end_cursor,
-            has_more_issues: value.issues.has_next_page,
+            issue_cursor: value.issues.page.end_cursor,
+            has_more_issues: value.issues.page.has_next_page,
         }
     }
 }
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub(crate) struct Issue {
+    /// The name of the `--hosts` host that the issue was fetched from, if
+    /// not github.com
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) host: Option<String>,
     pub(crate) repo: String,
     pub(crate) number: u64,
+    /// The issue's numeric ID as used by the REST API; only fetched when
+    /// `--database-ids` is given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) database_id: Option<u64>,
     pub(crate) title: String,
-    //pub(crate) author: String,
+    pub(crate) author: Option<Author>,
+    /// The issue's labels; only fetched when `--labels`, `--label-ids`, or
+    /// `--label-matrix` is given
     // Note: Reportedly, the max number of labels on an issue is 100
-    //pub(crate) labels: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) labels: Option<Vec<Label>>,
     pub(crate) url: String,
+    /// When the issue was last updated; only fetched when `--stale-after` is
+    /// given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) updated_at: Option<String>,
+    /// Whether a pull request that will close the issue is linked to it;
+    /// only fetched when `--linked-prs` is given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) has_linked_pr: Option<bool>,
+    /// The issue's parent issue, if it is a sub-issue; only fetched when
+    /// `--sub-issues` is given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) parent: Option<ParentIssue>,
+    /// The numbers of the issue's sub-issues; only fetched when
+    /// `--sub-issues` is given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) sub_issues: Option<SubIssues>,
+    /// Whether the issue is pinned to its repository; only fetched when
+    /// `--pinned` is given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) pinned: Option<bool>,
+}
+
+impl Issue {
+    /// The names of the fields of serialized issues, which may be selected
+    /// with `--fields`
+    pub(crate) const FIELDS: &'static [&'static str] = &[
+        "host",
+        "repo",
+        "number",
+        "database_id",
+        "title",
+        "author",
+        "labels",
+        "url",
+        "updated_at",
+        "has_linked_pr",
+        "parent",
+        "sub_issues",
+        "pinned",
+    ];
+
+    pub(crate) fn is_bot_authored(&self) -> bool {
+        self.author.as_ref().is_some_and(|a| a.is_bot)
+    }
+
+    /// Set whether the issue is pinned to its repository, given a map from
+    /// repository full names to the numbers of their pinned issues
+    pub(crate) fn mark_pinned(&mut self, pinned_issues: &HashMap<String, Vec<u64>>) {
+        self.pinned = Some(
+            pinned_issues
+                .get(&self.repo)
+                .is_some_and(|numbers| numbers.contains(&self.number)),
+        );
+    }
+
+    /// Check & canonicalize the issue's URL against the web base URL of the
+    /// host it was fetched from
+    pub(crate) fn canonicalize_url(&mut self, base_url: &str) -> Result<(), IssueUrlError> {
+        self.url = gqlient::canonicalize_issue_url(&self.url, base_url, &self.repo, self.number)?;
+        Ok(())
+    }
+
+    /// Replace the issue's host, repository, title, author, labels, URL, and
+    /// parent issue's repository with hashes
+    pub(crate) fn anonymize(&mut self, anon: &Anonymizer) {
+        if let Some(ref mut host) = self.host {
+            *host = anon.host(host);
+        }
+        self.repo = anon.repo(&self.repo);
+        self.title = anon.title(&self.title);
+        if let Some(ref mut author) = self.author {
+            author.login = anon.author(&author.login);
+        }
+        for label in self.labels.iter_mut().flatten() {
+            match label {
+                Label::Name(name) | Label::WithId { name, .. } => *name = anon.label(name),
+            }
+        }
+        self.url = Anonymizer::issue_url(&self.repo, self.number);
+        if let Some(ref mut parent) = self.parent {
+            parent.repo = anon.repo(&parent.repo);
+        }
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
 struct RawIssue {
     number: u64,
+    #[serde(default, rename = "databaseId")]
+    database_id: Option<u64>,
     title: String,
+    author: Option<Author>,
+    #[serde(default, deserialize_with = "gqlient::optional_singleton_field")]
+    labels: Option<Vec<RawLabel>>,
     url: String,
+    #[serde(default, rename = "updatedAt")]
+    updated_at: Option<String>,
+    #[serde(
+        default,
+        rename = "closedByPullRequestsReferences",
+        deserialize_with = "gqlient::optional_singleton_field"
+    )]
+    linked_prs: Option<u64>,
+    #[serde(default)]
+    parent: Option<ParentIssue>,
+    #[serde(default, rename = "subIssuesSummary")]
+    sub_issues: Option<SubIssues>,
+}
+
+/// A label on an issue, output as a plain name unless `--label-ids` is
+/// given, in which case it is output as an object with `id` and `name` fields
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(untagged)]
+pub(crate) enum Label {
+    Name(String),
+    WithId { id: Id, name: String },
+}
+
+impl Label {
+    pub(crate) fn name(&self) -> &str {
+        match self {
+            Label::Name(name) | Label::WithId { name, .. } => name,
+        }
+    }
+}
+
+impl AsRef<str> for Label {
+    fn as_ref(&self) -> &str {
+        self.name()
+    }
+}
+
+impl From<RawLabel> for Label {
+    fn from(value: RawLabel) -> Label {
+        match value.id {
+            Some(id) => Label::WithId {
+                id,
+                name: value.name,
+            },
+            None => Label::Name(value.name),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+struct RawLabel {
+    #[serde(default)]
+    id: Option<Id>,
+    name: String,
 }
@@ -0,0 +1,198 @@
+mod queries;
+mod types;
+use crate::queries::{AddLabel, GetIssues};
+use crate::types::RepoLabel;
+use anyhow::{bail, Context};
+use clap::Parser;
+use gqlient::{
+    progress, BatchMutator, Client, Filter, JsonMap, MutationOutcome, RateLimit, Singleton,
+};
+use indoc::indoc;
+use std::num::NonZeroUsize;
+use std::time::Instant;
+
+/// Measure time to apply a label to open GitHub issues via batched GraphQL
+/// mutations
+#[derive(Clone, Debug, Eq, Parser, PartialEq)]
+struct Arguments {
+    /// Send API requests to the given base URL (e.g., that of a local test
+    /// server) instead of to api.github.com
+    ///
+    /// GraphQL requests are sent to the URL with "/graphql" appended.
+    #[arg(long, value_name = "URL")]
+    api_url: Option<String>,
+
+    /// Number of sub-queries or mutations to make per GraphQL request
+    #[arg(short = 'B', long)]
+    batch_size: Option<NonZeroUsize>,
+
+    /// Fetch issues and compose the mutation requests, but do not send them
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Only label issues matching the given filter expression
+    #[arg(long, value_name = "EXPR")]
+    filter: Option<Filter>,
+
+    /// Number of items to request per page of results
+    #[arg(short = 'P', long, default_value = "100")]
+    page_size: NonZeroUsize,
+
+    /// Do not log progress messages to stderr; warnings and errors are still
+    /// logged
+    #[arg(short, long, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Show a live status line while fetching, and show the composed
+    /// requests when `--dry-run` is given
+    #[arg(short, long)]
+    verbose: bool,
+
+    /// Name of the label to apply; it must already exist in each repository
+    label: String,
+
+    /// Repositories (in the form "OWNER/NAME") whose open issues should be
+    /// labelled
+    #[arg(required = true)]
+    repos: Vec<String>,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Arguments::parse();
+    gqlient::set_quiet(args.quiet);
+    let mut client = match args.api_url {
+        Some(ref url) => Client::new_with_local_token_at(url)?,
+        None => Client::new_with_local_token()?,
+    };
+    if let Some(bsz) = args.batch_size {
+        client.batch_size(bsz);
+    }
+    client.verbose(args.verbose);
+    let start_rate_limit = client.get_rate_limit()?;
+    let big_start = Instant::now();
+
+    progress!(
+        "Looking up label {:?} in {} repositories …",
+        args.label,
+        args.repos.len()
+    );
+    // Each paginator is keyed by the ID of the label to apply to the issues
+    // it fetches.
+    let mut issue_queries = Vec::new();
+    for fullname in &args.repos {
+        let Some((owner, name)) = fullname.split_once('/') else {
+            bail!("invalid repository {fullname:?}: expected OWNER/NAME");
+        };
+        let repo = lookup_label(&client, owner, name, &args.label)
+            .with_context(|| format!("failed to look up repository {fullname}"))?;
+        match repo.label {
+            Some(label_id) => {
+                issue_queries.push((label_id, GetIssues::new(repo.id, args.page_size)));
+            }
+            None => eprintln!(
+                "[!] Repository {fullname} has no label named {:?}; skipping",
+                args.label
+            ),
+        }
+    }
+
+    progress!("Fetching issues for {} repositories …", issue_queries.len());
+    let start = Instant::now();
+    let results = client.batch_paginate(issue_queries)?;
+    let elapsed = start.elapsed();
+    let mut issue_qty = 0;
+    let mut filtered_out = 0;
+    let mut already_labelled = 0;
+    let mut mutations = Vec::new();
+    for pr in results {
+        for issue in pr.items {
+            issue_qty += 1;
+            if args.filter.as_ref().is_some_and(|f| !f.matches(&issue)) {
+                filtered_out += 1;
+            } else if issue
+                .labels
+                .iter()
+                .any(|lbl| lbl.eq_ignore_ascii_case(&args.label))
+            {
+                already_labelled += 1;
+            } else {
+                mutations.push((issue.url, AddLabel::new(issue.id, pr.key.clone())));
+            }
+        }
+    }
+    progress!("Fetched {issue_qty} issues in {elapsed:?}");
+    if args.filter.is_some() {
+        progress!("Filtered out {filtered_out} issues not matching filter");
+    }
+    progress!("Skipped {already_labelled} issues that already have the label");
+
+    progress!("Labelling {} issues …", mutations.len());
+    let start = Instant::now();
+    let results = BatchMutator::new(&client, mutations)
+        .dry_run(args.dry_run)
+        .run();
+    let elapsed = start.elapsed();
+    let mut applied = 0;
+    let mut failed = 0;
+    for r in results {
+        match r.outcome {
+            MutationOutcome::Applied(()) => applied += 1,
+            MutationOutcome::Failed(e) => {
+                eprintln!("[!] Failed to label {}: {e:#}", r.key);
+                failed += 1;
+            }
+            MutationOutcome::DryRun => (),
+        }
+    }
+    if args.dry_run {
+        progress!("Dry run; no issues were labelled");
+    } else {
+        progress!("Labelled {applied} issues in {elapsed:?}");
+    }
+
+    let big_elapsed = big_start.elapsed();
+    progress!("Total time: {big_elapsed:?}");
+
+    let end_rate_limit = match client.last_rate_limit_headers() {
+        Some(rl) => RateLimit::from(rl),
+        None => client.get_rate_limit()?,
+    };
+    if let Some(used) = end_rate_limit.used_since(start_rate_limit) {
+        progress!("Used {used} rate limit points");
+    } else {
+        progress!("Could not determine rate limit points used due to intervening reset");
+    }
+
+    if failed > 0 {
+        bail!("failed to label {failed} issues");
+    }
+    Ok(())
+}
+
+/// Look up the ID of the repository `owner/name` and of its label named
+/// `label`
+fn lookup_label(
+    client: &Client,
+    owner: &str,
+    name: &str,
+    label: &str,
+) -> anyhow::Result<RepoLabel> {
+    let query = String::from(indoc! {"
+        query ($owner: String!, $name: String!, $label: String!) {
+            repository(owner: $owner, name: $name) {
+                id
+                label(name: $label) {
+                    id
+                }
+            }
+        }
+    "});
+    let mut variables = JsonMap::new();
+    variables.insert(String::from("owner"), owner.into());
+    variables.insert(String::from("name"), name.into());
+    variables.insert(String::from("label"), label.into());
+    let data = client.query(query, variables)?;
+    let repo = serde_json::from_value::<Singleton<RepoLabel>>(data.into())
+        .context("failed to deserialize repository label lookup")?;
+    Ok(repo.0)
+}
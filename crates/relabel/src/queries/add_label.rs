@@ -0,0 +1,75 @@
+use gqlient::{Id, Query, Variable};
+use indoc::indoc;
+use std::fmt::{self, Write};
+
+/// A mutation adding a label to an issue
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct AddLabel {
+    issue_id: Id,
+    label_id: Id,
+    prefix: Option<String>,
+}
+
+impl AddLabel {
+    pub(crate) fn new(issue_id: Id, label_id: Id) -> AddLabel {
+        AddLabel {
+            issue_id,
+            label_id,
+            prefix: None,
+        }
+    }
+
+    fn varname(&self, name: &str) -> String {
+        match self.prefix {
+            Some(ref prefix) => format!("{prefix}_{name}"),
+            None => String::from(name),
+        }
+    }
+}
+
+impl Query for AddLabel {
+    type Output = ();
+
+    fn with_variable_prefix(mut self, prefix: String) -> Self {
+        self.prefix = Some(prefix);
+        self
+    }
+
+    fn write_graphql<W: Write>(&self, mut s: W) -> fmt::Result {
+        writeln!(
+            s,
+            indoc! {"
+            addLabelsToLabelable(
+                input: {{labelableId: ${issue_id_varname}, labelIds: [${label_id_varname}]}}
+            ) {{
+                clientMutationId
+            }}
+        "},
+            issue_id_varname = self.varname("issue_id"),
+            label_id_varname = self.varname("label_id"),
+        )
+    }
+
+    fn variables(&self) -> [(String, Variable); 2] {
+        [
+            (
+                self.varname("issue_id"),
+                Variable {
+                    gql_type: String::from("ID!"),
+                    value: self.issue_id.clone().into(),
+                },
+            ),
+            (
+                self.varname("label_id"),
+                Variable {
+                    gql_type: String::from("ID!"),
+                    value: self.label_id.clone().into(),
+                },
+            ),
+        ]
+    }
+
+    fn parse_response(&self, _value: serde_json::Value) -> Result<(), serde_json::Error> {
+        Ok(())
+    }
+}
@@ -0,0 +1,145 @@
+use crate::types::{Issue, RepoWithIssues};
+use gqlient::{Cursor, Id, Page, Paginator, Query, Variable};
+use indoc::indoc;
+use std::fmt::{self, Write};
+use std::num::NonZeroUsize;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct GetIssues {
+    repo_id: Id,
+    page_size: NonZeroUsize,
+}
+
+impl GetIssues {
+    pub(crate) fn new(repo_id: Id, page_size: NonZeroUsize) -> GetIssues {
+        GetIssues { repo_id, page_size }
+    }
+}
+
+impl Paginator for GetIssues {
+    type Item = Issue;
+    type Query = GetIssuesQuery;
+
+    fn for_cursor(&self, cursor: Option<&Cursor>) -> GetIssuesQuery {
+        GetIssuesQuery::new(self.repo_id.clone(), cursor.cloned(), self.page_size)
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct GetIssuesQuery {
+    repo_id: Id,
+    cursor: Option<Cursor>,
+    page_size: NonZeroUsize,
+    prefix: Option<String>,
+}
+
+impl GetIssuesQuery {
+    fn new(repo_id: Id, cursor: Option<Cursor>, page_size: NonZeroUsize) -> GetIssuesQuery {
+        GetIssuesQuery {
+            repo_id,
+            cursor,
+            page_size,
+            prefix: None,
+        }
+    }
+
+    fn repo_id_varname(&self) -> String {
+        match self.prefix {
+            Some(ref prefix) => format!("{prefix}_repo_id"),
+            None => String::from("repo_id"),
+        }
+    }
+
+    /// Queries without a cursor all share a single null `$cursor` variable
+    fn cursor_varname(&self) -> String {
+        match (&self.prefix, &self.cursor) {
+            (Some(prefix), Some(_)) => format!("{prefix}_cursor"),
+            _ => String::from("cursor"),
+        }
+    }
+}
+
+impl Query for GetIssuesQuery {
+    type Output = Page<Issue>;
+
+    fn with_variable_prefix(mut self, prefix: String) -> Self {
+        self.prefix = Some(prefix);
+        self
+    }
+
+    fn write_graphql<W: Write>(&self, mut s: W) -> fmt::Result {
+        writeln!(
+            s,
+            indoc! {"
+            node(id: ${repo_id_varname}) {{
+                ... on Repository {{
+                    nameWithOwner
+                    issues(
+                        first: $page_size,
+                        after: ${cursor_varname},
+                        orderBy: {{field: CREATED_AT, direction: ASC}},
+                        states: [OPEN],
+                    ) {{
+                        nodes {{
+                            id
+                            number
+                            title
+                            author {{
+                                login
+                                __typename
+                            }}
+                            url
+                            labels(first: 100) {{
+                                nodes {{
+                                    name
+                                }}
+                            }}
+                        }}
+                        pageInfo {{
+                            endCursor
+                            hasNextPage
+                        }}
+                    }}
+                }}
+            }}
+        "},
+            repo_id_varname = self.repo_id_varname(),
+            cursor_varname = self.cursor_varname(),
+        )
+    }
+
+    fn variables(&self) -> [(String, Variable); 3] {
+        [
+            (
+                self.repo_id_varname(),
+                Variable {
+                    gql_type: String::from("ID!"),
+                    value: self.repo_id.clone().into(),
+                },
+            ),
+            (
+                self.cursor_varname(),
+                Variable {
+                    gql_type: String::from("String"),
+                    value: self.cursor.clone().into(),
+                },
+            ),
+            (
+                String::from("page_size"),
+                Variable {
+                    gql_type: String::from("Int!"),
+                    value: self.page_size.get().into(),
+                },
+            ),
+        ]
+    }
+
+    fn parse_response(&self, value: serde_json::Value) -> Result<Page<Issue>, serde_json::Error> {
+        let raw = gqlient::from_value::<RepoWithIssues>(value)?;
+        Ok(Page {
+            items: raw.issues,
+            end_cursor: raw.issue_cursor,
+            has_next_page: raw.has_more_issues,
+        })
+    }
+}
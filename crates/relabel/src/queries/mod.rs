@@ -0,0 +1,36 @@
+mod add_label;
+mod get_issues;
+pub(crate) use self::add_label::AddLabel;
+pub(crate) use self::get_issues::GetIssues;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gqlient::{compose_request, Cursor, Id, Paginator, Query};
+    use insta::assert_snapshot;
+    use std::num::NonZeroUsize;
+
+    fn render<Q: Query>(queries: Vec<Q>) -> String {
+        let (doc, variables) = compose_request(queries).unwrap();
+        format!(
+            "{doc}\n{}",
+            serde_json::to_string_pretty(&variables).unwrap()
+        )
+    }
+
+    fn id(s: &str) -> Id {
+        serde_json::from_value(s.into()).unwrap()
+    }
+
+    #[test]
+    fn get_issues_batch() {
+        let page_size = NonZeroUsize::new(50).unwrap();
+        let first = GetIssues::new(id("R_kgDOABCDEF"), page_size);
+        let second = GetIssues::new(id("R_kgDOGHIJKL"), page_size);
+        let cursor = serde_json::from_value::<Cursor>("Y3Vyc29yOnYyOpHOABCDEF".into()).unwrap();
+        assert_snapshot!(render(vec![
+            first.for_cursor(None),
+            second.for_cursor(Some(&cursor)),
+        ]));
+    }
+}
@@ -0,0 +1,79 @@
+use gqlient::{Author, Cursor, Id, Page};
+use serde::{Deserialize, Serialize};
+
+/// A repository and the ID of the label to apply to its issues, as looked up
+/// by name
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub(crate) struct RepoLabel {
+    pub(crate) id: Id,
+    #[serde(deserialize_with = "gqlient::optional_singleton_field")]
+    pub(crate) label: Option<Id>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(from = "RawRepoDetails")]
+pub(crate) struct RepoWithIssues {
+    pub(crate) issues: Vec<Issue>,
+    pub(crate) issue_cursor: Option<Cursor>,
+    pub(crate) has_more_issues: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct RawRepoDetails {
+    name_with_owner: String,
+    issues: Page<RawIssue>,
+}
+
+impl From<RawRepoDetails> for RepoWithIssues {
+    fn from(value: RawRepoDetails) -> RepoWithIssues {
+        RepoWithIssues {
+            issues: value
+                .issues
+                .items
+                .into_iter()
+                .map(|ri| Issue {
+                    id: ri.id,
+                    repo: value.name_with_owner.clone(),
+                    number: ri.number,
+                    title: ri.title,
+                    author: ri.author,
+                    labels: ri.labels.into_iter().map(|lbl| lbl.name).collect(),
+                    url: ri.url,
+                })
+                .collect(),
+            issue_cursor: value.issues.end_cursor,
+            has_more_issues: value.issues.has_next_page,
+        }
+    }
+}
+
+/// An open issue, serialized in the same form as the issues output by the
+/// other programs (plus the issue's node ID) so that the same filter
+/// expressions can be used
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub(crate) struct Issue {
+    pub(crate) id: Id,
+    pub(crate) repo: String,
+    pub(crate) number: u64,
+    pub(crate) title: String,
+    pub(crate) author: Option<Author>,
+    pub(crate) labels: Vec<String>,
+    pub(crate) url: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+struct RawIssue {
+    id: Id,
+    number: u64,
+    title: String,
+    author: Option<Author>,
+    #[serde(deserialize_with = "gqlient::singleton_field")]
+    labels: Vec<RawLabel>,
+    url: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+struct RawLabel {
+    name: String,
+}
@@ -0,0 +1,72 @@
+use fixtures::{FakeGitHub, FakeOrg, FixtureServer};
+use serde_json::Value;
+use std::process::Command;
+
+fn is_mutation(payload: &Value) -> bool {
+    payload["query"]
+        .as_str()
+        .is_some_and(|q| q.trim_start().starts_with("mutation"))
+}
+
+#[test]
+fn label_fake_repos() {
+    let github = FakeGitHub::new([FakeOrg::new("fake-org", 2, 30, 1)]);
+    let server = FixtureServer::start(move |payload| github.respond(payload));
+    let output = Command::new(env!("CARGO_BIN_EXE_relabel"))
+        .env("GH_TOKEN", "not-a-real-token")
+        .arg("--api-url")
+        .arg(server.api_url())
+        .arg("triage")
+        .arg("fake-org/repo-0000")
+        .arg("fake-org/repo-0001")
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Labelled 60 issues"), "{stderr}");
+
+    // Every issue lacks the label, so each one is labelled with the label ID
+    // looked up for its repository.
+    let mut labelled = Vec::new();
+    for payload in server.requests().iter().filter(|p| is_mutation(p)) {
+        let variables = payload["variables"].as_object().unwrap();
+        for (var, issue_id) in variables {
+            if let Some(prefix) = var.strip_suffix("_issue_id") {
+                let issue_id = issue_id.as_str().unwrap();
+                let label_id = &variables[&format!("{prefix}_label_id")];
+                // "I_fake-org_0000_1" → "LA_R_fake-org_0000_triage"
+                let repo = issue_id.rsplit_once('_').unwrap().0.replacen("I_", "R_", 1);
+                assert_eq!(label_id, &format!("LA_{repo}_triage"));
+                labelled.push(issue_id.to_owned());
+            }
+        }
+    }
+    labelled.sort_unstable();
+    labelled.dedup();
+    assert_eq!(labelled.len(), 60);
+}
+
+#[test]
+fn dry_run_makes_no_mutations() {
+    let github = FakeGitHub::new([FakeOrg::new("fake-org", 1, 5, 0)]);
+    let server = FixtureServer::start(move |payload| github.respond(payload));
+    let output = Command::new(env!("CARGO_BIN_EXE_relabel"))
+        .env("GH_TOKEN", "not-a-real-token")
+        .arg("--api-url")
+        .arg(server.api_url())
+        .arg("--dry-run")
+        .arg("triage")
+        .arg("fake-org/repo-0000")
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(!server.requests().iter().any(is_mutation));
+}
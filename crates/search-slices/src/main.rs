@@ -0,0 +1,377 @@
+mod queries;
+mod search;
+mod slices;
+mod types;
+use crate::queries::{CountSlice, GetOwnerRepos, SearchIssues};
+use crate::search::SearchQuery;
+use crate::slices::{Slice, MAX_SEARCH_RESULTS};
+use anyhow::{bail, Context};
+use clap::{CommandFactory, FromArgMatches, Parser};
+use cli_common::{
+    new_run_uuid, resolve_owner_kinds, run_on_complete, CommonArgs, SummaryFormat, LOCKED_EXIT_CODE,
+};
+use gqlient::{
+    peak_rss, progress, IsolatedResults, OwnerKind, OwnerOutcomes, RateLimit, RepoOrder,
+    RepoPrivacy, TransferStats,
+};
+use patharg::OutputArg;
+use serde::Serialize;
+use serde_jsonlines::WriteExt;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::num::NonZeroUsize;
+use std::process::ExitCode;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Fetch the full issue histories of GitHub repositories via searches sliced
+/// by creation date
+#[derive(Clone, Debug, Eq, Parser, PartialEq)]
+struct Arguments {
+    /// Also fetch the names of each issue's labels
+    #[arg(long)]
+    labels: bool,
+
+    /// Dump fetched issue information to the given file
+    ///
+    /// This option can be given multiple times to write the same issues to
+    /// multiple files.
+    #[arg(short, long)]
+    outfile: Vec<OutputArg>,
+
+    /// Only fetch issues matching the given GitHub search syntax
+    ///
+    /// The query's qualifiers are checked locally before anything is fetched,
+    /// and the query is added to the search for every slice.
+    #[arg(long, value_name = "QUERY")]
+    query: Option<SearchQuery>,
+
+    #[command(flatten)]
+    common: CommonArgs,
+}
+
+fn main() -> anyhow::Result<ExitCode> {
+    let matches = Arguments::command().get_matches();
+    let mut args = Arguments::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    args.common.apply_profile(&matches)?;
+    args.common.check_summary_output(&args.outfile)?;
+    gqlient::set_quiet(args.common.quiet);
+    let Some(_lock) = args.common.lock()? else {
+        return Ok(ExitCode::from(LOCKED_EXIT_CODE));
+    };
+    let owners = args.common.owners()?;
+    let anonymizer = args.common.anonymizer()?;
+    let dumps = args.common.phase_dumps()?;
+    let client = args.common.client()?;
+    let filter = args.query.clone().unwrap_or_default();
+
+    let big_start = Instant::now();
+    let timestamp = SystemTime::now();
+    let run_uuid = new_run_uuid()?;
+    let start_rate_limit = client.get_rate_limit()?;
+    let mut owner_outcomes = OwnerOutcomes::new(&owners);
+    let owner_kinds = resolve_owner_kinds(&client, &owners)?;
+
+    if !filter.is_empty() {
+        progress!("Only fetching issues matching: {filter}");
+    }
+    progress!("Fetching repositories …");
+    let owner_queries = owners.iter().map(|owner| {
+        (
+            owner.clone(),
+            GetOwnerRepos::new(
+                owner.clone(),
+                args.common.page_size,
+                args.common.repo_order,
+                args.common.privacy,
+            ),
+        )
+    });
+    let phase = client.phase("fetch repositories");
+    let start = Instant::now();
+    let IsolatedResults {
+        results: repos,
+        failures,
+    } = client.batch_paginate_isolated(owner_queries);
+    let elapsed = start.elapsed();
+    drop(phase);
+    dumps.dump("fetch repositories", &repos)?;
+    for f in failures {
+        owner_outcomes.fail(&f.key, f.error);
+    }
+    let repos = repos
+        .into_iter()
+        .flat_map(|pr| pr.items)
+        .collect::<Vec<_>>();
+    let total_issues: u64 = repos.iter().map(|repo| repo.issues).sum();
+    progress!(
+        "Fetched {} repositories ({} issues in total) in {:?}",
+        repos.len(),
+        total_issues,
+        elapsed
+    );
+
+    // Repositories with few enough issues can be searched in one go; the
+    // rest start out as single slices covering all time, which are counted
+    // and halved until each one's search results fit under GitHub's limit.
+    let mut slices = Vec::new();
+    let mut to_count = Vec::new();
+    for repo in &repos {
+        if repo.issues == 0 {
+            continue;
+        }
+        let slice = Slice::whole(repo.fullname.clone(), timestamp);
+        if repo.issues <= MAX_SEARCH_RESULTS {
+            slices.push(slice);
+        } else {
+            to_count.push(slice);
+        }
+    }
+    let mut slices_split = 0;
+    if !to_count.is_empty() {
+        progress!(
+            "Slicing the histories of {} repositories with more than {} issues …",
+            to_count.len(),
+            MAX_SEARCH_RESULTS
+        );
+        let phase = client.phase("count slices");
+        let start = Instant::now();
+        while !to_count.is_empty() {
+            let queries = std::mem::take(&mut to_count).into_iter().map(|slice| {
+                let paginator = CountSlice::new(&slice, &filter);
+                (slice, paginator)
+            });
+            let IsolatedResults { results, failures } = client.batch_paginate_isolated(queries);
+            dumps.dump("count slices", &results)?;
+            for f in failures {
+                owner_outcomes.fail(
+                    f.key.owner(),
+                    format!("failed to count issues in {}: {:#}", f.key, f.error),
+                );
+            }
+            for pr in results {
+                let count = pr.items.first().copied().unwrap_or_default();
+                if count == 0 {
+                    continue;
+                } else if count <= MAX_SEARCH_RESULTS {
+                    slices.push(pr.key);
+                } else if let Some((earlier, later)) = pr.key.split() {
+                    slices_split += 1;
+                    to_count.push(earlier);
+                    to_count.push(later);
+                } else {
+                    eprintln!(
+                        "[!] {} has {count} issues created within a single second; only the first {MAX_SEARCH_RESULTS} can be fetched",
+                        pr.key
+                    );
+                    slices.push(pr.key);
+                }
+            }
+        }
+        drop(phase);
+        progress!("Split slices {slices_split} times in {:?}", start.elapsed());
+    }
+    slices.sort();
+
+    progress!("Fetching issues in {} slices …", slices.len());
+    let issue_queries = slices.iter().map(|slice| {
+        let paginator = SearchIssues::new(slice, &filter, args.common.page_size, args.labels);
+        (slice.clone(), paginator)
+    });
+    let phase = client.phase("fetch issues");
+    let start = Instant::now();
+    let IsolatedResults {
+        results: slice_issues,
+        failures,
+    } = client.batch_paginate_isolated(issue_queries);
+    let elapsed = start.elapsed();
+    drop(phase);
+    dumps.dump("fetch issues", &slice_issues)?;
+    for f in failures {
+        owner_outcomes.fail(
+            f.key.owner(),
+            format!("failed to fetch issues in {}: {:#}", f.key, f.error),
+        );
+    }
+    let mut issues = slice_issues
+        .into_iter()
+        .flat_map(|pr| pr.items)
+        .collect::<Vec<_>>();
+    progress!("Fetched {} issues in {:?}", issues.len(), elapsed);
+
+    let big_elapsed = big_start.elapsed();
+    progress!("Total fetch time: {big_elapsed:?}");
+
+    let web_url = client.web_url();
+    for issue in &mut issues {
+        if let Err(e) = issue.canonicalize_url(&web_url) {
+            eprintln!("[!] {e}");
+        }
+    }
+
+    if let Some(ref anon) = anonymizer {
+        for issue in &mut issues {
+            issue.anonymize(anon);
+        }
+    }
+
+    let end_rate_limit = match client.last_rate_limit_headers() {
+        Some(rl) => RateLimit::from(rl),
+        None => client.get_rate_limit()?,
+    };
+    let rate_limit_points = end_rate_limit.used_since(start_rate_limit);
+    let points_by_phase = client.points_by_phase();
+    let transfer = client.transfer_stats();
+    let partial_responses = client.partial_responses();
+    let page_size_reductions = client.page_size_reductions();
+    let query_hashes = client.query_hashes();
+    if let Some(used) = rate_limit_points {
+        progress!("Used {used} rate limit points");
+    } else {
+        progress!("Could not determine rate limit points used due to intervening reset");
+    }
+    if !points_by_phase.is_empty() {
+        let by_phase = points_by_phase
+            .iter()
+            .map(|(phase, points)| format!("{phase}: {points}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        progress!("Rate limit points by phase: {by_phase}");
+    }
+    progress!("{transfer}");
+    if partial_responses > 0 {
+        eprintln!(
+            "[!] {partial_responses} responses included errors for some sub-queries, which were retried"
+        );
+    }
+    if page_size_reductions > 0 {
+        progress!("Lowered the page sizes of sub-queries {page_size_reductions} times");
+    }
+    let peak_rss_bytes = peak_rss();
+    if let Some(bytes) = peak_rss_bytes {
+        progress!("Peak memory usage: {} MiB", bytes / (1024 * 1024));
+    }
+    progress!("{owner_outcomes}");
+    for (owner, error) in owner_outcomes.failures() {
+        eprintln!("[!] {owner}: {error}");
+    }
+    client.export_traces()?;
+
+    let mut report = Report {
+        program: env!("CARGO_BIN_NAME"),
+        commit: option_env!("GIT_COMMIT"),
+        timestamp: humantime::format_rfc3339(timestamp).to_string(),
+        run_uuid,
+        owners,
+        owner_kinds,
+        parameters: Parameters {
+            batch_size: args.common.batch_size(),
+            page_size: args.common.page_size,
+            repo_order: args.common.repo_order,
+            privacy: args.common.privacy,
+            tls_backend: gqlient::TLS_BACKEND,
+            labels: args.labels,
+            query: args.query.clone(),
+        },
+        repositories: repos.len(),
+        issues: issues.len(),
+        slices: slices.len(),
+        slices_split,
+        elapsed: big_elapsed,
+        rate_limit_points,
+        points_by_phase,
+        transfer,
+        partial_responses,
+        page_size_reductions,
+        query_hashes,
+        peak_rss_bytes,
+        owner_outcomes,
+    };
+
+    if let Some(ref anon) = anonymizer {
+        report.owners = report
+            .owners
+            .iter()
+            .map(|owner| anon.owner(owner))
+            .collect();
+        report.owner_kinds = anon.owner_kinds(&report.owner_kinds);
+        report.owner_outcomes = anon.owner_outcomes(&report.owner_outcomes);
+        report.parameters.query = report.parameters.query.map(|q| q.anonymize(anon));
+    }
+
+    args.common.save_report(&report)?;
+
+    for outfile in args.outfile {
+        progress!("Dumping to {outfile:#} …");
+        let mut fp = outfile.create().context("failed to open file")?;
+        fp.write_json_lines(&issues)
+            .context("failed to dump issues")?;
+        fp.flush().context("failed to flush filehandle")?;
+    }
+
+    if args.common.summary == SummaryFormat::Json {
+        let mut stdout = std::io::stdout().lock();
+        serde_json::to_writer(&mut stdout, &report).context("failed to write summary")?;
+        writeln!(stdout).context("failed to write summary")?;
+        stdout.flush().context("failed to flush stdout")?;
+    }
+
+    let mut hook_failed = false;
+    if let Some(ref command) = args.common.on_complete {
+        progress!("Running on-complete command …");
+        if let Err(e) = run_on_complete(command, &report) {
+            eprintln!("[!] {e:#}");
+            hook_failed = true;
+        }
+    }
+
+    if report.owner_outcomes.failed() > 0 {
+        bail!(
+            "failed to fetch data for {} of {} owners",
+            report.owner_outcomes.failed(),
+            report.owners.len()
+        );
+    }
+
+    Ok(if hook_failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    })
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+struct Report {
+    program: &'static str,
+    commit: Option<&'static str>,
+    timestamp: String,
+    run_uuid: String,
+    owners: Vec<String>,
+    owner_kinds: BTreeMap<String, OwnerKind>,
+    parameters: Parameters,
+    repositories: usize,
+    issues: usize,
+    slices: usize,
+    slices_split: usize,
+    #[serde(serialize_with = "gqlient::serialize_duration")]
+    elapsed: Duration,
+    rate_limit_points: Option<u32>,
+    points_by_phase: BTreeMap<String, u64>,
+    transfer: TransferStats,
+    partial_responses: usize,
+    page_size_reductions: usize,
+    query_hashes: BTreeMap<String, u64>,
+    peak_rss_bytes: Option<u64>,
+    owner_outcomes: OwnerOutcomes,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+struct Parameters {
+    batch_size: usize,
+    page_size: NonZeroUsize,
+    repo_order: RepoOrder,
+    privacy: RepoPrivacy,
+    tls_backend: &'static str,
+    labels: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    query: Option<SearchQuery>,
+}
@@ -0,0 +1,88 @@
+use crate::search::SearchQuery;
+use crate::slices::Slice;
+use gqlient::{Cursor, Page, Paginator, Query, Singleton, Variable};
+use indoc::indoc;
+use std::fmt::{self, Write};
+
+/// Fetches the number of issues in a [`Slice`] that match a `--query` filter
+/// without fetching the issues themselves.  The results are always a single
+/// page containing the count.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct CountSlice {
+    search_query: String,
+}
+
+impl CountSlice {
+    pub(crate) fn new(slice: &Slice, filter: &SearchQuery) -> CountSlice {
+        CountSlice {
+            search_query: slice.search_query(filter),
+        }
+    }
+}
+
+impl Paginator for CountSlice {
+    type Item = u64;
+    type Query = CountSliceQuery;
+
+    fn for_cursor(&self, _cursor: Option<&Cursor>) -> CountSliceQuery {
+        CountSliceQuery {
+            search_query: self.search_query.clone(),
+            prefix: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct CountSliceQuery {
+    search_query: String,
+    prefix: Option<String>,
+}
+
+impl CountSliceQuery {
+    fn search_query_varname(&self) -> String {
+        match self.prefix {
+            Some(ref prefix) => format!("{prefix}_search_query"),
+            None => String::from("search_query"),
+        }
+    }
+}
+
+impl Query for CountSliceQuery {
+    type Output = Page<u64>;
+
+    fn with_variable_prefix(mut self, prefix: String) -> Self {
+        self.prefix = Some(prefix);
+        self
+    }
+
+    fn write_graphql<W: Write>(&self, mut s: W) -> fmt::Result {
+        writeln!(
+            s,
+            indoc! {"
+            search(query: ${search_query_varname}, type: ISSUE) {{
+                issueCount
+            }}
+        "},
+            search_query_varname = self.search_query_varname(),
+        )
+    }
+
+    fn variables(&self) -> [(String, Variable); 1] {
+        [(
+            self.search_query_varname(),
+            Variable {
+                gql_type: String::from("String!"),
+                value: self.search_query.clone().into(),
+            },
+        )]
+    }
+
+    fn parse_response(&self, value: serde_json::Value) -> Result<Page<u64>, serde_json::Error> {
+        let Singleton(count) = gqlient::from_value::<Singleton<u64>>(value)?;
+        Ok(Page {
+            items: vec![count],
+            end_cursor: None,
+            has_next_page: false,
+        })
+    }
+}
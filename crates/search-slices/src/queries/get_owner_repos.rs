@@ -0,0 +1,145 @@
+use crate::types::Repository;
+use gqlient::{Cursor, Page, Paginator, Query, RepoOrder, RepoPrivacy, Singleton, Variable};
+use indoc::indoc;
+use std::fmt::{self, Write};
+use std::num::NonZeroUsize;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct GetOwnerRepos {
+    owner: String,
+    page_size: NonZeroUsize,
+    order: RepoOrder,
+    privacy: RepoPrivacy,
+}
+
+impl GetOwnerRepos {
+    pub(crate) fn new(
+        owner: String,
+        page_size: NonZeroUsize,
+        order: RepoOrder,
+        privacy: RepoPrivacy,
+    ) -> GetOwnerRepos {
+        GetOwnerRepos {
+            owner,
+            page_size,
+            order,
+            privacy,
+        }
+    }
+}
+
+impl Paginator for GetOwnerRepos {
+    type Item = Repository;
+    type Query = GetOwnerReposQuery;
+
+    fn for_cursor(&self, cursor: Option<&Cursor>) -> GetOwnerReposQuery {
+        GetOwnerReposQuery {
+            owner: self.owner.clone(),
+            cursor: cursor.cloned(),
+            page_size: self.page_size,
+            order: self.order,
+            privacy: self.privacy,
+            prefix: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct GetOwnerReposQuery {
+    owner: String,
+    cursor: Option<Cursor>,
+    page_size: NonZeroUsize,
+    order: RepoOrder,
+    privacy: RepoPrivacy,
+    prefix: Option<String>,
+}
+
+impl GetOwnerReposQuery {
+    fn owner_varname(&self) -> String {
+        match self.prefix {
+            Some(ref prefix) => format!("{prefix}_owner"),
+            None => String::from("owner"),
+        }
+    }
+
+    /// Queries without a cursor all share a single null `$cursor` variable
+    fn cursor_varname(&self) -> String {
+        match (&self.prefix, &self.cursor) {
+            (Some(prefix), Some(_)) => format!("{prefix}_cursor"),
+            _ => String::from("cursor"),
+        }
+    }
+}
+
+impl Query for GetOwnerReposQuery {
+    type Output = Page<Repository>;
+
+    fn with_variable_prefix(mut self, prefix: String) -> Self {
+        self.prefix = Some(prefix);
+        self
+    }
+
+    fn write_graphql<W: Write>(&self, mut s: W) -> fmt::Result {
+        writeln!(
+            s,
+            indoc! {"
+            repositoryOwner(login: ${owner_varname}) {{
+                repositories(
+                    orderBy: $order,
+                    ownerAffiliations: [OWNER],
+                    isArchived: false,
+                    isFork: false,
+                    privacy: $privacy,
+                    first: $page_size,
+                    after: ${cursor_varname},
+                ) {{
+                    nodes {{
+                        nameWithOwner
+                        issues {{
+                            totalCount
+                        }}
+                    }}
+                    pageInfo {{
+                        endCursor
+                        hasNextPage
+                    }}
+                }}
+            }}
+        "},
+            owner_varname = self.owner_varname(),
+            cursor_varname = self.cursor_varname(),
+        )
+    }
+
+    fn variables(&self) -> [(String, Variable); 5] {
+        [
+            (
+                self.owner_varname(),
+                Variable {
+                    gql_type: String::from("String!"),
+                    value: self.owner.clone().into(),
+                },
+            ),
+            (
+                self.cursor_varname(),
+                Variable {
+                    gql_type: String::from("String"),
+                    value: self.cursor.clone().into(),
+                },
+            ),
+            (
+                String::from("page_size"),
+                Variable {
+                    gql_type: String::from("Int!"),
+                    value: self.page_size.get().into(),
+                },
+            ),
+            (String::from("order"), self.order.as_variable()),
+            (String::from("privacy"), self.privacy.as_variable()),
+        ]
+    }
+
+    fn parse_response(&self, value: serde_json::Value) -> Result<Self::Output, serde_json::Error> {
+        gqlient::from_value::<Singleton<Self::Output>>(value).map(|r| r.0)
+    }
+}
@@ -0,0 +1,89 @@
+mod count_slice;
+mod get_owner_repos;
+mod search_issues;
+pub(crate) use self::count_slice::CountSlice;
+pub(crate) use self::get_owner_repos::GetOwnerRepos;
+pub(crate) use self::search_issues::SearchIssues;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::SearchQuery;
+    use crate::slices::Slice;
+    use gqlient::{compose_request, Cursor, Paginator, Query, RepoOrder, RepoPrivacy};
+    use insta::assert_snapshot;
+    use std::num::NonZeroUsize;
+
+    fn render<Q: Query>(queries: Vec<Q>) -> String {
+        let (doc, variables) = compose_request(queries).unwrap();
+        format!(
+            "{doc}\n{}",
+            serde_json::to_string_pretty(&variables).unwrap()
+        )
+    }
+
+    fn cursor(s: &str) -> Cursor {
+        serde_json::from_value(s.into()).unwrap()
+    }
+
+    fn slice(repo: &str, start: u64, end: u64) -> Slice {
+        Slice {
+            repo: String::from(repo),
+            start,
+            end,
+        }
+    }
+
+    #[test]
+    fn get_owner_repos_batch() {
+        let page_size = NonZeroUsize::new(100).unwrap();
+        let first = GetOwnerRepos::new(
+            String::from("jwodder"),
+            page_size,
+            RepoOrder::Name,
+            RepoPrivacy::Public,
+        );
+        let second = GetOwnerRepos::new(
+            String::from("wheelodex"),
+            page_size,
+            RepoOrder::Name,
+            RepoPrivacy::Public,
+        );
+        assert_snapshot!(render(vec![
+            first.for_cursor(None),
+            second.for_cursor(Some(&cursor("Y3Vyc29yOnYyOpHOABCDEF"))),
+        ]));
+    }
+
+    #[test]
+    fn count_slice_batch() {
+        let (first, second) = slice("jwodder/query-issues", 0, 1_700_000_000)
+            .split()
+            .unwrap();
+        assert_snapshot!(render(vec![
+            CountSlice::new(&first, &SearchQuery::default()).for_cursor(None),
+            CountSlice::new(&second, &SearchQuery::default()).for_cursor(None),
+        ]));
+    }
+
+    #[test]
+    fn search_issues_batch() {
+        let page_size = NonZeroUsize::new(100).unwrap();
+        let first = SearchIssues::new(
+            &slice("jwodder/query-issues", 0, 850_000_000),
+            &SearchQuery::default(),
+            page_size,
+            true,
+        );
+        let second = SearchIssues::new(
+            &slice("jwodder/query-issues", 850_000_001, 1_700_000_000),
+            &SearchQuery::default(),
+            page_size,
+            true,
+        );
+        assert_snapshot!(render(vec![
+            first.for_cursor(None),
+            second.for_cursor(Some(&cursor("Y3Vyc29yOjEwMA=="))),
+        ]));
+    }
+}
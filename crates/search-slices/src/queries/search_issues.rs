@@ -0,0 +1,194 @@
+use crate::search::SearchQuery;
+use crate::slices::Slice;
+use crate::types::Issue;
+use gqlient::{Cursor, Page, Paginator, Query, Variable};
+use indoc::indoc;
+use std::fmt::{self, Write};
+use std::num::NonZeroUsize;
+
+/// Fetches the issues in a [`Slice`] via GitHub's `search` connection
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct SearchIssues {
+    search_query: String,
+    page_size: NonZeroUsize,
+    labels: bool,
+    /// Whether the page size has been lowered from the one given on the
+    /// command line and so must be passed in a variable of its own
+    own_page_size: bool,
+}
+
+impl SearchIssues {
+    /// Create a paginator for the issues in `slice` that match `filter`,
+    /// fetching their labels if `labels` is true
+    pub(crate) fn new(
+        slice: &Slice,
+        filter: &SearchQuery,
+        page_size: NonZeroUsize,
+        labels: bool,
+    ) -> SearchIssues {
+        SearchIssues {
+            search_query: slice.search_query(filter),
+            page_size,
+            labels,
+            own_page_size: false,
+        }
+    }
+}
+
+impl Paginator for SearchIssues {
+    type Item = Issue;
+    type Query = SearchIssuesQuery;
+
+    fn for_cursor(&self, cursor: Option<&Cursor>) -> SearchIssuesQuery {
+        SearchIssuesQuery {
+            search_query: self.search_query.clone(),
+            cursor: cursor.cloned(),
+            page_size: self.page_size,
+            labels: self.labels,
+            own_page_size: self.own_page_size,
+            prefix: None,
+        }
+    }
+
+    /// Each issue counts as one node, plus one for each label that can be
+    /// fetched for it if labels are fetched
+    fn nodes_per_page(&self) -> Option<u64> {
+        let page_size = u64::try_from(self.page_size.get()).unwrap_or(u64::MAX);
+        let per_issue = if self.labels { 101 } else { 1 };
+        Some(page_size.saturating_mul(per_issue))
+    }
+
+    /// Halve the page size, down to a minimum of one issue per page
+    fn reduce_page_size(&mut self) -> Option<NonZeroUsize> {
+        let page_size = NonZeroUsize::new(self.page_size.get() / 2)?;
+        self.page_size = page_size;
+        self.own_page_size = true;
+        Some(page_size)
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct SearchIssuesQuery {
+    search_query: String,
+    cursor: Option<Cursor>,
+    page_size: NonZeroUsize,
+    labels: bool,
+    own_page_size: bool,
+    prefix: Option<String>,
+}
+
+impl SearchIssuesQuery {
+    fn search_query_varname(&self) -> String {
+        match self.prefix {
+            Some(ref prefix) => format!("{prefix}_search_query"),
+            None => String::from("search_query"),
+        }
+    }
+
+    /// Queries without a cursor all share a single null `$cursor` variable
+    fn cursor_varname(&self) -> String {
+        match (&self.prefix, &self.cursor) {
+            (Some(prefix), Some(_)) => format!("{prefix}_cursor"),
+            _ => String::from("cursor"),
+        }
+    }
+
+    /// Queries with the default page size all share a single `$page_size`
+    /// variable
+    fn page_size_varname(&self) -> String {
+        match self.prefix {
+            Some(ref prefix) if self.own_page_size => format!("{prefix}_page_size"),
+            _ => String::from("page_size"),
+        }
+    }
+}
+
+impl Query for SearchIssuesQuery {
+    type Output = Page<Issue>;
+
+    fn with_variable_prefix(mut self, prefix: String) -> Self {
+        self.prefix = Some(prefix);
+        self
+    }
+
+    fn write_graphql<W: Write>(&self, mut s: W) -> fmt::Result {
+        writeln!(
+            s,
+            indoc! {"
+            search(
+                query: ${search_query_varname},
+                type: ISSUE,
+                first: ${page_size_varname},
+                after: ${cursor_varname},
+            ) {{
+                nodes {{
+                    ... on Issue {{
+                        repository {{
+                            nameWithOwner
+                        }}
+                        number
+                        title
+                        author {{
+                            login
+                            __typename
+                        }}
+                        state
+                        createdAt
+                        closedAt
+                        labels(first: 100) @include(if: $labels) {{
+                            nodes {{
+                                name
+                            }}
+                        }}
+                        url
+                    }}
+                }}
+                pageInfo {{
+                    endCursor
+                    hasNextPage
+                }}
+            }}
+        "},
+            search_query_varname = self.search_query_varname(),
+            cursor_varname = self.cursor_varname(),
+            page_size_varname = self.page_size_varname(),
+        )
+    }
+
+    fn variables(&self) -> [(String, Variable); 4] {
+        [
+            (
+                self.search_query_varname(),
+                Variable {
+                    gql_type: String::from("String!"),
+                    value: self.search_query.clone().into(),
+                },
+            ),
+            (
+                self.cursor_varname(),
+                Variable {
+                    gql_type: String::from("String"),
+                    value: self.cursor.clone().into(),
+                },
+            ),
+            (
+                self.page_size_varname(),
+                Variable {
+                    gql_type: String::from("Int!"),
+                    value: self.page_size.get().into(),
+                },
+            ),
+            (
+                String::from("labels"),
+                Variable {
+                    gql_type: String::from("Boolean!"),
+                    value: self.labels.into(),
+                },
+            ),
+        ]
+    }
+
+    fn parse_response(&self, value: serde_json::Value) -> Result<Page<Issue>, serde_json::Error> {
+        gqlient::from_value::<Page<Issue>>(value)
+    }
+}
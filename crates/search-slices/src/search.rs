@@ -0,0 +1,598 @@
+use gqlient::Anonymizer;
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// Additional GitHub search syntax given with `--query`, added to the search
+/// for every slice
+///
+/// The query is split into whitespace-separated terms (with double-quoted
+/// phrases kept together), each of which is either a keyword, `AND`, `OR`,
+/// `NOT`, or a possibly-negated `qualifier:value` pair.  Qualifiers are
+/// checked against those that GitHub supports for issue searches, and their
+/// values are checked where GitHub only accepts certain forms, so that a
+/// mistyped query fails up front rather than silently matching nothing.
+/// Qualifiers that the program sets itself for each slice (`repo:`,
+/// `created:`, etc.) and qualifiers that only apply to pull requests are
+/// rejected.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub(crate) struct SearchQuery {
+    terms: Vec<Term>,
+}
+
+impl SearchQuery {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// Return a copy of the query with the logins and labels that it matches
+    /// on replaced with hashes and all other values that could identify
+    /// anything (keywords, milestones, teams, etc.) redacted
+    pub(crate) fn anonymize(&self, anon: &Anonymizer) -> SearchQuery {
+        let terms = self
+            .terms
+            .iter()
+            .map(|term| match term {
+                Term::Keyword(_) => Term::Keyword(String::from("<redacted>")),
+                Term::Operator(_) => term.clone(),
+                Term::Qualifier {
+                    negated,
+                    name,
+                    value,
+                } => {
+                    let value = match Qualifier::get(name).map(|q| q.kind) {
+                        Some(Kind::Login) => quote(&anon.author(&unquote(value))),
+                        Some(Kind::Label) => quote(&anon.label(&unquote(value))),
+                        Some(Kind::Text) => String::from("<redacted>"),
+                        _ => value.clone(),
+                    };
+                    Term::Qualifier {
+                        negated: *negated,
+                        name: name.clone(),
+                        value,
+                    }
+                }
+            })
+            .collect();
+        SearchQuery { terms }
+    }
+}
+
+impl FromStr for SearchQuery {
+    type Err = ParseSearchQueryError;
+
+    fn from_str(s: &str) -> Result<SearchQuery, ParseSearchQueryError> {
+        let mut terms = Vec::new();
+        let mut operators = 0;
+        for word in split_words(s)? {
+            let term = Term::parse(&word)?;
+            match term {
+                Term::Operator(_) => operators += 1,
+                // Every slice's search is already restricted to issues.
+                Term::Qualifier {
+                    negated: false,
+                    ref name,
+                    ref value,
+                } if matches!(name.as_str(), "is" | "type")
+                    && unquote(value).eq_ignore_ascii_case("issue") =>
+                {
+                    continue
+                }
+                _ => (),
+            }
+            terms.push(term);
+        }
+        if operators > MAX_OPERATORS {
+            return Err(ParseSearchQueryError::new(format!(
+                "GitHub allows at most {MAX_OPERATORS} AND, OR, and NOT operators in a search"
+            )));
+        }
+        Ok(SearchQuery { terms })
+    }
+}
+
+/// Queries are serialized in their normalized string form, which is exactly
+/// what is added to each slice's search
+impl Serialize for SearchQuery {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl fmt::Display for SearchQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, term) in self.terms.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{term}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The most `AND`, `OR`, and `NOT` operators that GitHub accepts in a single
+/// search
+const MAX_OPERATORS: usize = 5;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Term {
+    /// A word or quoted phrase to search for
+    Keyword(String),
+    /// `AND`, `OR`, or `NOT`
+    Operator(String),
+    Qualifier {
+        negated: bool,
+        /// The qualifier's name, lowercased
+        name: String,
+        /// The qualifier's value, with any quotes
+        value: String,
+    },
+}
+
+impl Term {
+    fn parse(word: &str) -> Result<Term, ParseSearchQueryError> {
+        if matches!(word, "AND" | "OR" | "NOT") {
+            return Ok(Term::Operator(word.to_owned()));
+        }
+        let (negated, rest) = match word.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, word),
+        };
+        let Some((name, value)) = rest
+            .split_once(':')
+            .filter(|(name, _)| !name.is_empty() && !name.contains('"'))
+        else {
+            return Ok(Term::Keyword(word.to_owned()));
+        };
+        let name = name.to_ascii_lowercase();
+        let Some(qualifier) = Qualifier::get(&name) else {
+            return Err(ParseSearchQueryError::new(format!(
+                "unknown search qualifier {name:?}"
+            )));
+        };
+        let unquoted = unquote(value);
+        if unquoted.is_empty() {
+            return Err(ParseSearchQueryError::new(format!(
+                "no value given for qualifier {name:?}"
+            )));
+        }
+        qualifier.kind.check(&name, &unquoted)?;
+        Ok(Term::Qualifier {
+            negated,
+            name,
+            value: value.to_owned(),
+        })
+    }
+}
+
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Term::Keyword(s) | Term::Operator(s) => write!(f, "{s}"),
+            Term::Qualifier {
+                negated,
+                name,
+                value,
+            } => {
+                if *negated {
+                    write!(f, "-")?;
+                }
+                write!(f, "{name}:{value}")
+            }
+        }
+    }
+}
+
+/// A search qualifier accepted in `--query`
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Qualifier {
+    name: &'static str,
+    kind: Kind,
+}
+
+impl Qualifier {
+    fn get(name: &str) -> Option<Qualifier> {
+        QUALIFIERS.iter().copied().find(|q| q.name == name)
+    }
+}
+
+/// The forms of value that a qualifier accepts
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Kind {
+    /// The login of a user, or `@me`
+    Login,
+    /// The name of a label
+    Label,
+    /// Free-form text, such as a milestone title or team name
+    Text,
+    /// One of a fixed set of values
+    OneOf(&'static [&'static str]),
+    /// A comma-separated list of values from a fixed set
+    ListOf(&'static [&'static str]),
+    /// A number or range of numbers, such as `>10` or `5..20`
+    Number,
+    /// A date or range of dates, such as `>=2024-01-01` or
+    /// `2024-01-01..2024-06-30`
+    Date,
+    /// Set by the program itself for each slice
+    Reserved,
+    /// Only applies to pull requests
+    PullRequest,
+}
+
+impl Kind {
+    fn check(self, name: &str, value: &str) -> Result<(), ParseSearchQueryError> {
+        let ok = match self {
+            Kind::Login => value == "@me" || is_login(value),
+            Kind::Label | Kind::Text => true,
+            Kind::OneOf(values) => values.iter().any(|v| v.eq_ignore_ascii_case(value)),
+            Kind::ListOf(values) => value
+                .split(',')
+                .all(|item| values.iter().any(|v| v.eq_ignore_ascii_case(item))),
+            Kind::Number => is_range(value, |n| n.parse::<u64>().is_ok()),
+            Kind::Date => is_range(value, is_date),
+            Kind::Reserved => {
+                return Err(ParseSearchQueryError::new(format!(
+                    "qualifier {name:?} is set by search-slices for each slice and cannot be given"
+                )))
+            }
+            Kind::PullRequest => {
+                return Err(ParseSearchQueryError::new(format!(
+                    "qualifier {name:?} only applies to pull requests, which search-slices does not fetch"
+                )))
+            }
+        };
+        if ok {
+            Ok(())
+        } else {
+            Err(ParseSearchQueryError::new(format!(
+                "invalid value {value:?} for qualifier {name:?}"
+            )))
+        }
+    }
+}
+
+static QUALIFIERS: &[Qualifier] = &[
+    Qualifier {
+        name: "archived",
+        kind: Kind::OneOf(&["true", "false"]),
+    },
+    Qualifier {
+        name: "assignee",
+        kind: Kind::Login,
+    },
+    Qualifier {
+        name: "author",
+        kind: Kind::Login,
+    },
+    Qualifier {
+        name: "base",
+        kind: Kind::PullRequest,
+    },
+    Qualifier {
+        name: "closed",
+        kind: Kind::Date,
+    },
+    Qualifier {
+        name: "comments",
+        kind: Kind::Number,
+    },
+    Qualifier {
+        name: "commenter",
+        kind: Kind::Login,
+    },
+    Qualifier {
+        name: "created",
+        kind: Kind::Reserved,
+    },
+    Qualifier {
+        name: "draft",
+        kind: Kind::PullRequest,
+    },
+    Qualifier {
+        name: "head",
+        kind: Kind::PullRequest,
+    },
+    Qualifier {
+        name: "in",
+        kind: Kind::ListOf(&["title", "body", "comments"]),
+    },
+    Qualifier {
+        name: "interactions",
+        kind: Kind::Number,
+    },
+    Qualifier {
+        name: "involves",
+        kind: Kind::Login,
+    },
+    Qualifier {
+        name: "is",
+        kind: Kind::OneOf(&[
+            "open", "closed", "issue", "locked", "unlocked", "public", "private", "blocked",
+            "blocking",
+        ]),
+    },
+    Qualifier {
+        name: "label",
+        kind: Kind::Label,
+    },
+    Qualifier {
+        name: "language",
+        kind: Kind::Text,
+    },
+    Qualifier {
+        name: "linked",
+        kind: Kind::OneOf(&["pr"]),
+    },
+    Qualifier {
+        name: "mentions",
+        kind: Kind::Login,
+    },
+    Qualifier {
+        name: "merged",
+        kind: Kind::PullRequest,
+    },
+    Qualifier {
+        name: "milestone",
+        kind: Kind::Text,
+    },
+    Qualifier {
+        name: "no",
+        kind: Kind::OneOf(&["assignee", "label", "milestone", "project"]),
+    },
+    Qualifier {
+        name: "org",
+        kind: Kind::Reserved,
+    },
+    Qualifier {
+        name: "project",
+        kind: Kind::Text,
+    },
+    Qualifier {
+        name: "reactions",
+        kind: Kind::Number,
+    },
+    Qualifier {
+        name: "reason",
+        kind: Kind::OneOf(&["completed", "not planned"]),
+    },
+    Qualifier {
+        name: "repo",
+        kind: Kind::Reserved,
+    },
+    Qualifier {
+        name: "review",
+        kind: Kind::PullRequest,
+    },
+    Qualifier {
+        name: "review-requested",
+        kind: Kind::PullRequest,
+    },
+    Qualifier {
+        name: "reviewed-by",
+        kind: Kind::PullRequest,
+    },
+    Qualifier {
+        name: "state",
+        kind: Kind::OneOf(&["open", "closed"]),
+    },
+    Qualifier {
+        name: "status",
+        kind: Kind::PullRequest,
+    },
+    Qualifier {
+        name: "team",
+        kind: Kind::Text,
+    },
+    Qualifier {
+        name: "team-review-requested",
+        kind: Kind::PullRequest,
+    },
+    Qualifier {
+        name: "type",
+        kind: Kind::OneOf(&["issue"]),
+    },
+    Qualifier {
+        name: "updated",
+        kind: Kind::Date,
+    },
+    Qualifier {
+        name: "user",
+        kind: Kind::Reserved,
+    },
+];
+
+/// Split a search query into whitespace-separated words, keeping
+/// double-quoted phrases (including quoted qualifier values) together
+fn split_words(s: &str) -> Result<Vec<String>, ParseSearchQueryError> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut quoted = false;
+    for c in s.chars() {
+        if c == '"' {
+            quoted = !quoted;
+        } else if c.is_whitespace() && !quoted {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            continue;
+        }
+        word.push(c);
+    }
+    if quoted {
+        return Err(ParseSearchQueryError::new("unterminated quoted phrase"));
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    Ok(words)
+}
+
+fn unquote(value: &str) -> String {
+    value.replace('"', "")
+}
+
+/// Quote `value` if it contains whitespace
+fn quote(value: &str) -> String {
+    if value.contains(char::is_whitespace) {
+        format!("\"{value}\"")
+    } else {
+        value.to_owned()
+    }
+}
+
+/// Test whether `value` is a plausible GitHub login: alphanumeric characters
+/// and hyphens, optionally followed by `[bot]` for an app's bot account
+fn is_login(value: &str) -> bool {
+    let name = value.strip_suffix("[bot]").unwrap_or(value);
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+/// Test whether `value` is a single value accepted by `check` optionally
+/// preceded by a comparison operator, or a range `A..B` of such values where
+/// either end may be `*`
+fn is_range<F: Fn(&str) -> bool>(value: &str, check: F) -> bool {
+    if let Some((start, end)) = value.split_once("..") {
+        return (start == "*" || check(start))
+            && (end == "*" || check(end))
+            && !(start == "*" && end == "*");
+    }
+    let value = [">=", "<=", ">", "<"]
+        .iter()
+        .find_map(|op| value.strip_prefix(op))
+        .unwrap_or(value);
+    check(value)
+}
+
+/// Test whether `value` is a date (`YYYY-MM-DD`) or an RFC 3339 timestamp
+fn is_date(value: &str) -> bool {
+    if humantime::parse_rfc3339_weak(value.trim_end_matches('Z')).is_ok() {
+        return true;
+    }
+    let mut parts = value.split('-');
+    let (Some(y), Some(m), Some(d), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return false;
+    };
+    humantime::parse_rfc3339_weak(&format!("{y}-{m}-{d}T00:00:00")).is_ok()
+        && y.len() == 4
+        && m.len() == 2
+        && d.len() == 2
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct ParseSearchQueryError(String);
+
+impl ParseSearchQueryError {
+    fn new<S: Into<String>>(msg: S) -> ParseSearchQueryError {
+        ParseSearchQueryError(msg.into())
+    }
+}
+
+impl fmt::Display for ParseSearchQueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid search query: {}", self.0)
+    }
+}
+
+impl std::error::Error for ParseSearchQueryError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_err(s: &str) -> String {
+        s.parse::<SearchQuery>().unwrap_err().0
+    }
+
+    #[test]
+    fn normalize() {
+        let query =
+            "  crash  in:body -label:\"needs info\"  is:issue  comments:>=5 author:octo-cat ";
+        assert_eq!(
+            query.parse::<SearchQuery>().unwrap().to_string(),
+            "crash in:body -label:\"needs info\" comments:>=5 author:octo-cat"
+        );
+    }
+
+    #[test]
+    fn quoted_phrase() {
+        let query = "\"out of memory\" OR segfault reason:\"not planned\"";
+        assert_eq!(
+            query.parse::<SearchQuery>().unwrap().to_string(),
+            "\"out of memory\" OR segfault reason:\"not planned\""
+        );
+    }
+
+    #[test]
+    fn ranges() {
+        for query in [
+            "updated:2024-01-01..2024-06-30",
+            "closed:>=2024-01-01T12:00:00Z",
+            "updated:*..2024-06-30",
+            "reactions:10..*",
+            "comments:<3",
+        ] {
+            assert!(query.parse::<SearchQuery>().is_ok(), "{query}");
+        }
+        assert_eq!(
+            parse_err("updated:2024-13-01"),
+            r#"invalid value "2024-13-01" for qualifier "updated""#
+        );
+        assert_eq!(
+            parse_err("comments:many"),
+            r#"invalid value "many" for qualifier "comments""#
+        );
+    }
+
+    #[test]
+    fn rejected_qualifiers() {
+        assert_eq!(
+            parse_err("repo:jwodder/query-issues"),
+            r#"qualifier "repo" is set by search-slices for each slice and cannot be given"#
+        );
+        assert_eq!(
+            parse_err("Created:>2024-01-01"),
+            r#"qualifier "created" is set by search-slices for each slice and cannot be given"#
+        );
+        assert_eq!(
+            parse_err("review:approved"),
+            r#"qualifier "review" only applies to pull requests, which search-slices does not fetch"#
+        );
+        assert_eq!(
+            parse_err("is:pr"),
+            r#"invalid value "pr" for qualifier "is""#
+        );
+        assert_eq!(
+            parse_err("lable:bug"),
+            r#"unknown search qualifier "lable""#
+        );
+        assert_eq!(
+            parse_err("label:"),
+            r#"no value given for qualifier "label""#
+        );
+        assert_eq!(parse_err("\"unterminated"), "unterminated quoted phrase");
+        assert_eq!(
+            parse_err("a OR b OR c OR d OR e OR f OR g"),
+            "GitHub allows at most 5 AND, OR, and NOT operators in a search"
+        );
+    }
+
+    #[test]
+    fn anonymize() {
+        let anon = Anonymizer::new(b"0123456789abcdef");
+        let query = "crash author:octocat label:\"needs info\" milestone:v2 comments:>5"
+            .parse::<SearchQuery>()
+            .unwrap();
+        assert_eq!(
+            query.anonymize(&anon).to_string(),
+            format!(
+                "<redacted> author:{} label:{} milestone:<redacted> comments:>5",
+                anon.author("octocat"),
+                anon.label("needs info"),
+            )
+        );
+    }
+}
@@ -0,0 +1,91 @@
+use crate::search::SearchQuery;
+use serde::Serialize;
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The most results that GitHub's `search` connection will return for a
+/// single query, no matter how many match
+pub(crate) const MAX_SEARCH_RESULTS: u64 = 1000;
+
+/// A range of issue creation times in a repository, fetched with a single
+/// paginated search.  Both ends are inclusive and given in seconds since the
+/// Unix epoch.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub(crate) struct Slice {
+    pub(crate) repo: String,
+    pub(crate) start: u64,
+    pub(crate) end: u64,
+}
+
+impl Slice {
+    /// Return a slice covering every issue in the repository `repo` created
+    /// up through `until`
+    pub(crate) fn whole(repo: String, until: SystemTime) -> Slice {
+        let end = until.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        Slice {
+            repo,
+            start: 0,
+            end,
+        }
+    }
+
+    /// Return the search query for the issues in the slice that match
+    /// `filter`
+    pub(crate) fn search_query(&self, filter: &SearchQuery) -> String {
+        let mut query = format!(
+            "repo:{} is:issue created:{}..{}",
+            self.repo,
+            timestamp(self.start),
+            timestamp(self.end)
+        );
+        if !filter.is_empty() {
+            query.push(' ');
+            query.push_str(&filter.to_string());
+        }
+        query
+    }
+
+    /// Split the slice into two halves at its midpoint, or return `None` if
+    /// the slice only covers a single second
+    pub(crate) fn split(&self) -> Option<(Slice, Slice)> {
+        if self.start >= self.end {
+            return None;
+        }
+        let mid = self.start + (self.end - self.start) / 2;
+        Some((
+            Slice {
+                repo: self.repo.clone(),
+                start: self.start,
+                end: mid,
+            },
+            Slice {
+                repo: self.repo.clone(),
+                start: mid + 1,
+                end: self.end,
+            },
+        ))
+    }
+
+    /// Return the owner of the slice's repository
+    pub(crate) fn owner(&self) -> &str {
+        self.repo.split('/').next().unwrap_or(&self.repo)
+    }
+}
+
+impl fmt::Display for Slice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} (created {}..{})",
+            self.repo,
+            timestamp(self.start),
+            timestamp(self.end)
+        )
+    }
+}
+
+/// Format a number of seconds since the Unix epoch as an RFC 3339 timestamp,
+/// as accepted by the `created:` search qualifier
+fn timestamp(secs: u64) -> humantime::Rfc3339Timestamp {
+    humantime::format_rfc3339_seconds(UNIX_EPOCH + Duration::from_secs(secs))
+}
@@ -0,0 +1,91 @@
+use gqlient::{Anonymizer, Author, IssueUrlError};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub(crate) struct Repository {
+    #[serde(rename(deserialize = "nameWithOwner"))]
+    pub(crate) fullname: String,
+    /// The number of issues in the repository, open & closed
+    #[serde(deserialize_with = "gqlient::singleton_field")]
+    pub(crate) issues: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(from = "RawIssue")]
+pub(crate) struct Issue {
+    pub(crate) repo: String,
+    pub(crate) number: u64,
+    pub(crate) title: String,
+    pub(crate) author: Option<Author>,
+    pub(crate) state: String,
+    pub(crate) created_at: String,
+    pub(crate) closed_at: Option<String>,
+    /// The names of the issue's labels; only fetched when `--labels` is
+    /// given
+    // Note: Reportedly, the max number of labels on an issue is 100
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) labels: Option<Vec<String>>,
+    pub(crate) url: String,
+}
+
+impl Issue {
+    /// Check & canonicalize the issue's URL against the web base URL of the
+    /// host it was fetched from
+    pub(crate) fn canonicalize_url(&mut self, base_url: &str) -> Result<(), IssueUrlError> {
+        self.url = gqlient::canonicalize_issue_url(&self.url, base_url, &self.repo, self.number)?;
+        Ok(())
+    }
+
+    /// Replace the issue's repository, title, author, labels, and URL with
+    /// hashes
+    pub(crate) fn anonymize(&mut self, anon: &Anonymizer) {
+        self.repo = anon.repo(&self.repo);
+        self.title = anon.title(&self.title);
+        if let Some(ref mut author) = self.author {
+            author.login = anon.author(&author.login);
+        }
+        for label in self.labels.iter_mut().flatten() {
+            *label = anon.label(label);
+        }
+        self.url = Anonymizer::issue_url(&self.repo, self.number);
+    }
+}
+
+impl From<RawIssue> for Issue {
+    fn from(value: RawIssue) -> Issue {
+        Issue {
+            repo: value.repository,
+            number: value.number,
+            title: value.title,
+            author: value.author,
+            state: value.state,
+            created_at: value.created_at,
+            closed_at: value.closed_at,
+            labels: value
+                .labels
+                .map(|labels| labels.into_iter().map(|lbl| lbl.name).collect()),
+            url: value.url,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct RawIssue {
+    #[serde(deserialize_with = "gqlient::singleton_field")]
+    repository: String,
+    number: u64,
+    title: String,
+    author: Option<Author>,
+    state: String,
+    created_at: String,
+    closed_at: Option<String>,
+    #[serde(default, deserialize_with = "gqlient::optional_singleton_field")]
+    labels: Option<Vec<RawLabel>>,
+    url: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+struct RawLabel {
+    name: String,
+}
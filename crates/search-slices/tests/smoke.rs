@@ -0,0 +1,140 @@
+use fixtures::{FakeGitHub, FakeOrg, FixtureServer};
+use serde_json::Value;
+use std::path::Path;
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+
+fn read_json_lines(path: &Path) -> Vec<Value> {
+    std::fs::read_to_string(path)
+        .expect("failed to read JSON Lines file")
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("invalid JSON line"))
+        .collect()
+}
+
+#[test]
+fn fetch_sliced_histories() {
+    // More issues per repository than a single search can return, so that
+    // the repositories' histories have to be sliced
+    let github = FakeGitHub::new([FakeOrg::new("fake-org", 2, 1200, 1)]);
+    let server = FixtureServer::start(move |payload| github.respond(payload));
+    let tmpdir = tempfile::tempdir().unwrap();
+    let outfile = tmpdir.path().join("issues.jsonl");
+    let report_file = tmpdir.path().join("report.jsonl");
+    let output = Command::new(env!("CARGO_BIN_EXE_search-slices"))
+        .env("GH_TOKEN", "not-a-real-token")
+        .arg("--api-url")
+        .arg(server.api_url())
+        .arg("--labels")
+        .arg("--report-file")
+        .arg(&report_file)
+        .arg("--outfile")
+        .arg(&outfile)
+        .arg("fake-org")
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let issues = read_json_lines(&outfile);
+    assert_eq!(issues.len(), 2400);
+    let mut numbers = issues
+        .iter()
+        .map(|issue| {
+            (
+                issue["repo"].as_str().unwrap().to_owned(),
+                issue["number"].as_u64().unwrap(),
+            )
+        })
+        .collect::<Vec<_>>();
+    numbers.sort_unstable();
+    numbers.dedup();
+    assert_eq!(numbers.len(), 2400, "some issues were fetched twice");
+    assert!(issues
+        .iter()
+        .all(|issue| issue["labels"] == serde_json::json!(["label-000"])));
+
+    let reports = read_json_lines(&report_file);
+    assert_eq!(reports.len(), 1);
+    let report = &reports[0];
+    assert_eq!(report["program"], "search-slices");
+    assert_eq!(report["owners"], serde_json::json!(["fake-org"]));
+    assert_eq!(report["repositories"], 2);
+    assert_eq!(report["issues"], 2400);
+    assert!(report["slices"].as_u64().is_some_and(|n| n >= 4));
+    assert_eq!(report["parameters"]["labels"], true);
+}
+
+#[test]
+fn query_is_added_to_searches() {
+    let github = FakeGitHub::new([FakeOrg::new("fake-org", 1, 1200, 0)]);
+    let searches = Arc::new(Mutex::new(Vec::new()));
+    let recorded = Arc::clone(&searches);
+    let server = FixtureServer::start(move |payload| {
+        if let Some(vars) = payload["variables"].as_object() {
+            let mut searches = recorded.lock().expect("mutex should not be poisoned");
+            searches.extend(
+                vars.iter()
+                    .filter(|(name, _)| name.ends_with("search_query"))
+                    .filter_map(|(_, value)| value.as_str().map(String::from)),
+            );
+        }
+        github.respond(payload)
+    });
+    let tmpdir = tempfile::tempdir().unwrap();
+    let report_file = tmpdir.path().join("report.jsonl");
+    let output = Command::new(env!("CARGO_BIN_EXE_search-slices"))
+        .env("GH_TOKEN", "not-a-real-token")
+        .arg("--api-url")
+        .arg(server.api_url())
+        .arg("--query")
+        .arg("crash  in:body is:issue -label:\"needs info\"")
+        .arg("--report-file")
+        .arg(&report_file)
+        .arg("fake-org")
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let searches = searches.lock().unwrap();
+    assert!(!searches.is_empty());
+    assert!(searches
+        .iter()
+        .all(|q| q.ends_with(" crash in:body -label:\"needs info\"")
+            && q.matches("is:issue").count() == 1));
+    let reports = read_json_lines(&report_file);
+    assert_eq!(
+        reports[0]["parameters"]["query"],
+        "crash in:body -label:\"needs info\""
+    );
+}
+
+#[test]
+fn invalid_query_fails_before_fetching() {
+    let github = FakeGitHub::new([FakeOrg::new("fake-org", 1, 10, 0)]);
+    let requests = Arc::new(Mutex::new(0));
+    let counted = Arc::clone(&requests);
+    let server = FixtureServer::start(move |payload| {
+        *counted.lock().expect("mutex should not be poisoned") += 1;
+        github.respond(payload)
+    });
+    let output = Command::new(env!("CARGO_BIN_EXE_search-slices"))
+        .env("GH_TOKEN", "not-a-real-token")
+        .arg("--api-url")
+        .arg(server.api_url())
+        .arg("--query")
+        .arg("repo:other/repo")
+        .arg("fake-org")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("qualifier \"repo\" is set by search-slices"));
+    assert_eq!(*requests.lock().unwrap(), 0);
+}
@@ -1,12 +1,17 @@
-use crate::queries::GetIssues;
-use crate::types::{Issue, IssueState, RepoDetails};
+use crate::queries::{GetIssues, GetPullRequests, IssueFields};
+use crate::retention::KeepClosed;
+use crate::types::{Issue, IssueState, RepoActivity, RepoDetails};
 use anyhow::Context;
-use gqlient::{Cursor, Id, Ided};
+use gqlient::{
+    Anonymizer, Cursor, Filter, Id, Ided, IssueKind, KeyedCollection, RepoPermission,
+    RepoVisibility, RepositoryInfo,
+};
 use serde::{de::Deserializer, Deserialize, Serialize};
 use std::collections::{btree_map::Entry, BTreeMap};
 use std::fmt;
 use std::io;
 use std::num::NonZeroUsize;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 #[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
 #[serde(transparent)]
@@ -25,11 +30,38 @@ impl Database {
         Ok(())
     }
 
+    /// Return a copy of the database with all repository names and issue
+    /// details replaced by hashes
+    pub(crate) fn anonymized(&self, anon: &Anonymizer) -> Database {
+        let mut db = self.clone();
+        for repo in db.0.values_mut() {
+            repo.anonymize(anon);
+        }
+        db
+    }
+
+    pub(crate) fn get(&self, repo_id: &Id) -> Option<&RepoWithIssues> {
+        self.0.get(repo_id)
+    }
+
     pub(crate) fn get_mut(&mut self, repo_id: &Id) -> Option<&mut RepoWithIssues> {
         self.0.get_mut(repo_id)
     }
 
-    pub(crate) fn update_repositories<I>(&mut self, iter: I) -> RepoDiff
+    /// Replace the repositories in the database with those in `iter`, except
+    /// that the repositories of owners in `failed_owners` (whose repositories
+    /// could not be fetched) are left as they are.
+    ///
+    /// Returns a summary of the changes along with the full names of the
+    /// repositories that are no longer present; the latter are not counted in
+    /// the summary until their removals are classified and passed to
+    /// [`RepoDiff::record_removal()`].
+    pub(crate) fn update_repositories<I>(
+        &mut self,
+        iter: I,
+        failed_owners: &[String],
+        changes: &mut Vec<IssueChange>,
+    ) -> (RepoDiff, Vec<String>)
     where
         I: IntoIterator<Item = Ided<RepoDetails>>,
     {
@@ -39,10 +71,35 @@ impl Database {
             if let Some(mut repo_w_issues) = self.0.remove(&id) {
                 if repo_w_issues.repository != repo {
                     report.modified += 1;
+                    let fullname = repo_w_issues.repository.fullname();
                     if repo.open_issues == 0 {
-                        report.closed_issues += repo_w_issues.issues.len();
+                        let dropped = repo_w_issues.drop_open(IssueKind::Issue);
+                        report.closed_issues += dropped.len();
                         repo_w_issues.issue_cursor = None;
-                        repo_w_issues.issues.clear();
+                        for (issue_id, old) in dropped {
+                            changes.push(IssueChange {
+                                repo: fullname.clone(),
+                                issue_id: issue_id.clone(),
+                                old: Some(old.clone()),
+                                new: None,
+                            });
+                            repo_w_issues.disappeared.insert(issue_id, old);
+                        }
+                    }
+                    if repo.open_pull_requests == Some(0) {
+                        // `--backfill-closed` only looks up issues, so pull
+                        // requests are dropped without being kept for it.
+                        let dropped = repo_w_issues.drop_open(IssueKind::PullRequest);
+                        report.closed_issues += dropped.len();
+                        repo_w_issues.pr_cursor = None;
+                        for (issue_id, old) in dropped {
+                            changes.push(IssueChange {
+                                repo: fullname.clone(),
+                                issue_id,
+                                old: Some(old),
+                                new: None,
+                            });
+                        }
                     }
                     repo_w_issues.repository = repo;
                 }
@@ -53,31 +110,196 @@ impl Database {
                     RepoWithIssues {
                         repository: repo,
                         issue_cursor: None,
+                        pr_cursor: None,
+                        last_refreshed: None,
+                        last_run_uuid: None,
+                        refreshed_details: None,
                         issues: BTreeMap::new(),
+                        disappeared: BTreeMap::new(),
                     },
                 );
                 report.added += 1;
             }
         }
-        report.deleted = self.0.len();
-        self.0 = newmap;
-        report
+        let mut removed = Vec::new();
+        for (id, repo) in std::mem::replace(&mut self.0, newmap) {
+            if failed_owners
+                .iter()
+                .any(|owner| owner.eq_ignore_ascii_case(&repo.repository.owner))
+            {
+                self.0.insert(id, repo);
+            } else {
+                removed.push(repo.repository.fullname());
+            }
+        }
+        (report, removed)
+    }
+
+    pub(crate) fn issues(&self) -> impl Iterator<Item = &Issue> + '_ {
+        self.0.values().flat_map(|repo| repo.issues.values())
+    }
+
+    /// Return the number of repositories with open issues that are
+    /// unchanged since their issues were last fetched
+    pub(crate) fn unchanged_repos(&self) -> usize {
+        self.0
+            .values()
+            .filter(|repo| repo.repository.open_issues != 0 && repo.is_unchanged())
+            .count()
+    }
+
+    /// Return the IDs of the issues awaiting `--backfill-closed`, each paired
+    /// with the ID of its repository and a name of the form `owner/repo#123`
+    /// for use in messages
+    pub(crate) fn disappeared_issues(&self) -> impl Iterator<Item = (Id, Id, String)> + '_ {
+        self.0.iter().flat_map(|(repo_id, repo)| {
+            let fullname = repo.repository.fullname();
+            repo.disappeared.iter().map(move |(issue_id, issue)| {
+                (
+                    repo_id.clone(),
+                    issue_id.clone(),
+                    format!("{fullname}#{}", issue.number),
+                )
+            })
+        })
+    }
+
+    /// Resolve the issue `issue_id` awaiting `--backfill-closed` in the
+    /// repository `repo_id` given its current state, which is `None` if the
+    /// issue no longer exists.  The issue is no longer awaiting backfill
+    /// afterwards.
+    ///
+    /// If the issue is closed, the change from its last known open state to
+    /// its current state is returned, and, if `keep_closed` is true, the
+    /// closed issue is put back in the database.  Issues that have since been
+    /// reopened are left for the repository's next refresh to pick up.
+    pub(crate) fn resolve_disappeared(
+        &mut self,
+        repo_id: &Id,
+        issue_id: &Id,
+        current: Option<Issue>,
+        keep_closed: bool,
+    ) -> Option<IssueChange> {
+        let repo = self.0.get_mut(repo_id)?;
+        let old = repo.disappeared.remove(issue_id)?;
+        let new = current.filter(|iss| iss.state == IssueState::Closed)?;
+        if keep_closed {
+            repo.issues.insert(issue_id.clone(), new.clone());
+        }
+        Some(IssueChange {
+            repo: repo.repository.fullname(),
+            issue_id: issue_id.clone(),
+            old: Some(old),
+            new: Some(new),
+        })
+    }
+
+    /// Remove the closed issues that have outlived the retention period of
+    /// `policy` as of `now`, returning the number of issues removed
+    pub(crate) fn prune_closed(&mut self, policy: KeepClosed, now: SystemTime) -> usize {
+        let mut pruned = 0;
+        for repo in self.0.values_mut() {
+            let before = repo.issues.len();
+            repo.issues.retain(|_, iss| {
+                iss.state == IssueState::Open || !policy.expired(iss.closed_at.as_deref(), now)
+            });
+            pruned += before - repo.issues.len();
+        }
+        pruned
     }
 
     pub(crate) fn issue_paginators(
         &self,
         page_size: NonZeroUsize,
+        fields: IssueFields,
+        skip_unchanged: bool,
+        size_pages: bool,
     ) -> impl Iterator<Item = (Id, GetIssues)> + '_ {
         self.0
             .iter()
-            .filter(|(_, repo)| repo.repository.open_issues != 0)
+            .filter(move |(_, repo)| repo.needs_refresh(skip_unchanged))
             .map(move |(id, repo)| {
                 (
                     id.clone(),
-                    GetIssues::new(id.clone(), repo.issue_cursor.clone(), page_size),
+                    repo.issue_paginator(id, page_size, fields, size_pages),
                 )
             })
     }
+
+    /// Return paginators for fetching the pull requests of the repositories
+    /// with open pull requests, for `--include-prs`
+    pub(crate) fn pr_paginators(
+        &self,
+        page_size: NonZeroUsize,
+        database_ids: bool,
+        labels: bool,
+    ) -> impl Iterator<Item = (Id, GetPullRequests)> + '_ {
+        self.0
+            .iter()
+            .filter(|(_, repo)| {
+                repo.repository
+                    .open_pull_requests
+                    .is_some_and(|qty| qty > 0)
+            })
+            .map(move |(id, repo)| {
+                (
+                    id.clone(),
+                    GetPullRequests::new(
+                        id.clone(),
+                        repo.pr_cursor.clone(),
+                        page_size,
+                        database_ids,
+                        labels,
+                    ),
+                )
+            })
+    }
+
+    /// Select the repositories whose issues should be refreshed this run
+    /// given a budget of rate limit points.
+    ///
+    /// Repositories with open issues are considered in order of when they
+    /// were last refreshed, least recent (or never) first, and are selected
+    /// until the estimated cost of fetching their issues would exceed
+    /// `points_budget`.  Repositories that are not selected keep their issue
+    /// cursors and so will be at the front of the line on the next run.
+    ///
+    /// Returns the paginators for the selected repositories along with the
+    /// IDs of the repositories deferred.
+    pub(crate) fn scheduled_issue_paginators(
+        &self,
+        page_size: NonZeroUsize,
+        fields: IssueFields,
+        skip_unchanged: bool,
+        size_pages: bool,
+        batch_size: usize,
+        points_budget: u32,
+    ) -> (Vec<(Id, GetIssues)>, Vec<Id>) {
+        let mut candidates = self
+            .0
+            .iter()
+            .filter(|(_, repo)| repo.needs_refresh(skip_unchanged))
+            .collect::<Vec<_>>();
+        candidates.sort_by_key(|(_, repo)| repo.last_refreshed);
+        let mut pages = 0;
+        let mut selected = Vec::new();
+        let mut deferred = Vec::new();
+        for (id, repo) in candidates {
+            let new_pages = pages + repo.estimated_pages(page_size);
+            if !selected.is_empty()
+                && estimated_points(new_pages, page_size, fields.labels, batch_size) > points_budget
+            {
+                deferred.push(id.clone());
+                continue;
+            }
+            pages = new_pages;
+            selected.push((
+                id.clone(),
+                repo.issue_paginator(id, page_size, fields, size_pages),
+            ));
+        }
+        (selected, deferred)
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -85,35 +307,211 @@ pub(crate) struct RepoWithIssues {
     #[serde(deserialize_with = "deser_repo_details")]
     repository: RepoDetails,
     issue_cursor: Option<Cursor>,
+    /// The end cursor of the repository's pull requests as of when they were
+    /// last fetched with `--include-prs`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pr_cursor: Option<Cursor>,
+    /// When the repository's issues were last fetched, as seconds since the
+    /// Unix epoch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last_refreshed: Option<u64>,
+    /// The UUID of the run that last fetched the repository's issues
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last_run_uuid: Option<String>,
+    /// The repository's details as of when its issues were last fetched
+    #[serde(
+        default,
+        deserialize_with = "deser_opt_repo_details",
+        skip_serializing_if = "Option::is_none"
+    )]
+    refreshed_details: Option<RepoDetails>,
     issues: BTreeMap<Id, Issue>,
+    /// Issues that were removed from `issues` when the repository's open
+    /// issue count dropped to zero, before their closing details could be
+    /// fetched; these are looked up individually by `--backfill-closed`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    disappeared: BTreeMap<Id, Issue>,
+}
+
+impl KeyedCollection<Id> for Database {
+    type Value = RepoWithIssues;
+
+    fn value_mut(&mut self, repo_id: &Id) -> Option<&mut RepoWithIssues> {
+        self.get_mut(repo_id)
+    }
 }
 
 impl RepoWithIssues {
+    pub(crate) fn repository(&self) -> &RepoDetails {
+        &self.repository
+    }
+
+    fn anonymize(&mut self, anon: &Anonymizer) {
+        self.repository.anonymize(anon);
+        if let Some(ref mut details) = self.refreshed_details {
+            details.anonymize(anon);
+        }
+        let fullname = self.repository.fullname();
+        for issue in self
+            .issues
+            .values_mut()
+            .chain(self.disappeared.values_mut())
+        {
+            issue.anonymize(anon, &fullname);
+        }
+    }
+
     pub(crate) fn set_issue_cursor(&mut self, cursor: Option<Cursor>) {
         self.issue_cursor = cursor;
     }
 
-    pub(crate) fn update_issues<I>(&mut self, issues: I) -> IssueDiff
+    pub(crate) fn set_pr_cursor(&mut self, cursor: Option<Cursor>) {
+        self.pr_cursor = cursor;
+    }
+
+    /// Remove & return the open issues or pull requests (per `kind`) in the
+    /// database.  Closed ones kept by `--keep-closed` stay where they are.
+    fn drop_open(&mut self, kind: IssueKind) -> BTreeMap<Id, Issue> {
+        let (open, rest) = std::mem::take(&mut self.issues)
+            .into_iter()
+            .partition(|(_, iss)| iss.kind == kind && iss.state == IssueState::Open);
+        self.issues = rest;
+        open
+    }
+
+    pub(crate) fn mark_refreshed(&mut self, timestamp: SystemTime, run_uuid: &str) {
+        self.last_refreshed = timestamp
+            .duration_since(UNIX_EPOCH)
+            .ok()
+            .map(|d| d.as_secs());
+        self.last_run_uuid = Some(run_uuid.to_owned());
+        // The details are only needed for detecting unchanged repositories,
+        // which requires activity timestamps.
+        self.refreshed_details = self
+            .repository
+            .activity
+            .is_some()
+            .then(|| self.repository.clone());
+    }
+
+    /// Returns true if the repository's issues have been fetched before and
+    /// neither its open issue count nor its activity timestamps have changed
+    /// since then (changes to its stargazer & fork counts are ignored).
+    /// Repositories fetched without activity timestamps are never considered
+    /// unchanged.
+    fn is_unchanged(&self) -> bool {
+        self.issue_cursor.is_some()
+            && self.repository.activity.is_some()
+            && self
+                .refreshed_details
+                .as_ref()
+                .is_some_and(|details| details.same_except_popularity(&self.repository))
+    }
+
+    fn needs_refresh(&self, skip_unchanged: bool) -> bool {
+        self.repository.open_issues != 0 && !(skip_unchanged && self.is_unchanged())
+    }
+
+    /// Return a paginator for fetching the repository's issues.
+    ///
+    /// If `size_pages` is true and the repository's issues have never been
+    /// fetched, the page size is reduced to the repository's open issue
+    /// count, as that is how many issues are expected to be fetched.
+    fn issue_paginator(
+        &self,
+        id: &Id,
+        page_size: NonZeroUsize,
+        fields: IssueFields,
+        size_pages: bool,
+    ) -> GetIssues {
+        let paginator = GetIssues::new(id.clone(), self.issue_cursor.clone(), page_size, fields);
+        if size_pages && self.issue_cursor.is_none() {
+            paginator.expecting(self.repository.open_issues)
+        } else {
+            paginator
+        }
+    }
+
+    /// Estimate how many pages of issues will be fetched for this repository.
+    ///
+    /// Repositories that have never been fetched need all of their open
+    /// issues fetched; for the rest, only issues updated since the last run
+    /// are fetched, which usually fit in a single page.
+    fn estimated_pages(&self, page_size: NonZeroUsize) -> u64 {
+        if self.issue_cursor.is_none() {
+            self.repository
+                .open_issues
+                .div_ceil(page_size.get() as u64)
+                .max(1)
+        } else {
+            1
+        }
+    }
+
+    /// For `--exclude-bots`, remove the bot-authored issues (or pull
+    /// requests) from `issues`, which were fetched from the repository, and
+    /// return the number removed.
+    ///
+    /// Any bot-authored issues already in the database (e.g., from a run
+    /// without `--exclude-bots`) are removed as well, as the repository's
+    /// cursor moves past the skipped issues, and so their closing or
+    /// modification would never be applied.
+    pub(crate) fn exclude_bots(&mut self, issues: &mut Vec<Ided<Issue>>) -> usize {
+        let before = issues.len();
+        issues.retain(|iss| !iss.data.is_bot_authored());
+        self.issues.retain(|_, iss| !iss.is_bot_authored());
+        self.disappeared.retain(|_, iss| !iss.is_bot_authored());
+        before - issues.len()
+    }
+
+    /// Update the repository's issues (or pull requests) with those fetched
+    /// from it.  Open issues that are now closed (or merged) are removed from
+    /// the database unless `keep_closed` is true.
+    pub(crate) fn update_issues<I>(
+        &mut self,
+        issues: I,
+        keep_closed: bool,
+        changes: &mut Vec<IssueChange>,
+    ) -> IssueDiff
     where
         I: IntoIterator<Item = Ided<Issue>>,
     {
         let mut report = IssueDiff::default();
+        let fullname = self.repository.fullname();
         for Ided { id, data: iss } in issues {
-            match self.issues.entry(id) {
-                Entry::Occupied(o) if iss.state == IssueState::Closed => {
+            // The issue's current state is known now, so it no longer needs
+            // to be backfilled.
+            self.disappeared.remove(&id);
+            let change = |old, new| IssueChange {
+                repo: fullname.clone(),
+                issue_id: id.clone(),
+                old,
+                new,
+            };
+            match self.issues.entry(id.clone()) {
+                Entry::Occupied(mut o)
+                    if iss.state != IssueState::Open && o.get().state == IssueState::Open =>
+                {
                     report.open_closed += 1;
-                    o.remove();
+                    let old = if keep_closed {
+                        o.insert(iss.clone())
+                    } else {
+                        o.remove()
+                    };
+                    changes.push(change(Some(old), Some(iss)));
                 }
-                Entry::Vacant(_) if iss.state == IssueState::Closed => report.already_closed += 1,
+                Entry::Vacant(_) if iss.state != IssueState::Open => report.already_closed += 1,
                 Entry::Occupied(mut o) => {
                     if o.get() != &iss {
                         report.modified += 1;
-                        o.insert(iss);
+                        let old = o.insert(iss.clone());
+                        changes.push(change(Some(old), Some(iss)));
                     }
                 }
                 Entry::Vacant(v) => {
                     report.added += 1;
-                    v.insert(iss);
+                    v.insert(iss.clone());
+                    changes.push(change(None, Some(iss)));
                 }
             }
         }
@@ -121,6 +519,59 @@ impl RepoWithIssues {
     }
 }
 
+/// Estimate the rate limit points needed to fetch `pages` pages of issues in
+/// batches of `batch_size`.
+///
+/// Each page of issues costs one request for the issues connection plus, if
+/// `labels` is true, one request per issue for its labels connection, and
+/// GitHub charges one point per hundred such requests (minimum one) per
+/// GraphQL query.
+fn estimated_points(pages: u64, page_size: NonZeroUsize, labels: bool, batch_size: usize) -> u32 {
+    let batch_size = u64::try_from(batch_size.max(1)).unwrap_or(u64::MAX);
+    let per_page = 1 + if labels { page_size.get() as u64 } else { 0 };
+    let full_batches = pages / batch_size;
+    let remainder = pages % batch_size;
+    let mut points = full_batches * (batch_size * per_page).div_ceil(100).max(1);
+    if remainder != 0 {
+        points += (remainder * per_page).div_ceil(100).max(1);
+    }
+    u32::try_from(points).unwrap_or(u32::MAX)
+}
+
+/// A change made to an issue in the database
+///
+/// `old` is `None` if the issue was newly added, and `new` is `None` if the
+/// issue was removed without fetching its current state (which happens when
+/// its repository no longer has any open issues).  The current states of
+/// such issues are later recorded by `--backfill-closed` as changes from their
+/// last open states.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub(crate) struct IssueChange {
+    pub(crate) repo: String,
+    pub(crate) issue_id: Id,
+    pub(crate) old: Option<Issue>,
+    pub(crate) new: Option<Issue>,
+}
+
+impl IssueChange {
+    /// Test whether either the old or the new state of the issue matches
+    /// `filter`
+    pub(crate) fn matches(&self, filter: &Filter) -> bool {
+        [&self.old, &self.new]
+            .into_iter()
+            .flatten()
+            .any(|iss| filter.matches(iss))
+    }
+
+    /// Replace the repository name and issue details with hashes
+    pub(crate) fn anonymize(&mut self, anon: &Anonymizer) {
+        self.repo = anon.repo(&self.repo);
+        for issue in [&mut self.old, &mut self.new].into_iter().flatten() {
+            issue.anonymize(anon, &self.repo);
+        }
+    }
+}
+
 fn deser_repo_details<'de, D>(deserializer: D) -> Result<RepoDetails, D::Error>
 where
     D: Deserializer<'de>,
@@ -129,34 +580,157 @@ where
     struct DirectDetails {
         owner: String,
         name: String,
+        #[serde(default)]
+        database_id: Option<u64>,
+        #[serde(default)]
+        visibility: Option<RepoVisibility>,
+        #[serde(default)]
+        is_template: bool,
+        #[serde(default)]
+        viewer_permission: Option<RepoPermission>,
         open_issues: u64,
+        #[serde(default)]
+        open_pull_requests: Option<u64>,
+        #[serde(default)]
+        stargazer_count: Option<u64>,
+        #[serde(default)]
+        fork_count: Option<u64>,
+        #[serde(flatten)]
+        activity: Option<RepoActivity>,
     }
 
     let DirectDetails {
         owner,
         name,
+        database_id,
+        visibility,
+        is_template,
+        viewer_permission,
         open_issues,
+        open_pull_requests,
+        stargazer_count,
+        fork_count,
+        activity,
     } = DirectDetails::deserialize(deserializer)?;
     Ok(RepoDetails {
         owner,
         name,
+        database_id,
+        visibility,
+        is_template,
+        viewer_permission,
         open_issues,
+        open_pull_requests,
+        stargazer_count,
+        fork_count,
+        activity,
     })
 }
 
+fn deser_opt_repo_details<'de, D>(deserializer: D) -> Result<Option<RepoDetails>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+    #[serde(transparent)]
+    struct Wrapper(#[serde(deserialize_with = "deser_repo_details")] RepoDetails);
+
+    Ok(Option::<Wrapper>::deserialize(deserializer)?.map(|w| w.0))
+}
+
+/// Why a repository is no longer among those fetched for its owner
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum RemovalReason {
+    /// The repository does not exist or is not visible to the access token
+    Deleted,
+    /// The repository has been made private
+    Private,
+    /// The repository has been archived
+    Archived,
+    /// The repository has been transferred to another owner or renamed
+    Moved,
+    /// The repository still exists publicly under the same name but is no
+    /// longer listed for its owner
+    Unlisted,
+    /// The repository's status could not be determined
+    Unknown,
+}
+
+impl RemovalReason {
+    /// Classify the removal of the repository `fullname` given its current
+    /// details as returned by the REST API
+    pub(crate) fn classify(fullname: &str, info: Option<&RepositoryInfo>) -> RemovalReason {
+        match info {
+            None => RemovalReason::Deleted,
+            Some(info) if info.private => RemovalReason::Private,
+            Some(info) if info.archived => RemovalReason::Archived,
+            Some(info) if !info.full_name.eq_ignore_ascii_case(fullname) => RemovalReason::Moved,
+            Some(_) => RemovalReason::Unlisted,
+        }
+    }
+}
+
+/// A repository that was removed from the database, as recorded in the run
+/// report
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub(crate) struct RemovedRepo {
+    pub(crate) repo: String,
+    pub(crate) reason: RemovalReason,
+    /// The repository's new full name, if it was moved
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) moved_to: Option<String>,
+}
+
+impl RemovedRepo {
+    /// Replace the repository's old and new names with hashes
+    pub(crate) fn anonymize(&mut self, anon: &Anonymizer) {
+        self.repo = anon.repo(&self.repo);
+        if let Some(ref mut moved_to) = self.moved_to {
+            *moved_to = anon.repo(moved_to);
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
 pub(crate) struct RepoDiff {
     added: usize,
     modified: usize,
     deleted: usize,
+    made_private: usize,
+    archived: usize,
+    moved: usize,
+    unlisted: usize,
+    unknown: usize,
     pub(crate) closed_issues: usize,
 }
 
 impl RepoDiff {
+    pub(crate) fn record_removal(&mut self, reason: RemovalReason) {
+        let count = match reason {
+            RemovalReason::Deleted => &mut self.deleted,
+            RemovalReason::Private => &mut self.made_private,
+            RemovalReason::Archived => &mut self.archived,
+            RemovalReason::Moved => &mut self.moved,
+            RemovalReason::Unlisted => &mut self.unlisted,
+            RemovalReason::Unknown => &mut self.unknown,
+        };
+        *count += 1;
+    }
+
+    fn removed(&self) -> usize {
+        self.deleted
+            .saturating_add(self.made_private)
+            .saturating_add(self.archived)
+            .saturating_add(self.moved)
+            .saturating_add(self.unlisted)
+            .saturating_add(self.unknown)
+    }
+
     pub(crate) fn repos_touched(&self) -> usize {
         self.added
             .saturating_add(self.modified)
-            .saturating_add(self.deleted)
+            .saturating_add(self.removed())
     }
 }
 
@@ -164,9 +738,24 @@ impl fmt::Display for RepoDiff {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "{} repositories added, {} repositories modified, {} repositories deleted, {} issues bulk closed",
-            self.added, self.modified, self.deleted, self.closed_issues
-        )
+            "{} repositories added, {} repositories modified, {} repositories removed",
+            self.added,
+            self.modified,
+            self.removed()
+        )?;
+        if self.removed() > 0 {
+            write!(
+                f,
+                " ({} deleted, {} made private, {} archived, {} moved, {} unlisted, {} unknown)",
+                self.deleted,
+                self.made_private,
+                self.archived,
+                self.moved,
+                self.unlisted,
+                self.unknown
+            )?;
+        }
+        write!(f, ", {} issues bulk closed", self.closed_issues)
     }
 }
 
@@ -204,3 +793,101 @@ impl std::ops::AddAssign for IssueDiff {
         self.already_closed += rhs.already_closed;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn repo_with_issues() -> RepoWithIssues {
+        RepoWithIssues {
+            repository: serde_json::from_value(json!({
+                "owner": {"login": "jwodder"},
+                "name": "foo",
+                "issues": {"totalCount": 2},
+            }))
+            .unwrap(),
+            issue_cursor: None,
+            pr_cursor: None,
+            last_refreshed: None,
+            last_run_uuid: None,
+            refreshed_details: None,
+            issues: BTreeMap::new(),
+            disappeared: BTreeMap::new(),
+        }
+    }
+
+    fn issue(id: &str, number: u64, author: &str, state: &str) -> Ided<Issue> {
+        let typename = if author.ends_with("[bot]") {
+            "Bot"
+        } else {
+            "User"
+        };
+        serde_json::from_value(json!({
+            "id": id,
+            "number": number,
+            "title": format!("Issue {number}"),
+            "author": {"login": author, "__typename": typename},
+            "state": state,
+            "url": format!("https://github.com/jwodder/foo/issues/{number}"),
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn exclude_bots_drops_stored_bot_issue() {
+        let mut repo = repo_with_issues();
+        let mut changes = Vec::new();
+        // A run without `--exclude-bots` stores both issues.
+        repo.update_issues(
+            [
+                issue("I_1", 1, "jwodder", "OPEN"),
+                issue("I_2", 2, "dependabot[bot]", "OPEN"),
+            ],
+            false,
+            &mut changes,
+        );
+        assert_eq!(repo.issues.len(), 2);
+        // The bot's issue is then closed and fetched with `--exclude-bots`.
+        let mut fetched = vec![
+            issue("I_1", 1, "jwodder", "OPEN"),
+            issue("I_2", 2, "dependabot[bot]", "CLOSED"),
+        ];
+        assert_eq!(repo.exclude_bots(&mut fetched), 1);
+        repo.update_issues(fetched, false, &mut changes);
+        assert_eq!(
+            repo.issues
+                .values()
+                .map(|iss| iss.number)
+                .collect::<Vec<_>>(),
+            [1]
+        );
+        assert!(repo
+            .issues
+            .values()
+            .all(|iss| iss.state == IssueState::Open && !iss.is_bot_authored()));
+    }
+
+    #[test]
+    fn exclude_bots_without_fetched_bot_issue() {
+        let mut repo = repo_with_issues();
+        let mut changes = Vec::new();
+        repo.update_issues(
+            [issue("I_2", 2, "dependabot[bot]", "OPEN")],
+            false,
+            &mut changes,
+        );
+        // Even if the bot's issue is not among those fetched, it no longer
+        // lingers in the database.
+        let mut fetched = vec![issue("I_3", 3, "jwodder", "OPEN")];
+        assert_eq!(repo.exclude_bots(&mut fetched), 0);
+        repo.update_issues(fetched, false, &mut changes);
+        assert_eq!(
+            repo.issues
+                .values()
+                .map(|iss| iss.number)
+                .collect::<Vec<_>>(),
+            [3]
+        );
+    }
+}
@@ -0,0 +1,64 @@
+use anyhow::Context;
+use patharg::InputArg;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashSet};
+
+/// Rules for normalizing the names of issue labels before they are stored in
+/// the database, loaded from a JSON file of the form:
+///
+/// ```json
+/// {
+///     "case_fold": true,
+///     "rename": {
+///         "bug": "type:bug"
+///     }
+/// }
+/// ```
+///
+/// Case-folding (if enabled) is applied first, followed by renaming.  If
+/// `case_fold` is true, the keys of `rename` are matched case-insensitively.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct LabelRules {
+    case_fold: bool,
+    rename: BTreeMap<String, String>,
+}
+
+impl LabelRules {
+    pub(crate) fn load(infile: &InputArg) -> anyhow::Result<LabelRules> {
+        let src = infile
+            .read_to_string()
+            .with_context(|| format!("failed to read label rules from {infile:#}"))?;
+        let mut rules = serde_json::from_str::<LabelRules>(&src)
+            .with_context(|| format!("failed to parse label rules from {infile:#}"))?;
+        if rules.case_fold {
+            rules.rename = std::mem::take(&mut rules.rename)
+                .into_iter()
+                .map(|(k, v)| (k.to_lowercase(), v))
+                .collect();
+        }
+        Ok(rules)
+    }
+
+    /// Normalize the given label names in place, removing any duplicates that
+    /// result
+    pub(crate) fn normalize(&self, labels: &mut Vec<String>) {
+        let mut seen = HashSet::new();
+        let normalized = std::mem::take(labels)
+            .into_iter()
+            .map(|name| {
+                let name = if self.case_fold {
+                    name.to_lowercase()
+                } else {
+                    name
+                };
+                match self.rename.get(&name) {
+                    Some(new_name) => new_name.clone(),
+                    None => name,
+                }
+            })
+            .filter(|name| seen.insert(name.clone()))
+            .collect();
+        *labels = normalized;
+    }
+}
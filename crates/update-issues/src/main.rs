@@ -1,29 +1,129 @@
 mod db;
+mod labels;
+mod pretty;
 mod queries;
+mod retention;
 mod types;
-use crate::db::{Database, IssueDiff};
-use crate::queries::GetOwnerRepos;
-use anyhow::Context;
-use clap::Parser;
-use gqlient::{Client, PaginationResults, DEFAULT_BATCH_SIZE};
+use crate::db::{Database, IssueChange, IssueDiff, RemovalReason, RemovedRepo};
+use crate::labels::LabelRules;
+use crate::pretty::write_pretty_diff;
+use crate::queries::{GetClosedIssue, GetOwnerRepos, IssueFields};
+use crate::retention::KeepClosed;
+use crate::types::IssueState;
+use anyhow::{bail, Context};
+use clap::{CommandFactory, FromArgMatches, Parser};
+use cli_common::{
+    new_run_uuid, resolve_owner_kinds, run_on_complete, write_label_matrix, CommonArgs,
+    SummaryFormat, LOCKED_EXIT_CODE,
+};
+use gqlient::{
+    join_results, peak_rss, progress, Filter, Id, Ided, IsolatedResults, LabelMatrix, OwnerKind,
+    OwnerOutcomes, PaginationResults, RateLimit, RepoOrder, RepoPrivacy, TransferStats,
+};
 use patharg::{InputArg, OutputArg};
 use serde::Serialize;
 use serde_jsonlines::append_json_lines;
+use std::collections::BTreeMap;
+use std::io::{IsTerminal, Write};
 use std::num::NonZeroUsize;
 use std::path::PathBuf;
+use std::process::ExitCode;
 use std::time::{Duration, Instant, SystemTime};
 
+/// Number of repositories with the longest issue fetch times to log
+const SLOWEST_REPOS_TO_LOG: usize = 5;
+
 /// Measure time to create & update a local database of open GitHub issues
 #[derive(Clone, Debug, Eq, Parser, PartialEq)]
 struct Arguments {
-    /// Number of sub-queries to make per GraphQL request
-    #[arg(short = 'B', long)]
-    batch_size: Option<NonZeroUsize>,
+    /// Look up the current states of the issues that were dropped from the
+    /// database because their repositories no longer had any open issues, and
+    /// record when and why they were closed in the `--history` file
+    #[arg(long)]
+    backfill_closed: bool,
+
+    /// Also fetch the numeric database IDs (as used by the REST API) of
+    /// repositories and issues
+    #[arg(long)]
+    database_ids: bool,
+
+    /// Stop fetching issues once this much time has passed since the start
+    /// of the run, deferring the remaining repositories to a later run
+    ///
+    /// Accepts durations like "90s" or "5m".
+    #[arg(long, value_name = "DURATION", value_parser = humantime::parse_duration)]
+    deadline: Option<Duration>,
+
+    /// Omit issues authored by bots from the output
+    #[arg(long)]
+    exclude_bots: bool,
+
+    /// Only include issues matching the given filter expression in the
+    /// `--history` and `--label-matrix` output
+    #[arg(long, value_name = "EXPR")]
+    filter: Option<Filter>,
+
+    /// Append a record of every change made to an issue in the database to
+    /// the given file
+    #[arg(long, value_name = "PATH")]
+    history: Option<PathBuf>,
+
+    /// Also fetch each repository's open pull requests and store them in the
+    /// database alongside the issues
+    ///
+    /// Pull requests are fetched for every repository with open pull
+    /// requests, so this cannot be combined with `--deadline` or
+    /// `--points-budget`.
+    #[arg(long, conflicts_with_all = ["deadline", "points_budget"])]
+    include_prs: bool,
 
     /// Load the initial database state from the given file
     #[arg(short, long)]
     infile: Option<InputArg>,
 
+    /// How long to keep closed issues in the database: a duration since each
+    /// issue was closed (e.g., "90d"), "forever", or "none"
+    ///
+    /// Closed issues that have outlived the retention period are pruned from
+    /// the database at the end of each run.
+    #[arg(long, default_value_t, value_name = "PERIOD")]
+    keep_closed: KeepClosed,
+
+    /// Normalize issue label names using the rules in the given JSON file
+    #[arg(long, value_name = "PATH")]
+    label_rules: Option<InputArg>,
+
+    /// Also fetch the names of each issue's labels
+    ///
+    /// Labels are always fetched when `--label-rules` or `--label-matrix` is
+    /// given.
+    #[arg(long)]
+    labels: bool,
+
+    /// Also fetch whether each issue has a linked pull request that will
+    /// close it
+    #[arg(long)]
+    linked_prs: bool,
+
+    /// Write a matrix of how often each pair of labels appears together on
+    /// an open issue to the given file
+    ///
+    /// The matrix is written as CSV if the file name ends in ".csv" and as
+    /// JSON otherwise.
+    #[arg(long, value_name = "PATH")]
+    label_matrix: Option<OutputArg>,
+
+    /// Limit each request for issues to as many repositories as fit within
+    /// the given number of nodes, as counted toward GitHub's limit of 500,000
+    /// nodes per request
+    ///
+    /// Repositories whose issues have never been fetched are queried with a
+    /// page size no larger than their number of open issues, so that more
+    /// small repositories fit in each request.  Each request still has at
+    /// most `--batch-size` repositories.
+    #[arg(long, value_name = "NODES")]
+    node_limit: Option<u64>,
+
     /// Do not write the updated database state to `--infile`
     ///
     /// Mutually exclusive with `--outfile`
@@ -36,17 +136,32 @@ struct Arguments {
     #[arg(short, long)]
     outfile: Option<OutputArg>,
 
-    /// Number of items to request per page of results
-    #[arg(short = 'P', long, default_value = "100")]
-    page_size: NonZeroUsize,
+    /// Only refresh as many repositories' issues as are estimated to fit in
+    /// the given number of rate limit points, deferring the rest to a later
+    /// run
+    #[arg(long, value_name = "INT")]
+    points_budget: Option<u32>,
+
+    /// Print the changes made to issues during the run to stderr as a report
+    /// with added, closed, and modified sections for each repository
+    ///
+    /// The report is colored if stderr is a terminal and `NO_COLOR` is not
+    /// set.  Like `--history`, it only includes issues matching `--filter`.
+    #[arg(long)]
+    pretty_diff: bool,
 
-    /// Append a run report to the given file
-    #[arg(short = 'R', long)]
-    report_file: Option<PathBuf>,
+    /// Also fetch when each repository and its issues were last changed, and
+    /// only fetch issues for repositories that have changed since their
+    /// issues were last fetched
+    #[arg(long)]
+    skip_unchanged: bool,
 
-    /// GitHub owners/organizations of repositories to fetch open issues for
-    #[arg(required = true)]
-    owners: Vec<String>,
+    /// Also fetch each issue's parent issue and the numbers of its sub-issues
+    #[arg(long)]
+    sub_issues: bool,
+
+    #[command(flatten)]
+    common: CommonArgs,
 }
 
 impl Arguments {
@@ -61,114 +176,617 @@ impl Arguments {
     }
 }
 
-fn main() -> anyhow::Result<()> {
-    let args = Arguments::parse();
+fn main() -> anyhow::Result<ExitCode> {
+    let matches = Arguments::command().get_matches();
+    let mut args = Arguments::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    args.common.apply_profile(&matches)?;
+    args.common
+        .check_summary_output(args.outfile().iter().chain(&args.label_matrix))?;
+    gqlient::set_quiet(args.common.quiet);
+    let Some(_lock) = args.common.lock()? else {
+        return Ok(ExitCode::from(LOCKED_EXIT_CODE));
+    };
+    let owners = args.common.owners()?;
+    let anonymizer = args.common.anonymizer()?;
+    let dumps = args.common.phase_dumps()?;
+    if anonymizer.is_some() && args.infile.is_some() && args.outfile.is_none() && !args.no_save {
+        bail!("--anonymize requires --outfile or --no-save when --infile is given so that the database is not overwritten with anonymized data");
+    }
     let mut db = if let Some(ref infile) = args.infile {
-        eprintln!("[·] Loading {infile:#} …");
+        progress!("Loading {infile:#} …");
         Database::load(infile.open()?)?
     } else {
         Database::default()
     };
 
-    let mut client = Client::new_with_local_token()?;
-    if let Some(bsz) = args.batch_size {
-        client.batch_size(bsz);
+    let label_rules = match args.label_rules {
+        Some(ref path) => LabelRules::load(path)?,
+        None => LabelRules::default(),
+    };
+
+    let mut client = args.common.client()?;
+    let web_url = client.web_url();
+    if let Some(nodes) = args.node_limit {
+        client.node_limit(nodes);
     }
     let start_rate_limit = client.get_rate_limit()?;
 
     let big_start = Instant::now();
     let timestamp = SystemTime::now();
+    let run_uuid = new_run_uuid()?;
+    let owner_kinds = resolve_owner_kinds(&client, &owners)?;
 
-    eprintln!("[·] Fetching repositories …");
-    let owner_paginators = args.owners.iter().map(|owner| {
+    let issue_fields = IssueFields {
+        database_ids: args.database_ids,
+        labels: args.labels || args.label_rules.is_some() || args.label_matrix.is_some(),
+        linked_prs: args.linked_prs,
+        sub_issues: args.sub_issues,
+    };
+
+    progress!("Fetching repositories …");
+    let owner_paginators = owners.iter().map(|owner| {
         (
             owner.clone(),
-            GetOwnerRepos::new(owner.clone(), args.page_size),
+            GetOwnerRepos::new(
+                owner.clone(),
+                args.common.page_size,
+                args.common.repo_order,
+                args.common.privacy,
+                args.skip_unchanged,
+                args.database_ids,
+                args.include_prs,
+            ),
         )
     });
+    let mut owner_outcomes = OwnerOutcomes::new(&owners);
+    let phase = client.phase("fetch repositories");
     let start = Instant::now();
-    let repos = client.batch_paginate(owner_paginators)?;
+    let IsolatedResults {
+        results: repos,
+        failures,
+    } = client.batch_paginate_isolated(owner_paginators);
     let elapsed = start.elapsed();
+    drop(phase);
+    dumps.dump("fetch repositories", &repos)?;
+    let mut failed_owners = Vec::with_capacity(failures.len());
+    for f in failures {
+        owner_outcomes.fail(&f.key, f.error);
+        failed_owners.push(f.key);
+    }
     let repos = repos
         .into_iter()
         .flat_map(|pr| pr.items)
         .collect::<Vec<_>>();
     let all_repos_qty = repos.len();
-    eprintln!("[·] Fetched {all_repos_qty} repositories in {elapsed:?}");
+    progress!("Fetched {all_repos_qty} repositories in {elapsed:?}");
+
+    let mut changes = Vec::new();
+    let (mut rdiff, removed) = db.update_repositories(repos, &failed_owners, &mut changes);
+    if !removed.is_empty() {
+        progress!(
+            "Checking status of {} repositories no longer listed …",
+            removed.len()
+        );
+    }
+    let mut removed_repos = Vec::with_capacity(removed.len());
+    for repo in removed {
+        let (reason, moved_to) = match client.get_repository(&repo) {
+            Ok(info) => {
+                let reason = RemovalReason::classify(&repo, info.as_ref());
+                let moved_to = info
+                    .filter(|_| reason == RemovalReason::Moved)
+                    .map(|i| i.full_name);
+                (reason, moved_to)
+            }
+            Err(e) => {
+                eprintln!("[!] {e:#}");
+                (RemovalReason::Unknown, None)
+            }
+        };
+        rdiff.record_removal(reason);
+        removed_repos.push(RemovedRepo {
+            repo,
+            reason,
+            moved_to,
+        });
+    }
+    progress!("{rdiff}");
 
-    let rdiff = db.update_repositories(repos);
-    eprintln!("[·] {rdiff}");
+    let batch_size = args.common.batch_size();
+    let repos_unchanged = if args.skip_unchanged {
+        let qty = db.unchanged_repos();
+        progress!("Skipping {qty} repositories unchanged since their last refresh");
+        qty
+    } else {
+        0
+    };
+    let (paginators, deferred) = match args.points_budget {
+        Some(budget) => db.scheduled_issue_paginators(
+            args.common.page_size,
+            issue_fields,
+            args.skip_unchanged,
+            args.node_limit.is_some(),
+            batch_size,
+            budget,
+        ),
+        None => (
+            db.issue_paginators(
+                args.common.page_size,
+                issue_fields,
+                args.skip_unchanged,
+                args.node_limit.is_some(),
+            )
+            .collect(),
+            Vec::new(),
+        ),
+    };
+    let mut repos_deferred = deferred.len();
+    for repo_id in deferred {
+        skip_repo_owner(&mut owner_outcomes, &db, &repo_id);
+    }
+    if repos_deferred > 0 {
+        progress!("Deferring {repos_deferred} repositories to stay within points budget");
+    }
 
-    eprintln!("[·] Fetching issues …");
+    progress!("Fetching issues …");
+    let phase = client.phase("fetch issues");
     let start = Instant::now();
-    let mut repo_qty = 0;
-    let issues = client.batch_paginate(
-        db.issue_paginators(args.page_size)
-            .inspect(|_| repo_qty += 1),
-    )?;
+    let mut repo_qty = paginators.len();
+    let mut failures = Vec::new();
+    let issues = if let Some(deadline) = args.deadline {
+        let mut issues = Vec::new();
+        let mut queue = paginators.into_iter();
+        loop {
+            if big_start.elapsed() >= deadline {
+                repos_deferred += queue.len();
+                repo_qty -= queue.len();
+                progress!("Deadline reached; deferring {} repositories", queue.len());
+                for (repo_id, _) in queue {
+                    skip_repo_owner(&mut owner_outcomes, &db, &repo_id);
+                }
+                break;
+            }
+            let chunk = queue.by_ref().take(batch_size).collect::<Vec<_>>();
+            if chunk.is_empty() {
+                break;
+            }
+            let r = client.batch_paginate_isolated(chunk);
+            issues.extend(r.results);
+            failures.extend(r.failures);
+        }
+        issues
+    } else {
+        let r = client.batch_paginate_isolated(paginators);
+        failures = r.failures;
+        r.results
+    };
     let elapsed = start.elapsed();
+    drop(phase);
+    dumps.dump("fetch issues", &issues)?;
+    repo_qty -= failures.len();
+    for f in failures {
+        if let Some(repo) = db.get(&f.key) {
+            let repo = repo.repository();
+            owner_outcomes.fail(
+                &repo.owner,
+                format!(
+                    "failed to fetch issues for {}: {:#}",
+                    repo.fullname(),
+                    f.error
+                ),
+            );
+        }
+    }
     let qty: usize = issues.iter().map(|pr| pr.items.len()).sum();
-    eprintln!("[·] Fetched {qty} issues from {repo_qty} repositories in {elapsed:?}");
+    progress!("Fetched {qty} issues from {repo_qty} repositories in {elapsed:?}");
 
     let mut idiff = IssueDiff::default();
-    for PaginationResults {
-        key: repo_id,
-        items,
-        end_cursor,
-    } in issues
-    {
-        let Some(repo) = db.get_mut(&repo_id) else {
-            // TODO: Warn? Error?
-            continue;
-        };
+    let mut bot_issues_skipped = 0;
+    let mut repo_timings = Vec::new();
+    // TODO: Warn or error on results for repositories not in the database?
+    join_results(&mut db, issues, |repo, pr| {
+        let PaginationResults {
+            mut items,
+            end_cursor,
+            requests,
+            elapsed,
+            ..
+        } = pr;
+        if args.exclude_bots {
+            bot_issues_skipped += repo.exclude_bots(&mut items);
+        }
+        let fullname = repo.repository().fullname();
+        for Ided { data: issue, .. } in &mut items {
+            if let Err(e) = issue.canonicalize_url(&web_url, &fullname) {
+                eprintln!("[!] {e}");
+            }
+            if let Some(labels) = issue.labels.as_mut() {
+                label_rules.normalize(labels);
+            }
+        }
         repo.set_issue_cursor(end_cursor);
-        idiff += repo.update_issues(items);
+        repo.mark_refreshed(timestamp, &run_uuid);
+        let diff = repo.update_issues(items, args.keep_closed.keeps_closed(), &mut changes);
+        if diff.issues_touched() > 0 {
+            repo_timings.push(RepoTiming {
+                repo: fullname,
+                elapsed,
+                requests,
+                issues_updated: diff.issues_touched(),
+            });
+        }
+        idiff += diff;
+    });
+    progress!("{idiff}");
+    repo_timings.sort_by_key(|rt| std::cmp::Reverse(rt.elapsed));
+    for rt in repo_timings.iter().take(SLOWEST_REPOS_TO_LOG) {
+        progress!(
+            "{}: {} issues updated in {:?} ({} requests)",
+            rt.repo,
+            rt.issues_updated,
+            rt.elapsed,
+            rt.requests
+        );
+    }
+
+    let mut pr_qty = 0;
+    let mut prdiff = IssueDiff::default();
+    if args.include_prs {
+        let paginators = db
+            .pr_paginators(
+                args.common.page_size,
+                issue_fields.database_ids,
+                issue_fields.labels,
+            )
+            .collect::<Vec<_>>();
+        progress!(
+            "Fetching pull requests for {} repositories …",
+            paginators.len()
+        );
+        let phase = client.phase("fetch pull requests");
+        let start = Instant::now();
+        let IsolatedResults {
+            results: prs,
+            failures,
+        } = client.batch_paginate_isolated(paginators);
+        let elapsed = start.elapsed();
+        drop(phase);
+        dumps.dump("fetch pull requests", &prs)?;
+        for f in failures {
+            if let Some(repo) = db.get(&f.key) {
+                let repo = repo.repository();
+                owner_outcomes.fail(
+                    &repo.owner,
+                    format!(
+                        "failed to fetch pull requests for {}: {:#}",
+                        repo.fullname(),
+                        f.error
+                    ),
+                );
+            }
+        }
+        pr_qty = prs.iter().map(|pr| pr.items.len()).sum();
+        progress!("Fetched {pr_qty} pull requests in {elapsed:?}");
+        join_results(&mut db, prs, |repo, pr| {
+            let PaginationResults {
+                mut items,
+                end_cursor,
+                ..
+            } = pr;
+            if args.exclude_bots {
+                bot_issues_skipped += repo.exclude_bots(&mut items);
+            }
+            let fullname = repo.repository().fullname();
+            for Ided { data: pr, .. } in &mut items {
+                if let Err(e) = pr.canonicalize_url(&web_url, &fullname) {
+                    eprintln!("[!] {e}");
+                }
+                if let Some(labels) = pr.labels.as_mut() {
+                    label_rules.normalize(labels);
+                }
+            }
+            repo.set_pr_cursor(end_cursor);
+            prdiff += repo.update_issues(items, args.keep_closed.keeps_closed(), &mut changes);
+        });
+        progress!("Pull requests: {prdiff}");
+    }
+
+    if args.exclude_bots {
+        progress!("Skipped {bot_issues_skipped} bot-authored issues");
+    }
+
+    let mut issues_backfilled = 0;
+    if args.backfill_closed {
+        let backfill = db
+            .disappeared_issues()
+            .map(|(repo_id, issue_id, name)| {
+                let paginator = GetClosedIssue::new(issue_id.clone(), issue_fields);
+                ((repo_id, issue_id, name), paginator)
+            })
+            .collect::<Vec<_>>();
+        progress!(
+            "Backfilling closing details for {} issues …",
+            backfill.len()
+        );
+        let phase = client.phase("backfill closed issues");
+        let start = Instant::now();
+        let IsolatedResults { results, failures } = client.batch_paginate_isolated(backfill);
+        let elapsed = start.elapsed();
+        drop(phase);
+        dumps.dump("backfill closed issues", &results)?;
+        for f in failures {
+            let (_, _, name) = f.key;
+            eprintln!("[!] Failed to backfill {name}: {:#}", f.error);
+        }
+        for pr in results {
+            let (repo_id, issue_id, _) = pr.key;
+            let Some(fullname) = db.get(&repo_id).map(|repo| repo.repository().fullname()) else {
+                continue;
+            };
+            let mut current = pr.items.into_iter().next();
+            if let Some(issue) = current.as_mut() {
+                if let Err(e) = issue.canonicalize_url(&web_url, &fullname) {
+                    eprintln!("[!] {e}");
+                }
+                if let Some(labels) = issue.labels.as_mut() {
+                    label_rules.normalize(labels);
+                }
+            }
+            if let Some(change) = db.resolve_disappeared(
+                &repo_id,
+                &issue_id,
+                current,
+                args.keep_closed.keeps_closed(),
+            ) {
+                issues_backfilled += 1;
+                changes.push(change);
+            }
+        }
+        progress!("Backfilled {issues_backfilled} closed issues in {elapsed:?}");
+    }
+
+    let closed_issues_pruned = db.prune_closed(args.keep_closed, SystemTime::now());
+    if closed_issues_pruned > 0 {
+        progress!("Pruned {closed_issues_pruned} closed issues past their retention period");
+    }
+
+    let cursors_reset = client.cursors_reset();
+    if cursors_reset > 0 {
+        eprintln!(
+            "[!] Restarted pagination from the beginning for {cursors_reset} queries whose cursors were rejected"
+        );
     }
-    eprintln!("[·] {idiff}");
 
     let big_elapsed = big_start.elapsed();
-    eprintln!("[·] Total fetch time: {big_elapsed:?}");
+    progress!("Total fetch time: {big_elapsed:?}");
 
-    let end_rate_limit = client.get_rate_limit()?;
+    let end_rate_limit = match client.last_rate_limit_headers() {
+        Some(rl) => RateLimit::from(rl),
+        None => client.get_rate_limit()?,
+    };
     let rate_limit_points = end_rate_limit.used_since(start_rate_limit);
+    let points_by_phase = client.points_by_phase();
+    let transfer = client.transfer_stats();
+    let partial_responses = client.partial_responses();
+    let page_size_reductions = client.page_size_reductions();
+    let query_hashes = client.query_hashes();
     if let Some(used) = rate_limit_points {
-        eprintln!("[·] Used {used} rate limit points");
+        progress!("Used {used} rate limit points");
     } else {
-        eprintln!("[·] Could not determine rate limit points used due to intervening reset");
-    }
-
-    if let Some(ref report_file) = args.report_file {
-        eprintln!("[·] Appending report to {} …", report_file.display());
-        let report = Report {
-            program: env!("CARGO_BIN_NAME"),
-            commit: option_env!("GIT_COMMIT"),
-            timestamp: humantime::format_rfc3339(timestamp).to_string(),
-            owners: args.owners.clone(),
-            parameters: Parameters {
-                batch_size: match args.batch_size {
-                    Some(bs) => bs.get(),
-                    None => DEFAULT_BATCH_SIZE,
-                },
-                page_size: args.page_size,
-            },
-            repositories: all_repos_qty,
-            open_issues: qty,
-            repos_with_open_issues: repo_qty,
-            repos_updated: rdiff.repos_touched(),
-            issues_updated: rdiff.closed_issues.saturating_add(idiff.issues_touched()),
-            elapsed: big_elapsed,
-            rate_limit_points,
-        };
-        append_json_lines(report_file, std::iter::once(report))
-            .context("failed to write report")?;
+        progress!("Could not determine rate limit points used due to intervening reset");
+    }
+    if !points_by_phase.is_empty() {
+        let by_phase = points_by_phase
+            .iter()
+            .map(|(phase, points)| format!("{phase}: {points}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        progress!("Rate limit points by phase: {by_phase}");
+    }
+    progress!("{transfer}");
+    if partial_responses > 0 {
+        eprintln!(
+            "[!] {partial_responses} responses included errors for some sub-queries, which were retried"
+        );
+    }
+    if page_size_reductions > 0 {
+        progress!("Lowered the page sizes of sub-queries {page_size_reductions} times");
+    }
+
+    let retained_issues = db.issues().count();
+    let retained_labels: usize = db
+        .issues()
+        .map(|issue| issue.labels.as_ref().map_or(0, Vec::len))
+        .sum();
+    let peak_rss_bytes = peak_rss();
+    if let Some(bytes) = peak_rss_bytes {
+        progress!("Peak memory usage: {} MiB", bytes / (1024 * 1024));
+    }
+    progress!("Retained {retained_issues} issues with {retained_labels} labels");
+    progress!("{owner_outcomes}");
+    for (owner, error) in owner_outcomes.failures() {
+        eprintln!("[!] {owner}: {error}");
+    }
+
+    let mut report = Report {
+        program: env!("CARGO_BIN_NAME"),
+        commit: option_env!("GIT_COMMIT"),
+        timestamp: humantime::format_rfc3339(timestamp).to_string(),
+        run_uuid: run_uuid.clone(),
+        owners,
+        owner_kinds,
+        parameters: Parameters {
+            batch_size,
+            page_size: args.common.page_size,
+            repo_order: args.common.repo_order,
+            privacy: args.common.privacy,
+            tls_backend: gqlient::TLS_BACKEND,
+            points_budget: args.points_budget,
+            deadline: args.deadline,
+            node_limit: args.node_limit,
+            skip_unchanged: args.skip_unchanged,
+            keep_closed: args.keep_closed,
+            include_prs: args.include_prs,
+            labels: issue_fields.labels,
+        },
+        repositories: all_repos_qty,
+        open_issues: qty,
+        pull_requests: pr_qty,
+        repos_with_open_issues: repo_qty,
+        repos_deferred,
+        repos_unchanged,
+        bot_issues_skipped,
+        repos_updated: rdiff.repos_touched(),
+        removed_repos,
+        issues_updated: rdiff
+            .closed_issues
+            .saturating_add(idiff.issues_touched())
+            .saturating_add(prdiff.issues_touched()),
+        issues_backfilled,
+        closed_issues_pruned,
+        cursors_reset,
+        repo_timings,
+        elapsed: big_elapsed,
+        rate_limit_points,
+        points_by_phase,
+        transfer,
+        partial_responses,
+        page_size_reductions,
+        query_hashes,
+        peak_rss_bytes,
+        retained_issues,
+        retained_labels,
+        owner_outcomes,
+    };
+
+    if let Some(ref anon) = anonymizer {
+        report.owners = report
+            .owners
+            .iter()
+            .map(|owner| anon.owner(owner))
+            .collect();
+        report.owner_kinds = anon.owner_kinds(&report.owner_kinds);
+        report.owner_outcomes = anon.owner_outcomes(&report.owner_outcomes);
+        for removed in &mut report.removed_repos {
+            removed.anonymize(anon);
+        }
+        for rt in &mut report.repo_timings {
+            rt.repo = anon.repo(&rt.repo);
+        }
+    }
+
+    if args.pretty_diff {
+        let mut shown = changes
+            .iter()
+            .filter(|change| args.filter.as_ref().map_or(true, |f| change.matches(f)))
+            .cloned()
+            .collect::<Vec<_>>();
+        if let Some(ref anon) = anonymizer {
+            for change in &mut shown {
+                change.anonymize(anon);
+            }
+        }
+        let stderr = std::io::stderr();
+        let color = stderr.is_terminal() && std::env::var_os("NO_COLOR").is_none();
+        write_pretty_diff(stderr.lock(), &shown, color).context("failed to write diff")?;
+    }
+
+    if let Some(ref history_file) = args.history {
+        progress!(
+            "Appending {} issue changes to {} …",
+            changes.len(),
+            history_file.display()
+        );
+        let timestamp = humantime::format_rfc3339(timestamp).to_string();
+        append_json_lines(
+            history_file,
+            changes
+                .into_iter()
+                .filter(|change| args.filter.as_ref().map_or(true, |f| change.matches(f)))
+                .map(|mut change| {
+                    if let Some(ref anon) = anonymizer {
+                        change.anonymize(anon);
+                    }
+                    HistoryEntry {
+                        timestamp: timestamp.clone(),
+                        run_uuid: run_uuid.clone(),
+                        change,
+                    }
+                }),
+        )
+        .context("failed to write history")?;
     }
 
+    args.common.save_report(&report)?;
+
     if let Some(outfile) = args.outfile() {
-        eprintln!("[·] Dumping to {outfile:#} …");
-        db.dump(outfile.create()?)?;
+        progress!("Dumping to {outfile:#} …");
+        match anonymizer {
+            Some(ref anon) => db.anonymized(anon).dump(outfile.create()?)?,
+            None => db.dump(outfile.create()?)?,
+        }
+    }
+
+    if let Some(ref outfile) = args.label_matrix {
+        progress!("Writing label co-occurrence matrix to {outfile:#} …");
+        let mut matrix = LabelMatrix::new();
+        for issue in db.issues().filter(|iss| {
+            iss.state == IssueState::Open && args.filter.as_ref().map_or(true, |f| f.matches(iss))
+        }) {
+            match anonymizer {
+                Some(ref anon) => matrix.add(
+                    &issue
+                        .labels
+                        .iter()
+                        .flatten()
+                        .map(|lbl| anon.label(lbl))
+                        .collect::<Vec<_>>(),
+                ),
+                None => matrix.add(issue.labels.as_deref().unwrap_or_default()),
+            }
+        }
+        write_label_matrix(&matrix, outfile)?;
+    }
+
+    if args.common.summary == SummaryFormat::Json {
+        let mut stdout = std::io::stdout().lock();
+        serde_json::to_writer(&mut stdout, &report).context("failed to write summary")?;
+        writeln!(stdout).context("failed to write summary")?;
+        stdout.flush().context("failed to flush stdout")?;
+    }
+
+    client.export_traces()?;
+
+    let mut hook_failed = false;
+    if let Some(ref command) = args.common.on_complete {
+        progress!("Running on-complete command …");
+        if let Err(e) = run_on_complete(command, &report) {
+            eprintln!("[!] {e:#}");
+            hook_failed = true;
+        }
+    }
+
+    if report.owner_outcomes.failed() > 0 {
+        bail!(
+            "failed to fetch data for {} of {} owners",
+            report.owner_outcomes.failed(),
+            report.owners.len()
+        );
     }
 
-    Ok(())
+    Ok(if hook_failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    })
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+struct HistoryEntry {
+    timestamp: String,
+    run_uuid: String,
+    #[serde(flatten)]
+    change: IssueChange,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Serialize)]
@@ -176,19 +794,75 @@ struct Report {
     program: &'static str,
     commit: Option<&'static str>,
     timestamp: String,
+    run_uuid: String,
     owners: Vec<String>,
+    owner_kinds: BTreeMap<String, OwnerKind>,
     parameters: Parameters,
     repositories: usize,
     open_issues: usize,
+    pull_requests: usize,
     repos_with_open_issues: usize,
+    repos_deferred: usize,
+    repos_unchanged: usize,
+    bot_issues_skipped: usize,
     repos_updated: usize,
+    removed_repos: Vec<RemovedRepo>,
     issues_updated: usize,
+    issues_backfilled: usize,
+    closed_issues_pruned: usize,
+    cursors_reset: usize,
+    repo_timings: Vec<RepoTiming>,
+    #[serde(serialize_with = "gqlient::serialize_duration")]
     elapsed: Duration,
     rate_limit_points: Option<u32>,
+    points_by_phase: BTreeMap<String, u64>,
+    transfer: TransferStats,
+    partial_responses: usize,
+    page_size_reductions: usize,
+    query_hashes: BTreeMap<String, u64>,
+    peak_rss_bytes: Option<u64>,
+    retained_issues: usize,
+    retained_labels: usize,
+    owner_outcomes: OwnerOutcomes,
+}
+
+/// How long it took to fetch the updated issues for a repository in which
+/// issues were changed
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+struct RepoTiming {
+    repo: String,
+    #[serde(serialize_with = "gqlient::serialize_duration")]
+    elapsed: Duration,
+    requests: usize,
+    issues_updated: usize,
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize)]
 struct Parameters {
     batch_size: usize,
     page_size: NonZeroUsize,
+    repo_order: RepoOrder,
+    privacy: RepoPrivacy,
+    tls_backend: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    points_budget: Option<u32>,
+    #[serde(
+        serialize_with = "gqlient::serialize_opt_duration",
+        skip_serializing_if = "Option::is_none"
+    )]
+    deadline: Option<Duration>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    node_limit: Option<u64>,
+    skip_unchanged: bool,
+    keep_closed: KeepClosed,
+    include_prs: bool,
+    labels: bool,
+}
+
+/// Record that the owner of the repository with the given ID had some of its
+/// repositories deferred to a later run
+fn skip_repo_owner(outcomes: &mut OwnerOutcomes, db: &Database, repo_id: &Id) {
+    if let Some(repo) = db.get(repo_id) {
+        outcomes.skip(&repo.repository().owner);
+    }
 }
@@ -0,0 +1,230 @@
+use crate::db::IssueChange;
+use crate::types::{Issue, IssueState};
+use anstyle::{AnsiColor, Color, Style};
+use gqlient::Id;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::io::{self, Write};
+
+const REPO: Style = Style::new().bold();
+const ADDED: Style = fg(AnsiColor::Green);
+const CLOSED: Style = fg(AnsiColor::Red);
+const MODIFIED: Style = fg(AnsiColor::Yellow);
+const DIM: Style = Style::new().dimmed();
+
+const fn fg(color: AnsiColor) -> Style {
+    Style::new().fg_color(Some(Color::Ansi(color)))
+}
+
+/// Write the given issue changes to `out` as a console report with added,
+/// closed, and modified sections for each repository, using ANSI colors if
+/// `color` is true.
+///
+/// Successive changes to the same issue (such as an issue dropped along with
+/// its repository's open issues and then backfilled by `--backfill-closed`)
+/// are merged into a single change from the first old state to the last new
+/// state.
+pub(crate) fn write_pretty_diff<'a, W, I>(mut out: W, changes: I, color: bool) -> io::Result<()>
+where
+    W: Write,
+    I: IntoIterator<Item = &'a IssueChange>,
+{
+    let mut repos: BTreeMap<&str, BTreeMap<&Id, Merged<'a>>> = BTreeMap::new();
+    for change in changes {
+        repos
+            .entry(&change.repo)
+            .or_default()
+            .entry(&change.issue_id)
+            .and_modify(|m| m.new = change.new.as_ref())
+            .or_insert(Merged {
+                old: change.old.as_ref(),
+                new: change.new.as_ref(),
+            });
+    }
+    let p = Painter(color);
+    let mut any = false;
+    for (repo, issues) in repos {
+        let sections = Sections::new(issues.into_values());
+        if sections.is_empty() {
+            continue;
+        }
+        if any {
+            writeln!(out)?;
+        }
+        any = true;
+        writeln!(out, "{}", p.paint(REPO, repo))?;
+        if !sections.added.is_empty() {
+            writeln!(
+                out,
+                "  {}",
+                p.paint(ADDED, format_args!("Added ({})", sections.added.len()))
+            )?;
+            for issue in sections.added {
+                write!(out, "    {} {}", p.paint(ADDED, "+"), summary(issue))?;
+                if let Some(labels) = issue.labels.as_ref().filter(|lbls| !lbls.is_empty()) {
+                    write!(
+                        out,
+                        " {}",
+                        p.paint(DIM, format_args!("[{}]", labels.join(", ")))
+                    )?;
+                }
+                writeln!(out)?;
+            }
+        }
+        if !sections.closed.is_empty() {
+            writeln!(
+                out,
+                "  {}",
+                p.paint(CLOSED, format_args!("Closed ({})", sections.closed.len()))
+            )?;
+            for (old, new) in sections.closed {
+                let how = match new.map(|iss| iss.state_reason.as_deref()) {
+                    Some(Some(reason)) => reason.to_lowercase().replace('_', " "),
+                    Some(None) => String::from("closed"),
+                    None => String::from("no longer open; not yet backfilled"),
+                };
+                writeln!(
+                    out,
+                    "    {} {} {}",
+                    p.paint(CLOSED, "-"),
+                    summary(new.unwrap_or(old)),
+                    p.paint(DIM, format_args!("({how})"))
+                )?;
+            }
+        }
+        if !sections.modified.is_empty() {
+            writeln!(
+                out,
+                "  {}",
+                p.paint(
+                    MODIFIED,
+                    format_args!("Modified ({})", sections.modified.len())
+                )
+            )?;
+            for (old, new) in sections.modified {
+                write!(out, "    {} {}", p.paint(MODIFIED, "~"), summary(new))?;
+                // Label changes can only be shown when both states include
+                // labels, i.e., when both were fetched with labels enabled
+                if let (Some(old_labels), Some(new_labels)) = (&old.labels, &new.labels) {
+                    for label in new_labels {
+                        if !old_labels.contains(label) {
+                            write!(out, " {}", p.paint(ADDED, format_args!("+{label}")))?;
+                        }
+                    }
+                    for label in old_labels {
+                        if !new_labels.contains(label) {
+                            write!(out, " {}", p.paint(CLOSED, format_args!("-{label}")))?;
+                        }
+                    }
+                }
+                writeln!(out)?;
+                if old.title != new.title {
+                    writeln!(
+                        out,
+                        "        {}",
+                        p.paint(DIM, format_args!("was: {:?}", old.title))
+                    )?;
+                }
+                let others = other_changes(old, new);
+                if !others.is_empty() {
+                    writeln!(
+                        out,
+                        "        {}",
+                        p.paint(DIM, format_args!("also changed: {}", others.join(", ")))
+                    )?;
+                }
+            }
+        }
+    }
+    if !any {
+        writeln!(out, "No issue changes")?;
+    }
+    out.flush()
+}
+
+/// An issue's state before its first change in a run and after its last
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Merged<'a> {
+    old: Option<&'a Issue>,
+    new: Option<&'a Issue>,
+}
+
+/// A repository's merged issue changes, sorted into report sections by issue
+/// number
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+struct Sections<'a> {
+    added: Vec<&'a Issue>,
+    /// Closed issues' old states paired with their new states, if known
+    closed: Vec<(&'a Issue, Option<&'a Issue>)>,
+    modified: Vec<(&'a Issue, &'a Issue)>,
+}
+
+impl<'a> Sections<'a> {
+    fn new<I: IntoIterator<Item = Merged<'a>>>(iter: I) -> Sections<'a> {
+        let mut sections = Sections::default();
+        for m in iter {
+            match (m.old, m.new) {
+                (None, Some(new)) => sections.added.push(new),
+                (Some(old), Some(new))
+                    if old.state == IssueState::Open && new.state != IssueState::Open =>
+                {
+                    sections.closed.push((old, Some(new)));
+                }
+                (Some(old), None) => sections.closed.push((old, None)),
+                (Some(old), Some(new)) => sections.modified.push((old, new)),
+                (None, None) => (),
+            }
+        }
+        sections.added.sort_by_key(|iss| iss.number);
+        sections.closed.sort_by_key(|(iss, _)| iss.number);
+        sections.modified.sort_by_key(|(_, iss)| iss.number);
+        sections
+    }
+
+    fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.closed.is_empty() && self.modified.is_empty()
+    }
+}
+
+fn summary(issue: &Issue) -> String {
+    format!("#{} {}", issue.number, issue.title)
+}
+
+/// Return the names of the fields other than the title and labels that
+/// differ between two states of an issue
+fn other_changes(old: &Issue, new: &Issue) -> Vec<&'static str> {
+    let mut fields = Vec::new();
+    if old.author != new.author {
+        fields.push("author");
+    }
+    if old.database_id != new.database_id {
+        fields.push("database_id");
+    }
+    if old.url != new.url {
+        fields.push("url");
+    }
+    if old.has_linked_pr != new.has_linked_pr {
+        fields.push("has_linked_pr");
+    }
+    if old.parent != new.parent {
+        fields.push("parent");
+    }
+    if old.sub_issues != new.sub_issues {
+        fields.push("sub_issues");
+    }
+    fields
+}
+
+/// Applies styles to text if coloring is enabled
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Painter(bool);
+
+impl Painter {
+    fn paint<T: Display>(self, style: Style, text: T) -> String {
+        if self.0 {
+            format!("{}{text}{}", style.render(), style.render_reset())
+        } else {
+            text.to_string()
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use gqlient::Variable;
+
+/// The optional fields fetched for each issue.  Each field is fetched (via
+/// `@include`) only if the `Boolean!` variable of the same name is true.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub(crate) struct IssueFields {
+    /// Fetch the issue's `databaseId` (`--database-ids`)
+    pub(crate) database_ids: bool,
+    /// Fetch the issue's labels (`--labels`, `--label-rules`, or
+    /// `--label-matrix`)
+    pub(crate) labels: bool,
+    /// Fetch whether the issue has a linked pull request (`--linked-prs`)
+    pub(crate) linked_prs: bool,
+    /// Fetch the issue's parent & sub-issue counts (`--sub-issues`)
+    pub(crate) sub_issues: bool,
+}
+
+impl IssueFields {
+    /// Return the number of nodes that each issue counts for toward GitHub's
+    /// node limit: one for the issue itself, plus one for each label that can
+    /// be fetched for it and one for its linked pull request, if requested
+    pub(crate) fn nodes_per_issue(&self) -> u64 {
+        1 + if self.labels { 100 } else { 0 } + u64::from(self.linked_prs)
+    }
+
+    /// Return the query variables for the fields
+    pub(crate) fn variables(&self) -> impl Iterator<Item = (String, Variable)> {
+        [
+            ("database_ids", self.database_ids),
+            ("labels", self.labels),
+            ("linked_prs", self.linked_prs),
+            ("sub_issues", self.sub_issues),
+        ]
+        .into_iter()
+        .map(|(name, value)| {
+            (
+                String::from(name),
+                Variable {
+                    gql_type: String::from("Boolean!"),
+                    value: value.into(),
+                },
+            )
+        })
+    }
+}
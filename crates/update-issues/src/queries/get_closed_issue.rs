@@ -0,0 +1,121 @@
+use super::IssueFields;
+use crate::types::Issue;
+use gqlient::{Cursor, Id, Page, Paginator, Query, Variable};
+use indoc::indoc;
+use std::fmt::{self, Write};
+
+/// Fetches the current state of a single issue by node ID, including when
+/// and why it was closed.  The results are always a single page, which is
+/// empty if the issue no longer exists or cannot be accessed.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct GetClosedIssue {
+    issue_id: Id,
+    fields: IssueFields,
+}
+
+impl GetClosedIssue {
+    pub(crate) fn new(issue_id: Id, fields: IssueFields) -> GetClosedIssue {
+        GetClosedIssue { issue_id, fields }
+    }
+}
+
+impl Paginator for GetClosedIssue {
+    type Item = Issue;
+    type Query = GetClosedIssueQuery;
+
+    fn for_cursor(&self, _cursor: Option<&Cursor>) -> GetClosedIssueQuery {
+        GetClosedIssueQuery {
+            issue_id: self.issue_id.clone(),
+            fields: self.fields,
+            prefix: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct GetClosedIssueQuery {
+    issue_id: Id,
+    fields: IssueFields,
+    prefix: Option<String>,
+}
+
+impl GetClosedIssueQuery {
+    fn issue_id_varname(&self) -> String {
+        match self.prefix {
+            Some(ref prefix) => format!("{prefix}_issue_id"),
+            None => String::from("issue_id"),
+        }
+    }
+}
+
+impl Query for GetClosedIssueQuery {
+    type Output = Page<Issue>;
+
+    fn with_variable_prefix(mut self, prefix: String) -> Self {
+        self.prefix = Some(prefix);
+        self
+    }
+
+    fn write_graphql<W: Write>(&self, mut s: W) -> fmt::Result {
+        writeln!(
+            s,
+            indoc! {"
+            node(id: ${issue_id_varname}) {{
+                ... on Issue {{
+                    number
+                    databaseId @include(if: $database_ids)
+                    title
+                    author {{
+                        login
+                        __typename
+                    }}
+                    state
+                    stateReason
+                    closedAt
+                    url
+                    closedByPullRequestsReferences(first: 1) @include(if: $linked_prs) {{
+                        totalCount
+                    }}
+                    parent @include(if: $sub_issues) {{
+                        number
+                        repository {{
+                            nameWithOwner
+                        }}
+                    }}
+                    subIssuesSummary @include(if: $sub_issues) {{
+                        total
+                        completed
+                    }}
+                    labels(first: 100) @include(if: $labels) {{
+                        nodes {{
+                            name
+                        }}
+                    }}
+                }}
+            }}
+        "},
+            issue_id_varname = self.issue_id_varname(),
+        )
+    }
+
+    fn variables(&self) -> Vec<(String, Variable)> {
+        let mut variables = vec![(
+            self.issue_id_varname(),
+            Variable {
+                gql_type: String::from("ID!"),
+                value: self.issue_id.clone().into(),
+            },
+        )];
+        variables.extend(self.fields.variables());
+        variables
+    }
+
+    fn parse_response(&self, value: serde_json::Value) -> Result<Page<Issue>, serde_json::Error> {
+        let issue = gqlient::from_value::<Option<Issue>>(value)?;
+        Ok(Page {
+            items: issue.into_iter().collect(),
+            end_cursor: None,
+            has_next_page: false,
+        })
+    }
+}
@@ -1,3 +1,4 @@
+use super::IssueFields;
 use crate::types::Issue;
 use gqlient::{Cursor, Id, Ided, Page, Paginator, Query, Singleton, Variable};
 use indoc::indoc;
@@ -9,19 +10,48 @@ pub(crate) struct GetIssues {
     repo_id: Id,
     cursor: Option<Cursor>,
     page_size: NonZeroUsize,
+    fields: IssueFields,
     include_closed: bool,
+    /// Whether the page size differs from the one given on the command line
+    /// (due to [`GetIssues::expecting()`] or
+    /// [`Paginator::reduce_page_size()`]) and so must be passed in a variable
+    /// of its own
+    own_page_size: bool,
 }
 
 impl GetIssues {
-    pub(crate) fn new(repo_id: Id, cursor: Option<Cursor>, page_size: NonZeroUsize) -> GetIssues {
+    pub(crate) fn new(
+        repo_id: Id,
+        cursor: Option<Cursor>,
+        page_size: NonZeroUsize,
+        fields: IssueFields,
+    ) -> GetIssues {
         let include_closed = cursor.is_some();
         GetIssues {
             repo_id,
             cursor,
             page_size,
+            fields,
             include_closed,
+            own_page_size: false,
         }
     }
+
+    /// Request pages of no more than `issues` issues (or one issue, if
+    /// `issues` is zero), so that a repository expected to have few issues
+    /// to fetch counts for fewer nodes toward the node limit of each request
+    /// that it is in
+    pub(crate) fn expecting(mut self, issues: u64) -> GetIssues {
+        let expected = usize::try_from(issues)
+            .ok()
+            .and_then(NonZeroUsize::new)
+            .unwrap_or(NonZeroUsize::MIN);
+        if expected < self.page_size {
+            self.page_size = expected;
+            self.own_page_size = true;
+        }
+        self
+    }
 }
 
 impl Paginator for GetIssues {
@@ -37,10 +67,33 @@ impl Paginator for GetIssues {
             repo_id: self.repo_id.clone(),
             cursor,
             page_size: self.page_size,
+            fields: self.fields,
             include_closed: self.include_closed,
+            own_page_size: self.own_page_size,
             prefix: None,
         }
     }
+
+    /// Restart from the beginning of the repository's issues, still fetching
+    /// closed issues if a cursor was given, so that any issues closed since
+    /// the cursor was saved are still removed from the database
+    fn reset_cursor(&mut self) {
+        self.cursor = None;
+    }
+
+    /// See [`IssueFields::nodes_per_issue()`]
+    fn nodes_per_page(&self) -> Option<u64> {
+        let page_size = u64::try_from(self.page_size.get()).unwrap_or(u64::MAX);
+        Some(page_size.saturating_mul(self.fields.nodes_per_issue()))
+    }
+
+    /// Halve the page size, down to a minimum of one issue per page
+    fn reduce_page_size(&mut self) -> Option<NonZeroUsize> {
+        let page_size = NonZeroUsize::new(self.page_size.get() / 2)?;
+        self.page_size = page_size;
+        self.own_page_size = true;
+        Some(page_size)
+    }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -48,7 +101,9 @@ pub(crate) struct GetIssuesQuery {
     repo_id: Id,
     cursor: Option<Cursor>,
     page_size: NonZeroUsize,
+    fields: IssueFields,
     include_closed: bool,
+    own_page_size: bool,
     prefix: Option<String>,
 }
 
@@ -60,10 +115,20 @@ impl GetIssuesQuery {
         }
     }
 
+    /// Queries without a cursor all share a single null `$cursor` variable
     fn cursor_varname(&self) -> String {
+        match (&self.prefix, &self.cursor) {
+            (Some(prefix), Some(_)) => format!("{prefix}_cursor"),
+            _ => String::from("cursor"),
+        }
+    }
+
+    /// Queries with the default page size all share a single `$page_size`
+    /// variable
+    fn page_size_varname(&self) -> String {
         match self.prefix {
-            Some(ref prefix) => format!("{prefix}_cursor"),
-            None => String::from("cursor"),
+            Some(ref prefix) if self.own_page_size => format!("{prefix}_page_size"),
+            _ => String::from("page_size"),
         }
     }
 }
@@ -83,7 +148,7 @@ impl Query for GetIssuesQuery {
             node(id: ${repo_id_varname}) {{
                 ... on Repository {{
                     issues(
-                        first: {page_size},
+                        first: ${page_size_varname},
                         after: ${cursor_varname},
                         orderBy: {{field: UPDATED_AT, direction: ASC}},
                         states: [{states}],
@@ -91,9 +156,33 @@ impl Query for GetIssuesQuery {
                         nodes {{
                             id
                             number
+                            databaseId @include(if: $database_ids)
                             title
+                            author {{
+                                login
+                                __typename
+                            }}
                             state
+                            closedAt
                             url
+                            closedByPullRequestsReferences(first: 1) @include(if: $linked_prs) {{
+                                totalCount
+                            }}
+                            parent @include(if: $sub_issues) {{
+                                number
+                                repository {{
+                                    nameWithOwner
+                                }}
+                            }}
+                            subIssuesSummary @include(if: $sub_issues) {{
+                                total
+                                completed
+                            }}
+                            labels(first: 100) @include(if: $labels) {{
+                                nodes {{
+                                    name
+                                }}
+                            }}
                         }}
                         pageInfo {{
                             endCursor
@@ -105,7 +194,7 @@ impl Query for GetIssuesQuery {
         "},
             repo_id_varname = self.repo_id_varname(),
             cursor_varname = self.cursor_varname(),
-            page_size = self.page_size,
+            page_size_varname = self.page_size_varname(),
             states = if self.include_closed {
                 "OPEN, CLOSED"
             } else {
@@ -114,8 +203,8 @@ impl Query for GetIssuesQuery {
         )
     }
 
-    fn variables(&self) -> [(String, Variable); 2] {
-        [
+    fn variables(&self) -> Vec<(String, Variable)> {
+        let mut variables = vec![
             (
                 self.repo_id_varname(),
                 Variable {
@@ -130,10 +219,19 @@ impl Query for GetIssuesQuery {
                     value: self.cursor.clone().into(),
                 },
             ),
-        ]
+            (
+                self.page_size_varname(),
+                Variable {
+                    gql_type: String::from("Int!"),
+                    value: self.page_size.get().into(),
+                },
+            ),
+        ];
+        variables.extend(self.fields.variables());
+        variables
     }
 
     fn parse_response(&self, value: serde_json::Value) -> Result<Self::Output, serde_json::Error> {
-        serde_json::from_value::<Singleton<Self::Output>>(value).map(|r| r.0)
+        gqlient::from_value::<Singleton<Self::Output>>(value).map(|r| r.0)
     }
 }
@@ -1,5 +1,5 @@
 use crate::types::RepoDetails;
-use gqlient::{Cursor, Ided, Page, Paginator, Query, Singleton, Variable};
+use gqlient::{Cursor, Ided, Page, Paginator, Query, RepoOrder, RepoPrivacy, Singleton, Variable};
 use indoc::indoc;
 use std::fmt::{self, Write};
 use std::num::NonZeroUsize;
@@ -8,11 +8,32 @@ use std::num::NonZeroUsize;
 pub(crate) struct GetOwnerRepos {
     owner: String,
     page_size: NonZeroUsize,
+    order: RepoOrder,
+    privacy: RepoPrivacy,
+    activity: bool,
+    database_ids: bool,
+    include_prs: bool,
 }
 
 impl GetOwnerRepos {
-    pub(crate) fn new(owner: String, page_size: NonZeroUsize) -> GetOwnerRepos {
-        GetOwnerRepos { owner, page_size }
+    pub(crate) fn new(
+        owner: String,
+        page_size: NonZeroUsize,
+        order: RepoOrder,
+        privacy: RepoPrivacy,
+        activity: bool,
+        database_ids: bool,
+        include_prs: bool,
+    ) -> GetOwnerRepos {
+        GetOwnerRepos {
+            owner,
+            page_size,
+            order,
+            privacy,
+            activity,
+            database_ids,
+            include_prs,
+        }
     }
 }
 
@@ -21,7 +42,16 @@ impl Paginator for GetOwnerRepos {
     type Query = GetOwnerReposQuery;
 
     fn for_cursor(&self, cursor: Option<&Cursor>) -> GetOwnerReposQuery {
-        GetOwnerReposQuery::new(self.owner.clone(), cursor.cloned(), self.page_size)
+        GetOwnerReposQuery::new(
+            self.owner.clone(),
+            cursor.cloned(),
+            self.page_size,
+            self.order,
+            self.privacy,
+            self.activity,
+            self.database_ids,
+            self.include_prs,
+        )
     }
 }
 
@@ -30,15 +60,35 @@ pub(crate) struct GetOwnerReposQuery {
     owner: String,
     cursor: Option<Cursor>,
     page_size: NonZeroUsize,
+    order: RepoOrder,
+    privacy: RepoPrivacy,
+    activity: bool,
+    database_ids: bool,
+    include_prs: bool,
     prefix: Option<String>,
 }
 
 impl GetOwnerReposQuery {
-    fn new(owner: String, cursor: Option<Cursor>, page_size: NonZeroUsize) -> GetOwnerReposQuery {
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        owner: String,
+        cursor: Option<Cursor>,
+        page_size: NonZeroUsize,
+        order: RepoOrder,
+        privacy: RepoPrivacy,
+        activity: bool,
+        database_ids: bool,
+        include_prs: bool,
+    ) -> GetOwnerReposQuery {
         GetOwnerReposQuery {
             owner,
             cursor,
             page_size,
+            order,
+            privacy,
+            activity,
+            database_ids,
+            include_prs,
             prefix: None,
         }
     }
@@ -50,10 +100,11 @@ impl GetOwnerReposQuery {
         }
     }
 
+    /// Queries without a cursor all share a single null `$cursor` variable
     fn cursor_varname(&self) -> String {
-        match self.prefix {
-            Some(ref prefix) => format!("{prefix}_cursor"),
-            None => String::from("cursor"),
+        match (&self.prefix, &self.cursor) {
+            (Some(prefix), Some(_)) => format!("{prefix}_cursor"),
+            _ => String::from("cursor"),
         }
     }
 }
@@ -72,12 +123,12 @@ impl Query for GetOwnerReposQuery {
             indoc! {"
             repositoryOwner(login: ${owner_varname}) {{
                 repositories(
-                    orderBy: {{field: NAME, direction: ASC}},
+                    orderBy: $order,
                     ownerAffiliations: [OWNER],
                     isArchived: false,
                     isFork: false,
-                    privacy: PUBLIC,
-                    first: {page_size},
+                    privacy: $privacy,
+                    first: $page_size,
                     after: ${cursor_varname},
                 ) {{
                     nodes {{
@@ -86,9 +137,28 @@ impl Query for GetOwnerReposQuery {
                             login
                         }}
                         name
+                        databaseId @include(if: $database_ids)
+                        visibility
+                        isTemplate
+                        viewerPermission
                         issues (states: [OPEN]) {{
                             totalCount
                         }}
+                        openPullRequests: pullRequests(states: [OPEN]) @include(if: $include_prs) {{
+                            totalCount
+                        }}
+                        stargazerCount
+                        forkCount
+                        pushedAt @include(if: $activity)
+                        updatedAt @include(if: $activity)
+                        latestIssue: issues(
+                            first: 1,
+                            orderBy: {{field: UPDATED_AT, direction: DESC}},
+                        ) @include(if: $activity) {{
+                            nodes {{
+                                updatedAt
+                            }}
+                        }}
                     }}
                     pageInfo {{
                         endCursor
@@ -99,11 +169,10 @@ impl Query for GetOwnerReposQuery {
         "},
             owner_varname = self.owner_varname(),
             cursor_varname = self.cursor_varname(),
-            page_size = self.page_size,
         )
     }
 
-    fn variables(&self) -> [(String, Variable); 2] {
+    fn variables(&self) -> [(String, Variable); 8] {
         [
             (
                 self.owner_varname(),
@@ -119,10 +188,40 @@ impl Query for GetOwnerReposQuery {
                     value: self.cursor.clone().into(),
                 },
             ),
+            (
+                String::from("page_size"),
+                Variable {
+                    gql_type: String::from("Int!"),
+                    value: self.page_size.get().into(),
+                },
+            ),
+            (String::from("order"), self.order.as_variable()),
+            (String::from("privacy"), self.privacy.as_variable()),
+            (
+                String::from("activity"),
+                Variable {
+                    gql_type: String::from("Boolean!"),
+                    value: self.activity.into(),
+                },
+            ),
+            (
+                String::from("database_ids"),
+                Variable {
+                    gql_type: String::from("Boolean!"),
+                    value: self.database_ids.into(),
+                },
+            ),
+            (
+                String::from("include_prs"),
+                Variable {
+                    gql_type: String::from("Boolean!"),
+                    value: self.include_prs.into(),
+                },
+            ),
         ]
     }
 
     fn parse_response(&self, value: serde_json::Value) -> Result<Self::Output, serde_json::Error> {
-        serde_json::from_value::<Singleton<Self::Output>>(value).map(|r| r.0)
+        gqlient::from_value::<Singleton<Self::Output>>(value).map(|r| r.0)
     }
 }
@@ -0,0 +1,228 @@
+use crate::types::Issue;
+use gqlient::{Cursor, Id, Ided, Page, Paginator, Query, Singleton, Variable};
+use indoc::indoc;
+use std::fmt::{self, Write};
+use std::num::NonZeroUsize;
+
+/// Paginates over the pull requests of a repository for `--include-prs`,
+/// fetching the fields that issues and pull requests have in common.  Like
+/// [`GetIssues`](super::GetIssues), only open pull requests are fetched for a
+/// repository without a cursor, and pull requests in all states updated since
+/// the cursor are fetched otherwise.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct GetPullRequests {
+    repo_id: Id,
+    cursor: Option<Cursor>,
+    page_size: NonZeroUsize,
+    database_ids: bool,
+    labels: bool,
+    include_closed: bool,
+    /// Whether the page size has been lowered from the one given on the
+    /// command line and so must be passed in a variable of its own
+    own_page_size: bool,
+}
+
+impl GetPullRequests {
+    pub(crate) fn new(
+        repo_id: Id,
+        cursor: Option<Cursor>,
+        page_size: NonZeroUsize,
+        database_ids: bool,
+        labels: bool,
+    ) -> GetPullRequests {
+        let include_closed = cursor.is_some();
+        GetPullRequests {
+            repo_id,
+            cursor,
+            page_size,
+            database_ids,
+            labels,
+            include_closed,
+            own_page_size: false,
+        }
+    }
+}
+
+impl Paginator for GetPullRequests {
+    type Item = Ided<Issue>;
+    type Query = GetPullRequestsQuery;
+
+    fn for_cursor(&self, cursor: Option<&Cursor>) -> GetPullRequestsQuery {
+        let cursor = match cursor {
+            Some(c) => Some(c.clone()),
+            None => self.cursor.clone(),
+        };
+        GetPullRequestsQuery {
+            repo_id: self.repo_id.clone(),
+            cursor,
+            page_size: self.page_size,
+            database_ids: self.database_ids,
+            labels: self.labels,
+            include_closed: self.include_closed,
+            own_page_size: self.own_page_size,
+            prefix: None,
+        }
+    }
+
+    /// Restart from the beginning of the repository's pull requests, still
+    /// fetching closed & merged pull requests if a cursor was given
+    fn reset_cursor(&mut self) {
+        self.cursor = None;
+    }
+
+    /// Each pull request counts as one node, plus one for each label that can
+    /// be fetched for it if labels are requested
+    fn nodes_per_page(&self) -> Option<u64> {
+        let page_size = u64::try_from(self.page_size.get()).unwrap_or(u64::MAX);
+        Some(page_size.saturating_mul(if self.labels { 101 } else { 1 }))
+    }
+
+    /// Halve the page size, down to a minimum of one pull request per page
+    fn reduce_page_size(&mut self) -> Option<NonZeroUsize> {
+        let page_size = NonZeroUsize::new(self.page_size.get() / 2)?;
+        self.page_size = page_size;
+        self.own_page_size = true;
+        Some(page_size)
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct GetPullRequestsQuery {
+    repo_id: Id,
+    cursor: Option<Cursor>,
+    page_size: NonZeroUsize,
+    database_ids: bool,
+    labels: bool,
+    include_closed: bool,
+    own_page_size: bool,
+    prefix: Option<String>,
+}
+
+impl GetPullRequestsQuery {
+    fn repo_id_varname(&self) -> String {
+        match self.prefix {
+            Some(ref prefix) => format!("{prefix}_repo_id"),
+            None => String::from("repo_id"),
+        }
+    }
+
+    /// Queries without a cursor all share a single null `$cursor` variable
+    fn cursor_varname(&self) -> String {
+        match (&self.prefix, &self.cursor) {
+            (Some(prefix), Some(_)) => format!("{prefix}_cursor"),
+            _ => String::from("cursor"),
+        }
+    }
+
+    /// Queries with the default page size all share a single `$page_size`
+    /// variable
+    fn page_size_varname(&self) -> String {
+        match self.prefix {
+            Some(ref prefix) if self.own_page_size => format!("{prefix}_page_size"),
+            _ => String::from("page_size"),
+        }
+    }
+}
+
+impl Query for GetPullRequestsQuery {
+    type Output = Page<Ided<Issue>>;
+
+    fn with_variable_prefix(mut self, prefix: String) -> Self {
+        self.prefix = Some(prefix);
+        self
+    }
+
+    fn write_graphql<W: Write>(&self, mut s: W) -> fmt::Result {
+        writeln!(
+            s,
+            indoc! {"
+            node(id: ${repo_id_varname}) {{
+                ... on Repository {{
+                    pullRequests(
+                        first: ${page_size_varname},
+                        after: ${cursor_varname},
+                        orderBy: {{field: UPDATED_AT, direction: ASC}},
+                        states: [{states}],
+                    ) {{
+                        nodes {{
+                            __typename
+                            id
+                            number
+                            databaseId @include(if: $database_ids)
+                            title
+                            author {{
+                                login
+                                __typename
+                            }}
+                            state
+                            closedAt
+                            url
+                            labels(first: 100) @include(if: $labels) {{
+                                nodes {{
+                                    name
+                                }}
+                            }}
+                        }}
+                        pageInfo {{
+                            endCursor
+                            hasNextPage
+                        }}
+                    }}
+                }}
+            }}
+        "},
+            repo_id_varname = self.repo_id_varname(),
+            cursor_varname = self.cursor_varname(),
+            page_size_varname = self.page_size_varname(),
+            states = if self.include_closed {
+                "OPEN, CLOSED, MERGED"
+            } else {
+                "OPEN"
+            },
+        )
+    }
+
+    fn variables(&self) -> [(String, Variable); 5] {
+        [
+            (
+                self.repo_id_varname(),
+                Variable {
+                    gql_type: String::from("ID!"),
+                    value: self.repo_id.clone().into(),
+                },
+            ),
+            (
+                self.cursor_varname(),
+                Variable {
+                    gql_type: String::from("String"),
+                    value: self.cursor.clone().into(),
+                },
+            ),
+            (
+                self.page_size_varname(),
+                Variable {
+                    gql_type: String::from("Int!"),
+                    value: self.page_size.get().into(),
+                },
+            ),
+            (
+                String::from("database_ids"),
+                Variable {
+                    gql_type: String::from("Boolean!"),
+                    value: self.database_ids.into(),
+                },
+            ),
+            (
+                String::from("labels"),
+                Variable {
+                    gql_type: String::from("Boolean!"),
+                    value: self.labels.into(),
+                },
+            ),
+        ]
+    }
+
+    fn parse_response(&self, value: serde_json::Value) -> Result<Self::Output, serde_json::Error> {
+        gqlient::from_value::<Singleton<Self::Output>>(value).map(|r| r.0)
+    }
+}
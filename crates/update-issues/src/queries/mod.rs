@@ -1,4 +1,191 @@
+mod fields;
+mod get_closed_issue;
 mod get_issues;
 mod get_owner_repos;
+mod get_pull_requests;
+pub(crate) use self::fields::IssueFields;
+pub(crate) use self::get_closed_issue::GetClosedIssue;
 pub(crate) use self::get_issues::GetIssues;
 pub(crate) use self::get_owner_repos::GetOwnerRepos;
+pub(crate) use self::get_pull_requests::GetPullRequests;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fixtures::FakeOrg;
+    use gqlient::{compose_request, Cursor, Id, Paginator, Query, RepoOrder, RepoPrivacy};
+    use insta::assert_snapshot;
+    use std::num::NonZeroUsize;
+
+    fn render<Q: Query>(queries: Vec<Q>) -> String {
+        let (doc, variables) = compose_request(queries).unwrap();
+        format!(
+            "{doc}\n{}",
+            serde_json::to_string_pretty(&variables).unwrap()
+        )
+    }
+
+    fn cursor(s: &str) -> Cursor {
+        serde_json::from_value(s.into()).unwrap()
+    }
+
+    fn id(s: &str) -> Id {
+        serde_json::from_value(s.into()).unwrap()
+    }
+
+    #[test]
+    fn get_owner_repos_first_page() {
+        let paginator = GetOwnerRepos::new(
+            String::from("jwodder"),
+            NonZeroUsize::new(100).unwrap(),
+            RepoOrder::Name,
+            RepoPrivacy::Public,
+            false,
+            false,
+            false,
+        );
+        assert_snapshot!(render(vec![paginator.for_cursor(None)]));
+    }
+
+    #[test]
+    fn get_owner_repos_batch() {
+        let page_size = NonZeroUsize::new(25).unwrap();
+        let first = GetOwnerRepos::new(
+            String::from("jwodder"),
+            page_size,
+            RepoOrder::Name,
+            RepoPrivacy::Private,
+            true,
+            true,
+            true,
+        );
+        let second = GetOwnerRepos::new(
+            String::from("wheelodex"),
+            page_size,
+            RepoOrder::Name,
+            RepoPrivacy::Private,
+            true,
+            true,
+            true,
+        );
+        assert_snapshot!(render(vec![
+            first.for_cursor(None),
+            second.for_cursor(Some(&cursor("Y3Vyc29yOnYyOpHOABCDEF"))),
+        ]));
+    }
+
+    #[test]
+    fn get_issues_new_repo() {
+        let paginator = GetIssues::new(
+            id("R_kgDOABCDEF"),
+            None,
+            NonZeroUsize::new(100).unwrap(),
+            IssueFields::default(),
+        );
+        assert_snapshot!(render(vec![paginator.for_cursor(None)]));
+    }
+
+    #[test]
+    fn get_issues_batch() {
+        // A repository seen for the first time alongside one being updated
+        // from a saved cursor, which also fetches issues closed since then
+        let page_size = NonZeroUsize::new(25).unwrap();
+        let fields = IssueFields {
+            database_ids: true,
+            labels: true,
+            linked_prs: true,
+            sub_issues: true,
+        };
+        let first = GetIssues::new(id("R_kgDOABCDEF"), None, page_size, fields);
+        let second = GetIssues::new(
+            id("R_kgDOGHIJKL"),
+            Some(cursor("Y3Vyc29yOnYyOpHOGHIJKL")),
+            page_size,
+            fields,
+        );
+        assert_snapshot!(render(vec![
+            first.for_cursor(None),
+            second.for_cursor(None)
+        ]));
+    }
+
+    #[test]
+    fn get_issues_sized_batch() {
+        // A small repository whose page size was reduced to its open issue
+        // count alongside one using the default page size
+        let page_size = NonZeroUsize::new(100).unwrap();
+        let fields = IssueFields::default();
+        let first = GetIssues::new(id("R_kgDOABCDEF"), None, page_size, fields).expecting(3);
+        let second = GetIssues::new(id("R_kgDOGHIJKL"), None, page_size, fields).expecting(250);
+        assert_snapshot!(render(vec![
+            first.for_cursor(None),
+            second.for_cursor(None)
+        ]));
+    }
+
+    #[test]
+    fn get_pull_requests_batch() {
+        // As with issues, pull requests closed or merged since a saved cursor
+        // are fetched as well
+        let page_size = NonZeroUsize::new(25).unwrap();
+        let first = GetPullRequests::new(id("R_kgDOABCDEF"), None, page_size, false, true);
+        let second = GetPullRequests::new(
+            id("R_kgDOGHIJKL"),
+            Some(cursor("Y3Vyc29yOnYyOpHOGHIJKL")),
+            page_size,
+            false,
+            true,
+        );
+        assert_snapshot!(render(vec![
+            first.for_cursor(None),
+            second.for_cursor(None)
+        ]));
+    }
+
+    #[test]
+    fn get_closed_issue_batch() {
+        let first = GetClosedIssue::new(id("I_kwDOABCDEF"), IssueFields::default());
+        let second = GetClosedIssue::new(id("I_kwDOGHIJKL"), IssueFields::default());
+        assert_snapshot!(render(vec![
+            first.for_cursor(None),
+            second.for_cursor(None)
+        ]));
+    }
+
+    #[test]
+    fn get_issues_fake_repo() {
+        let org = FakeOrg::new("fake-org", 1, 250, 3);
+        let repo = &org.repos()[0];
+        let paginator = GetIssues::new(
+            id(&repo.id()),
+            None,
+            NonZeroUsize::new(100).unwrap(),
+            IssueFields {
+                labels: true,
+                ..IssueFields::default()
+            },
+        );
+        let mut after: Option<Cursor> = None;
+        let mut issues = Vec::new();
+        let mut pages = 0;
+        loop {
+            let after_str = after.as_ref().map(ToString::to_string);
+            let data = serde_json::json!({"issues": repo.issues_page(100, after_str.as_deref())});
+            let page = paginator
+                .for_cursor(after.as_ref())
+                .parse_response(data)
+                .unwrap();
+            issues.extend(page.items);
+            pages += 1;
+            if !page.has_next_page {
+                break;
+            }
+            after = page.end_cursor;
+        }
+        assert_eq!(pages, 3);
+        assert_eq!(issues.len(), org.total_issues());
+        assert!(issues
+            .iter()
+            .all(|issue| issue.data.labels.as_ref().map(Vec::len) == Some(3)));
+    }
+}
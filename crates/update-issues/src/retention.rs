@@ -0,0 +1,80 @@
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+
+/// How long closed issues are kept in the database, as set by `--keep-closed`
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub(crate) enum KeepClosed {
+    /// Closed issues are removed from the database as soon as they are seen
+    #[default]
+    None,
+    /// Closed issues are kept in the database indefinitely
+    Forever,
+    /// Closed issues are kept in the database until the given amount of time
+    /// has passed since they were closed
+    For(Duration),
+}
+
+impl KeepClosed {
+    /// Returns true if closed issues are kept in the database at all
+    pub(crate) fn keeps_closed(&self) -> bool {
+        *self != KeepClosed::None
+    }
+
+    /// Test whether an issue that was closed at `closed_at` (an RFC 3339
+    /// timestamp) has outlived the retention period as of `now`.  Issues
+    /// whose closing times are missing or cannot be parsed are only expired
+    /// if closed issues are not kept at all.
+    pub(crate) fn expired(&self, closed_at: Option<&str>, now: SystemTime) -> bool {
+        match self {
+            KeepClosed::None => true,
+            KeepClosed::Forever => false,
+            KeepClosed::For(period) => closed_at
+                .and_then(|ts| humantime::parse_rfc3339(ts).ok())
+                .and_then(|t| t.checked_add(*period))
+                .is_some_and(|expiry| expiry <= now),
+        }
+    }
+}
+
+impl fmt::Display for KeepClosed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeepClosed::None => f.write_str("none"),
+            KeepClosed::Forever => f.write_str("forever"),
+            KeepClosed::For(period) => write!(f, "{}", humantime::format_duration(*period)),
+        }
+    }
+}
+
+impl FromStr for KeepClosed {
+    type Err = ParseKeepClosedError;
+
+    fn from_str(s: &str) -> Result<KeepClosed, ParseKeepClosedError> {
+        match s {
+            "none" => Ok(KeepClosed::None),
+            "forever" => Ok(KeepClosed::Forever),
+            _ => humantime::parse_duration(s)
+                .map(KeepClosed::For)
+                .map_err(|_| ParseKeepClosedError),
+        }
+    }
+}
+
+impl Serialize for KeepClosed {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct ParseKeepClosedError;
+
+impl fmt::Display for ParseKeepClosedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(r#"invalid retention period; expected a duration, "forever", or "none""#)
+    }
+}
+
+impl std::error::Error for ParseKeepClosedError {}
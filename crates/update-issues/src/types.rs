@@ -1,26 +1,196 @@
-use serde::{Deserialize, Serialize};
+use gqlient::{
+    Anonymizer, Author, IssueKind, IssueUrlError, ParentIssue, RepoPermission, RepoVisibility,
+    SubIssues,
+};
+use serde::{de::Deserializer, Deserialize, Serialize};
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub(crate) struct RepoDetails {
     #[serde(deserialize_with = "gqlient::singleton_field")]
     pub(crate) owner: String,
     pub(crate) name: String,
+    /// The repository's numeric ID as used by the REST API; only fetched
+    /// when `--database-ids` is given
+    #[serde(default, alias = "databaseId", skip_serializing_if = "Option::is_none")]
+    pub(crate) database_id: Option<u64>,
+    /// The repository's visibility; only absent for repositories in
+    /// databases written before it was recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) visibility: Option<RepoVisibility>,
+    #[serde(default, alias = "isTemplate")]
+    pub(crate) is_template: bool,
+    /// The access token's level of access to the repository, if any
+    #[serde(
+        default,
+        alias = "viewerPermission",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub(crate) viewer_permission: Option<RepoPermission>,
     #[serde(
         rename(deserialize = "issues"),
         deserialize_with = "gqlient::singleton_field"
     )]
     pub(crate) open_issues: u64,
+    /// The repository's number of open pull requests; only fetched when
+    /// `--include-prs` is given
+    #[serde(
+        default,
+        rename(deserialize = "openPullRequests"),
+        deserialize_with = "gqlient::optional_singleton_field",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub(crate) open_pull_requests: Option<u64>,
+    /// The repository's number of stargazers; only absent for repositories
+    /// in databases written before it was recorded
+    #[serde(
+        default,
+        alias = "stargazerCount",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub(crate) stargazer_count: Option<u64>,
+    /// The repository's number of forks; only absent for repositories in
+    /// databases written before it was recorded
+    #[serde(default, alias = "forkCount", skip_serializing_if = "Option::is_none")]
+    pub(crate) fork_count: Option<u64>,
+    /// When the repository and its issues were last changed; only fetched
+    /// when `--skip-unchanged` is given
+    #[serde(flatten)]
+    pub(crate) activity: Option<RepoActivity>,
+}
+
+impl RepoDetails {
+    pub(crate) fn fullname(&self) -> String {
+        format!("{}/{}", self.owner, self.name)
+    }
+
+    /// Returns true if the details are the same as `other`'s apart from the
+    /// stargazer, fork, and open pull request counts, which can change
+    /// without any change to the repository's issues
+    pub(crate) fn same_except_popularity(&self, other: &RepoDetails) -> bool {
+        let normalized = RepoDetails {
+            open_pull_requests: other.open_pull_requests,
+            stargazer_count: other.stargazer_count,
+            fork_count: other.fork_count,
+            ..self.clone()
+        };
+        normalized == *other
+    }
+
+    /// Replace the repository's owner and name with hashes
+    pub(crate) fn anonymize(&mut self, anon: &Anonymizer) {
+        self.owner = anon.owner(&self.owner);
+        self.name = anon.repo_name(&self.name);
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub(crate) struct RepoActivity {
+    #[serde(alias = "pushedAt")]
+    pushed_at: Option<String>,
+    #[serde(alias = "updatedAt")]
+    updated_at: String,
+    /// When the most recently updated issue (open or closed) was updated
+    #[serde(
+        alias = "latestIssue",
+        default,
+        deserialize_with = "deser_latest_issue"
+    )]
+    latest_issue_update: Option<String>,
 }
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub(crate) struct Issue {
     pub(crate) number: u64,
+    /// Whether the item is an issue or a pull request; the latter are only
+    /// fetched when `--include-prs` is given
+    #[serde(
+        default,
+        alias = "__typename",
+        skip_serializing_if = "IssueKind::is_issue"
+    )]
+    pub(crate) kind: IssueKind,
+    /// The issue's numeric ID as used by the REST API; only fetched when
+    /// `--database-ids` is given
+    #[serde(default, alias = "databaseId", skip_serializing_if = "Option::is_none")]
+    pub(crate) database_id: Option<u64>,
     pub(crate) title: String,
-    //pub(crate) author: String,
+    #[serde(default)]
+    pub(crate) author: Option<Author>,
+    /// The names of the issue's labels; only fetched when `--labels`,
+    /// `--label-rules`, or `--label-matrix` is given
     // Note: Reportedly, the max number of labels on an issue is 100
-    //pub(crate) labels: Vec<String>,
+    #[serde(
+        default,
+        deserialize_with = "deser_labels",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub(crate) labels: Option<Vec<String>>,
     pub(crate) state: IssueState,
+    /// Why the issue was closed (e.g., `"COMPLETED"` or `"NOT_PLANNED"`);
+    /// only fetched for issues resolved by `--backfill-closed`
+    #[serde(
+        default,
+        alias = "stateReason",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub(crate) state_reason: Option<String>,
+    /// When the issue was closed, if it is closed
+    #[serde(default, alias = "closedAt", skip_serializing_if = "Option::is_none")]
+    pub(crate) closed_at: Option<String>,
     pub(crate) url: String,
+    /// Whether a pull request that will close the issue is linked to it;
+    /// only fetched when `--linked-prs` is given
+    #[serde(
+        default,
+        alias = "closedByPullRequestsReferences",
+        deserialize_with = "deser_has_linked_pr",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub(crate) has_linked_pr: Option<bool>,
+    /// The issue's parent issue, if it is a sub-issue; only fetched when
+    /// `--sub-issues` is given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) parent: Option<ParentIssue>,
+    /// The numbers of the issue's sub-issues; only fetched when
+    /// `--sub-issues` is given
+    #[serde(
+        default,
+        alias = "subIssuesSummary",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub(crate) sub_issues: Option<SubIssues>,
+}
+
+impl Issue {
+    pub(crate) fn is_bot_authored(&self) -> bool {
+        self.author.as_ref().is_some_and(|a| a.is_bot)
+    }
+
+    pub(crate) fn canonicalize_url(
+        &mut self,
+        base_url: &str,
+        repo: &str,
+    ) -> Result<(), IssueUrlError> {
+        self.url = gqlient::canonicalize_issue_url(&self.url, base_url, repo, self.number)?;
+        Ok(())
+    }
+
+    /// Replace the issue's title, author, labels, URL, and parent issue's
+    /// repository with hashes.  `repo` is the already-anonymized full name of
+    /// the issue's repository.
+    pub(crate) fn anonymize(&mut self, anon: &Anonymizer, repo: &str) {
+        self.title = anon.title(&self.title);
+        if let Some(ref mut author) = self.author {
+            author.login = anon.author(&author.login);
+        }
+        for label in self.labels.iter_mut().flatten() {
+            *label = anon.label(label);
+        }
+        self.url = Anonymizer::issue_url(repo, self.number);
+        if let Some(ref mut parent) = self.parent {
+            parent.repo = anon.repo(&parent.repo);
+        }
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -28,4 +198,81 @@ pub(crate) struct Issue {
 pub(crate) enum IssueState {
     Open,
     Closed,
+    /// Only used for pull requests
+    Merged,
+}
+
+// Labels are stored in the database as a list of names, but the GraphQL API
+// returns them as a connection of label objects, so accept either form.
+fn deser_labels<'de, D>(deserializer: D) -> Result<Option<Vec<String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+    #[serde(untagged)]
+    enum RawLabels {
+        Names(Vec<String>),
+        Connection { nodes: Vec<RawLabel> },
+    }
+
+    #[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+    struct RawLabel {
+        name: String,
+    }
+
+    Ok(
+        Option::<RawLabels>::deserialize(deserializer)?.map(|labels| match labels {
+            RawLabels::Names(names) => names,
+            RawLabels::Connection { nodes } => nodes.into_iter().map(|lbl| lbl.name).collect(),
+        }),
+    )
+}
+
+fn deser_has_linked_pr<'de, D>(deserializer: D) -> Result<Option<bool>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+    #[serde(untagged)]
+    enum RawLinkedPr {
+        Flag(bool),
+        Connection {
+            #[serde(rename = "totalCount")]
+            total_count: u64,
+        },
+    }
+
+    Ok(
+        Option::<RawLinkedPr>::deserialize(deserializer)?.map(|raw| match raw {
+            RawLinkedPr::Flag(b) => b,
+            RawLinkedPr::Connection { total_count } => total_count > 0,
+        }),
+    )
+}
+
+// The database stores the timestamp directly, but the GraphQL API returns it
+// inside a single-issue connection, so accept either form.
+fn deser_latest_issue<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+    #[serde(untagged)]
+    enum RawLatestIssue {
+        Timestamp(String),
+        Connection { nodes: Vec<RawIssueUpdate> },
+    }
+
+    #[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+    struct RawIssueUpdate {
+        #[serde(rename = "updatedAt")]
+        updated_at: String,
+    }
+
+    Ok(
+        Option::<RawLatestIssue>::deserialize(deserializer)?.and_then(|raw| match raw {
+            RawLatestIssue::Timestamp(ts) => Some(ts),
+            RawLatestIssue::Connection { nodes } => nodes.into_iter().next().map(|n| n.updated_at),
+        }),
+    )
 }
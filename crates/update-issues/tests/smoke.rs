@@ -0,0 +1,97 @@
+use fixtures::{FakeGitHub, FakeOrg, FixtureServer};
+use serde_json::Value;
+use std::ffi::OsStr;
+use std::path::Path;
+use std::process::{Command, Output};
+
+fn read_json_lines(path: &Path) -> Vec<Value> {
+    std::fs::read_to_string(path)
+        .expect("failed to read JSON Lines file")
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("invalid JSON line"))
+        .collect()
+}
+
+fn update_issues(server: &FixtureServer, args: &[&OsStr]) -> Output {
+    let output = Command::new(env!("CARGO_BIN_EXE_update-issues"))
+        .env("GH_TOKEN", "not-a-real-token")
+        .arg("--api-url")
+        .arg(server.api_url())
+        .args(args)
+        .arg("fake-org")
+        .output()
+        .expect("failed to run update-issues");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    output
+}
+
+#[test]
+fn create_then_update_database() {
+    let github = FakeGitHub::new([FakeOrg::new("fake-org", 3, 150, 2)]);
+    let server = FixtureServer::start(move |payload| github.respond(payload));
+    let tmpdir = tempfile::tempdir().unwrap();
+    let db1 = tmpdir.path().join("db1.json");
+    let db2 = tmpdir.path().join("db2.json");
+    let report_file = tmpdir.path().join("report.jsonl");
+
+    update_issues(
+        &server,
+        &[
+            OsStr::new("--report-file"),
+            report_file.as_os_str(),
+            OsStr::new("--outfile"),
+            db1.as_os_str(),
+        ],
+    );
+    let db = serde_json::from_str::<Value>(&std::fs::read_to_string(&db1).unwrap()).unwrap();
+    let repos = db.as_object().unwrap();
+    assert_eq!(repos.len(), 3);
+    assert!(repos.values().all(|repo| repo["issues"]
+        .as_object()
+        .is_some_and(|issues| issues.len() == 150)));
+
+    update_issues(
+        &server,
+        &[
+            OsStr::new("--report-file"),
+            report_file.as_os_str(),
+            OsStr::new("--infile"),
+            db1.as_os_str(),
+            OsStr::new("--outfile"),
+            db2.as_os_str(),
+        ],
+    );
+    // Nothing changed on the server, so the repositories and their issues are
+    // the same in the updated database.
+    let strip = |path: &Path| {
+        let db = serde_json::from_str::<Value>(&std::fs::read_to_string(path).unwrap()).unwrap();
+        db.as_object()
+            .unwrap()
+            .iter()
+            .map(|(id, repo)| {
+                (
+                    id.clone(),
+                    (repo["repository"].clone(), repo["issues"].clone()),
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(strip(&db1), strip(&db2));
+
+    let reports = read_json_lines(&report_file);
+    assert_eq!(reports.len(), 2);
+    for report in &reports {
+        assert_eq!(report["program"], "update-issues");
+        assert_eq!(report["owners"], serde_json::json!(["fake-org"]));
+        assert_eq!(report["repositories"], 3);
+    }
+    // The second run only fetches issues after the saved cursors, of which
+    // there are none.
+    assert_eq!(reports[0]["open_issues"], 450);
+    assert_eq!(reports[1]["open_issues"], 0);
+    assert_eq!(reports[1]["issues_updated"], 0);
+}